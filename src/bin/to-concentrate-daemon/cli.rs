@@ -1,6 +1,7 @@
 use std::path::PathBuf;
 
 use clap::Parser;
+use clap_complete::Shell;
 use tracing::Level;
 
 #[derive(Debug, Parser)]
@@ -9,10 +10,41 @@ pub struct Arguments {
     /// Path to a custom configuration file
     #[arg(short, long)]
     pub config: Option<PathBuf>,
+    /// Path to the UNIX socket. Overrides `runtime.socket` in the
+    /// configuration file
+    #[arg(long, value_name = "PATH")]
+    pub socket: Option<PathBuf>,
+    /// Path to the PID file. Overrides `runtime.pid` in the configuration
+    /// file
+    #[arg(long, value_name = "PATH")]
+    pub pid: Option<PathBuf>,
     /// Maximum logging level the subscriber should use
     #[arg(short, long, default_value_t = Level::INFO)]
     pub verbosity: Level,
+    /// Append logs to this file instead of printing them to stdout
+    #[arg(long, value_name = "PATH")]
+    pub log_file: Option<PathBuf>,
     /// Whether to daemonize the process
-    #[arg(short, long)]
+    #[arg(short, long, conflicts_with = "ephemeral")]
     pub daemonize: bool,
+    /// Fail instead of generating a default configuration if it is missing
+    #[arg(long)]
+    pub no_create_config: bool,
+    /// Wait in the paused state instead of counting down when the timer
+    /// starts afresh. Same as `runtime.start_paused` in the configuration
+    /// file
+    #[arg(long)]
+    pub start_paused: bool,
+    /// Run without any persistent state, e.g. for demos
+    #[arg(long)]
+    pub ephemeral: bool,
+    /// Remove a stale or invalid PID file left by a crashed daemon
+    #[arg(long, conflicts_with = "ephemeral")]
+    pub force: bool,
+    /// Validate the configuration and the environment, then exit
+    #[arg(long, conflicts_with_all = ["daemonize", "ephemeral"])]
+    pub check: bool,
+    /// Print a completion script for the shell to stdout and exit
+    #[arg(long, value_name = "SHELL", hide = true)]
+    pub completions: Option<Shell>,
 }
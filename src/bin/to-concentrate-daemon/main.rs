@@ -1,7 +1,7 @@
 mod cli;
 mod setup;
 
-use clap::Parser;
+use clap::{CommandFactory, Parser};
 use snafu::{prelude::*, Whatever};
 
 use crate::cli::Arguments;
@@ -11,19 +11,48 @@ use crate::cli::Arguments;
 async fn main() -> Result<(), Whatever> {
     let arg = Arguments::parse();
 
-    let subscriber = tracing_subscriber::FmtSubscriber::builder()
-        .with_max_level(arg.verbosity)
-        .pretty()
-        .finish();
-    tracing::subscriber::set_global_default(subscriber)
-        .whatever_context("Could not setup logger")?;
+    if let Some(shell) = arg.completions {
+        let mut command = Arguments::command();
+        clap_complete::generate(
+            shell,
+            &mut command,
+            env!("CARGO_BIN_NAME"),
+            &mut std::io::stdout(),
+        );
+        return Ok(());
+    }
 
-    let server = setup::bootstrap(arg).await?;
+    run(arg).await
+}
+
+async fn run(arg: Arguments) -> Result<(), Whatever> {
+    if arg.check {
+        let _guard = setup::logger(&arg)?;
+        let summary = setup::check(&arg)?;
+        print!("{summary}");
+        return Ok(());
+    }
+
+    if arg.ephemeral {
+        let _guard = setup::logger(&arg)?;
+        let (daemon, leftovers) = setup::ephemeral(arg).await?;
+
+        daemon
+            .serve()
+            .await
+            .whatever_context("Server failed to serve with fatal")?;
+
+        setup::cleanup(leftovers);
+        return Ok(());
+    }
+
+    let (server, leftovers, _guard) = setup::bootstrap(arg).await?;
 
     server
         .serve()
         .await
         .whatever_context("Server failed to serve with fatal")?;
 
+    setup::cleanup(leftovers);
     Ok(())
 }
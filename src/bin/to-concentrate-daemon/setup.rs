@@ -1,31 +1,60 @@
 use std::cell::LazyCell;
 use std::fs;
+use std::net::SocketAddr;
+use std::os::unix::net::UnixStream;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use snafu::{prelude::*, Whatever};
 use to_concentrate::daemon::app::listener::Listener;
-use to_concentrate::daemon::config::{self, Configuration};
-use to_concentrate::daemon::outbound::NotifyService;
-use to_concentrate::daemon::repository::{DurationConfiguration, NotificationConfiguration};
+use to_concentrate::daemon::config::{
+    self, Configuration, NotificationBackendOption, SignalOption,
+};
+use to_concentrate::daemon::outbound::SessionIdleDetector;
+use to_concentrate::daemon::outbound::{CommandNotifyService, FileStateStore, NotifyService};
+use to_concentrate::daemon::outbound::{CsvStageLog, NoStageLog, RateLimitedNotifier};
+use to_concentrate::daemon::repository::{
+    BehaviorConfiguration, DurationConfiguration, NotificationConfiguration, StageConfiguration,
+};
 use to_concentrate::daemon::runtime::{Environment, ProcessController};
-use to_concentrate::daemon::{Server, UnixListener};
+use to_concentrate::daemon::{ConfigLoader, Daemon, Server, TcpListener, UnixListener};
+use to_concentrate::domain::daemon::outbound::{NotifyPort, StageLog};
 use to_concentrate::domain::daemon::ApplicationCore;
 use to_concentrate::tracing_report;
+use to_concentrate::utils::stream;
 use to_concentrate::utils::xdg::{Xdg, XdgBaseKind};
+use tokio::signal::unix::SignalKind;
+use tracing::subscriber::DefaultGuard;
+use tracing::Subscriber;
+use tracing_appender::non_blocking::{NonBlockingBuilder, WorkerGuard};
+use tracing_subscriber::fmt::MakeWriter;
 
 use crate::cli::Arguments;
 
 const APP_NAME: &str = "to-concentrate";
 
 struct EnvironmentPath {
+    config: PathBuf,
     socket: PathBuf,
     pid: PathBuf,
+    state: PathBuf,
 }
 
-#[tracing::instrument(skip(arg))]
-pub async fn bootstrap(arg: Arguments) -> Result<Server, Whatever> {
-    let (configuration, env_path) = configuration(&arg)
+/// Files removed by [`cleanup`] after the daemon shuts down.
+pub type Leftovers = Vec<PathBuf>;
+
+/// Prepare the daemon to serve, daemonizing the process if asked to. The
+/// logger is installed along the way, and the returned guard of it should
+/// live until the daemon exits.
+pub async fn bootstrap(
+    arg: Arguments,
+) -> Result<(Server, Leftovers, Option<WorkerGuard>), Whatever> {
+    // The writing thread of the logger would be left in the parent process,
+    // so logs are written synchronously until the process is daemonized.
+    let early = early_logger(&arg)?;
+
+    let (configuration, env_path) = configuration(&arg, !arg.no_create_config)
         .inspect(|_| tracing::info!("Loaded configuration"))
         .inspect_err(|err| tracing_report!(err))?;
 
@@ -33,38 +62,219 @@ pub async fn bootstrap(arg: Arguments) -> Result<Server, Whatever> {
         .inspect(|_| tracing::info!("Initialized environment"))
         .inspect_err(|err| tracing_report!(err))?;
 
-    process(&arg, env_path.pid)
+    process(&arg, &env_path.pid)
         .inspect(|_| tracing::info!("Finished process-related operations"))
         .inspect_err(|err| tracing_report!(err))?;
 
-    let listener = listener(env_path.socket)
-        .inspect(|_| tracing::info!("Initialized socket"))
-        .inspect_err(|err| tracing_report!(err))?;
+    drop(early);
+    let guard = logger(&arg)?;
+
+    let mut leftovers = vec![env_path.pid];
+
+    let listener = match configuration.runtime.tcp_address {
+        Some(address) => tcp_listener(address),
+        None => listener(&env_path.socket, configuration.runtime.socket_mode)
+            .inspect(|_| leftovers.extend(socket_file(env_path.socket))),
+    }
+    .inspect(|_| tracing::info!("Initialized socket"))
+    .inspect_err(|err| tracing_report!(err))?;
 
-    let core = core(configuration)
+    let signals = signals(&configuration.runtime.shutdown_signals);
+
+    let core = core(configuration, env_path.state)
         .await
         .inspect(|_| tracing::info!("Initialized server core"))
         .inspect_err(|err| tracing_report!(err))?;
 
-    let server = Server::new(listener, core);
+    let server = Server::new(listener, core)
+        .with_signals(signals)
+        .with_reload(loader(&arg));
     tracing::info!("Initialized application");
-    Ok(server)
+    Ok((server, leftovers, guard))
+}
+
+/// Install the global logger, which appends logs to `--log-file` from a
+/// background thread, or prints them to stdout. The logs left in the queue
+/// are written once the returned guard is dropped.
+///
+/// # Errors
+///
+/// This function will return an error if the log file could not be opened or
+/// a logger has been installed.
+pub fn logger(arg: &Arguments) -> Result<Option<WorkerGuard>, Whatever> {
+    let (subscriber, guard) = match log_file(arg)? {
+        Some(file) => {
+            // Logging waits for a full queue rather than dropping logs.
+            let (writer, guard) = NonBlockingBuilder::default().lossy(false).finish(file);
+            (subscriber(arg, writer, false), Some(guard))
+        }
+        None => (subscriber(arg, std::io::stdout, true), None),
+    };
+
+    tracing::subscriber::set_global_default(subscriber)
+        .whatever_context("Could not setup logger")?;
+    Ok(guard)
+}
+
+/// Install a logger like [`logger`] for the current thread until the returned
+/// guard is dropped, which writes logs synchronously without any thread.
+fn early_logger(arg: &Arguments) -> Result<DefaultGuard, Whatever> {
+    let subscriber = match log_file(arg)? {
+        Some(file) => subscriber(arg, Mutex::new(file), false),
+        None => subscriber(arg, std::io::stdout, true),
+    };
+    Ok(tracing::subscriber::set_default(subscriber))
+}
+
+fn log_file(arg: &Arguments) -> Result<Option<fs::File>, Whatever> {
+    arg.log_file
+        .as_ref()
+        .map(|path| fs::OpenOptions::new().create(true).append(true).open(path))
+        .transpose()
+        .whatever_context("Could not open log file")
+}
+
+fn subscriber<W>(arg: &Arguments, writer: W, ansi: bool) -> Box<dyn Subscriber + Send + Sync>
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let subscriber = tracing_subscriber::FmtSubscriber::builder()
+        .with_max_level(arg.verbosity)
+        .pretty()
+        .with_ansi(ansi)
+        .with_writer(writer)
+        .finish();
+    Box::new(subscriber)
+}
+
+/// Validate the configuration and the environment like [`bootstrap`], and
+/// bind the listener to prove that it works, but exit instead of serving.
+/// Return a summary of the resolved paths, one `key = value` per line.
+#[tracing::instrument(skip(arg))]
+pub fn check(arg: &Arguments) -> Result<String, Whatever> {
+    // A missing configuration file fails the check rather than being created.
+    let (configuration, env_path) = configuration(arg, false)
+        .inspect(|_| tracing::info!("Loaded configuration"))
+        .inspect_err(|err| tracing_report!(err))?;
+
+    environment(&env_path)
+        .inspect(|_| tracing::info!("Initialized environment"))
+        .inspect_err(|err| tracing_report!(err))?;
+
+    notify_port(&configuration).inspect_err(|err| tracing_report!(err))?;
+
+    let endpoint = match configuration.runtime.tcp_address {
+        Some(address) => tcp_listener(address).map(|_| format!("address = {address}")),
+        None => check_socket(&env_path.socket, configuration.runtime.socket_mode),
+    }
+    .inspect(|_| tracing::info!("Checked socket"))
+    .inspect_err(|err| tracing_report!(err))?;
+
+    Ok(format!(
+        "config = {}\n{endpoint}\npid = {}\nstate = {}\n",
+        env_path.config.display(),
+        env_path.pid.display(),
+        env_path.state.display(),
+    ))
+}
+
+/// Bind to the socket at `path` and remove it again. A socket in use by a
+/// running daemon is left alone.
+fn check_socket(path: &Path, mode: u32) -> Result<String, Whatever> {
+    if stream::abstract_name(path).is_some() {
+        // Binding fails if another daemon holds the name.
+        drop(listener(path, mode)?);
+        return Ok(format!("socket = {}", path.display()));
+    }
+
+    ensure_whatever!(
+        UnixStream::connect(path).is_err(),
+        "A daemon is already listening on {}",
+        path.display()
+    );
+
+    drop(listener(path, mode)?);
+    let _ = fs::remove_file(path);
+    Ok(format!("socket = {}", path.display()))
+}
+
+#[tracing::instrument(skip(arg))]
+pub async fn ephemeral(arg: Arguments) -> Result<(Daemon, Leftovers), Whatever> {
+    let mut configuration = match config_path(&arg) {
+        Some(path) => config::load_with_path(path),
+        None => config::load_default(),
+    }
+    .whatever_context("Could not load configuration")
+    .inspect(|_| tracing::info!("Loaded configuration"))
+    .inspect_err(|err| tracing_report!(err))?;
+    configuration.runtime.start_paused |= arg.start_paused;
+
+    let socket = match arg
+        .socket
+        .as_ref()
+        .or(configuration.runtime.socket.as_ref())
+    {
+        Some(socket) => socket.clone(),
+        None => std::env::temp_dir().join(format!("{APP_NAME}-{}.socket", std::process::id())),
+    };
+
+    let mut leftovers = Vec::new();
+
+    let listener = match configuration.runtime.tcp_address {
+        Some(address) => {
+            tcp_listener(address).inspect(|_| tracing::info!(%address, "Initialized socket"))
+        }
+        None => listener(&socket, configuration.runtime.socket_mode).inspect(|_| {
+            tracing::info!(socket = %socket.display(), "Initialized socket");
+            leftovers.extend(socket_file(socket.clone()));
+        }),
+    }
+    .inspect_err(|err| tracing_report!(err))?;
+
+    let signals = signals(&configuration.runtime.shutdown_signals);
+
+    let daemon = Daemon::ephemeral_with_listener(configuration, listener)
+        .await
+        .whatever_context("Could not setup application core")
+        .inspect(|_| tracing::info!("Initialized ephemeral application"))
+        .inspect_err(|err| tracing_report!(err))?
+        .with_signals(signals);
+
+    Ok((daemon, leftovers))
+}
+
+/// Remove the files left by a daemon which has shut down.
+pub fn cleanup(leftovers: Leftovers) {
+    for path in leftovers {
+        match fs::remove_file(&path) {
+            Ok(()) => tracing::info!(path = %path.display(), "Removed runtime file"),
+            Err(err) => {
+                tracing::warn!(path = %path.display(), %err, "Could not remove runtime file")
+            }
+        }
+    }
 }
 
 fn environment(env_path: &EnvironmentPath) -> Result<(), Whatever> {
-    let socket_parent = env_path.socket.parent().whatever_context(format!(
-        "Invalid socket path: {}",
-        env_path.socket.display()
-    ))?;
+    let mut env = Environment::new();
+
+    if stream::abstract_name(&env_path.socket).is_none() {
+        let socket_parent = env_path.socket.parent().whatever_context(format!(
+            "Invalid socket path: {}",
+            env_path.socket.display()
+        ))?;
+        env.register_directory(socket_parent);
+    }
 
     let pid_parent = env_path
         .pid
         .parent()
         .whatever_context(format!("Invalid PID path: {}", env_path.pid.display()))?;
-
-    let mut env = Environment::new();
-    env.register_directory(socket_parent);
     env.register_directory(pid_parent);
+
+    if let Some(state_parent) = env_path.state.parent() {
+        env.register_directory(state_parent);
+    }
     env.setup().whatever_context("Could not setup environment")
 }
 
@@ -74,21 +284,62 @@ fn process<P: AsRef<Path>>(arg: &Arguments, pid_path: P) -> Result<(), Whatever>
         pid_path.as_ref().to_path_buf(),
         arg.daemonize,
     )
+    .with_force(arg.force)
     .start()
     .whatever_context("Could not prepare process")
 }
 
-fn configuration(arg: &Arguments) -> Result<(Arc<Configuration>, EnvironmentPath), Whatever> {
-    let res = match &arg.config {
-        Some(path) => config::load_with_path(path.clone()),
-        None => config::load_with_xdg(APP_NAME.to_owned()),
+/// Resolve the path of the configuration file, preferring `--config` to the
+/// environment variable. Return `None` to use the XDG base directories.
+fn config_path(arg: &Arguments) -> Option<PathBuf> {
+    config::config_path(arg.config.as_deref(), |key| std::env::var(key).ok())
+}
+
+/// Load the configuration from the same file as on startup, without creating
+/// it again.
+fn loader(arg: &Arguments) -> ConfigLoader {
+    let path = config_path(arg);
+    let start_paused = arg.start_paused;
+    Box::new(move || {
+        let mut configuration = match &path {
+            Some(path) => config::load_with_path(path),
+            None => config::load_with_xdg(APP_NAME.to_owned(), false),
+        }?;
+        configuration.runtime.start_paused |= start_paused;
+        Ok(configuration)
+    })
+}
+
+/// Load the configuration and resolve the paths used by the daemon. A default
+/// configuration file is written if it's missing and `create_new` is set.
+fn configuration(
+    arg: &Arguments,
+    create_new: bool,
+) -> Result<(Arc<Configuration>, EnvironmentPath), Whatever> {
+    let res = match config_path(arg) {
+        Some(path) => config::load_with_path(path),
+        None => config::load_with_xdg(APP_NAME.to_owned(), create_new),
     };
 
-    let configuration = res.whatever_context("Could not load configuration")?;
+    let mut configuration = res.whatever_context("Could not load configuration")?;
+    configuration.runtime.start_paused |= arg.start_paused;
 
     let xdg = LazyCell::new(|| Xdg::new(APP_NAME));
 
-    let socket = match &configuration.runtime.socket {
+    let config = match config_path(arg) {
+        Some(config) => config,
+        None => xdg
+            .as_ref()
+            .map_err(Clone::clone)
+            .and_then(|xdg| xdg.resolve(XdgBaseKind::Config, "config.toml"))
+            .whatever_context("Could not use XDG base directories")?,
+    };
+
+    let socket = match arg
+        .socket
+        .as_ref()
+        .or(configuration.runtime.socket.as_ref())
+    {
         Some(socket) => socket.clone(),
         None => xdg
             .as_ref()
@@ -97,8 +348,8 @@ fn configuration(arg: &Arguments) -> Result<(Arc<Configuration>, EnvironmentPath
             .whatever_context("Could not use XDG base directories")?,
     };
 
-    let pid = match &configuration.runtime.pid {
-        Some(socket) => socket.clone(),
+    let pid = match arg.pid.as_ref().or(configuration.runtime.pid.as_ref()) {
+        Some(pid) => pid.clone(),
         None => xdg
             .as_ref()
             .map_err(Clone::clone)
@@ -106,23 +357,112 @@ fn configuration(arg: &Arguments) -> Result<(Arc<Configuration>, EnvironmentPath
             .whatever_context("Could not use XDG base directories")?,
     };
 
-    let env_path = EnvironmentPath { socket, pid };
+    let state = match &configuration.runtime.state {
+        Some(state) => state.clone(),
+        None => xdg
+            .as_ref()
+            .map_err(Clone::clone)
+            .and_then(|xdg| xdg.resolve(XdgBaseKind::Runtime, "daemon.state"))
+            .whatever_context("Could not use XDG base directories")?,
+    };
+
+    let env_path = EnvironmentPath {
+        config,
+        socket,
+        pid,
+        state,
+    };
     Ok((Arc::new(configuration), env_path))
 }
 
-fn listener<P: AsRef<Path>>(path: P) -> Result<Box<dyn Listener>, Whatever> {
+/// Bind to the socket at `path` and restrict it to `mode`. An abstract
+/// socket has neither a stale file to replace nor a permission to set.
+fn listener<P: AsRef<Path>>(path: P, mode: u32) -> Result<Box<dyn Listener>, Whatever> {
+    if stream::abstract_name(path.as_ref()).is_some() {
+        let listener = UnixListener::new(&path)
+            .whatever_context(format!("Could not bind to {}", path.as_ref().display()))?;
+        return Ok(Box::new(listener));
+    }
+
     let _ = fs::remove_file(&path);
-    UnixListener::new(&path)
+    let listener = UnixListener::new(&path)
+        .whatever_context(format!("Could not bind to {}", path.as_ref().display()))?;
+
+    let mut env = Environment::new();
+    env.register_permission(&path, mode);
+    env.setup()
+        .whatever_context("Could not set the socket's permission")?;
+    Ok(Box::new(listener))
+}
+
+/// Return the file of the socket at `path` for [`cleanup`], if it has one.
+fn socket_file(path: PathBuf) -> Option<PathBuf> {
+    stream::abstract_name(&path).is_none().then_some(path)
+}
+
+fn signals(options: &[SignalOption]) -> Vec<SignalKind> {
+    options
+        .iter()
+        .map(|option| match option {
+            SignalOption::Sigterm => SignalKind::terminate(),
+            SignalOption::Sigint => SignalKind::interrupt(),
+            SignalOption::Sighup => SignalKind::hangup(),
+            SignalOption::Sigquit => SignalKind::quit(),
+        })
+        .collect()
+}
+
+fn tcp_listener(address: SocketAddr) -> Result<Box<dyn Listener>, Whatever> {
+    TcpListener::new(address)
         .map(|listener| -> Box<dyn Listener> { Box::new(listener) })
-        .whatever_context(format!("Could not bind to {}", path.as_ref().display()))
+        .whatever_context(format!("Could not bind to {address}"))
 }
 
-async fn core(config: Arc<Configuration>) -> Result<ApplicationCore, Whatever> {
-    let notify_port = Arc::new(NotifyService::new(APP_NAME.to_owned()));
+fn notify_port(config: &Configuration) -> Result<Arc<dyn NotifyPort>, Whatever> {
+    let notify_port: Arc<dyn NotifyPort> = match config.notification.backend {
+        NotificationBackendOption::Desktop => Arc::new(NotifyService::new(APP_NAME.to_owned())),
+        NotificationBackendOption::Command => {
+            let command = config
+                .notification
+                .command
+                .clone()
+                .whatever_context("The command notification backend requires `command`")?;
+            Arc::new(CommandNotifyService::new(command))
+        }
+    };
+
+    match config.notification.min_interval.filter(|&secs| secs > 0) {
+        Some(secs) => Ok(Arc::new(RateLimitedNotifier::new(
+            notify_port,
+            Duration::from_secs(secs),
+        ))),
+        None => Ok(notify_port),
+    }
+}
+
+async fn core(config: Arc<Configuration>, state: PathBuf) -> Result<ApplicationCore, Whatever> {
+    let notify_port = notify_port(&config)?;
+    let idle_detector = Arc::new(SessionIdleDetector::new());
+    let state_store = Arc::new(FileStateStore::new(state));
+    let stage_log: Arc<dyn StageLog> = match &config.runtime.stats_file {
+        Some(path) => Arc::new(CsvStageLog::new(path.clone())),
+        None => Arc::new(NoStageLog::new()),
+    };
     let duration_repository = Arc::new(DurationConfiguration::new(Arc::clone(&config)));
-    let notification_repository = Arc::new(NotificationConfiguration::new(config));
+    let notification_repository = Arc::new(NotificationConfiguration::new(Arc::clone(&config)));
+    let stage_repository = Arc::new(StageConfiguration::new(Arc::clone(&config)));
+    let behavior_repository = Arc::new(BehaviorConfiguration::new(config));
 
-    ApplicationCore::setup(notify_port, duration_repository, notification_repository)
-        .await
-        .whatever_context("Could not setup application core")
+    ApplicationCore::setup(
+        notify_port,
+        idle_detector,
+        state_store,
+        stage_log,
+        duration_repository,
+        notification_repository,
+        stage_repository,
+        behavior_repository,
+    )
+    .await
+    .whatever_context("Could not setup application core")
 }
@@ -1,7 +1,12 @@
 use std::path::PathBuf;
 
-use clap::{Parser, Subcommand};
-use to_concentrate::client::app::{Command as ClientCommand, QueryArguments};
+use clap::{Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use to_concentrate::client::app::{Command as ClientCommand, QueryArguments, QueryFormat};
+use to_concentrate::client::app::{SkipArguments, WatchArguments};
+use to_concentrate::client::config;
+use to_concentrate::domain::entity::{AutoPauseTrigger, StageState};
+use tokio::time::Duration;
 use tracing::Level;
 
 #[derive(Debug, Parser)]
@@ -10,6 +15,32 @@ pub struct Arguments {
     /// Path to a custom configuration file
     #[arg(short, long)]
     pub config: Option<PathBuf>,
+    /// Path to the daemon's UNIX socket. Overrides `runtime.socket` in the
+    /// configuration file
+    #[arg(long, value_name = "PATH")]
+    pub socket: Option<PathBuf>,
+    /// Path to the daemon's PID file. Overrides `runtime.pid` in the
+    /// configuration file
+    #[arg(long, value_name = "PATH")]
+    pub pid: Option<PathBuf>,
+    /// Name of the timer session to control. Uses the daemon's default
+    /// session if omitted
+    #[arg(long)]
+    pub session: Option<String>,
+    /// Maximum seconds to wait for the daemon's response, where 0 waits
+    /// forever. Overrides `client.request_timeout` in the configuration file
+    /// [default: 5 after connecting]
+    #[arg(long)]
+    pub timeout: Option<u64>,
+    /// Number of extra attempts to connect to the daemon. Overrides
+    /// `client.retries` in the configuration file
+    #[arg(long)]
+    pub retries: Option<u32>,
+    /// Maximum seconds to keep connecting to the daemon, waiting longer after
+    /// each failure. Overrides `client.connect_deadline` in the
+    /// configuration file
+    #[arg(long)]
+    pub connect_deadline: Option<u64>,
     #[command(subcommand)]
     pub command: Command,
 }
@@ -46,9 +77,138 @@ pub enum Command {
         /// Show the past duration in the current stage
         #[arg(short, long)]
         past: bool,
+        /// Show whether the current or upcoming break is a long one
+        #[arg(short, long)]
+        long_break: bool,
+        /// Show the number of rounds of stages completed since the timer
+        /// started
+        #[arg(long)]
+        cycles: bool,
+        /// Show whether notifications are muted
+        #[arg(long)]
+        muted: bool,
+        /// Show the past and total duration as a single `past/total` fraction
+        #[arg(short, long)]
+        fraction: bool,
+        /// Exit with a code indicating the current stage: 10 for preparation,
+        /// 11 for concentration, 12 for relaxation and 5 if not running
+        #[arg(short, long)]
+        exit_on_stage: bool,
+        /// Show durations as seconds like `885s` instead of `14:45` in the
+        /// plain format
+        #[arg(long)]
+        seconds: bool,
+        /// Output format of the selected fields: `plain`, `toml`, `json`, or a
+        /// template like `{stage}: {remaining}` with the placeholders
+        /// `{current}`, `{stage}`, `{total}`, `{remaining}` and `{past}`.
+        /// Overrides `client.default_format` in the configuration file
+        #[arg(long, value_parser = parse_format, conflicts_with = "fraction")]
+        format: Option<FormatArgument>,
+        /// Print the selected fields as JSON, which is the same as
+        /// `--format json`
+        #[arg(long, conflicts_with_all = ["fraction", "format"])]
+        json: bool,
+        /// Block until the timer enters the stage, then exit with 0
+        #[arg(
+            long,
+            value_name = "STAGE",
+            ignore_case = true,
+            conflicts_with_all = ["fraction", "exit_on_stage"]
+        )]
+        poll_until: Option<StageState>,
+        /// Give up `--poll-until` after the seconds and exit with 2
+        #[arg(long, value_name = "SECONDS", requires = "poll_until")]
+        poll_timeout: Option<u64>,
     },
+    /// Show the current stage and those following it for one round
+    Schedule,
+    /// Show the completed concentrations and their total time
+    Stats,
     /// Skip the current stage
-    Skip,
+    Skip {
+        /// Emit the skipped stage's notification
+        #[arg(short, long, conflicts_with = "silent")]
+        notify: bool,
+        /// Skip without emitting any notification (default)
+        #[arg(short, long)]
+        silent: bool,
+    },
+    /// Restart the timer from the initial stage
+    Reset,
+    /// Stop showing notifications while the timer goes on
+    Mute,
+    /// Show notifications again
+    Unmute,
+    /// Lengthen the current stage, or shorten it with a negative value
+    Adjust {
+        /// Seconds to add to the current stage, e.g. 300 or -300
+        #[arg(allow_negative_numbers = true)]
+        seconds: i64,
+    },
+    /// Set the total duration of the current stage only
+    SetDuration {
+        /// Total duration of the current stage, e.g. 50m, 1h30m or 3000
+        #[arg(value_parser = parse_duration)]
+        duration: Duration,
+    },
+    /// Jump to the next stage of the kind, starting it afresh
+    SkipTo {
+        /// Kind of the stage to jump to
+        #[arg(ignore_case = true)]
+        stage: StageState,
+    },
+    /// Stop the timer and shut down the daemon
+    Stop,
+    /// Pause the timer automatically when the screen locks
+    OnLock,
+    /// Resume the timer paused by a screen lock
+    OnUnlock,
+    /// Pause the timer automatically when the battery runs low
+    OnLowBattery,
+    /// Resume the timer paused by a low battery
+    OnAcPower,
+    /// Display the timer's status continuously until Ctrl-C is pressed
+    Watch {
+        /// Seconds between two refreshes
+        #[arg(
+            short,
+            long,
+            value_name = "SECONDS",
+            default_value_t = 1,
+            value_parser = clap::value_parser!(u64).range(1..),
+        )]
+        interval: u64,
+    },
+    /// Print every change of the timer's state until Ctrl-C is pressed,
+    /// subscribing again if the daemon restarts
+    Subscribe,
+    /// Report whether the daemon is running
+    Status,
+    /// Check whether the daemon and its timer respond, exiting with 0 if so
+    Health,
+    /// Inspect the client's configuration
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Print a completion script for the shell to stdout
+    #[command(hide = true)]
+    Completions {
+        /// Shell to generate the completion script for
+        shell: Shell,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Print the paths of the configuration file, the socket and the PID file
+    Paths,
+    /// Print the default configuration, or write it to a new file
+    Generate {
+        /// Path to write the configuration to, which must not exist yet
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
 }
 
 impl From<Command> for ClientCommand {
@@ -63,14 +223,104 @@ impl From<Command> for ClientCommand {
                 total,
                 remaining,
                 past,
+                long_break,
+                cycles,
+                muted,
+                fraction,
+                exit_on_stage,
+                seconds,
+                format,
+                json,
+                poll_until,
+                poll_timeout,
             } => Self::Query(QueryArguments {
                 current,
                 stage,
                 total,
                 remaining,
                 past,
+                long_break,
+                cycles,
+                muted,
+                fraction,
+                exit_on_stage,
+                seconds,
+                format: match format {
+                    _ if json => Some(QueryFormat::Json),
+                    Some(FormatArgument::Named(format)) => Some(format.into()),
+                    _ => None,
+                },
+                template: match format {
+                    Some(FormatArgument::Template(template)) => Some(template),
+                    _ => None,
+                },
+                poll_until,
+                poll_timeout: poll_timeout.map(Duration::from_secs),
+            }),
+            Command::Schedule => Self::Schedule,
+            Command::Stats => Self::Stats,
+            Command::Skip { notify, .. } => Self::Skip(SkipArguments { notify }),
+            Command::Reset => Self::Reset,
+            Command::Mute => Self::Mute,
+            Command::Unmute => Self::Unmute,
+            Command::Adjust { seconds } => Self::Adjust(seconds),
+            Command::SetDuration { duration } => Self::SetDuration(duration),
+            Command::SkipTo { stage } => Self::SkipTo(stage),
+            Command::Stop => Self::Stop,
+            Command::OnLock => Self::AutoPause(AutoPauseTrigger::ScreenLock),
+            Command::OnUnlock => Self::AutoResume(AutoPauseTrigger::ScreenLock),
+            Command::OnLowBattery => Self::AutoPause(AutoPauseTrigger::LowBattery),
+            Command::OnAcPower => Self::AutoResume(AutoPauseTrigger::LowBattery),
+            Command::Watch { interval } => Self::Watch(WatchArguments {
+                interval: Duration::from_secs(interval),
             }),
-            Command::Skip => Self::Skip,
+            Command::Subscribe => Self::Subscribe,
+            Command::Status => Self::Status,
+            Command::Health => Self::Health,
+            // Handled by `main` without a daemon.
+            Command::Completions { .. } => unreachable!("completions are not sent to the daemon"),
+            Command::Config { .. } => unreachable!("configuration is not sent to the daemon"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Format {
+    /// Aligned `key = value` lines
+    Plain,
+    /// A TOML document with durations in seconds
+    Toml,
+    /// A JSON object with durations in seconds
+    Json,
+}
+
+/// Value of `query --format`, which is a template if it has any placeholder.
+#[derive(Debug, Clone)]
+pub enum FormatArgument {
+    Named(Format),
+    Template(String),
+}
+
+fn parse_format(value: &str) -> Result<FormatArgument, String> {
+    if value.contains('{') {
+        Ok(FormatArgument::Template(value.to_owned()))
+    } else {
+        Format::from_str(value, true).map(FormatArgument::Named)
+    }
+}
+
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    config::parse_seconds(value)
+        .map(Duration::from_secs)
+        .map_err(|err| err.to_string())
+}
+
+impl From<Format> for QueryFormat {
+    fn from(value: Format) -> Self {
+        match value {
+            Format::Plain => Self::Plain,
+            Format::Toml => Self::Toml,
+            Format::Json => Self::Json,
         }
     }
 }
@@ -85,4 +335,84 @@ mod tests {
     fn arguments_parse() {
         Arguments::command().debug_assert();
     }
+
+    #[test]
+    fn arguments_parse_format() {
+        let parse = |format| {
+            let args = ["to-concentrate", "query", "--format", format];
+            ClientCommand::from(Arguments::try_parse_from(args).unwrap().command)
+        };
+
+        match parse("toml") {
+            ClientCommand::Query(args) => {
+                assert_eq!(args.format, Some(QueryFormat::Toml));
+                assert_eq!(args.template, None);
+            }
+            _ => unreachable!(),
+        }
+        match parse("{stage}: {remaining}") {
+            ClientCommand::Query(args) => {
+                assert_eq!(args.format, None);
+                assert_eq!(args.template.as_deref(), Some("{stage}: {remaining}"));
+            }
+            _ => unreachable!(),
+        }
+        assert!(
+            Arguments::try_parse_from(["to-concentrate", "query", "--format", "yaml"]).is_err()
+        );
+    }
+
+    #[test]
+    fn arguments_parse_skip_to() {
+        let args = Arguments::try_parse_from(["to-concentrate", "skip-to", "Relaxation"]).unwrap();
+        assert!(matches!(
+            ClientCommand::from(args.command),
+            ClientCommand::SkipTo(StageState::Relaxation)
+        ));
+
+        let err = Arguments::try_parse_from(["to-concentrate", "skip-to", "lunch"]).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("[possible values: preparation, concentration, relaxation]"));
+    }
+
+    #[test]
+    fn arguments_parse_set_duration() {
+        let parse = |duration| {
+            let args = ["to-concentrate", "set-duration", duration];
+            Arguments::try_parse_from(args).map(|args| ClientCommand::from(args.command))
+        };
+
+        assert_eq!(
+            parse("50m").unwrap(),
+            ClientCommand::SetDuration(Duration::from_secs(3000))
+        );
+        assert_eq!(
+            parse("90").unwrap(),
+            ClientCommand::SetDuration(Duration::from_secs(90))
+        );
+        assert!(parse("50x").is_err());
+    }
+
+    #[test]
+    fn arguments_parse_stage_round_trip() {
+        for stage in StageState::value_variants() {
+            for name in [stage.to_string(), stage.to_string().to_lowercase()] {
+                let args = ["to-concentrate", "query", "--poll-until", &name];
+                match ClientCommand::from(Arguments::try_parse_from(args).unwrap().command) {
+                    ClientCommand::Query(args) => assert_eq!(args.poll_until, Some(*stage)),
+                    _ => unreachable!(),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn arguments_parse_completions() {
+        let args = Arguments::try_parse_from(["to-concentrate", "completions", "bash"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Command::Completions { shell: Shell::Bash }
+        ));
+    }
 }
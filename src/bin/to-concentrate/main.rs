@@ -1,20 +1,54 @@
 mod cli;
 mod setup;
 
-use clap::Parser;
-use cli::Arguments;
+use clap::{CommandFactory, Parser};
+use cli::{Arguments, Command, ConfigCommand};
 use snafu::{prelude::*, Whatever};
+use to_concentrate::client::app::EXIT_SUCCESS;
 
 #[snafu::report]
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> Result<(), Whatever> {
     let args = Arguments::parse();
-    let client = setup::bootstrap(&args).whatever_context("Could not bootstrap application")?;
 
-    client
-        .run(args.command.into())
-        .await
-        .whatever_context("Client failed to run")?;
+    if let Command::Completions { shell } = args.command {
+        let mut command = Arguments::command();
+        clap_complete::generate(
+            shell,
+            &mut command,
+            env!("CARGO_BIN_NAME"),
+            &mut std::io::stdout(),
+        );
+        return Ok(());
+    }
+
+    if let Command::Config {
+        command: ConfigCommand::Paths,
+    } = args.command
+    {
+        let paths = setup::paths(&args).whatever_context("Could not resolve paths")?;
+        print!("{paths}");
+        return Ok(());
+    }
+
+    if let Command::Config {
+        command: ConfigCommand::Generate { output },
+    } = &args.command
+    {
+        return setup::generate(output.as_deref());
+    }
+
+    let client = setup::bootstrap(&args).whatever_context("Could not bootstrap application")?;
 
-    Ok(())
+    match client.run(args.command.into()).await {
+        Ok(EXIT_SUCCESS) => Ok(()),
+        Ok(code) => std::process::exit(code.into()),
+        Err(err) => match err.exit_code() {
+            Some(code) => {
+                eprintln!("{}", snafu::Report::from_error(err));
+                std::process::exit(code.into());
+            }
+            None => Err(err).whatever_context("Client failed to run"),
+        },
+    }
 }
@@ -1,46 +1,169 @@
 use std::cell::LazyCell;
-use std::path::PathBuf;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use snafu::{prelude::*, Whatever};
-use to_concentrate::client::app::connector::{Connector, UnixConnector};
-use to_concentrate::client::config;
+use to_concentrate::client::app::connector::{BackoffConnector, Connector, RetryConnector};
+use to_concentrate::client::app::connector::{TcpConnector, UnixConnector};
+use to_concentrate::client::app::ClientOptions;
+use to_concentrate::client::config::{self, ClientSection, Configuration};
 use to_concentrate::client::outbound::{
-    InitService, PauseService, QueryService, ResumeService, SkipService,
+    AdjustService, AutoPauseService, AutoResumeService, HealthService, InitService, MuteService,
+    PauseService, QueryService, ResetService, ResumeService, ScheduleService, SetDurationService,
+    SkipService, SkipToService, StatsService, StatusService, StopService, SubscribeService,
+    UnmuteService,
 };
 use to_concentrate::client::Client;
 use to_concentrate::domain::client::ApplicationCore;
 use to_concentrate::utils::xdg::{Xdg, XdgBaseKind};
+use tokio::time::Duration;
 use tracing::Level;
 
 use crate::cli::{Arguments, Command};
 
 const APP_NAME: &str = "to-concentrate";
 const DAEMON_NAME: &str = "to-concentrate-daemon";
+const RETRY_INTERVAL: Duration = Duration::from_millis(200);
+const BACKOFF_INITIAL: Duration = Duration::from_millis(50);
+/// How long `init` waits for the daemon to listen if no deadline is set.
+const INIT_DEADLINE: Duration = Duration::from_secs(5);
+/// How long a request may take if no timeout is set, on top of the time spent
+/// on connecting.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
 
-struct EnvironmentPath {
-    socket: PathBuf,
-    pid: PathBuf,
+/// Paths which the client uses to find its configuration and the daemon.
+pub struct EnvironmentPath {
+    pub config: PathBuf,
+    pub socket: PathBuf,
+    pub pid: PathBuf,
+}
+
+impl Display for EnvironmentPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        writeln!(f, "config = {}", self.config.display())?;
+        writeln!(f, "socket = {}", self.socket.display())?;
+        writeln!(f, "pid = {}", self.pid.display())
+    }
 }
 
 pub fn bootstrap(args: &Arguments) -> Result<Client, Whatever> {
-    let env_path = environment(args)?;
-    let core = core(args, env_path);
-    let client = Client::new(core);
+    let configuration = configuration(args)?;
+    let env_path = environment(args, &configuration)?;
+    let endpoint: Arc<dyn Connector> = match configuration.runtime.tcp_address {
+        Some(address) => Arc::new(TcpConnector::new(address)),
+        None => Arc::new(UnixConnector::new(&env_path.socket)),
+    };
+    let core = core(
+        args,
+        env_path,
+        endpoint,
+        retries(args, &configuration.client),
+        connect_deadline(args, &configuration.client),
+    );
+    let client = Client::with_options(core, options(args, &configuration.client));
     Ok(client)
 }
 
-fn environment(args: &Arguments) -> Result<EnvironmentPath, Whatever> {
-    let res = match &args.config {
-        Some(path) => config::load_with_path(path.clone()),
-        None => config::load_with_xdg(APP_NAME.to_owned()),
+/// Resolve the paths in effect for `args`, the same as [`bootstrap`] does.
+pub fn paths(args: &Arguments) -> Result<EnvironmentPath, Whatever> {
+    let configuration = configuration(args)?;
+    environment(args, &configuration)
+}
+
+/// Print the default configuration, or write it to `output`. An existing
+/// file is never overwritten.
+pub fn generate(output: Option<&Path>) -> Result<(), Whatever> {
+    let Some(output) = output else {
+        print!("{}", config::DEFAULT_CONTENT);
+        return Ok(());
     };
 
-    let configuration = res.whatever_context("Could not load configuration")?;
+    let mut file = File::options()
+        .write(true)
+        .create_new(true)
+        .open(output)
+        .with_whatever_context(|_| format!("Could not create {}", output.display()))?;
+    file.write_all(config::DEFAULT_CONTENT.as_bytes())
+        .with_whatever_context(|_| format!("Could not write to {}", output.display()))
+}
+
+fn configuration(args: &Arguments) -> Result<Configuration, Whatever> {
+    let res = match config_path(args) {
+        Some(path) => config::load_with_path(path),
+        None => config::load_with_xdg(APP_NAME.to_owned(), true),
+    };
 
+    res.whatever_context("Could not load configuration")
+}
+
+/// Resolve the path of the configuration file, preferring `--config` to the
+/// environment variable. Return `None` to use the XDG base directories.
+fn config_path(args: &Arguments) -> Option<PathBuf> {
+    config::config_path(args.config.as_deref(), |key| std::env::var(key).ok())
+}
+
+/// Resolve the [`ClientOptions`], preferring command line flags to the
+/// configuration file. A timeout of zero waits forever, and requests time out
+/// after [`REQUEST_TIMEOUT`] once connected if no timeout is set.
+fn options(args: &Arguments, section: &ClientSection) -> ClientOptions {
+    let request_timeout = match args.timeout.or(section.request_timeout) {
+        Some(0) => None,
+        Some(secs) => Some(Duration::from_secs(secs)),
+        None => {
+            let connecting = match args.command {
+                Command::Init { .. } => connect_deadline(args, section).unwrap_or(INIT_DEADLINE),
+                _ => connect_deadline(args, section).unwrap_or_default(),
+            };
+            Some(REQUEST_TIMEOUT + connecting)
+        }
+    };
+    let default_format = section.default_format.map(Into::into).unwrap_or_default();
+
+    ClientOptions {
+        request_timeout,
+        default_format,
+    }
+}
+
+/// Resolve the number of extra connection attempts, preferring command line
+/// flags to the configuration file.
+fn retries(args: &Arguments, section: &ClientSection) -> u32 {
+    args.retries.unwrap_or(section.retries)
+}
+
+/// Resolve how long to keep connecting to the daemon with backoff, preferring
+/// command line flags to the configuration file.
+fn connect_deadline(args: &Arguments, section: &ClientSection) -> Option<Duration> {
+    args.connect_deadline
+        .or(section.connect_deadline)
+        .map(Duration::from_secs)
+}
+
+/// Resolve the paths, preferring the command line flags and the
+/// configuration file to XDG base directories.
+fn environment(
+    args: &Arguments,
+    configuration: &Configuration,
+) -> Result<EnvironmentPath, Whatever> {
     let xdg = LazyCell::new(|| Xdg::new(APP_NAME));
 
-    let socket = match &configuration.runtime.socket {
+    let config = match config_path(args) {
+        Some(config) => config,
+        None => xdg
+            .as_ref()
+            .map_err(Clone::clone)
+            .and_then(|xdg| xdg.resolve(XdgBaseKind::Config, "config.toml"))
+            .whatever_context("Could not use XDG base directories")?,
+    };
+
+    let socket = match args
+        .socket
+        .as_ref()
+        .or(configuration.runtime.socket.as_ref())
+    {
         Some(socket) => socket.clone(),
         None => xdg
             .as_ref()
@@ -49,8 +172,8 @@ fn environment(args: &Arguments) -> Result<EnvironmentPath, Whatever> {
             .whatever_context("Could not use XDG base directories")?,
     };
 
-    let pid = match &configuration.runtime.pid {
-        Some(socket) => socket.clone(),
+    let pid = match args.pid.as_ref().or(configuration.runtime.pid.as_ref()) {
+        Some(pid) => pid.clone(),
         None => xdg
             .as_ref()
             .map_err(Clone::clone)
@@ -58,11 +181,21 @@ fn environment(args: &Arguments) -> Result<EnvironmentPath, Whatever> {
             .whatever_context("Could not use XDG base directories")?,
     };
 
-    let env_path = EnvironmentPath { socket, pid };
+    let env_path = EnvironmentPath {
+        config,
+        socket,
+        pid,
+    };
     Ok(env_path)
 }
 
-fn core(args: &Arguments, env_path: EnvironmentPath) -> Arc<ApplicationCore> {
+fn core(
+    args: &Arguments,
+    env_path: EnvironmentPath,
+    endpoint: Arc<dyn Connector>,
+    retries: u32,
+    deadline: Option<Duration>,
+) -> Arc<ApplicationCore> {
     let executable = match &args.command {
         Command::Init { executable, .. } => executable.clone(),
         _ => None,
@@ -73,21 +206,261 @@ fn core(args: &Arguments, env_path: EnvironmentPath) -> Arc<ApplicationCore> {
         _ => Level::INFO,
     };
 
-    let connector: Arc<dyn Connector> = Arc::new(UnixConnector::new(env_path.socket));
-
-    let init_port = Arc::new(InitService::new(
-        executable,
+    // Checking the status or health should not wait for a daemon which is
+    // down.
+    let status_port = Arc::new(StatusService::new(
+        Arc::clone(&endpoint),
         env_path.pid.to_path_buf(),
         DAEMON_NAME.to_owned(),
-        args.config.clone(),
-        verbosity,
+    ));
+    let health_port = Arc::new(HealthService::new(
+        Arc::clone(&endpoint),
+        args.session.clone(),
+    ));
+
+    // A daemon which has just been launched needs some time to listen.
+    let readiness = Arc::new(BackoffConnector::new(
+        Arc::clone(&endpoint),
+        BACKOFF_INITIAL,
+        deadline.unwrap_or(INIT_DEADLINE),
     ));
 
-    let pause_port = Arc::new(PauseService::new(Arc::clone(&connector)));
-    let resume_port = Arc::new(ResumeService::new(Arc::clone(&connector)));
-    let query_port = Arc::new(QueryService::new(Arc::clone(&connector)));
-    let skip_port = Arc::new(SkipService::new(Arc::clone(&connector)));
+    let endpoint: Arc<dyn Connector> = match deadline {
+        Some(deadline) => Arc::new(BackoffConnector::new(endpoint, BACKOFF_INITIAL, deadline)),
+        None => endpoint,
+    };
+    let connector: Arc<dyn Connector> =
+        Arc::new(RetryConnector::new(endpoint, retries, RETRY_INTERVAL));
 
-    let core = ApplicationCore::setup(init_port, pause_port, resume_port, query_port, skip_port);
+    let init_port = Arc::new(
+        InitService::new(
+            executable,
+            env_path.pid.to_path_buf(),
+            DAEMON_NAME.to_owned(),
+            config_path(args),
+            verbosity,
+        )
+        .with_paths(args.socket.clone(), args.pid.clone())
+        .with_readiness(readiness),
+    );
+
+    let pause_port = Arc::new(PauseService::new(
+        Arc::clone(&connector),
+        args.session.clone(),
+    ));
+    let resume_port = Arc::new(ResumeService::new(
+        Arc::clone(&connector),
+        args.session.clone(),
+    ));
+    let query_port = Arc::new(QueryService::new(
+        Arc::clone(&connector),
+        args.session.clone(),
+    ));
+    let schedule_port = Arc::new(ScheduleService::new(
+        Arc::clone(&connector),
+        args.session.clone(),
+    ));
+    let stats_port = Arc::new(StatsService::new(
+        Arc::clone(&connector),
+        args.session.clone(),
+    ));
+    let subscribe_port = Arc::new(SubscribeService::new(
+        Arc::clone(&connector),
+        args.session.clone(),
+    ));
+    let skip_port = Arc::new(SkipService::new(
+        Arc::clone(&connector),
+        args.session.clone(),
+    ));
+    let reset_port = Arc::new(ResetService::new(
+        Arc::clone(&connector),
+        args.session.clone(),
+    ));
+    let mute_port = Arc::new(MuteService::new(
+        Arc::clone(&connector),
+        args.session.clone(),
+    ));
+    let unmute_port = Arc::new(UnmuteService::new(
+        Arc::clone(&connector),
+        args.session.clone(),
+    ));
+    let adjust_port = Arc::new(AdjustService::new(
+        Arc::clone(&connector),
+        args.session.clone(),
+    ));
+    let set_duration_port = Arc::new(SetDurationService::new(
+        Arc::clone(&connector),
+        args.session.clone(),
+    ));
+    let skip_to_port = Arc::new(SkipToService::new(
+        Arc::clone(&connector),
+        args.session.clone(),
+    ));
+    let stop_port = Arc::new(StopService::new(Arc::clone(&connector)));
+    let auto_pause_port = Arc::new(AutoPauseService::new(
+        Arc::clone(&connector),
+        args.session.clone(),
+    ));
+    let auto_resume_port = Arc::new(AutoResumeService::new(
+        Arc::clone(&connector),
+        args.session.clone(),
+    ));
+
+    let core = ApplicationCore::setup(
+        init_port,
+        pause_port,
+        resume_port,
+        query_port,
+        schedule_port,
+        stats_port,
+        subscribe_port,
+        skip_port,
+        reset_port,
+        mute_port,
+        unmute_port,
+        adjust_port,
+        set_duration_port,
+        skip_to_port,
+        stop_port,
+        auto_pause_port,
+        auto_resume_port,
+        status_port,
+        health_port,
+    );
     Arc::new(core)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use clap::Parser;
+    use to_concentrate::client::app::QueryFormat;
+    use to_concentrate::client::config::FormatOption;
+
+    #[test]
+    fn options_from_configuration() {
+        let args = Arguments::parse_from(["to-concentrate", "pause"]);
+        let section = ClientSection {
+            request_timeout: Some(5),
+            retries: 2,
+            connect_deadline: Some(3),
+            default_format: Some(FormatOption::Toml),
+        };
+
+        let expected = ClientOptions {
+            request_timeout: Some(Duration::from_secs(5)),
+            default_format: QueryFormat::Toml,
+        };
+        assert_eq!(options(&args, &section), expected);
+        assert_eq!(retries(&args, &section), 2);
+        assert_eq!(
+            connect_deadline(&args, &section),
+            Some(Duration::from_secs(3))
+        );
+
+        let expected = ClientOptions {
+            request_timeout: Some(REQUEST_TIMEOUT),
+            default_format: QueryFormat::Plain,
+        };
+        assert_eq!(options(&args, &ClientSection::default()), expected);
+        assert_eq!(retries(&args, &ClientSection::default()), 0);
+        assert_eq!(connect_deadline(&args, &ClientSection::default()), None);
+    }
+
+    #[test]
+    fn environment_from_configuration() {
+        let args = Arguments::parse_from(["to-concentrate", "--config", "/etc/tc.toml", "pause"]);
+        let mut configuration = config::load_default().unwrap();
+        configuration.runtime.socket = Some(PathBuf::from("/tmp/tc.socket"));
+        configuration.runtime.pid = Some(PathBuf::from("/tmp/tc.pid"));
+
+        let paths = environment(&args, &configuration).unwrap();
+        assert_eq!(
+            paths.to_string(),
+            "config = /etc/tc.toml\nsocket = /tmp/tc.socket\npid = /tmp/tc.pid\n"
+        );
+    }
+
+    #[test]
+    fn environment_flags_take_precedence() {
+        let args = Arguments::parse_from([
+            "to-concentrate",
+            "--config",
+            "/etc/tc.toml",
+            "--socket",
+            "/run/tc.socket",
+            "--pid",
+            "/run/tc.pid",
+            "pause",
+        ]);
+        let mut configuration = config::load_default().unwrap();
+        configuration.runtime.socket = Some(PathBuf::from("/tmp/tc.socket"));
+        configuration.runtime.pid = Some(PathBuf::from("/tmp/tc.pid"));
+
+        let paths = environment(&args, &configuration).unwrap();
+        assert_eq!(paths.socket, PathBuf::from("/run/tc.socket"));
+        assert_eq!(paths.pid, PathBuf::from("/run/tc.pid"));
+    }
+
+    #[test]
+    fn options_flags_take_precedence() {
+        let args = Arguments::parse_from([
+            "to-concentrate",
+            "--timeout",
+            "1",
+            "--retries",
+            "0",
+            "--connect-deadline",
+            "1",
+            "pause",
+        ]);
+        let section = ClientSection {
+            request_timeout: Some(5),
+            retries: 2,
+            connect_deadline: Some(3),
+            default_format: None,
+        };
+
+        let options = options(&args, &section);
+        assert_eq!(options.request_timeout, Some(Duration::from_secs(1)));
+        assert_eq!(retries(&args, &section), 0);
+        assert_eq!(
+            connect_deadline(&args, &section),
+            Some(Duration::from_secs(1))
+        );
+    }
+
+    #[test]
+    fn options_default_timeout() {
+        let timeout = |args: &[&str], section: &ClientSection| {
+            let args = Arguments::parse_from(args);
+            options(&args, section).request_timeout
+        };
+        let section = ClientSection::default();
+
+        // Connecting is not counted against the default timeout.
+        assert_eq!(
+            timeout(
+                &["to-concentrate", "--connect-deadline", "3", "pause"],
+                &section
+            ),
+            Some(REQUEST_TIMEOUT + Duration::from_secs(3))
+        );
+        assert_eq!(
+            timeout(&["to-concentrate", "init"], &section),
+            Some(REQUEST_TIMEOUT + INIT_DEADLINE)
+        );
+
+        // Zero waits forever.
+        assert_eq!(
+            timeout(&["to-concentrate", "--timeout", "0", "pause"], &section),
+            None
+        );
+        let section = ClientSection {
+            request_timeout: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(timeout(&["to-concentrate", "pause"], &section), None);
+    }
+}
@@ -1,34 +1,139 @@
+use std::fmt::Write;
+use std::future::Future;
+use std::io::Write as IoWrite;
 use std::sync::Arc;
 
 use snafu::prelude::*;
+use tokio::sync::mpsc::Receiver;
+use tokio::time::Duration;
 
-use crate::client::app::command::{Command, QueryArguments};
-use crate::domain::client::outbound::{InitDaemonError, RequestDaemonError};
+use crate::client::app::command::WatchArguments;
+use crate::client::app::command::{Command, QueryArguments, QueryFormat, SkipArguments};
+use crate::domain::client::outbound::{
+    DaemonHealth, DaemonStatus, InitDaemonError, QueryResponse, RequestDaemonError,
+};
 use crate::domain::client::ApplicationCore;
+use crate::domain::entity::{AutoPauseTrigger, ScheduledStage, StageState, TimerStats};
+use crate::domain::entity::{TimerEvent, TimerStatus};
+use crate::utils::clock::format_clock;
+
+/// Exit code for success.
+pub const EXIT_SUCCESS: u8 = 0;
+/// Exit code of `query --poll-until` when the stage is not reached in time.
+pub const EXIT_POLL_TIMEOUT: u8 = 2;
+/// Exit code of `status`, or any other command, when the daemon is not
+/// running.
+pub const EXIT_NOT_RUNNING: u8 = 3;
+/// Exit code when the daemon replies with an unexpected response.
+pub const EXIT_BAD_RESPONSE: u8 = 4;
+/// Exit code of `query --exit-on-stage` when the timer is not running, which
+/// stays apart from the code 1 of any other failure.
+pub const EXIT_PAUSED: u8 = 5;
+/// Exit code of `query --exit-on-stage` in the `Preparation` stage.
+pub const EXIT_PREPARATION: u8 = 10;
+/// Exit code of `query --exit-on-stage` in the `Concentration` stage.
+pub const EXIT_CONCENTRATION: u8 = 11;
+/// Exit code of `query --exit-on-stage` in the `Relaxation` stage.
+pub const EXIT_RELAXATION: u8 = 12;
+
+/// Tunable behavior of a [`Client`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClientOptions {
+    /// Maximum time to wait for a request to the daemon. Wait forever if it is
+    /// `None`.
+    pub request_timeout: Option<Duration>,
+    /// Output format of `query` if the command does not specify one.
+    pub default_format: QueryFormat,
+}
+
+/// Interval between two queries of `query --poll-until`.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Number of cells in the progress bar of `watch`.
+const PROGRESS_WIDTH: usize = 20;
 
 /// Main business logic implementation in client side.
 pub struct Client {
     core: Arc<ApplicationCore>,
+    options: ClientOptions,
 }
 
 impl Client {
     /// Creates a new [`Client`].
     pub fn new(core: Arc<ApplicationCore>) -> Self {
-        Self { core }
+        Self::with_options(core, ClientOptions::default())
     }
 
-    /// Run specific function according to `command`.
+    /// Creates a new [`Client`] with custom [`ClientOptions`].
+    pub fn with_options(core: Arc<ApplicationCore>, options: ClientOptions) -> Self {
+        Self { core, options }
+    }
+
+    /// Run specific function according to `command`. Return the exit code
+    /// which the process should use.
     ///
     /// # Errors
     ///
     /// This function will return an error if any error occurs.
-    pub async fn run(&self, command: Command) -> Result<(), ClientError> {
+    pub async fn run(&self, command: Command) -> Result<u8, ClientError> {
         match command {
-            Command::Init => self.init().await,
-            Command::Pause => self.pause().await,
-            Command::Resume => self.resume().await,
+            // Launching a daemon is not a request, so it is never timed out.
+            Command::Init => self.init().await.map(|_| EXIT_SUCCESS),
+            // Polling lasts for long, so each query is timed out instead.
+            Command::Query(QueryArguments {
+                poll_until: Some(stage),
+                poll_timeout,
+                ..
+            }) => self.poll_until(stage, poll_timeout).await,
+            // Watching lasts until interrupted, so each query is timed out.
+            Command::Watch(args) => self.watch(args).await,
+            // Subscribing lasts until interrupted, so only the first
+            // subscription is timed out.
+            Command::Subscribe => self.subscribe().await,
+            command => self.with_timeout(self.request(command)).await,
+        }
+    }
+
+    /// Apply the request timeout to `request`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `request` fails or times out.
+    async fn with_timeout<T>(
+        &self,
+        request: impl Future<Output = Result<T, ClientError>>,
+    ) -> Result<T, ClientError> {
+        match self.options.request_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, request)
+                .await
+                .map_err(|_| TimeoutSnafu { timeout }.build())?,
+            None => request.await,
+        }
+    }
+
+    /// Send the request corresponding to `command` to daemon.
+    async fn request(&self, command: Command) -> Result<u8, ClientError> {
+        match command {
+            Command::Init => self.init().await.map(|_| EXIT_SUCCESS),
+            Command::Pause => self.pause().await.map(|_| EXIT_SUCCESS),
+            Command::Resume => self.resume().await.map(|_| EXIT_SUCCESS),
             Command::Query(args) => self.query(args).await,
-            Command::Skip => self.skip().await,
+            Command::Schedule => self.schedule().await.map(|_| EXIT_SUCCESS),
+            Command::Stats => self.stats().await.map(|_| EXIT_SUCCESS),
+            Command::Skip(args) => self.skip(args).await.map(|_| EXIT_SUCCESS),
+            Command::Reset => self.reset().await.map(|_| EXIT_SUCCESS),
+            Command::Mute => self.mute().await.map(|_| EXIT_SUCCESS),
+            Command::Unmute => self.unmute().await.map(|_| EXIT_SUCCESS),
+            Command::Adjust(delta_secs) => self.adjust(delta_secs).await.map(|_| EXIT_SUCCESS),
+            Command::SetDuration(total) => self.set_duration(total).await.map(|_| EXIT_SUCCESS),
+            Command::SkipTo(stage) => self.skip_to(stage).await.map(|_| EXIT_SUCCESS),
+            Command::Stop => self.stop().await.map(|_| EXIT_SUCCESS),
+            Command::AutoPause(trigger) => self.auto_pause(trigger).await.map(|_| EXIT_SUCCESS),
+            Command::AutoResume(trigger) => self.auto_resume(trigger).await.map(|_| EXIT_SUCCESS),
+            Command::Watch(_) => unreachable!("Watch should have been dispatched in `run`"),
+            Command::Subscribe => unreachable!("Subscribe should have been dispatched in `run`"),
+            Command::Status => Ok(self.status().await),
+            Command::Health => self.health().await.map(|_| EXIT_SUCCESS),
         }
     }
 
@@ -48,7 +153,7 @@ impl Client {
     /// This function will return an error if the client fails to receive a
     /// valid response.
     async fn pause(&self) -> Result<(), ClientError> {
-        self.core.pause.pause().await.context(RequestSnafu)
+        self.core.pause.pause().await.map_err(Into::into)
     }
 
     /// Send `resume` request to daemon.
@@ -58,7 +163,7 @@ impl Client {
     /// This function will return an error if the client fails to receive a
     /// valid response.
     async fn resume(&self) -> Result<(), ClientError> {
-        self.core.resume.resume().await.context(RequestSnafu)
+        self.core.resume.resume().await.map_err(Into::into)
     }
 
     /// Send `query` request to daemon.
@@ -67,66 +172,1249 @@ impl Client {
     ///
     /// This function will return an error if the client fails to receive a
     /// valid response.
-    async fn query(&self, args: QueryArguments) -> Result<(), ClientError> {
-        let response = self.core.query.query().await.context(RequestSnafu)?;
-        let enable_all =
-            !args.current && !args.stage && !args.total && !args.remaining && !args.past;
-        let mut outputs = Vec::new();
+    async fn query(&self, args: QueryArguments) -> Result<u8, ClientError> {
+        let response = self.core.query.query().await?;
+        let code = if args.exit_on_stage {
+            Self::stage_exit_code(&response)
+        } else {
+            EXIT_SUCCESS
+        };
+
+        if args.fraction {
+            println!("{}", Self::format_fraction(&response));
+            return Ok(code);
+        }
+
+        if let Some(template) = &args.template {
+            println!("{}", Self::format_template(template, &response)?);
+            return Ok(code);
+        }
+
+        let fields = Self::select_fields(&args, response);
+        match args.format.unwrap_or(self.options.default_format) {
+            QueryFormat::Plain => print!("{}", Self::format_plain(fields, args.seconds)),
+            QueryFormat::Toml => print!("{}", Self::format_toml(fields)),
+            QueryFormat::Json => println!("{}", Self::format_json(fields)),
+        }
+
+        Ok(code)
+    }
+
+    /// Pick the fields chosen by `args`, or all fields if none is chosen.
+    fn select_fields(args: &QueryArguments, response: QueryResponse) -> Vec<(&str, QueryField)> {
+        let enable_all = !args.current
+            && !args.stage
+            && !args.total
+            && !args.remaining
+            && !args.past
+            && !args.long_break
+            && !args.cycles
+            && !args.muted;
+        let mut fields = Vec::new();
 
         if enable_all || args.current {
-            outputs.push(("Current".to_owned(), response.current));
+            fields.push(("Current", QueryField::Text(response.current.to_string())));
         }
 
         if enable_all || args.stage {
-            outputs.push(("Stage".to_owned(), response.stage));
+            fields.push(("Stage", QueryField::Text(response.stage)));
         }
 
         if enable_all || args.total {
-            let value = format!("{}s", response.total.as_secs());
-            outputs.push(("Total".to_owned(), value));
+            fields.push(("Total", QueryField::Seconds(response.total.as_secs())));
         }
 
         if enable_all || args.remaining {
-            let value = format!("{}s", response.remaining.as_secs());
-            outputs.push(("Remaining".to_owned(), value));
+            fields.push((
+                "Remaining",
+                QueryField::Seconds(response.remaining.as_secs()),
+            ));
         }
 
         if enable_all || args.past {
-            let value = format!("{}s", response.past.as_secs());
-            outputs.push(("Past".to_owned(), value));
+            fields.push(("Past", QueryField::Seconds(response.past.as_secs())));
         }
 
-        let key_align = outputs
+        if enable_all || args.long_break {
+            fields.push(("Long break", QueryField::Flag(response.long_break)));
+        }
+
+        if enable_all || args.cycles {
+            fields.push((
+                "Completed cycles",
+                QueryField::Count(response.completed_cycles),
+            ));
+        }
+
+        if enable_all || args.muted {
+            fields.push(("Muted", QueryField::Flag(response.muted)));
+        }
+
+        fields
+    }
+
+    /// Format fields as aligned `key = value` lines. Durations are shown as
+    /// clocks, or as seconds if `seconds` is set.
+    fn format_plain(fields: Vec<(&str, QueryField)>, seconds: bool) -> String {
+        let key_align = fields
             .iter()
             .map(|(key, _)| key.len())
             .max()
             .unwrap_or_default();
 
-        for (mut key, value) in outputs {
-            let padding = " ".to_owned().repeat(key_align - key.len());
-            key.push_str(&padding);
-            println!("{key} = {value}");
+        let mut output = String::new();
+
+        for (key, value) in fields {
+            let value = match value {
+                QueryField::Text(text) => text,
+                QueryField::Seconds(secs) if seconds => format!("{secs}s"),
+                QueryField::Seconds(secs) => format_clock(Duration::from_secs(secs)),
+                QueryField::Count(count) => count.to_string(),
+                QueryField::Flag(flag) => if flag { "yes" } else { "no" }.to_owned(),
+            };
+            let _ = writeln!(output, "{key:key_align$} = {value}");
+        }
+
+        output
+    }
+
+    /// Format fields as a TOML document with lowercase keys.
+    fn format_toml(fields: Vec<(&str, QueryField)>) -> String {
+        let table: toml::Table = fields
+            .into_iter()
+            .map(|(key, value)| {
+                let value = match value {
+                    QueryField::Text(text) => toml::Value::String(text),
+                    QueryField::Seconds(seconds) => {
+                        toml::Value::Integer(seconds.try_into().unwrap_or(i64::MAX))
+                    }
+                    QueryField::Count(count) => toml::Value::Integer(count.into()),
+                    QueryField::Flag(flag) => toml::Value::Boolean(flag),
+                };
+                (key.to_lowercase().replace(' ', "_"), value)
+            })
+            .collect();
+
+        table.to_string()
+    }
+
+    /// Format fields as a JSON object with the same keys as TOML.
+    fn format_json(fields: Vec<(&str, QueryField)>) -> String {
+        let object: serde_json::Map<_, _> = fields
+            .into_iter()
+            .map(|(key, value)| {
+                let value = match value {
+                    QueryField::Text(text) => serde_json::Value::String(text),
+                    QueryField::Seconds(seconds) => serde_json::Value::from(seconds),
+                    QueryField::Count(count) => serde_json::Value::from(count),
+                    QueryField::Flag(flag) => serde_json::Value::Bool(flag),
+                };
+                (key.to_lowercase().replace(' ', "_"), value)
+            })
+            .collect();
+
+        serde_json::Value::Object(object).to_string()
+    }
+
+    /// Query the daemon repeatedly until the timer enters `stage`. Return
+    /// [`EXIT_POLL_TIMEOUT`] if it has not happened within `timeout`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any query fails, e.g. the daemon
+    /// exits while waiting.
+    async fn poll_until(
+        &self,
+        stage: StageState,
+        timeout: Option<Duration>,
+    ) -> Result<u8, ClientError> {
+        let poll = async {
+            let mut interval = tokio::time::interval(POLL_INTERVAL);
+
+            loop {
+                interval.tick().await;
+                let query = async { self.core.query.query().await.map_err(Into::into) };
+                let response = self.with_timeout(query).await?;
+
+                if response.kind == stage {
+                    return Ok(EXIT_SUCCESS);
+                }
+            }
+        };
+
+        match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, poll)
+                .await
+                .unwrap_or(Ok(EXIT_POLL_TIMEOUT)),
+            None => poll.await,
+        }
+    }
+
+    /// Redraw the timer's status on the same line of stdout every `interval`
+    /// until Ctrl-C is pressed.
+    ///
+    /// # Errors
+    ///
+    /// This function never fails. A failed query is shown in place of the
+    /// status and retried on the next refresh.
+    async fn watch(&self, args: WatchArguments) -> Result<u8, ClientError> {
+        let mut stdout = std::io::stdout();
+
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = self.watch_into(args.interval, &mut stdout) => {}
+        }
+
+        println!();
+        Ok(EXIT_SUCCESS)
+    }
+
+    /// Query the daemon every `interval` and redraw the status line in `out`.
+    async fn watch_into<W: IoWrite>(&self, interval: Duration, out: &mut W) {
+        let mut interval = tokio::time::interval(interval);
+
+        loop {
+            interval.tick().await;
+            let query = async { self.core.query.query().await.map_err(Into::into) };
+            let line = match self.with_timeout(query).await {
+                Ok(response) => Self::format_watch(&response),
+                Err(err) => format!("{err}, retrying..."),
+            };
+
+            // Clear the line before redrawing, since it may become shorter.
+            let _ = write!(out, "\r\x1b[2K{line}");
+            let _ = out.flush();
+        }
+    }
+
+    /// Print a line for every change of the timer's state until Ctrl-C is
+    /// pressed. The subscription is renewed whenever the daemon goes away.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the first subscription fails.
+    async fn subscribe(&self) -> Result<u8, ClientError> {
+        let subscribe = async { self.core.subscribe.subscribe().await.map_err(Into::into) };
+        let mut events = self.with_timeout(subscribe).await?;
+        let mut stdout = std::io::stdout();
+
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = Self::print_events(&mut events, &mut stdout) => {}
+        }
+
+        Ok(EXIT_SUCCESS)
+    }
+
+    /// Write a line in `out` for each of `events`.
+    async fn print_events<W: IoWrite>(events: &mut Receiver<TimerEvent>, out: &mut W) {
+        while let Some(event) = events.recv().await {
+            let _ = writeln!(out, "{}", Self::format_event(&event));
+            let _ = out.flush();
+        }
+    }
+
+    /// Format an event like `Running: Concentration, 25:00 left`.
+    fn format_event(event: &TimerEvent) -> String {
+        match event.current {
+            TimerStatus::Stopped => format!("Stopped: {}", event.stage),
+            current => format!(
+                "{current}: {}, {} left",
+                event.stage,
+                format_clock(event.remaining),
+            ),
+        }
+    }
+
+    /// Format the stage, remaining time and a progress bar in one line.
+    fn format_watch(response: &QueryResponse) -> String {
+        let total = response.total.as_secs_f64();
+        let ratio = if total > 0.0 {
+            (response.past.as_secs_f64() / total).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        let filled = (ratio * PROGRESS_WIDTH as f64).round() as usize;
+
+        let mut line = format!(
+            "{} [{}{}] {} left",
+            response.stage,
+            "#".repeat(filled),
+            "-".repeat(PROGRESS_WIDTH - filled),
+            format_clock(response.remaining),
+        );
+
+        if response.current == TimerStatus::Paused {
+            line.push_str(" (paused)");
+        }
+
+        line
+    }
+
+    /// Map the timer's state to an exit code for `query --exit-on-stage`.
+    fn stage_exit_code(response: &QueryResponse) -> u8 {
+        if response.current != TimerStatus::Running {
+            return EXIT_PAUSED;
+        }
+
+        match response.kind {
+            StageState::Preparation => EXIT_PREPARATION,
+            StageState::Concentration => EXIT_CONCENTRATION,
+            StageState::Relaxation => EXIT_RELAXATION,
+        }
+    }
+
+    /// Format the past and total duration as `past/total`, marking a paused
+    /// timer with a trailing `(paused)`.
+    fn format_fraction(response: &QueryResponse) -> String {
+        let past = format_clock(response.past);
+        let total = format_clock(response.total);
+
+        if response.current == TimerStatus::Paused {
+            format!("{past}/{total} (paused)")
+        } else {
+            format!("{past}/{total}")
+        }
+    }
+
+    /// Substitute the placeholders in `template` with the fields of
+    /// `response`. Durations are formatted as `MM:SS`, and `{{` and `}}`
+    /// stand for literal braces.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a placeholder is unknown or a
+    /// brace is unmatched.
+    fn format_template(template: &str, response: &QueryResponse) -> Result<String, ClientError> {
+        let mut output = String::new();
+        let mut rest = template;
+
+        while let Some(index) = rest.find(['{', '}']) {
+            output.push_str(&rest[..index]);
+            let brace = &rest[index..index + 1];
+            rest = &rest[index + 1..];
+
+            if let Some(after) = rest.strip_prefix(brace) {
+                output.push_str(brace);
+                rest = after;
+                continue;
+            }
+            ensure!(brace == "{", UnmatchedBraceSnafu { template });
+
+            let end = rest.find('}').context(UnmatchedBraceSnafu { template })?;
+            match &rest[..end] {
+                "current" => output.push_str(&response.current.to_string()),
+                "stage" => output.push_str(&response.stage),
+                "total" => output.push_str(&format_clock(response.total)),
+                "remaining" => output.push_str(&format_clock(response.remaining)),
+                "past" => output.push_str(&format_clock(response.past)),
+                name => return UnknownPlaceholderSnafu { name }.fail(),
+            }
+            rest = &rest[end + 1..];
         }
 
+        output.push_str(rest);
+        Ok(output)
+    }
+
+    /// Send `schedule` request to daemon and print the stages in one line.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the client fails to receive a
+    /// valid response.
+    async fn schedule(&self) -> Result<(), ClientError> {
+        let stages = self.core.schedule.schedule().await?;
+        println!("{}", Self::format_schedule(&stages));
+        Ok(())
+    }
+
+    /// Format the output of `schedule`, e.g. `Concentration 25:00 →
+    /// Relaxation 05:00`.
+    fn format_schedule(stages: &[ScheduledStage]) -> String {
+        stages
+            .iter()
+            .map(|stage| {
+                let duration = format_clock(stage.duration);
+                if stage.long_break {
+                    format!("{} (long break) {duration}", stage.name)
+                } else {
+                    format!("{} {duration}", stage.name)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" → ")
+    }
+
+    /// Send `stats` request to daemon and print the counters.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the client fails to receive a
+    /// valid response.
+    async fn stats(&self) -> Result<(), ClientError> {
+        let stats = self.core.stats.stats().await?;
+        print!("{}", Self::format_plain(Self::stats_fields(stats), false));
         Ok(())
     }
 
+    /// List the counters of `stats` as fields of the plain format.
+    fn stats_fields(stats: TimerStats) -> Vec<(&'static str, QueryField)> {
+        vec![
+            (
+                "Concentrations",
+                QueryField::Count(stats.completed_concentrations),
+            ),
+            (
+                "Concentration time",
+                QueryField::Seconds(stats.concentration_time.as_secs()),
+            ),
+            (
+                "Concentrations today",
+                QueryField::Count(stats.concentrations_today),
+            ),
+            (
+                "Concentration time today",
+                QueryField::Seconds(stats.concentration_time_today.as_secs()),
+            ),
+        ]
+    }
+
     /// Send `skip` request to daemon.
     ///
     /// # Errors
     ///
     /// This function will return an error if the client fails to receive a
     /// valid response.
-    async fn skip(&self) -> Result<(), ClientError> {
-        self.core.skip.skip().await.context(RequestSnafu)
+    async fn skip(&self, args: SkipArguments) -> Result<(), ClientError> {
+        self.core.skip.skip(args.notify).await.map_err(Into::into)
+    }
+
+    /// Send `reset` request to daemon.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the client fails to receive a
+    /// valid response.
+    async fn reset(&self) -> Result<(), ClientError> {
+        self.core.reset.reset().await.map_err(Into::into)
+    }
+
+    /// Send `mute` request to daemon.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the client fails to receive a
+    /// valid response.
+    async fn mute(&self) -> Result<(), ClientError> {
+        self.core.mute.mute().await.map_err(Into::into)
+    }
+
+    /// Send `unmute` request to daemon.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the client fails to receive a
+    /// valid response.
+    async fn unmute(&self) -> Result<(), ClientError> {
+        self.core.unmute.unmute().await.map_err(Into::into)
+    }
+
+    /// Send `adjust` request to daemon.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the client fails to receive a
+    /// valid response.
+    async fn adjust(&self, delta_secs: i64) -> Result<(), ClientError> {
+        self.core
+            .adjust
+            .adjust(delta_secs)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Send `set_duration` request to daemon.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the client fails to receive a
+    /// valid response.
+    async fn set_duration(&self, total: Duration) -> Result<(), ClientError> {
+        self.core
+            .set_duration
+            .set_duration(total)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Send `skip_to` request to daemon.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the client fails to receive a
+    /// valid response.
+    async fn skip_to(&self, stage: StageState) -> Result<(), ClientError> {
+        self.core.skip_to.skip_to(stage).await.map_err(Into::into)
+    }
+
+    /// Send `stop` request to daemon.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the client fails to receive a
+    /// valid response.
+    async fn stop(&self) -> Result<(), ClientError> {
+        self.core.stop.stop().await.map_err(Into::into)
+    }
+
+    /// Print whether the daemon is running and return [`EXIT_SUCCESS`] if it
+    /// is, or [`EXIT_NOT_RUNNING`] otherwise.
+    async fn status(&self) -> u8 {
+        let status = self.core.status.status().await;
+        println!("{}", Self::format_status(&status));
+        if status.reachable {
+            EXIT_SUCCESS
+        } else {
+            EXIT_NOT_RUNNING
+        }
+    }
+
+    /// Format the output of `status`. The daemon counts as running only if
+    /// it accepts connections.
+    fn format_status(status: &DaemonStatus) -> String {
+        match status {
+            DaemonStatus {
+                reachable: true,
+                pid: Some(pid),
+            } => format!("running (pid {pid})"),
+            DaemonStatus {
+                reachable: true,
+                pid: None,
+            } => "running".to_owned(),
+            DaemonStatus {
+                reachable: false, ..
+            } => "not running".to_owned(),
+        }
+    }
+
+    /// Send `health` request to daemon and print how long it has been up.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the client fails to receive a
+    /// valid response, e.g. when the timer does not respond in time.
+    async fn health(&self) -> Result<(), ClientError> {
+        let health = self.core.health.health().await?;
+        println!("{}", Self::format_health(&health));
+        Ok(())
+    }
+
+    /// Format the output of `health`.
+    fn format_health(health: &DaemonHealth) -> String {
+        format!(
+            "healthy, up for {}, timer {}",
+            format_clock(health.uptime),
+            health.status.to_string().to_lowercase(),
+        )
+    }
+
+    /// Send `auto_pause` request to daemon.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the client fails to receive a
+    /// valid response.
+    async fn auto_pause(&self, trigger: AutoPauseTrigger) -> Result<(), ClientError> {
+        self.core
+            .auto_pause
+            .auto_pause(trigger)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Send `auto_resume` request to daemon.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the client fails to receive a
+    /// valid response.
+    async fn auto_resume(&self, trigger: AutoPauseTrigger) -> Result<(), ClientError> {
+        self.core
+            .auto_resume
+            .auto_resume(trigger)
+            .await
+            .map_err(Into::into)
     }
 }
 
+/// A value of one field in `query`'s output.
+enum QueryField {
+    Text(String),
+    Seconds(u64),
+    Count(u32),
+    Flag(bool),
+}
+
 /// An error for client's operations.
 #[derive(Debug, Snafu)]
 pub enum ClientError {
     #[snafu(display("Could not initialize daemon"))]
     InitDaemon { source: InitDaemonError },
+    #[snafu(display("Daemon is not running at {endpoint}, start it with `to-concentrate init`"))]
+    Unavailable { endpoint: String },
     #[snafu(display("Could request daemon"))]
     Request { source: RequestDaemonError },
+    #[snafu(display("Daemon did not respond within {}s", timeout.as_secs_f64()))]
+    Timeout { timeout: Duration },
+    #[snafu(display("Unknown placeholder {{{name}}} in the query template"))]
+    UnknownPlaceholder { name: String },
+    #[snafu(display("Unmatched brace in the query template {template:?}"))]
+    UnmatchedBrace { template: String },
+}
+
+impl ClientError {
+    /// Returns the exit code dedicated to this error, or `None` if it is only
+    /// a generic failure.
+    pub fn exit_code(&self) -> Option<u8> {
+        match self {
+            Self::Unavailable { .. } => Some(EXIT_NOT_RUNNING),
+            Self::Request {
+                source: RequestDaemonError::BadResponse,
+            } => Some(EXIT_BAD_RESPONSE),
+            _ => None,
+        }
+    }
+}
+
+impl From<RequestDaemonError> for ClientError {
+    fn from(value: RequestDaemonError) -> Self {
+        match value {
+            RequestDaemonError::Unavailable { endpoint } => Self::Unavailable { endpoint },
+            source => Self::Request { source },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::domain::client::outbound::{
+        MockAdjustPort, MockAutoPausePort, MockAutoResumePort, MockHealthPort, MockInitPort,
+        MockMutePort, MockPausePort, MockQueryPort, MockResetPort, MockResumePort,
+        MockSchedulePort, MockSetDurationPort, MockSkipPort, MockSkipToPort, MockStatsPort,
+        MockStatusPort, MockStopPort, MockSubscribePort, MockUnmutePort,
+    };
+
+    #[tokio::test]
+    async fn client_run_error_unavailable() {
+        let mut pause = MockPausePort::new();
+        pause.expect_pause().returning(|| {
+            Box::pin(async {
+                Err(RequestDaemonError::Unavailable {
+                    endpoint: "daemon.socket".to_owned(),
+                })
+            })
+        });
+
+        let core = MockPorts {
+            pause,
+            ..Default::default()
+        }
+        .into_core();
+        let client = Client::new(Arc::new(core));
+
+        let err = client.run(Command::Pause).await.unwrap_err();
+        assert!(matches!(err, ClientError::Unavailable { .. }));
+        assert!(err.to_string().contains("to-concentrate init"));
+    }
+
+    #[test]
+    fn client_error_exit_code() {
+        let unavailable = RequestDaemonError::Unavailable {
+            endpoint: "daemon.socket".to_owned(),
+        };
+        assert_eq!(
+            ClientError::from(unavailable).exit_code(),
+            Some(EXIT_NOT_RUNNING)
+        );
+        assert_eq!(
+            ClientError::from(RequestDaemonError::BadResponse).exit_code(),
+            Some(EXIT_BAD_RESPONSE)
+        );
+
+        let timeout = ClientError::Timeout {
+            timeout: Duration::from_secs(1),
+        };
+        assert_eq!(timeout.exit_code(), None);
+    }
+
+    #[test]
+    fn client_format_fraction() {
+        let mut response = QueryResponse {
+            current: TimerStatus::Running,
+            stage: "Concentration".to_owned(),
+            kind: StageState::Concentration,
+            total: Duration::from_secs(1500),
+            remaining: Duration::from_secs(750),
+            past: Duration::from_secs(750),
+            long_break: false,
+            completed_cycles: 0,
+            muted: false,
+        };
+        assert_eq!(Client::format_fraction(&response), "12:30/25:00");
+
+        response.current = TimerStatus::Paused;
+        assert_eq!(Client::format_fraction(&response), "12:30/25:00 (paused)");
+    }
+
+    #[test]
+    fn client_stage_exit_code() {
+        let response = |current, kind| QueryResponse {
+            current,
+            stage: "Deep work".to_owned(),
+            kind,
+            total: Duration::from_secs(20),
+            remaining: Duration::from_secs(15),
+            past: Duration::from_secs(5),
+            long_break: false,
+            completed_cycles: 0,
+            muted: false,
+        };
+
+        let cases = [
+            (
+                response(TimerStatus::Running, StageState::Preparation),
+                EXIT_PREPARATION,
+            ),
+            (
+                response(TimerStatus::Running, StageState::Concentration),
+                EXIT_CONCENTRATION,
+            ),
+            (
+                response(TimerStatus::Running, StageState::Relaxation),
+                EXIT_RELAXATION,
+            ),
+            (
+                response(TimerStatus::Paused, StageState::Concentration),
+                EXIT_PAUSED,
+            ),
+        ];
+
+        for (response, code) in cases {
+            assert_eq!(Client::stage_exit_code(&response), code);
+        }
+    }
+
+    #[test]
+    fn client_exit_codes_distinct() {
+        // 1 is left to any other failure.
+        let codes = [
+            EXIT_SUCCESS,
+            1,
+            EXIT_POLL_TIMEOUT,
+            EXIT_NOT_RUNNING,
+            EXIT_BAD_RESPONSE,
+            EXIT_PAUSED,
+            EXIT_PREPARATION,
+            EXIT_CONCENTRATION,
+            EXIT_RELAXATION,
+        ];
+        for (i, code) in codes.iter().enumerate() {
+            assert!(!codes[i + 1..].contains(code), "{code} is used twice");
+        }
+    }
+
+    #[test]
+    fn client_format_status() {
+        let status = |pid, reachable| DaemonStatus { pid, reachable };
+        assert_eq!(
+            Client::format_status(&status(Some(42), true)),
+            "running (pid 42)"
+        );
+        assert_eq!(Client::format_status(&status(None, true)), "running");
+        assert_eq!(
+            Client::format_status(&status(Some(42), false)),
+            "not running"
+        );
+    }
+
+    #[tokio::test]
+    async fn client_run_status() {
+        for (reachable, code) in [(true, EXIT_SUCCESS), (false, EXIT_NOT_RUNNING)] {
+            let mut status = MockStatusPort::new();
+            status.expect_status().returning(move || {
+                Box::pin(async move {
+                    DaemonStatus {
+                        pid: None,
+                        reachable,
+                    }
+                })
+            });
+
+            let core = MockPorts {
+                status,
+                ..Default::default()
+            }
+            .into_core();
+            let client = Client::new(Arc::new(core));
+            assert_eq!(client.run(Command::Status).await.unwrap(), code);
+        }
+    }
+
+    #[test]
+    fn client_format_health() {
+        let health = DaemonHealth {
+            uptime: Duration::from_secs(3723),
+            status: TimerStatus::Paused,
+        };
+        assert_eq!(
+            Client::format_health(&health),
+            "healthy, up for 1:02:03, timer paused"
+        );
+    }
+
+    #[test]
+    fn client_format_stats() {
+        let stats = TimerStats {
+            completed_concentrations: 3,
+            concentration_time: Duration::from_secs(4500),
+            concentrations_today: 1,
+            concentration_time_today: Duration::from_secs(1500),
+        };
+        assert_eq!(
+            Client::format_plain(Client::stats_fields(stats), false),
+            "Concentrations           = 3\n\
+             Concentration time       = 1:15:00\n\
+             Concentrations today     = 1\n\
+             Concentration time today = 25:00\n",
+        );
+    }
+
+    #[test]
+    fn client_format_schedule() {
+        let stage = |name: &str, kind, secs, long_break| ScheduledStage {
+            name: name.to_owned(),
+            kind,
+            duration: Duration::from_secs(secs),
+            long_break,
+        };
+        let stages = [
+            stage("Concentration", StageState::Concentration, 885, false),
+            stage("Relaxation", StageState::Relaxation, 1800, true),
+        ];
+        assert_eq!(
+            Client::format_schedule(&stages),
+            "Concentration 14:45 → Relaxation (long break) 30:00"
+        );
+    }
+
+    #[test]
+    fn client_format_plain() {
+        let fields = || {
+            vec![
+                ("Stage", QueryField::Text("Concentration".to_owned())),
+                ("Remaining", QueryField::Seconds(885)),
+            ]
+        };
+
+        assert_eq!(
+            Client::format_plain(fields(), false),
+            "Stage     = Concentration\nRemaining = 14:45\n"
+        );
+        assert_eq!(
+            Client::format_plain(fields(), true),
+            "Stage     = Concentration\nRemaining = 885s\n"
+        );
+    }
+
+    #[test]
+    fn client_format_template() {
+        let response = QueryResponse {
+            current: TimerStatus::Running,
+            stage: "Concentration".to_owned(),
+            kind: StageState::Concentration,
+            total: Duration::from_secs(1500),
+            remaining: Duration::from_secs(885),
+            past: Duration::from_secs(615),
+            long_break: false,
+            completed_cycles: 0,
+            muted: false,
+        };
+        let format = |template| Client::format_template(template, &response);
+
+        assert_eq!(
+            format("{stage}: {remaining}").unwrap(),
+            "Concentration: 14:45"
+        );
+        assert_eq!(
+            format("{current} {past}/{total}").unwrap(),
+            "Running 10:15/25:00"
+        );
+        assert_eq!(format("{{{stage}}}").unwrap(), "{Concentration}");
+        assert!(matches!(
+            format("{stage} {elapsed}"),
+            Err(ClientError::UnknownPlaceholder { name }) if name == "elapsed",
+        ));
+        assert!(matches!(
+            format("{stage"),
+            Err(ClientError::UnmatchedBrace { .. }),
+        ));
+        assert!(matches!(
+            format("stage}"),
+            Err(ClientError::UnmatchedBrace { .. }),
+        ));
+    }
+
+    #[test]
+    fn client_format_toml() {
+        let response = QueryResponse {
+            current: TimerStatus::Running,
+            stage: "Concentration".to_owned(),
+            kind: StageState::Concentration,
+            total: Duration::from_secs(1500),
+            remaining: Duration::from_secs(900),
+            past: Duration::from_secs(600),
+            long_break: false,
+            completed_cycles: 0,
+            muted: false,
+        };
+        let args = QueryArguments {
+            current: false,
+            stage: true,
+            total: false,
+            remaining: true,
+            past: false,
+            long_break: true,
+            cycles: false,
+            muted: false,
+            fraction: false,
+            exit_on_stage: false,
+            seconds: false,
+            format: Some(QueryFormat::Toml),
+            template: None,
+            poll_until: None,
+            poll_timeout: None,
+        };
+
+        let output = Client::format_toml(Client::select_fields(&args, response));
+        let actual: toml::Table = toml::from_str(&output).unwrap();
+
+        let mut expected = toml::Table::new();
+        expected.insert("stage".to_owned(), "Concentration".into());
+        expected.insert("remaining".to_owned(), 900.into());
+        expected.insert("long_break".to_owned(), false.into());
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn client_format_json() {
+        let response = || QueryResponse {
+            current: TimerStatus::Paused,
+            stage: "Concentration".to_owned(),
+            kind: StageState::Concentration,
+            total: Duration::from_secs(1500),
+            remaining: Duration::from_secs(900),
+            past: Duration::from_secs(600),
+            long_break: true,
+            completed_cycles: 3,
+            muted: false,
+        };
+        let mut args = new_poll_arguments(StageState::Relaxation, None);
+        args.poll_until = None;
+        args.format = Some(QueryFormat::Json);
+
+        let output = Client::format_json(Client::select_fields(&args, response()));
+        let actual: serde_json::Value = serde_json::from_str(&output).unwrap();
+        let expected = serde_json::json!({
+            "current": "Paused",
+            "stage": "Concentration",
+            "total": 1500,
+            "remaining": 900,
+            "past": 600,
+            "long_break": true,
+            "completed_cycles": 3,
+            "muted": false,
+        });
+        assert_eq!(actual, expected);
+
+        args.remaining = true;
+        let output = Client::format_json(Client::select_fields(&args, response()));
+        assert_eq!(output, r#"{"remaining":900}"#);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn client_run_error_timeout() {
+        let mut pause = MockPausePort::new();
+        pause
+            .expect_pause()
+            .returning(|| Box::pin(std::future::pending()));
+
+        let core = MockPorts {
+            pause,
+            ..Default::default()
+        }
+        .into_core();
+        let options = ClientOptions {
+            request_timeout: Some(Duration::from_secs(3)),
+            ..Default::default()
+        };
+        let client = Client::with_options(Arc::new(core), options);
+
+        assert!(matches!(
+            client.run(Command::Pause).await,
+            Err(ClientError::Timeout { .. })
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn client_poll_until() {
+        let (query, stage) = new_shared_query();
+        let client = new_client_with_query(query);
+
+        // Skip a stage every few seconds in the background.
+        tokio::spawn(async move {
+            for _ in 0..2 {
+                tokio::time::sleep(Duration::from_secs(3)).await;
+                let mut stage = stage.lock().unwrap();
+                *stage = stage.map(next_stage);
+            }
+        });
+
+        let start = tokio::time::Instant::now();
+        let code = client
+            .run(Command::Query(new_poll_arguments(
+                StageState::Relaxation,
+                None,
+            )))
+            .await
+            .unwrap();
+        assert_eq!(code, EXIT_SUCCESS);
+        assert!(start.elapsed() >= Duration::from_secs(6));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn client_poll_until_timeout() {
+        let (query, _stage) = new_shared_query();
+        let client = new_client_with_query(query);
+
+        let args = new_poll_arguments(StageState::Relaxation, Some(Duration::from_secs(5)));
+        let code = client.run(Command::Query(args)).await.unwrap();
+        assert_eq!(code, EXIT_POLL_TIMEOUT);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn client_poll_until_error_unavailable() {
+        let (query, stage) = new_shared_query();
+        let client = new_client_with_query(query);
+
+        // The daemon exits while the client is waiting.
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(3)).await;
+            *stage.lock().unwrap() = None;
+        });
+
+        let args = new_poll_arguments(StageState::Relaxation, None);
+        assert!(matches!(
+            client.run(Command::Query(args)).await,
+            Err(ClientError::Unavailable { .. })
+        ));
+    }
+
+    #[test]
+    fn client_format_watch() {
+        let mut response = QueryResponse {
+            current: TimerStatus::Running,
+            stage: "Concentration".to_owned(),
+            kind: StageState::Concentration,
+            total: Duration::from_secs(1500),
+            remaining: Duration::from_secs(1125),
+            past: Duration::from_secs(375),
+            long_break: false,
+            completed_cycles: 0,
+            muted: false,
+        };
+        assert_eq!(
+            Client::format_watch(&response),
+            "Concentration [#####---------------] 18:45 left",
+        );
+
+        response.current = TimerStatus::Paused;
+        assert_eq!(
+            Client::format_watch(&response),
+            "Concentration [#####---------------] 18:45 left (paused)",
+        );
+    }
+
+    #[tokio::test]
+    async fn client_print_events() {
+        let (sender, mut events) = tokio::sync::mpsc::channel(4);
+        let event = |current| TimerEvent {
+            current,
+            stage: "Concentration".to_owned(),
+            kind: StageState::Concentration,
+            remaining: Duration::from_secs(1125),
+        };
+        for current in [
+            TimerStatus::Running,
+            TimerStatus::Paused,
+            TimerStatus::Stopped,
+        ] {
+            sender.send(event(current)).await.unwrap();
+        }
+        drop(sender);
+
+        let mut output = Vec::new();
+        Client::print_events(&mut events, &mut output).await;
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "Running: Concentration, 18:45 left\n\
+             Paused: Concentration, 18:45 left\n\
+             Stopped: Concentration\n",
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn client_watch_retry_unavailable() {
+        let (query, stage) = new_shared_query();
+        let client = new_client_with_query(query);
+
+        // The daemon is unavailable for a while in the middle of watching.
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(1500)).await;
+            let previous = stage.lock().unwrap().take();
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            *stage.lock().unwrap() = previous;
+        });
+
+        let mut output = Vec::new();
+        let watch = client.watch_into(Duration::from_secs(1), &mut output);
+        let res = tokio::time::timeout(Duration::from_millis(4500), watch).await;
+        assert!(res.is_err());
+
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<_> = output.split("\r\x1b[2K").skip(1).collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("Preparation"));
+        assert!(lines[2].contains("retrying"));
+        assert!(lines[3].contains("retrying"));
+        assert!(lines[4].starts_with("Preparation"));
+    }
+
+    /// Move on to the next stage as the daemon does with the default stages.
+    fn next_stage(stage: StageState) -> StageState {
+        match stage {
+            StageState::Preparation | StageState::Relaxation => StageState::Concentration,
+            StageState::Concentration => StageState::Relaxation,
+        }
+    }
+
+    /// Create a query port reporting a shared stage, or an unavailable daemon
+    /// if the stage is `None`.
+    fn new_shared_query() -> (MockQueryPort, Arc<std::sync::Mutex<Option<StageState>>>) {
+        let stage = Arc::new(std::sync::Mutex::new(Some(StageState::Preparation)));
+        let shared = Arc::clone(&stage);
+
+        let mut query = MockQueryPort::new();
+        query.expect_query().returning(move || {
+            let stage = *shared.lock().unwrap();
+            Box::pin(async move {
+                let stage = stage.ok_or(RequestDaemonError::Unavailable {
+                    endpoint: "daemon.socket".to_owned(),
+                })?;
+                Ok(QueryResponse {
+                    current: TimerStatus::Running,
+                    stage: stage.to_string(),
+                    kind: stage,
+                    total: Duration::from_secs(20),
+                    remaining: Duration::from_secs(15),
+                    past: Duration::from_secs(5),
+                    long_break: false,
+                    completed_cycles: 0,
+                    muted: false,
+                })
+            })
+        });
+
+        (query, stage)
+    }
+
+    #[derive(Default)]
+    struct MockPorts {
+        init: MockInitPort,
+        pause: MockPausePort,
+        resume: MockResumePort,
+        query: MockQueryPort,
+        schedule: MockSchedulePort,
+        stats: MockStatsPort,
+        subscribe: MockSubscribePort,
+        skip: MockSkipPort,
+        reset: MockResetPort,
+        mute: MockMutePort,
+        unmute: MockUnmutePort,
+        adjust: MockAdjustPort,
+        set_duration: MockSetDurationPort,
+        skip_to: MockSkipToPort,
+        stop: MockStopPort,
+        auto_pause: MockAutoPausePort,
+        auto_resume: MockAutoResumePort,
+        status: MockStatusPort,
+        health: MockHealthPort,
+    }
+
+    impl MockPorts {
+        fn into_core(self) -> ApplicationCore {
+            ApplicationCore::setup(
+                Arc::new(self.init),
+                Arc::new(self.pause),
+                Arc::new(self.resume),
+                Arc::new(self.query),
+                Arc::new(self.schedule),
+                Arc::new(self.stats),
+                Arc::new(self.subscribe),
+                Arc::new(self.skip),
+                Arc::new(self.reset),
+                Arc::new(self.mute),
+                Arc::new(self.unmute),
+                Arc::new(self.adjust),
+                Arc::new(self.set_duration),
+                Arc::new(self.skip_to),
+                Arc::new(self.stop),
+                Arc::new(self.auto_pause),
+                Arc::new(self.auto_resume),
+                Arc::new(self.status),
+                Arc::new(self.health),
+            )
+        }
+    }
+
+    fn new_client_with_query(query: MockQueryPort) -> Client {
+        let core = MockPorts {
+            query,
+            ..Default::default()
+        }
+        .into_core();
+        Client::new(Arc::new(core))
+    }
+
+    fn new_poll_arguments(stage: StageState, timeout: Option<Duration>) -> QueryArguments {
+        QueryArguments {
+            current: false,
+            stage: false,
+            total: false,
+            remaining: false,
+            past: false,
+            long_break: false,
+            cycles: false,
+            muted: false,
+            fraction: false,
+            exit_on_stage: false,
+            seconds: false,
+            format: None,
+            template: None,
+            poll_until: Some(stage),
+            poll_timeout: timeout,
+        }
+    }
 }
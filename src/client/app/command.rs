@@ -1,3 +1,8 @@
+use tokio::time::Duration;
+
+use crate::daemon::config::FormatOption;
+use crate::domain::entity::{AutoPauseTrigger, StageState};
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Command {
     /// Launch and initialize a daemon process
@@ -8,8 +13,38 @@ pub enum Command {
     Resume,
     /// Query the timer's status. Show all information if no flag is specified.
     Query(QueryArguments),
+    /// Show the current stage and those following it for one round
+    Schedule,
+    /// Show the completed concentrations and their total time
+    Stats,
     /// Skip the current stage
-    Skip,
+    Skip(SkipArguments),
+    /// Restart the timer from the initial stage
+    Reset,
+    /// Stop showing notifications while the timer goes on
+    Mute,
+    /// Show notifications again
+    Unmute,
+    /// Lengthen the current stage by the seconds, or shorten it if negative
+    Adjust(i64),
+    /// Make the current stage last the duration in all, for this stage only
+    SetDuration(Duration),
+    /// Start the next stage of the kind afresh
+    SkipTo(StageState),
+    /// Stop the timer and shut down the daemon
+    Stop,
+    /// Pause the timer on behalf of an external event
+    AutoPause(AutoPauseTrigger),
+    /// Resume the timer if it was paused by the same external event
+    AutoResume(AutoPauseTrigger),
+    /// Display the timer's status continuously until interrupted
+    Watch(WatchArguments),
+    /// Print every change of the timer's state until interrupted
+    Subscribe,
+    /// Report whether the daemon is running
+    Status,
+    /// Check whether the daemon and its timer respond
+    Health,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -24,4 +59,60 @@ pub struct QueryArguments {
     pub remaining: bool,
     /// Show the past duration in the current stage
     pub past: bool,
+    /// Show whether the current or upcoming break is a long one
+    pub long_break: bool,
+    /// Show the number of rounds of stages completed since the timer started
+    pub cycles: bool,
+    /// Show whether notifications are muted
+    pub muted: bool,
+    /// Show the past and total duration as a single `past/total` fraction
+    pub fraction: bool,
+    /// Exit with a code indicating the current stage
+    pub exit_on_stage: bool,
+    /// Show durations in the plain format as seconds like `885s` instead of
+    /// `14:45`
+    pub seconds: bool,
+    /// How to print the selected fields. Use the client's default format if
+    /// it is `None`.
+    pub format: Option<QueryFormat>,
+    /// Print one line following this template instead, which may contain
+    /// `{current}`, `{stage}`, `{total}`, `{remaining}` and `{past}`
+    pub template: Option<String>,
+    /// Block until the timer enters this stage instead of printing anything
+    pub poll_until: Option<StageState>,
+    /// Give up polling after this duration. Poll forever if it is `None`
+    pub poll_timeout: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QueryFormat {
+    /// Aligned `key = value` lines for humans
+    #[default]
+    Plain,
+    /// A TOML document with durations in seconds
+    Toml,
+    /// A JSON object with durations in seconds
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkipArguments {
+    /// Emit the skipped stage's notification
+    pub notify: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchArguments {
+    /// Time between two refreshes of the status
+    pub interval: Duration,
+}
+
+impl From<FormatOption> for QueryFormat {
+    fn from(value: FormatOption) -> Self {
+        match value {
+            FormatOption::Plain => Self::Plain,
+            FormatOption::Toml => Self::Toml,
+            FormatOption::Json => Self::Json,
+        }
+    }
 }
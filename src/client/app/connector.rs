@@ -1,13 +1,15 @@
-use std::io::{Error as IoError, ErrorKind as IoErrorKind};
+use std::io::{Error as IoError, ErrorKind as IoErrorKind, Result as IoResult};
+use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use snafu::prelude::*;
 use tokio::io::DuplexStream;
-use tokio::net::UnixStream;
+use tokio::net::{TcpStream, UnixStream};
 use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio::time::{Duration, Instant};
 
-use crate::utils::stream::Stream;
+use crate::utils::stream::{self, Stream};
 
 /// Abstract connector which returns a stream with a given endpoint.
 #[async_trait::async_trait]
@@ -26,6 +28,10 @@ pub trait Connector: Send + Sync + 'static {
 pub enum ConnectError {
     #[snafu(display("Endpoint {endpoint} is unavailable"))]
     Unavailable { endpoint: String },
+    #[snafu(display(
+        "Could not connect to abstract socket {endpoint}, which is only supported on Linux"
+    ))]
+    Unsupported { endpoint: String },
     #[snafu(display("Could not connect due to system error"))]
     System {
         #[snafu(source(from(IoError, Arc::new)))]
@@ -40,21 +46,53 @@ pub struct UnixConnector {
 }
 
 impl UnixConnector {
-    /// Create a [`UnixConnector`] which will connect to `path`.
+    /// Create a [`UnixConnector`] which will connect to `path`. A path
+    /// starting with `@` refers to an abstract socket on Linux.
     pub fn new<P: AsRef<Path>>(path: P) -> Self {
         Self {
             path: path.as_ref().to_path_buf(),
         }
     }
+
+    #[cfg(target_os = "linux")]
+    fn connect_abstract(name: &[u8]) -> IoResult<UnixStream> {
+        use std::os::linux::net::SocketAddrExt;
+        use std::os::unix::net::{SocketAddr, UnixStream as StdUnixStream};
+
+        // Connecting to a local socket does not block.
+        let address = SocketAddr::from_abstract_name(name)?;
+        let stream = StdUnixStream::connect_addr(&address)?;
+        stream.set_nonblocking(true)?;
+        UnixStream::from_std(stream)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn connect_abstract(_name: &[u8]) -> IoResult<UnixStream> {
+        Err(IoErrorKind::Unsupported.into())
+    }
 }
 
 #[async_trait::async_trait]
 impl Connector for UnixConnector {
     async fn connect(&self) -> Result<Box<dyn Stream>, ConnectError> {
-        match UnixStream::connect(self.path.as_path()).await {
+        let name = stream::abstract_name(&self.path);
+        let res = match name {
+            Some(name) => Self::connect_abstract(name),
+            None => UnixStream::connect(self.path.as_path()).await,
+        };
+
+        match res {
             Ok(stream) => Ok(Box::new(stream)),
-            Err(err) => match err.kind() {
-                IoErrorKind::NotFound => UnavailableSnafu {
+            Err(err) => match (err.kind(), name) {
+                // An abstract socket has no file, so one without a listener
+                // refuses connections instead.
+                (IoErrorKind::NotFound, _) | (IoErrorKind::ConnectionRefused, Some(_)) => {
+                    UnavailableSnafu {
+                        endpoint: self.path.to_string_lossy(),
+                    }
+                    .fail()
+                }
+                (IoErrorKind::Unsupported, _) => UnsupportedSnafu {
                     endpoint: self.path.to_string_lossy(),
                 }
                 .fail(),
@@ -64,6 +102,117 @@ impl Connector for UnixConnector {
     }
 }
 
+/// A [`Connector`] implementation which returns a [`TcpStream`].
+#[derive(Debug, Clone)]
+pub struct TcpConnector {
+    address: SocketAddr,
+}
+
+impl TcpConnector {
+    /// Create a [`TcpConnector`] which will connect to `address`.
+    pub fn new(address: SocketAddr) -> Self {
+        Self { address }
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for TcpConnector {
+    async fn connect(&self) -> Result<Box<dyn Stream>, ConnectError> {
+        match TcpStream::connect(self.address).await {
+            Ok(stream) => Ok(Box::new(stream)),
+            Err(err) => match err.kind() {
+                IoErrorKind::ConnectionRefused => UnavailableSnafu {
+                    endpoint: self.address.to_string(),
+                }
+                .fail(),
+                _ => Err(err).context(SystemSnafu),
+            },
+        }
+    }
+}
+
+/// A [`Connector`] wrapper which retries connecting to the inner connector's
+/// endpoint if it fails, e.g. when the daemon is still starting.
+pub struct RetryConnector {
+    inner: Arc<dyn Connector>,
+    retries: u32,
+    interval: Duration,
+}
+
+impl RetryConnector {
+    /// Create a [`RetryConnector`] which tries `inner` at most `retries + 1`
+    /// times, waiting `interval` between two attempts.
+    pub fn new(inner: Arc<dyn Connector>, retries: u32, interval: Duration) -> Self {
+        Self {
+            inner,
+            retries,
+            interval,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for RetryConnector {
+    async fn connect(&self) -> Result<Box<dyn Stream>, ConnectError> {
+        let mut remaining = self.retries;
+
+        loop {
+            match self.inner.connect().await {
+                Ok(stream) => return Ok(stream),
+                Err(err) if remaining == 0 => return Err(err),
+                Err(_) => {
+                    remaining -= 1;
+                    tokio::time::sleep(self.interval).await;
+                }
+            }
+        }
+    }
+}
+
+/// A [`Connector`] wrapper which keeps retrying the inner connector until
+/// `deadline` has passed since the first attempt. The wait between two
+/// attempts starts from `initial` and doubles every time, so that a daemon
+/// which has just been launched is found quickly without busy polling.
+pub struct BackoffConnector {
+    inner: Arc<dyn Connector>,
+    initial: Duration,
+    deadline: Duration,
+}
+
+impl BackoffConnector {
+    /// Create a [`BackoffConnector`] which tries `inner` for at most
+    /// `deadline`, waiting `initial` after the first failure.
+    pub fn new(inner: Arc<dyn Connector>, initial: Duration, deadline: Duration) -> Self {
+        Self {
+            inner,
+            initial,
+            deadline,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for BackoffConnector {
+    async fn connect(&self) -> Result<Box<dyn Stream>, ConnectError> {
+        let deadline = Instant::now() + self.deadline;
+        let mut interval = self.initial;
+
+        loop {
+            let err = match self.inner.connect().await {
+                Ok(stream) => return Ok(stream),
+                Err(err) => err,
+            };
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(err);
+            }
+            tokio::time::sleep(interval.min(remaining)).await;
+            interval = interval.saturating_mul(2);
+        }
+    }
+}
+
 /// A [`Connector`] implementation which returns a [`DuplexStream`]. This is
 /// typically used for testing purpose.
 #[derive(Debug, Clone)]
@@ -101,6 +250,8 @@ impl Connector for DuplexConnector {
 
 #[cfg(test)]
 mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
     use bytes::BytesMut;
     use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
@@ -115,6 +266,30 @@ mod tests {
         ))
     }
 
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn unix_connector_abstract_error_unavailable() {
+        let connector = UnixConnector::new("@to-concentrate-inexistent");
+        assert!(matches!(
+            connector.connect().await,
+            Err(ConnectError::Unavailable { .. })
+        ))
+    }
+
+    #[tokio::test]
+    async fn tcp_connector_error_unavailable() {
+        // Bind and release a port, so that nothing listens on it.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        drop(listener);
+
+        let connector = TcpConnector::new(address);
+        assert!(matches!(
+            connector.connect().await,
+            Err(ConnectError::Unavailable { .. })
+        ))
+    }
+
     #[tokio::test]
     async fn duplex_connector() {
         let (connector, mut peer) = DuplexConnector::new(256);
@@ -137,4 +312,100 @@ mod tests {
             Err(ConnectError::Unavailable { .. })
         ));
     }
+
+    #[tokio::test(start_paused = true)]
+    async fn retry_connector() {
+        let (connector, mut peer) = DuplexConnector::new(256);
+        let connector = FlakyConnector::new(connector, 2);
+        let attempts = Arc::clone(&connector.attempts);
+
+        let connector = RetryConnector::new(Arc::new(connector), 2, Duration::from_millis(100));
+        tokio::spawn(async move { while peer.recv().await.is_some() {} });
+
+        assert!(connector.connect().await.is_ok());
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retry_connector_error_unavailable() {
+        let (connector, _peer) = DuplexConnector::new(256);
+        let connector = FlakyConnector::new(connector, 3);
+        let attempts = Arc::clone(&connector.attempts);
+
+        let connector = RetryConnector::new(Arc::new(connector), 2, Duration::from_millis(100));
+
+        assert!(matches!(
+            connector.connect().await,
+            Err(ConnectError::Unavailable { .. })
+        ));
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn backoff_connector() {
+        let (connector, mut peer) = DuplexConnector::new(256);
+        let connector = FlakyConnector::new(connector, 3);
+        let attempts = Arc::clone(&connector.attempts);
+
+        let connector = BackoffConnector::new(
+            Arc::new(connector),
+            Duration::from_millis(100),
+            Duration::from_secs(1),
+        );
+        tokio::spawn(async move { while peer.recv().await.is_some() {} });
+
+        let start = Instant::now();
+        assert!(connector.connect().await.is_ok());
+        assert_eq!(Instant::now() - start, Duration::from_millis(700));
+        assert_eq!(attempts.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn backoff_connector_error_unavailable() {
+        let (connector, _peer) = DuplexConnector::new(256);
+        let connector = FlakyConnector::new(connector, usize::MAX);
+        let attempts = Arc::clone(&connector.attempts);
+
+        let connector = BackoffConnector::new(
+            Arc::new(connector),
+            Duration::from_millis(100),
+            Duration::from_secs(1),
+        );
+
+        let start = Instant::now();
+        assert!(matches!(
+            connector.connect().await,
+            Err(ConnectError::Unavailable { .. })
+        ));
+        // Attempts at 0, 100, 300, 700 and finally 1000 milliseconds.
+        assert_eq!(Instant::now() - start, Duration::from_secs(1));
+        assert_eq!(attempts.load(Ordering::SeqCst), 5);
+    }
+
+    /// A [`Connector`] which is unavailable for the first few attempts.
+    struct FlakyConnector {
+        inner: DuplexConnector,
+        failures: usize,
+        attempts: Arc<AtomicUsize>,
+    }
+
+    impl FlakyConnector {
+        fn new(inner: DuplexConnector, failures: usize) -> Self {
+            Self {
+                inner,
+                failures,
+                attempts: Arc::new(AtomicUsize::new(0)),
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Connector for FlakyConnector {
+        async fn connect(&self) -> Result<Box<dyn Stream>, ConnectError> {
+            if self.attempts.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return UnavailableSnafu { endpoint: "flaky" }.fail();
+            }
+            self.inner.connect().await
+        }
+    }
 }
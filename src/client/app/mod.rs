@@ -2,5 +2,9 @@ pub mod client;
 pub mod command;
 pub mod connector;
 
-pub use client::{Client, ClientError};
-pub use command::{Command, QueryArguments};
+pub use client::{Client, ClientError, ClientOptions};
+pub use client::{
+    EXIT_BAD_RESPONSE, EXIT_CONCENTRATION, EXIT_NOT_RUNNING, EXIT_PAUSED, EXIT_POLL_TIMEOUT,
+    EXIT_PREPARATION, EXIT_RELAXATION, EXIT_SUCCESS,
+};
+pub use command::{Command, QueryArguments, QueryFormat, SkipArguments, WatchArguments};
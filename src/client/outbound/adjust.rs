@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use crate::client::app::connector::Connector;
+use crate::client::outbound::connect::request;
+use crate::domain::client::outbound::BadResponseSnafu;
+use crate::domain::client::outbound::{AdjustPort, RequestDaemonError};
+use crate::protocol::{Request, Response};
+
+/// A [`AdjustPort`] implementation
+pub struct AdjustService {
+    connector: Arc<dyn Connector>,
+    session: Option<String>,
+}
+
+impl AdjustService {
+    /// Creates a new service sending requests to the given session, or the
+    /// daemon's default session if it is `None`.
+    pub fn new(connector: Arc<dyn Connector>, session: Option<String>) -> Self {
+        Self { connector, session }
+    }
+}
+
+#[async_trait::async_trait]
+impl AdjustPort for AdjustService {
+    async fn adjust(&self, delta_secs: i64) -> Result<(), RequestDaemonError> {
+        let response = request(
+            self.connector.as_ref(),
+            self.session.clone(),
+            Request::Adjust { delta_secs },
+        )
+        .await?;
+
+        match response {
+            Response::Adjust => Ok(()),
+            _ => BadResponseSnafu.fail(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::client::app::connector::DuplexConnector;
+    use crate::client::outbound::connect::accept;
+    use crate::protocol::Protocol;
+
+    #[tokio::test]
+    async fn adjust_service_run() {
+        let (connector, mut server) = DuplexConnector::new(256);
+
+        tokio::spawn(async move {
+            let server = server.recv().await.unwrap();
+            let mut connection = accept(server).await;
+            let _ = connection.receive().await.unwrap();
+            let response = Protocol::Response(Response::Adjust);
+            connection.send(response.into()).await.unwrap();
+        });
+
+        let service = AdjustService::new(Arc::new(connector), None);
+        assert!(service.adjust(-60).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn adjust_service_error_unavailable() {
+        let (connector, server) = DuplexConnector::new(256);
+        drop(server);
+
+        let service = AdjustService::new(Arc::new(connector), None);
+        assert!(matches!(
+            service.adjust(-60).await,
+            Err(RequestDaemonError::Unavailable { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn adjust_service_error_unknown() {
+        let (connector, mut server) = DuplexConnector::new(256);
+
+        tokio::spawn(async move {
+            let _ = server.recv().await.unwrap();
+        });
+
+        let service = AdjustService::new(Arc::new(connector), None);
+        assert!(matches!(
+            service.adjust(-60).await,
+            Err(RequestDaemonError::Unknown { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn adjust_service_error_bad_response() {
+        let (connector, mut server) = DuplexConnector::new(256);
+
+        tokio::spawn(async move {
+            let server = server.recv().await.unwrap();
+            let mut connection = accept(server).await;
+            let _ = connection.receive().await.unwrap();
+            let response = Protocol::Response(Response::Pause);
+            connection.send(response.into()).await.unwrap();
+        });
+
+        let service = AdjustService::new(Arc::new(connector), None);
+        assert!(matches!(
+            service.adjust(-60).await,
+            Err(RequestDaemonError::BadResponse)
+        ));
+    }
+}
@@ -0,0 +1,113 @@
+use std::sync::Arc;
+
+use crate::client::app::connector::Connector;
+use crate::client::outbound::connect::request;
+use crate::domain::client::outbound::BadResponseSnafu;
+use crate::domain::client::outbound::{AutoPausePort, RequestDaemonError};
+use crate::domain::entity::AutoPauseTrigger;
+use crate::protocol::{Request, Response};
+
+/// An [`AutoPausePort`] implementation
+pub struct AutoPauseService {
+    connector: Arc<dyn Connector>,
+    session: Option<String>,
+}
+
+impl AutoPauseService {
+    /// Creates a new service sending requests to the given session, or the
+    /// daemon's default session if it is `None`.
+    pub fn new(connector: Arc<dyn Connector>, session: Option<String>) -> Self {
+        Self { connector, session }
+    }
+}
+
+#[async_trait::async_trait]
+impl AutoPausePort for AutoPauseService {
+    async fn auto_pause(&self, trigger: AutoPauseTrigger) -> Result<(), RequestDaemonError> {
+        let response = request(
+            self.connector.as_ref(),
+            self.session.clone(),
+            Request::AutoPause { trigger },
+        )
+        .await?;
+
+        match response {
+            Response::AutoPause => Ok(()),
+            _ => BadResponseSnafu.fail(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::client::app::connector::DuplexConnector;
+    use crate::client::outbound::connect::accept;
+    use crate::protocol::Protocol;
+
+    #[tokio::test]
+    async fn auto_pause_service_run() {
+        let (connector, mut server) = DuplexConnector::new(256);
+
+        tokio::spawn(async move {
+            let server = server.recv().await.unwrap();
+            let mut connection = accept(server).await;
+            let _ = connection.receive().await.unwrap();
+            let response = Protocol::Response(Response::AutoPause);
+            connection.send(response.into()).await.unwrap();
+        });
+
+        let service = AutoPauseService::new(Arc::new(connector), None);
+        assert!(service
+            .auto_pause(AutoPauseTrigger::ScreenLock)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn auto_pause_service_error_unavailable() {
+        let (connector, server) = DuplexConnector::new(256);
+        drop(server);
+
+        let service = AutoPauseService::new(Arc::new(connector), None);
+        assert!(matches!(
+            service.auto_pause(AutoPauseTrigger::ScreenLock).await,
+            Err(RequestDaemonError::Unavailable { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn auto_pause_service_error_unknown() {
+        let (connector, mut server) = DuplexConnector::new(256);
+
+        tokio::spawn(async move {
+            let _ = server.recv().await.unwrap();
+        });
+
+        let service = AutoPauseService::new(Arc::new(connector), None);
+        assert!(matches!(
+            service.auto_pause(AutoPauseTrigger::ScreenLock).await,
+            Err(RequestDaemonError::Unknown { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn auto_pause_service_error_bad_response() {
+        let (connector, mut server) = DuplexConnector::new(256);
+
+        tokio::spawn(async move {
+            let server = server.recv().await.unwrap();
+            let mut connection = accept(server).await;
+            let _ = connection.receive().await.unwrap();
+            let response = Protocol::Response(Response::Skip);
+            connection.send(response.into()).await.unwrap();
+        });
+
+        let service = AutoPauseService::new(Arc::new(connector), None);
+        assert!(matches!(
+            service.auto_pause(AutoPauseTrigger::ScreenLock).await,
+            Err(RequestDaemonError::BadResponse)
+        ));
+    }
+}
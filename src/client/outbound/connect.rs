@@ -0,0 +1,159 @@
+use snafu::prelude::*;
+
+use crate::client::app::connector::{ConnectError, Connector};
+use crate::domain::client::outbound::{BadResponseSnafu, DaemonSnafu, RequestDaemonError};
+use crate::domain::client::outbound::{UnavailableSnafu, VersionMismatchSnafu};
+use crate::protocol::{Connection, Protocol, ProtocolVersion, RequestFrameError};
+use crate::protocol::{Request, Response};
+use crate::utils::stream::Stream;
+
+/// Connect to the daemon and exchange [`Protocol::Hello`] with it. Return a
+/// connection which is ready for a request.
+///
+/// # Errors
+///
+/// This function will return an error if the daemon is unavailable or speaks
+/// an incompatible protocol.
+pub async fn connect(
+    connector: &dyn Connector,
+) -> Result<Connection<Box<dyn Stream>>, RequestDaemonError> {
+    let stream = match connector.connect().await {
+        Ok(stream) => stream,
+        Err(err) => match err {
+            ConnectError::Unavailable { endpoint } => return UnavailableSnafu { endpoint }.fail(),
+            err => return Err(err).whatever_context("Could not connect"),
+        },
+    };
+
+    let mut connection = Connection::from(stream);
+    let hello = Protocol::Hello {
+        version: ProtocolVersion::CURRENT,
+    };
+
+    let response = match connection.request(hello).await {
+        Ok(response) => response,
+        Err(err @ RequestFrameError::Send { .. }) => {
+            return Err(err).whatever_context("Could not send handshake")
+        }
+        Err(err) => return Err(err).whatever_context("Could not receive handshake"),
+    };
+
+    match response {
+        Protocol::Hello { version } if ProtocolVersion::CURRENT.is_compatible(&version) => {
+            Ok(connection)
+        }
+        Protocol::Hello { version } => VersionMismatchSnafu {
+            client: ProtocolVersion::CURRENT.to_string(),
+            server: version.to_string(),
+        }
+        .fail(),
+        _ => BadResponseSnafu.fail(),
+    }
+}
+
+/// Connect to the daemon, send `request` to `session`, or the default session
+/// if it is `None`, and return the daemon's response.
+///
+/// # Errors
+///
+/// This function will return an error if the daemon is unavailable, the
+/// exchange fails or the daemon replies with something other than a
+/// response.
+pub async fn request(
+    connector: &dyn Connector,
+    session: Option<String>,
+    request: Request,
+) -> Result<Response, RequestDaemonError> {
+    let mut connection = connect(connector).await?;
+    let request = Protocol::Request { session, request };
+
+    let response = match connection.request(request).await {
+        Ok(response) => response,
+        Err(err @ RequestFrameError::Send { .. }) => {
+            return Err(err).whatever_context("Could not send request")
+        }
+        Err(err) => return Err(err).whatever_context("Could not receive response"),
+    };
+
+    match response {
+        Protocol::Response(Response::Error { message }) => DaemonSnafu { message }.fail(),
+        Protocol::Response(response) => Ok(response),
+        _ => BadResponseSnafu.fail(),
+    }
+}
+
+/// Accept a connection from [`connect`] on the daemon's side in tests.
+#[cfg(test)]
+pub async fn accept<S: Stream>(stream: S) -> Connection<S> {
+    let mut connection = Connection::from(stream);
+    let _ = connection.receive().await.unwrap();
+    let hello = Protocol::Hello {
+        version: ProtocolVersion::CURRENT,
+    };
+    connection.send(hello.into()).await.unwrap();
+    connection
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::client::app::connector::DuplexConnector;
+
+    #[tokio::test]
+    async fn connect_handshake() {
+        let (connector, mut server) = DuplexConnector::new(256);
+
+        tokio::spawn(async move {
+            let server = server.recv().await.unwrap();
+            let _ = accept(server).await;
+        });
+
+        assert!(connect(&connector).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn connect_error_version_mismatch() {
+        let (connector, mut server) = DuplexConnector::new(256);
+
+        tokio::spawn(async move {
+            let server = server.recv().await.unwrap();
+            let mut connection = Connection::from(server);
+            let _ = connection.receive().await.unwrap();
+            let version = ProtocolVersion {
+                major: ProtocolVersion::CURRENT.major + 1,
+                minor: 0,
+            };
+            connection
+                .send(Protocol::Hello { version }.into())
+                .await
+                .unwrap();
+        });
+
+        let err = connect(&connector).await.err().unwrap();
+        assert!(matches!(err, RequestDaemonError::VersionMismatch { .. }));
+        assert!(err.to_string().contains("restart the daemon"));
+    }
+
+    #[tokio::test]
+    async fn request_error_daemon() {
+        let (connector, mut server) = DuplexConnector::new(256);
+
+        tokio::spawn(async move {
+            let server = server.recv().await.unwrap();
+            let mut connection = accept(server).await;
+            let _ = connection.receive().await.unwrap();
+            let response = Protocol::Response(Response::Error {
+                message: "Could not open the requested session".to_owned(),
+            });
+            connection.send(response.into()).await.unwrap();
+        });
+
+        let err = request(&connector, None, Request::Pause).await.unwrap_err();
+        assert!(matches!(err, RequestDaemonError::Daemon { .. }));
+        assert_eq!(
+            err.to_string(),
+            "Daemon could not handle the request: Could not open the requested session"
+        );
+    }
+}
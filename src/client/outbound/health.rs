@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use crate::client::app::connector::Connector;
+use crate::client::outbound::connect::request;
+use crate::domain::client::outbound::BadResponseSnafu;
+use crate::domain::client::outbound::{DaemonHealth, HealthPort, RequestDaemonError};
+use crate::protocol::{Request, Response};
+
+/// A [`HealthPort`] implementation
+pub struct HealthService {
+    connector: Arc<dyn Connector>,
+    session: Option<String>,
+}
+
+impl HealthService {
+    /// Creates a new service sending requests to the given session, or the
+    /// daemon's default session if it is `None`.
+    pub fn new(connector: Arc<dyn Connector>, session: Option<String>) -> Self {
+        Self { connector, session }
+    }
+}
+
+#[async_trait::async_trait]
+impl HealthPort for HealthService {
+    async fn health(&self) -> Result<DaemonHealth, RequestDaemonError> {
+        let response = request(
+            self.connector.as_ref(),
+            self.session.clone(),
+            Request::Health,
+        )
+        .await?;
+
+        match response {
+            Response::Health { uptime, status } => Ok(DaemonHealth { uptime, status }),
+            _ => BadResponseSnafu.fail(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tokio::time::Duration;
+
+    use crate::client::app::connector::DuplexConnector;
+    use crate::client::outbound::connect::accept;
+    use crate::domain::entity::TimerStatus;
+    use crate::protocol::Protocol;
+
+    #[tokio::test]
+    async fn health_service_run() {
+        let (connector, mut server) = DuplexConnector::new(256);
+
+        tokio::spawn(async move {
+            let server = server.recv().await.unwrap();
+            let mut connection = accept(server).await;
+            let _ = connection.receive().await.unwrap();
+            let response = Protocol::Response(Response::Health {
+                uptime: Duration::from_secs(90),
+                status: TimerStatus::Paused,
+            });
+            connection.send(response.into()).await.unwrap();
+        });
+
+        let service = HealthService::new(Arc::new(connector), None);
+        assert_eq!(
+            service.health().await.unwrap(),
+            DaemonHealth {
+                uptime: Duration::from_secs(90),
+                status: TimerStatus::Paused,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn health_service_error_unavailable() {
+        let (connector, server) = DuplexConnector::new(256);
+        drop(server);
+
+        let service = HealthService::new(Arc::new(connector), None);
+        assert!(matches!(
+            service.health().await,
+            Err(RequestDaemonError::Unavailable { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn health_service_error_unknown() {
+        let (connector, mut server) = DuplexConnector::new(256);
+
+        tokio::spawn(async move {
+            let _ = server.recv().await.unwrap();
+        });
+
+        let service = HealthService::new(Arc::new(connector), None);
+        assert!(matches!(
+            service.health().await,
+            Err(RequestDaemonError::Unknown { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn health_service_error_bad_response() {
+        let (connector, mut server) = DuplexConnector::new(256);
+
+        tokio::spawn(async move {
+            let server = server.recv().await.unwrap();
+            let mut connection = accept(server).await;
+            let _ = connection.receive().await.unwrap();
+            let response = Protocol::Response(Response::Pause);
+            connection.send(response.into()).await.unwrap();
+        });
+
+        let service = HealthService::new(Arc::new(connector), None);
+        assert!(matches!(
+            service.health().await,
+            Err(RequestDaemonError::BadResponse)
+        ));
+    }
+}
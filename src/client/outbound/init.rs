@@ -1,20 +1,31 @@
+use std::io::{self, IsTerminal};
 use std::path::PathBuf;
 use std::process::Stdio;
+use std::sync::Arc;
 
 use sysinfo::System;
 use tokio::process::Command;
+use tokio::task::JoinHandle;
+use tokio::time::Duration;
 use tracing::Level;
 
+use crate::client::app::connector::Connector;
 use crate::daemon::runtime::{ControlProcessError, ProcessController};
 use crate::domain::client::outbound::{InitDaemonError, InitPort};
 
-#[derive(Debug)]
+/// The most bytes of the daemon's output shown in an error. The end is kept,
+/// since that is where the reason of a failure usually goes.
+const OUTPUT_LIMIT: usize = 4096;
+
 pub struct InitService {
     executable: Option<PathBuf>,
     pid_file: PathBuf,
     daemon_name: String,
     config: Option<PathBuf>,
     verbosity: Level,
+    socket: Option<PathBuf>,
+    pid: Option<PathBuf>,
+    readiness: Option<Arc<dyn Connector>>,
 }
 
 impl InitService {
@@ -31,9 +42,27 @@ impl InitService {
             daemon_name,
             config,
             verbosity,
+            socket: None,
+            pid: None,
+            readiness: None,
         }
     }
 
+    /// Pass the socket and PID file paths to the daemon, overriding those in
+    /// its configuration.
+    pub fn with_paths(mut self, socket: Option<PathBuf>, pid: Option<PathBuf>) -> Self {
+        self.socket = socket;
+        self.pid = pid;
+        self
+    }
+
+    /// Wait until `connector` reaches the daemon before reporting success,
+    /// so that a command right after initialization finds it listening.
+    pub fn with_readiness(mut self, connector: Arc<dyn Connector>) -> Self {
+        self.readiness = Some(connector);
+        self
+    }
+
     fn detect_instance(&self) -> Result<(), InitDaemonError> {
         let system = System::new_all();
         match ProcessController::detect_instance(&system, &self.pid_file, &self.daemon_name) {
@@ -51,6 +80,7 @@ impl InitService {
 impl InitPort for InitService {
     async fn init(&self) -> Result<(), InitDaemonError> {
         self.detect_instance()?;
+        let _spinner = Spinner::start("Launching daemon...");
 
         let mut command = match &self.executable {
             Some(executable) => Command::new(executable),
@@ -68,24 +98,173 @@ impl InitPort for InitService {
         if let Some(path) = self.config.as_ref() {
             command.arg("--config").arg(path);
         }
+        if let Some(path) = self.socket.as_ref() {
+            command.arg("--socket").arg(path);
+        }
+        if let Some(path) = self.pid.as_ref() {
+            command.arg("--pid").arg(path);
+        }
 
-        let mut child = command.spawn().map_err(|err| InitDaemonError::Unknown {
+        let child = command.spawn().map_err(|err| InitDaemonError::Unknown {
             message: "Could not spawn daemon process".to_owned(),
             source: Some(err.into()),
         })?;
 
-        let status = child.wait().await.map_err(|err| InitDaemonError::Unknown {
-            message: "Could not get daemon status".to_owned(),
-            source: Some(err.into()),
-        })?;
+        // The daemon no longer writes to the pipe once it daemonizes, so the
+        // output ends as soon as the foreground process exits.
+        let output = child
+            .wait_with_output()
+            .await
+            .map_err(|err| InitDaemonError::Unknown {
+                message: "Could not get daemon status".to_owned(),
+                source: Some(err.into()),
+            })?;
+        let stderr = String::from_utf8_lossy(&output.stderr);
 
-        if !status.success() {
-            Err(InitDaemonError::Unknown {
-                message: "Daemon exited abnormally".to_owned(),
+        if !output.status.success() {
+            return Err(InitDaemonError::Unknown {
+                message: with_output("Daemon exited abnormally", &stderr),
                 source: None,
-            })
-        } else {
-            Ok(())
+            });
+        }
+
+        if let Some(connector) = self.readiness.as_ref() {
+            connector
+                .connect()
+                .await
+                .map_err(|err| InitDaemonError::Unknown {
+                    message: with_output("Daemon did not start listening", &stderr),
+                    source: Some(err.into()),
+                })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Append the daemon's `output` to `message` if there is any, keeping only
+/// its last [`OUTPUT_LIMIT`] bytes.
+fn with_output(message: &str, output: &str) -> String {
+    let output = output.trim();
+    if output.is_empty() {
+        return message.to_owned();
+    }
+
+    if output.len() <= OUTPUT_LIMIT {
+        return format!("{message}, with output:\n{output}");
+    }
+
+    let mut start = output.len() - OUTPUT_LIMIT;
+    while !output.is_char_boundary(start) {
+        start += 1;
+    }
+    format!("{message}, with output:\n...{}", &output[start..])
+}
+
+/// A progress indicator drawn on stderr while the daemon is launching. Nothing
+/// is drawn if stderr is not a terminal.
+struct Spinner {
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Spinner {
+    const FRAMES: [char; 4] = ['|', '/', '-', '\\'];
+
+    /// Start drawing the spinner with a message until it is dropped.
+    fn start(message: &'static str) -> Self {
+        if !io::stderr().is_terminal() {
+            return Self { handle: None };
         }
+
+        let handle = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_millis(100));
+            for frame in Self::FRAMES.iter().cycle() {
+                interval.tick().await;
+                eprint!("\r{frame} {message}");
+            }
+        });
+
+        Self {
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Drop for Spinner {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            handle.abort();
+            // Clear the whole line drawn by the spinner.
+            eprint!("\r\x1b[2K");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs::{self, Permissions};
+    use std::os::unix::fs::PermissionsExt;
+
+    use assert_fs::fixture::ChildPath;
+    use assert_fs::prelude::*;
+    use assert_fs::TempDir;
+
+    use crate::client::app::connector::UnixConnector;
+
+    #[tokio::test]
+    async fn init_service_exit_abnormally() {
+        let tmp = TempDir::new().expect("Test environment should support temporary directories");
+        let daemon = new_daemon(&tmp, "echo 'Invalid configuration' >&2\nexit 1");
+
+        let service = new_service(&tmp, &daemon);
+        let err = service.init().await.unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("Daemon exited abnormally"));
+        assert!(message.contains("Invalid configuration"));
+    }
+
+    #[test]
+    fn with_output_truncate() {
+        assert_eq!(with_output("Failed", " \n"), "Failed");
+        assert_eq!(
+            with_output("Failed", "error\n"),
+            "Failed, with output:\nerror"
+        );
+
+        let output = format!("{}end", "é".repeat(OUTPUT_LIMIT));
+        let message = with_output("Failed", &output);
+        assert!(message.ends_with("end"));
+        assert!(message.len() <= "Failed, with output:\n...".len() + OUTPUT_LIMIT);
+    }
+
+    #[tokio::test]
+    async fn init_service_unreachable() {
+        let tmp = TempDir::new().expect("Test environment should support temporary directories");
+        let daemon = new_daemon(&tmp, "echo 'Could not bind socket' >&2");
+
+        let service = new_service(&tmp, &daemon)
+            .with_readiness(Arc::new(UnixConnector::new(tmp.child("daemon.socket"))));
+        let err = service.init().await.unwrap_err();
+        assert!(err.to_string().contains("Could not bind socket"));
+    }
+
+    /// Write a shell script as a fake daemon running `body`.
+    fn new_daemon(tmp: &TempDir, body: &str) -> ChildPath {
+        let daemon = tmp.child("daemon");
+        daemon.write_str(&format!("#!/bin/sh\n{body}\n")).unwrap();
+        fs::set_permissions(&daemon, Permissions::from_mode(0o755)).unwrap();
+        daemon
+    }
+
+    fn new_service(tmp: &TempDir, daemon: &ChildPath) -> InitService {
+        InitService::new(
+            Some(daemon.to_path_buf()),
+            tmp.child("daemon.pid").to_path_buf(),
+            "to-concentrate-test-daemon".to_owned(),
+            None,
+            Level::INFO,
+        )
     }
 }
@@ -1,11 +1,40 @@
+mod adjust;
+mod auto_pause;
+mod auto_resume;
+mod connect;
+mod health;
 mod init;
+mod mute;
 mod pause;
 mod query;
+mod reset;
 mod resume;
+mod schedule;
+mod set_duration;
 mod skip;
+mod skip_to;
+mod stats;
+mod status;
+mod stop;
+mod subscribe;
+mod unmute;
 
+pub use adjust::AdjustService;
+pub use auto_pause::AutoPauseService;
+pub use auto_resume::AutoResumeService;
+pub use health::HealthService;
 pub use init::InitService;
+pub use mute::MuteService;
 pub use pause::PauseService;
 pub use query::QueryService;
+pub use reset::ResetService;
 pub use resume::ResumeService;
+pub use schedule::ScheduleService;
+pub use set_duration::SetDurationService;
 pub use skip::SkipService;
+pub use skip_to::SkipToService;
+pub use stats::StatsService;
+pub use status::StatusService;
+pub use stop::StopService;
+pub use subscribe::SubscribeService;
+pub use unmute::UnmuteService;
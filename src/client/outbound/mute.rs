@@ -0,0 +1,105 @@
+use std::sync::Arc;
+
+use crate::client::app::connector::Connector;
+use crate::client::outbound::connect::request;
+use crate::domain::client::outbound::BadResponseSnafu;
+use crate::domain::client::outbound::{MutePort, RequestDaemonError};
+use crate::protocol::{Request, Response};
+
+/// A [`MutePort`] implementation
+pub struct MuteService {
+    connector: Arc<dyn Connector>,
+    session: Option<String>,
+}
+
+impl MuteService {
+    /// Creates a new service sending requests to the given session, or the
+    /// daemon's default session if it is `None`.
+    pub fn new(connector: Arc<dyn Connector>, session: Option<String>) -> Self {
+        Self { connector, session }
+    }
+}
+
+#[async_trait::async_trait]
+impl MutePort for MuteService {
+    async fn mute(&self) -> Result<(), RequestDaemonError> {
+        let response =
+            request(self.connector.as_ref(), self.session.clone(), Request::Mute).await?;
+
+        match response {
+            Response::Mute => Ok(()),
+            _ => BadResponseSnafu.fail(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::client::app::connector::DuplexConnector;
+    use crate::client::outbound::connect::accept;
+    use crate::protocol::Protocol;
+
+    #[tokio::test]
+    async fn mute_service_run() {
+        let (connector, mut server) = DuplexConnector::new(256);
+
+        tokio::spawn(async move {
+            let server = server.recv().await.unwrap();
+            let mut connection = accept(server).await;
+            let _ = connection.receive().await.unwrap();
+            let response = Protocol::Response(Response::Mute);
+            connection.send(response.into()).await.unwrap();
+        });
+
+        let service = MuteService::new(Arc::new(connector), None);
+        assert!(service.mute().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn mute_service_error_unavailable() {
+        let (connector, server) = DuplexConnector::new(256);
+        drop(server);
+
+        let service = MuteService::new(Arc::new(connector), None);
+        assert!(matches!(
+            service.mute().await,
+            Err(RequestDaemonError::Unavailable { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn mute_service_error_unknown() {
+        let (connector, mut server) = DuplexConnector::new(256);
+
+        tokio::spawn(async move {
+            let _ = server.recv().await.unwrap();
+        });
+
+        let service = MuteService::new(Arc::new(connector), None);
+        assert!(matches!(
+            service.mute().await,
+            Err(RequestDaemonError::Unknown { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn mute_service_error_bad_response() {
+        let (connector, mut server) = DuplexConnector::new(256);
+
+        tokio::spawn(async move {
+            let server = server.recv().await.unwrap();
+            let mut connection = accept(server).await;
+            let _ = connection.receive().await.unwrap();
+            let response = Protocol::Response(Response::Pause);
+            connection.send(response.into()).await.unwrap();
+        });
+
+        let service = MuteService::new(Arc::new(connector), None);
+        assert!(matches!(
+            service.mute().await,
+            Err(RequestDaemonError::BadResponse)
+        ));
+    }
+}
@@ -1,63 +1,56 @@
 use std::sync::Arc;
 
-use snafu::prelude::*;
-
-use crate::client::app::connector::{ConnectError, Connector};
-use crate::domain::client::outbound::{BadResponseSnafu, UnavailableSnafu};
+use crate::client::app::connector::Connector;
+use crate::client::outbound::connect::request;
+use crate::domain::client::outbound::BadResponseSnafu;
 use crate::domain::client::outbound::{QueryPort, QueryResponse, RequestDaemonError};
-use crate::protocol::{Connection, Protocol, Request, Response};
+use crate::protocol::{Request, Response};
 
 /// A [`QueryPort`] implementation
 pub struct QueryService {
     connector: Arc<dyn Connector>,
+    session: Option<String>,
 }
 
 impl QueryService {
-    pub fn new(connector: Arc<dyn Connector>) -> Self {
-        Self { connector }
+    /// Creates a new service sending requests to the given session, or the
+    /// daemon's default session if it is `None`.
+    pub fn new(connector: Arc<dyn Connector>, session: Option<String>) -> Self {
+        Self { connector, session }
     }
 }
 
 #[async_trait::async_trait]
 impl QueryPort for QueryService {
     async fn query(&self) -> Result<QueryResponse, RequestDaemonError> {
-        let stream = match self.connector.connect().await {
-            Ok(stream) => stream,
-            Err(err) => match err {
-                ConnectError::Unavailable { endpoint } => {
-                    return UnavailableSnafu { endpoint }.fail()
-                }
-                err => return Err(err).whatever_context("Could not connect"),
-            },
-        };
-
-        let mut connection = Connection::from(stream);
-        let request = Protocol::Request(Request::Query);
-
-        connection
-            .send(request.into())
-            .await
-            .whatever_context("Could not send request")?;
-
-        let response: Protocol = connection
-            .receive()
-            .await
-            .whatever_context("Could not receive response")?
-            .into();
+        let response = request(
+            self.connector.as_ref(),
+            self.session.clone(),
+            Request::Query,
+        )
+        .await?;
 
         match response {
-            Protocol::Response(Response::Query {
+            Response::Query {
                 current,
                 stage,
+                kind,
                 total,
                 remaining,
                 past,
-            }) => Ok(QueryResponse {
+                long_break,
+                completed_cycles,
+                muted,
+            } => Ok(QueryResponse {
                 current,
                 stage,
+                kind,
                 total,
                 remaining,
                 past,
+                long_break,
+                completed_cycles,
+                muted,
             }),
             _ => BadResponseSnafu.fail(),
         }
@@ -71,6 +64,9 @@ mod tests {
     use tokio::time::Duration;
 
     use crate::client::app::connector::DuplexConnector;
+    use crate::client::outbound::connect::accept;
+    use crate::domain::entity::{StageState, TimerStatus};
+    use crate::protocol::Protocol;
 
     #[tokio::test]
     async fn query_service_run() {
@@ -78,20 +74,25 @@ mod tests {
 
         tokio::spawn(async move {
             let server = server.recv().await.unwrap();
-            let mut connection = Connection::from(server);
+            let mut connection = accept(server).await;
+            let _ = connection.receive().await.unwrap();
             let response = Protocol::Response(Response::Query {
-                current: "Running".to_owned(),
+                current: TimerStatus::Running,
                 stage: "Preparation".to_owned(),
+                kind: StageState::Preparation,
                 total: Duration::from_secs(20),
                 remaining: Duration::from_secs(15),
                 past: Duration::from_secs(5),
+                long_break: false,
+                completed_cycles: 0,
+                muted: false,
             });
             connection.send(response.into()).await.unwrap();
         });
 
-        let service = QueryService::new(Arc::new(connector));
+        let service = QueryService::new(Arc::new(connector), None);
         let response = service.query().await.unwrap();
-        assert_eq!(response.current, "Running");
+        assert_eq!(response.current, TimerStatus::Running);
         assert_eq!(response.stage, "Preparation");
         assert_eq!(response.total.as_secs(), 20);
         assert_eq!(response.remaining.as_secs(), 15);
@@ -103,7 +104,7 @@ mod tests {
         let (connector, server) = DuplexConnector::new(256);
         drop(server);
 
-        let service = QueryService::new(Arc::new(connector));
+        let service = QueryService::new(Arc::new(connector), None);
         assert!(matches!(
             service.query().await,
             Err(RequestDaemonError::Unavailable { .. })
@@ -118,7 +119,7 @@ mod tests {
             let _ = server.recv().await.unwrap();
         });
 
-        let service = QueryService::new(Arc::new(connector));
+        let service = QueryService::new(Arc::new(connector), None);
         assert!(matches!(
             service.query().await,
             Err(RequestDaemonError::Unknown { .. })
@@ -131,12 +132,13 @@ mod tests {
 
         tokio::spawn(async move {
             let server = server.recv().await.unwrap();
-            let mut connection = Connection::from(server);
+            let mut connection = accept(server).await;
+            let _ = connection.receive().await.unwrap();
             let response = Protocol::Response(Response::Skip);
             connection.send(response.into()).await.unwrap();
         });
 
-        let service = QueryService::new(Arc::new(connector));
+        let service = QueryService::new(Arc::new(connector), None);
         assert!(matches!(
             service.query().await,
             Err(RequestDaemonError::BadResponse)
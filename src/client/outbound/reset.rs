@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use crate::client::app::connector::Connector;
+use crate::client::outbound::connect::request;
+use crate::domain::client::outbound::BadResponseSnafu;
+use crate::domain::client::outbound::{RequestDaemonError, ResetPort};
+use crate::protocol::{Request, Response};
+
+/// A [`ResetPort`] implementation
+pub struct ResetService {
+    connector: Arc<dyn Connector>,
+    session: Option<String>,
+}
+
+impl ResetService {
+    /// Creates a new service sending requests to the given session, or the
+    /// daemon's default session if it is `None`.
+    pub fn new(connector: Arc<dyn Connector>, session: Option<String>) -> Self {
+        Self { connector, session }
+    }
+}
+
+#[async_trait::async_trait]
+impl ResetPort for ResetService {
+    async fn reset(&self) -> Result<(), RequestDaemonError> {
+        let response = request(
+            self.connector.as_ref(),
+            self.session.clone(),
+            Request::Reset,
+        )
+        .await?;
+
+        match response {
+            Response::Reset => Ok(()),
+            _ => BadResponseSnafu.fail(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::client::app::connector::DuplexConnector;
+    use crate::client::outbound::connect::accept;
+    use crate::protocol::Protocol;
+
+    #[tokio::test]
+    async fn reset_service_run() {
+        let (connector, mut server) = DuplexConnector::new(256);
+
+        tokio::spawn(async move {
+            let server = server.recv().await.unwrap();
+            let mut connection = accept(server).await;
+            let _ = connection.receive().await.unwrap();
+            let response = Protocol::Response(Response::Reset);
+            connection.send(response.into()).await.unwrap();
+        });
+
+        let service = ResetService::new(Arc::new(connector), None);
+        assert!(service.reset().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn reset_service_error_unavailable() {
+        let (connector, server) = DuplexConnector::new(256);
+        drop(server);
+
+        let service = ResetService::new(Arc::new(connector), None);
+        assert!(matches!(
+            service.reset().await,
+            Err(RequestDaemonError::Unavailable { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn reset_service_error_unknown() {
+        let (connector, mut server) = DuplexConnector::new(256);
+
+        tokio::spawn(async move {
+            let _ = server.recv().await.unwrap();
+        });
+
+        let service = ResetService::new(Arc::new(connector), None);
+        assert!(matches!(
+            service.reset().await,
+            Err(RequestDaemonError::Unknown { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn reset_service_error_bad_response() {
+        let (connector, mut server) = DuplexConnector::new(256);
+
+        tokio::spawn(async move {
+            let server = server.recv().await.unwrap();
+            let mut connection = accept(server).await;
+            let _ = connection.receive().await.unwrap();
+            let response = Protocol::Response(Response::Pause);
+            connection.send(response.into()).await.unwrap();
+        });
+
+        let service = ResetService::new(Arc::new(connector), None);
+        assert!(matches!(
+            service.reset().await,
+            Err(RequestDaemonError::BadResponse)
+        ));
+    }
+}
@@ -1,52 +1,37 @@
 use std::sync::Arc;
 
-use snafu::prelude::*;
-
-use crate::client::app::connector::{ConnectError, Connector};
-use crate::domain::client::outbound::{BadResponseSnafu, UnavailableSnafu};
+use crate::client::app::connector::Connector;
+use crate::client::outbound::connect::request;
+use crate::domain::client::outbound::BadResponseSnafu;
 use crate::domain::client::outbound::{RequestDaemonError, ResumePort};
-use crate::protocol::{Connection, Protocol, Request, Response};
+use crate::protocol::{Request, Response};
 
 /// A [`ResumePort`] implementation
 pub struct ResumeService {
     connector: Arc<dyn Connector>,
+    session: Option<String>,
 }
 
 impl ResumeService {
-    pub fn new(connector: Arc<dyn Connector>) -> Self {
-        Self { connector }
+    /// Creates a new service sending requests to the given session, or the
+    /// daemon's default session if it is `None`.
+    pub fn new(connector: Arc<dyn Connector>, session: Option<String>) -> Self {
+        Self { connector, session }
     }
 }
 
 #[async_trait::async_trait]
 impl ResumePort for ResumeService {
     async fn resume(&self) -> Result<(), RequestDaemonError> {
-        let stream = match self.connector.connect().await {
-            Ok(stream) => stream,
-            Err(err) => match err {
-                ConnectError::Unavailable { endpoint } => {
-                    return UnavailableSnafu { endpoint }.fail()
-                }
-                err => return Err(err).whatever_context("Could not connect"),
-            },
-        };
-
-        let mut connection = Connection::from(stream);
-        let request = Protocol::Request(Request::Resume);
-
-        connection
-            .send(request.into())
-            .await
-            .whatever_context("Could not send request")?;
-
-        let response: Protocol = connection
-            .receive()
-            .await
-            .whatever_context("Could not receive response")?
-            .into();
+        let response = request(
+            self.connector.as_ref(),
+            self.session.clone(),
+            Request::Resume,
+        )
+        .await?;
 
         match response {
-            Protocol::Response(Response::Resume) => Ok(()),
+            Response::Resume => Ok(()),
             _ => BadResponseSnafu.fail(),
         }
     }
@@ -57,6 +42,8 @@ mod tests {
     use super::*;
 
     use crate::client::app::connector::DuplexConnector;
+    use crate::client::outbound::connect::accept;
+    use crate::protocol::Protocol;
 
     #[tokio::test]
     async fn resume_service_run() {
@@ -64,12 +51,13 @@ mod tests {
 
         tokio::spawn(async move {
             let server = server.recv().await.unwrap();
-            let mut connection = Connection::from(server);
+            let mut connection = accept(server).await;
+            let _ = connection.receive().await.unwrap();
             let response = Protocol::Response(Response::Resume);
             connection.send(response.into()).await.unwrap();
         });
 
-        let service = ResumeService::new(Arc::new(connector));
+        let service = ResumeService::new(Arc::new(connector), None);
         assert!(service.resume().await.is_ok());
     }
 
@@ -78,7 +66,7 @@ mod tests {
         let (connector, server) = DuplexConnector::new(256);
         drop(server);
 
-        let service = ResumeService::new(Arc::new(connector));
+        let service = ResumeService::new(Arc::new(connector), None);
         assert!(matches!(
             service.resume().await,
             Err(RequestDaemonError::Unavailable { .. })
@@ -93,7 +81,7 @@ mod tests {
             let _ = server.recv().await.unwrap();
         });
 
-        let service = ResumeService::new(Arc::new(connector));
+        let service = ResumeService::new(Arc::new(connector), None);
         assert!(matches!(
             service.resume().await,
             Err(RequestDaemonError::Unknown { .. })
@@ -106,12 +94,13 @@ mod tests {
 
         tokio::spawn(async move {
             let server = server.recv().await.unwrap();
-            let mut connection = Connection::from(server);
+            let mut connection = accept(server).await;
+            let _ = connection.receive().await.unwrap();
             let response = Protocol::Response(Response::Skip);
             connection.send(response.into()).await.unwrap();
         });
 
-        let service = ResumeService::new(Arc::new(connector));
+        let service = ResumeService::new(Arc::new(connector), None);
         assert!(matches!(
             service.resume().await,
             Err(RequestDaemonError::BadResponse)
@@ -0,0 +1,124 @@
+use std::sync::Arc;
+
+use crate::client::app::connector::Connector;
+use crate::client::outbound::connect::request;
+use crate::domain::client::outbound::BadResponseSnafu;
+use crate::domain::client::outbound::{RequestDaemonError, SchedulePort};
+use crate::domain::entity::ScheduledStage;
+use crate::protocol::{Request, Response};
+
+/// A [`SchedulePort`] implementation
+pub struct ScheduleService {
+    connector: Arc<dyn Connector>,
+    session: Option<String>,
+}
+
+impl ScheduleService {
+    /// Creates a new service sending requests to the given session, or the
+    /// daemon's default session if it is `None`.
+    pub fn new(connector: Arc<dyn Connector>, session: Option<String>) -> Self {
+        Self { connector, session }
+    }
+}
+
+#[async_trait::async_trait]
+impl SchedulePort for ScheduleService {
+    async fn schedule(&self) -> Result<Vec<ScheduledStage>, RequestDaemonError> {
+        let response = request(
+            self.connector.as_ref(),
+            self.session.clone(),
+            Request::Schedule,
+        )
+        .await?;
+
+        match response {
+            Response::Schedule { stages } => Ok(stages),
+            _ => BadResponseSnafu.fail(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tokio::time::Duration;
+
+    use crate::client::app::connector::DuplexConnector;
+    use crate::client::outbound::connect::accept;
+    use crate::domain::entity::StageState;
+    use crate::protocol::Protocol;
+
+    #[tokio::test]
+    async fn schedule_service_run() {
+        let (connector, mut server) = DuplexConnector::new(256);
+
+        tokio::spawn(async move {
+            let server = server.recv().await.unwrap();
+            let mut connection = accept(server).await;
+            let _ = connection.receive().await.unwrap();
+            let response = Protocol::Response(Response::Schedule {
+                stages: vec![new_stage()],
+            });
+            connection.send(response.into()).await.unwrap();
+        });
+
+        let service = ScheduleService::new(Arc::new(connector), None);
+        assert_eq!(service.schedule().await.unwrap(), [new_stage()]);
+    }
+
+    #[tokio::test]
+    async fn schedule_service_error_unavailable() {
+        let (connector, server) = DuplexConnector::new(256);
+        drop(server);
+
+        let service = ScheduleService::new(Arc::new(connector), None);
+        assert!(matches!(
+            service.schedule().await,
+            Err(RequestDaemonError::Unavailable { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn schedule_service_error_unknown() {
+        let (connector, mut server) = DuplexConnector::new(256);
+
+        tokio::spawn(async move {
+            let _ = server.recv().await.unwrap();
+        });
+
+        let service = ScheduleService::new(Arc::new(connector), None);
+        assert!(matches!(
+            service.schedule().await,
+            Err(RequestDaemonError::Unknown { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn schedule_service_error_bad_response() {
+        let (connector, mut server) = DuplexConnector::new(256);
+
+        tokio::spawn(async move {
+            let server = server.recv().await.unwrap();
+            let mut connection = accept(server).await;
+            let _ = connection.receive().await.unwrap();
+            let response = Protocol::Response(Response::Pause);
+            connection.send(response.into()).await.unwrap();
+        });
+
+        let service = ScheduleService::new(Arc::new(connector), None);
+        assert!(matches!(
+            service.schedule().await,
+            Err(RequestDaemonError::BadResponse)
+        ));
+    }
+
+    fn new_stage() -> ScheduledStage {
+        ScheduledStage {
+            name: "Relaxation".to_owned(),
+            kind: StageState::Relaxation,
+            duration: Duration::from_secs(300),
+            long_break: false,
+        }
+    }
+}
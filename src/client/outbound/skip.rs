@@ -1,52 +1,37 @@
 use std::sync::Arc;
 
-use snafu::prelude::*;
-
-use crate::client::app::connector::{ConnectError, Connector};
-use crate::domain::client::outbound::{BadResponseSnafu, UnavailableSnafu};
+use crate::client::app::connector::Connector;
+use crate::client::outbound::connect::request;
+use crate::domain::client::outbound::BadResponseSnafu;
 use crate::domain::client::outbound::{RequestDaemonError, SkipPort};
-use crate::protocol::{Connection, Protocol, Request, Response};
+use crate::protocol::{Request, Response};
 
 /// A [`SkipPort`] implementation
 pub struct SkipService {
     connector: Arc<dyn Connector>,
+    session: Option<String>,
 }
 
 impl SkipService {
-    pub fn new(connector: Arc<dyn Connector>) -> Self {
-        Self { connector }
+    /// Creates a new service sending requests to the given session, or the
+    /// daemon's default session if it is `None`.
+    pub fn new(connector: Arc<dyn Connector>, session: Option<String>) -> Self {
+        Self { connector, session }
     }
 }
 
 #[async_trait::async_trait]
 impl SkipPort for SkipService {
-    async fn skip(&self) -> Result<(), RequestDaemonError> {
-        let stream = match self.connector.connect().await {
-            Ok(stream) => stream,
-            Err(err) => match err {
-                ConnectError::Unavailable { endpoint } => {
-                    return UnavailableSnafu { endpoint }.fail()
-                }
-                err => return Err(err).whatever_context("Could not connect"),
-            },
-        };
-
-        let mut connection = Connection::from(stream);
-        let request = Protocol::Request(Request::Skip);
-
-        connection
-            .send(request.into())
-            .await
-            .whatever_context("Could not send request")?;
-
-        let response: Protocol = connection
-            .receive()
-            .await
-            .whatever_context("Could not receive response")?
-            .into();
+    async fn skip(&self, notify: bool) -> Result<(), RequestDaemonError> {
+        let response = request(
+            self.connector.as_ref(),
+            self.session.clone(),
+            Request::Skip { notify },
+        )
+        .await?;
 
         match response {
-            Protocol::Response(Response::Skip) => Ok(()),
+            Response::Skip => Ok(()),
             _ => BadResponseSnafu.fail(),
         }
     }
@@ -57,6 +42,8 @@ mod tests {
     use super::*;
 
     use crate::client::app::connector::DuplexConnector;
+    use crate::client::outbound::connect::accept;
+    use crate::protocol::Protocol;
 
     #[tokio::test]
     async fn skip_service_run() {
@@ -64,13 +51,14 @@ mod tests {
 
         tokio::spawn(async move {
             let server = server.recv().await.unwrap();
-            let mut connection = Connection::from(server);
+            let mut connection = accept(server).await;
+            let _ = connection.receive().await.unwrap();
             let response = Protocol::Response(Response::Skip);
             connection.send(response.into()).await.unwrap();
         });
 
-        let service = SkipService::new(Arc::new(connector));
-        assert!(service.skip().await.is_ok());
+        let service = SkipService::new(Arc::new(connector), None);
+        assert!(service.skip(false).await.is_ok());
     }
 
     #[tokio::test]
@@ -78,9 +66,9 @@ mod tests {
         let (connector, server) = DuplexConnector::new(256);
         drop(server);
 
-        let service = SkipService::new(Arc::new(connector));
+        let service = SkipService::new(Arc::new(connector), None);
         assert!(matches!(
-            service.skip().await,
+            service.skip(false).await,
             Err(RequestDaemonError::Unavailable { .. })
         ));
     }
@@ -93,9 +81,9 @@ mod tests {
             let _ = server.recv().await.unwrap();
         });
 
-        let service = SkipService::new(Arc::new(connector));
+        let service = SkipService::new(Arc::new(connector), None);
         assert!(matches!(
-            service.skip().await,
+            service.skip(false).await,
             Err(RequestDaemonError::Unknown { .. })
         ));
     }
@@ -106,14 +94,15 @@ mod tests {
 
         tokio::spawn(async move {
             let server = server.recv().await.unwrap();
-            let mut connection = Connection::from(server);
+            let mut connection = accept(server).await;
+            let _ = connection.receive().await.unwrap();
             let response = Protocol::Response(Response::Pause);
             connection.send(response.into()).await.unwrap();
         });
 
-        let service = SkipService::new(Arc::new(connector));
+        let service = SkipService::new(Arc::new(connector), None);
         assert!(matches!(
-            service.skip().await,
+            service.skip(false).await,
             Err(RequestDaemonError::BadResponse)
         ));
     }
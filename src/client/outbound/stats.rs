@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use crate::client::app::connector::Connector;
+use crate::client::outbound::connect::request;
+use crate::domain::client::outbound::BadResponseSnafu;
+use crate::domain::client::outbound::{RequestDaemonError, StatsPort};
+use crate::domain::entity::TimerStats;
+use crate::protocol::{Request, Response};
+
+/// A [`StatsPort`] implementation
+pub struct StatsService {
+    connector: Arc<dyn Connector>,
+    session: Option<String>,
+}
+
+impl StatsService {
+    /// Creates a new service sending requests to the given session, or the
+    /// daemon's default session if it is `None`.
+    pub fn new(connector: Arc<dyn Connector>, session: Option<String>) -> Self {
+        Self { connector, session }
+    }
+}
+
+#[async_trait::async_trait]
+impl StatsPort for StatsService {
+    async fn stats(&self) -> Result<TimerStats, RequestDaemonError> {
+        let response = request(
+            self.connector.as_ref(),
+            self.session.clone(),
+            Request::Stats,
+        )
+        .await?;
+
+        match response {
+            Response::Stats { stats } => Ok(stats),
+            _ => BadResponseSnafu.fail(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tokio::time::Duration;
+
+    use crate::client::app::connector::DuplexConnector;
+    use crate::client::outbound::connect::accept;
+    use crate::protocol::Protocol;
+
+    #[tokio::test]
+    async fn stats_service_run() {
+        let (connector, mut server) = DuplexConnector::new(256);
+
+        tokio::spawn(async move {
+            let server = server.recv().await.unwrap();
+            let mut connection = accept(server).await;
+            let _ = connection.receive().await.unwrap();
+            let response = Protocol::Response(Response::Stats { stats: new_stats() });
+            connection.send(response.into()).await.unwrap();
+        });
+
+        let service = StatsService::new(Arc::new(connector), None);
+        assert_eq!(service.stats().await.unwrap(), new_stats());
+    }
+
+    #[tokio::test]
+    async fn stats_service_error_unavailable() {
+        let (connector, server) = DuplexConnector::new(256);
+        drop(server);
+
+        let service = StatsService::new(Arc::new(connector), None);
+        assert!(matches!(
+            service.stats().await,
+            Err(RequestDaemonError::Unavailable { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn stats_service_error_unknown() {
+        let (connector, mut server) = DuplexConnector::new(256);
+
+        tokio::spawn(async move {
+            let _ = server.recv().await.unwrap();
+        });
+
+        let service = StatsService::new(Arc::new(connector), None);
+        assert!(matches!(
+            service.stats().await,
+            Err(RequestDaemonError::Unknown { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn stats_service_error_bad_response() {
+        let (connector, mut server) = DuplexConnector::new(256);
+
+        tokio::spawn(async move {
+            let server = server.recv().await.unwrap();
+            let mut connection = accept(server).await;
+            let _ = connection.receive().await.unwrap();
+            let response = Protocol::Response(Response::Pause);
+            connection.send(response.into()).await.unwrap();
+        });
+
+        let service = StatsService::new(Arc::new(connector), None);
+        assert!(matches!(
+            service.stats().await,
+            Err(RequestDaemonError::BadResponse)
+        ));
+    }
+
+    fn new_stats() -> TimerStats {
+        TimerStats {
+            completed_concentrations: 3,
+            concentration_time: Duration::from_secs(4500),
+            concentrations_today: 1,
+            concentration_time_today: Duration::from_secs(1500),
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use sysinfo::System;
+
+use crate::client::app::connector::Connector;
+use crate::client::outbound::connect::connect;
+use crate::daemon::runtime::ProcessController;
+use crate::domain::client::outbound::{DaemonStatus, StatusPort};
+use crate::protocol::Protocol;
+
+/// A [`StatusPort`] implementation which reads the daemon's PID file and
+/// shakes hands with it.
+pub struct StatusService {
+    connector: Arc<dyn Connector>,
+    pid_file: PathBuf,
+    daemon_name: String,
+}
+
+impl StatusService {
+    pub fn new(connector: Arc<dyn Connector>, pid_file: PathBuf, daemon_name: String) -> Self {
+        Self {
+            connector,
+            pid_file,
+            daemon_name,
+        }
+    }
+
+    /// Get the PID of the running daemon. An unreadable PID file is treated
+    /// as if no daemon were recorded.
+    fn pid(&self) -> Option<u32> {
+        let system = System::new_all();
+        ProcessController::running_instance(&system, &self.pid_file, &self.daemon_name)
+            .ok()
+            .flatten()
+            .map(|pid| pid.as_u32())
+    }
+
+    /// Check whether the daemon accepts a connection, which is closed right
+    /// after the handshake.
+    async fn reachable(&self) -> bool {
+        let Ok(mut connection) = connect(self.connector.as_ref()).await else {
+            return false;
+        };
+        let _ = connection.send(Protocol::Quit.into()).await;
+        true
+    }
+}
+
+#[async_trait::async_trait]
+impl StatusPort for StatusService {
+    async fn status(&self) -> DaemonStatus {
+        DaemonStatus {
+            pid: self.pid(),
+            reachable: self.reachable().await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use assert_fs::{prelude::*, TempDir};
+
+    use crate::client::app::connector::DuplexConnector;
+    use crate::client::outbound::connect::accept;
+
+    #[tokio::test]
+    async fn status_service_running() {
+        let tmp = TempDir::new().expect("Test environment should support temporary directories");
+        let pid_file = tmp.child("daemon.pid");
+        pid_file.write_str(&std::process::id().to_string()).unwrap();
+        let (connector, mut server) = DuplexConnector::new(256);
+
+        tokio::spawn(async move {
+            let server = server.recv().await.unwrap();
+            let mut connection = accept(server).await;
+            let quit: Protocol = connection.receive().await.unwrap().into();
+            assert_eq!(quit, Protocol::Quit);
+        });
+
+        // The test process itself plays the daemon.
+        let name = System::new_all()
+            .process(sysinfo::get_current_pid().unwrap())
+            .unwrap()
+            .name()
+            .to_string_lossy()
+            .into_owned();
+        let service = StatusService::new(Arc::new(connector), pid_file.to_path_buf(), name);
+        assert_eq!(
+            service.status().await,
+            DaemonStatus {
+                pid: Some(std::process::id()),
+                reachable: true,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn status_service_not_running() {
+        let tmp = TempDir::new().expect("Test environment should support temporary directories");
+        let (connector, server) = DuplexConnector::new(256);
+        drop(server);
+
+        let service = StatusService::new(
+            Arc::new(connector),
+            tmp.child("daemon.pid").to_path_buf(),
+            "to-concentrate-daemon".to_owned(),
+        );
+        assert_eq!(
+            service.status().await,
+            DaemonStatus {
+                pid: None,
+                reachable: false,
+            }
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use crate::client::app::connector::Connector;
+use crate::client::outbound::connect::request;
+use crate::domain::client::outbound::BadResponseSnafu;
+use crate::domain::client::outbound::{RequestDaemonError, StopPort};
+use crate::protocol::{Request, Response};
+
+/// A [`StopPort`] implementation
+pub struct StopService {
+    connector: Arc<dyn Connector>,
+}
+
+impl StopService {
+    /// Creates a new service. Stopping always applies to the whole daemon, so
+    /// no session is involved.
+    pub fn new(connector: Arc<dyn Connector>) -> Self {
+        Self { connector }
+    }
+}
+
+#[async_trait::async_trait]
+impl StopPort for StopService {
+    async fn stop(&self) -> Result<(), RequestDaemonError> {
+        let response = request(self.connector.as_ref(), None, Request::Stop).await?;
+
+        match response {
+            Response::Stop => Ok(()),
+            _ => BadResponseSnafu.fail(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::client::app::connector::DuplexConnector;
+    use crate::client::outbound::connect::accept;
+    use crate::protocol::Protocol;
+
+    #[tokio::test]
+    async fn stop_service_run() {
+        let (connector, mut server) = DuplexConnector::new(256);
+
+        tokio::spawn(async move {
+            let server = server.recv().await.unwrap();
+            let mut connection = accept(server).await;
+            let _ = connection.receive().await.unwrap();
+            let response = Protocol::Response(Response::Stop);
+            connection.send(response.into()).await.unwrap();
+        });
+
+        let service = StopService::new(Arc::new(connector));
+        assert!(service.stop().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn stop_service_error_unavailable() {
+        let (connector, server) = DuplexConnector::new(256);
+        drop(server);
+
+        let service = StopService::new(Arc::new(connector));
+        assert!(matches!(
+            service.stop().await,
+            Err(RequestDaemonError::Unavailable { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn stop_service_error_unknown() {
+        let (connector, mut server) = DuplexConnector::new(256);
+
+        tokio::spawn(async move {
+            let _ = server.recv().await.unwrap();
+        });
+
+        let service = StopService::new(Arc::new(connector));
+        assert!(matches!(
+            service.stop().await,
+            Err(RequestDaemonError::Unknown { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn stop_service_error_bad_response() {
+        let (connector, mut server) = DuplexConnector::new(256);
+
+        tokio::spawn(async move {
+            let server = server.recv().await.unwrap();
+            let mut connection = accept(server).await;
+            let _ = connection.receive().await.unwrap();
+            let response = Protocol::Response(Response::Pause);
+            connection.send(response.into()).await.unwrap();
+        });
+
+        let service = StopService::new(Arc::new(connector));
+        assert!(matches!(
+            service.stop().await,
+            Err(RequestDaemonError::BadResponse)
+        ));
+    }
+}
@@ -0,0 +1,206 @@
+use std::sync::Arc;
+
+use snafu::prelude::*;
+use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio::time::Duration;
+
+use crate::client::app::connector::Connector;
+use crate::client::outbound::connect::connect;
+use crate::domain::client::outbound::{BadResponseSnafu, DaemonSnafu};
+use crate::domain::client::outbound::{RequestDaemonError, SubscribePort};
+use crate::domain::entity::TimerEvent;
+use crate::protocol::{Connection, Protocol, Request, RequestFrameError, Response};
+use crate::utils::stream::Stream;
+
+/// Number of events which can wait for the client to handle them.
+const EVENT_CAPACITY: usize = 16;
+
+/// Time between two attempts to subscribe again after the connection is lost.
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A [`SubscribePort`] implementation, which subscribes again whenever the
+/// connection to the daemon is lost, e.g. because the daemon has restarted.
+pub struct SubscribeService {
+    connector: Arc<dyn Connector>,
+    session: Option<String>,
+}
+
+impl SubscribeService {
+    /// Creates a new service sending requests to the given session, or the
+    /// daemon's default session if it is `None`.
+    pub fn new(connector: Arc<dyn Connector>, session: Option<String>) -> Self {
+        Self { connector, session }
+    }
+}
+
+#[async_trait::async_trait]
+impl SubscribePort for SubscribeService {
+    async fn subscribe(&self) -> Result<Receiver<TimerEvent>, RequestDaemonError> {
+        let (connection, event) = open(self.connector.as_ref(), self.session.clone()).await?;
+        let (sender, receiver) = mpsc::channel(EVENT_CAPACITY);
+        let _ = sender.try_send(event);
+
+        tokio::spawn(forward(
+            Arc::clone(&self.connector),
+            self.session.clone(),
+            connection,
+            sender,
+        ));
+        Ok(receiver)
+    }
+}
+
+/// Connect to the daemon and subscribe to `session`. Return the connection
+/// along with the first event, which describes the current state.
+///
+/// # Errors
+///
+/// This function will return an error if the daemon is unavailable, the
+/// exchange fails or the daemon replies with something other than an event.
+async fn open(
+    connector: &dyn Connector,
+    session: Option<String>,
+) -> Result<(Connection<Box<dyn Stream>>, TimerEvent), RequestDaemonError> {
+    let mut connection = connect(connector).await?;
+    let request = Protocol::Request {
+        session,
+        request: Request::Subscribe,
+    };
+
+    let response = match connection.request(request).await {
+        Ok(response) => response,
+        Err(err @ RequestFrameError::Send { .. }) => {
+            return Err(err).whatever_context("Could not send request")
+        }
+        Err(err) => return Err(err).whatever_context("Could not receive response"),
+    };
+
+    match response {
+        Protocol::Response(Response::Event { event }) => Ok((connection, event)),
+        Protocol::Response(Response::Error { message }) => DaemonSnafu { message }.fail(),
+        _ => BadResponseSnafu.fail(),
+    }
+}
+
+/// Pass the events from `connection` to `sender` until it is closed. Once the
+/// subscription ends, try to subscribe again every [`RECONNECT_INTERVAL`].
+async fn forward(
+    connector: Arc<dyn Connector>,
+    session: Option<String>,
+    mut connection: Connection<Box<dyn Stream>>,
+    sender: Sender<TimerEvent>,
+) {
+    loop {
+        while let Ok(frame) = connection.receive().await {
+            let Protocol::Response(Response::Event { event }) = frame.into() else {
+                break;
+            };
+            if sender.send(event).await.is_err() {
+                return;
+            }
+        }
+
+        connection = loop {
+            tokio::select! {
+                () = sender.closed() => return,
+                () = tokio::time::sleep(RECONNECT_INTERVAL) => {}
+            }
+
+            // The error is not `Send`, so it is dropped before awaiting.
+            let Ok((connection, event)) = open(connector.as_ref(), session.clone()).await else {
+                continue;
+            };
+            if sender.send(event).await.is_err() {
+                return;
+            }
+            break connection;
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::client::app::connector::DuplexConnector;
+    use crate::client::outbound::connect::accept;
+    use crate::domain::entity::{StageState, TimerStatus};
+
+    #[tokio::test(start_paused = true)]
+    async fn subscribe_service_run() {
+        let (connector, mut server) = DuplexConnector::new(256);
+
+        tokio::spawn(async move {
+            // The second connection follows the end of the first one.
+            for current in [TimerStatus::Running, TimerStatus::Paused] {
+                let server = server.recv().await.unwrap();
+                let mut connection = accept(server).await;
+                let _ = connection.receive().await.unwrap();
+                let response = Protocol::Response(Response::Event {
+                    event: new_event(current),
+                });
+                connection.send(response.into()).await.unwrap();
+            }
+        });
+
+        let service = SubscribeService::new(Arc::new(connector), None);
+        let mut events = service.subscribe().await.unwrap();
+        assert_eq!(events.recv().await, Some(new_event(TimerStatus::Running)));
+        assert_eq!(events.recv().await, Some(new_event(TimerStatus::Paused)));
+    }
+
+    #[tokio::test]
+    async fn subscribe_service_error_unavailable() {
+        let (connector, server) = DuplexConnector::new(256);
+        drop(server);
+
+        let service = SubscribeService::new(Arc::new(connector), None);
+        assert!(matches!(
+            service.subscribe().await,
+            Err(RequestDaemonError::Unavailable { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn subscribe_service_error_unknown() {
+        let (connector, mut server) = DuplexConnector::new(256);
+
+        tokio::spawn(async move {
+            let _ = server.recv().await.unwrap();
+        });
+
+        let service = SubscribeService::new(Arc::new(connector), None);
+        assert!(matches!(
+            service.subscribe().await,
+            Err(RequestDaemonError::Unknown { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn subscribe_service_error_bad_response() {
+        let (connector, mut server) = DuplexConnector::new(256);
+
+        tokio::spawn(async move {
+            let server = server.recv().await.unwrap();
+            let mut connection = accept(server).await;
+            let _ = connection.receive().await.unwrap();
+            let response = Protocol::Response(Response::Pause);
+            connection.send(response.into()).await.unwrap();
+        });
+
+        let service = SubscribeService::new(Arc::new(connector), None);
+        assert!(matches!(
+            service.subscribe().await,
+            Err(RequestDaemonError::BadResponse)
+        ));
+    }
+
+    fn new_event(current: TimerStatus) -> TimerEvent {
+        TimerEvent {
+            current,
+            stage: "Concentration".to_owned(),
+            kind: StageState::Concentration,
+            remaining: Duration::from_secs(900),
+        }
+    }
+}
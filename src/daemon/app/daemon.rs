@@ -0,0 +1,143 @@
+use std::sync::Arc;
+
+use tokio::signal::unix::SignalKind;
+
+use crate::client::app::connector::DuplexConnector;
+use crate::daemon::config::Configuration;
+use crate::daemon::outbound::{LogNotifyService, NoIdleDetector, NoStageLog, NoStateStore};
+use crate::daemon::repository::{
+    BehaviorConfiguration, DurationConfiguration, NotificationConfiguration, StageConfiguration,
+};
+use crate::domain::daemon::{ApplicationCore, SetupApplicationCoreError};
+
+use super::listener::{ChannelListener, Listener};
+use super::server::{Server, ServerError};
+
+/// Buffer size of the in-process streams of an ephemeral [`Daemon`].
+const BUFFER_SIZE: usize = 4096;
+
+/// A daemon assembled from an in-memory [`Configuration`].
+pub struct Daemon {
+    server: Server,
+}
+
+impl Daemon {
+    /// Create an ephemeral [`Daemon`] together with a [`DuplexConnector`]
+    /// connected to it. It has no socket, no PID file, saves no progress,
+    /// never detects idleness and only logs notifications, so nothing is left
+    /// on the filesystem.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the application core fails to
+    /// setup, e.g. the configuration is invalid.
+    pub async fn ephemeral(
+        config: Configuration,
+    ) -> Result<(Self, DuplexConnector), SetupApplicationCoreError> {
+        let (connector, peer) = DuplexConnector::new(BUFFER_SIZE);
+        let listener = Box::new(ChannelListener::new(peer));
+        let daemon = Self::ephemeral_with_listener(config, listener).await?;
+        Ok((daemon, connector))
+    }
+
+    /// Create an ephemeral [`Daemon`] which accepts connections from
+    /// `listener`. Like [`Daemon::ephemeral`], it has no PID file and only
+    /// logs notifications.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the application core fails to
+    /// setup, e.g. the configuration is invalid.
+    pub async fn ephemeral_with_listener(
+        config: Configuration,
+        listener: Box<dyn Listener>,
+    ) -> Result<Self, SetupApplicationCoreError> {
+        let config = Arc::new(config);
+        let core = ApplicationCore::setup(
+            Arc::new(LogNotifyService::new()),
+            Arc::new(NoIdleDetector::new()),
+            Arc::new(NoStateStore::new()),
+            Arc::new(NoStageLog::new()),
+            Arc::new(DurationConfiguration::new(Arc::clone(&config))),
+            Arc::new(NotificationConfiguration::new(Arc::clone(&config))),
+            Arc::new(StageConfiguration::new(Arc::clone(&config))),
+            Arc::new(BehaviorConfiguration::new(config)),
+        )
+        .await?;
+
+        Ok(Self {
+            server: Server::new(listener, core),
+        })
+    }
+
+    /// Shut down gracefully on any of `signals`. See [`Server::with_signals`].
+    pub fn with_signals(self, signals: Vec<SignalKind>) -> Self {
+        Self {
+            server: self.server.with_signals(signals),
+        }
+    }
+
+    /// Serve requests until the daemon is stopped or a fatal error occurs.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the server fails.
+    pub async fn serve(&self) -> Result<(), ServerError> {
+        self.server.serve().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::client::outbound::{PauseService, QueryService, StopService};
+    use crate::daemon::config;
+    use crate::domain::client::outbound::{PausePort, QueryPort, StopPort};
+    use crate::domain::entity::{StageState, TimerStatus};
+
+    #[tokio::test]
+    async fn daemon_ephemeral() {
+        let config = config::load_default().unwrap();
+        let (daemon, connector) = Daemon::ephemeral(config).await.unwrap();
+
+        let connector = Arc::new(connector);
+        let query = QueryService::new(Arc::clone(&connector) as _, None);
+        let pause = PauseService::new(connector, None);
+
+        // The server is not `Send`, so drive it alongside the client instead.
+        let client = async {
+            let response = query.query().await.unwrap();
+            assert_eq!(response.current, TimerStatus::Running);
+            assert_eq!(response.stage, StageState::Preparation.to_string());
+
+            pause.pause().await.unwrap();
+            let response = query.query().await.unwrap();
+            assert_eq!(response.current, TimerStatus::Paused);
+        };
+
+        tokio::select! {
+            res = daemon.serve() => panic!("Server stopped unexpectedly: {res:?}"),
+            _ = client => {}
+        }
+    }
+
+    #[tokio::test]
+    async fn daemon_ephemeral_stop_while_paused() {
+        let config = config::load_default().unwrap();
+        let (daemon, connector) = Daemon::ephemeral(config).await.unwrap();
+
+        let connector = Arc::new(connector);
+        let pause = PauseService::new(Arc::clone(&connector) as _, None);
+        let stop = StopService::new(connector);
+
+        let client = async {
+            pause.pause().await.unwrap();
+            stop.stop().await.unwrap();
+        };
+
+        // The daemon exits cleanly once the stop request is answered.
+        let (served, _) = tokio::join!(daemon.serve(), client);
+        assert!(served.is_ok());
+    }
+}
@@ -1,14 +1,16 @@
 use std::fmt::Debug;
-use std::io::{Error as IoError, ErrorKind as IoErrorKind};
+use std::io::{Error as IoError, ErrorKind as IoErrorKind, Result as IoResult};
+use std::net::{SocketAddr, TcpListener as StdTcpListener};
 use std::path::Path;
 use std::sync::Arc;
 
 use snafu::prelude::*;
 use tokio::io::DuplexStream;
-use tokio::net::UnixListener as TokioUnixListener;
+use tokio::net::{TcpListener as TokioTcpListener, UnixListener as TokioUnixListener};
 use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio::sync::Mutex;
 
-use crate::utils::stream::Stream;
+use crate::utils::stream::{self, Stream};
 
 /// Abstract listener which listens on a given endpoint and accepts connections.
 #[async_trait::async_trait]
@@ -32,6 +34,10 @@ pub enum ListenError {
         #[snafu(source(from(IoError, Arc::new)))]
         source: Arc<IoError>,
     },
+    #[snafu(display(
+        "Could not bind to abstract socket {endpoint}, which is only supported on Linux"
+    ))]
+    Unsupported { endpoint: String },
     #[snafu(display("Could not bind: {message}"))]
     BindUnknown { message: String },
     #[snafu(display("Could not accept connection due to system error"))]
@@ -48,17 +54,29 @@ pub struct UnixListener {
 }
 
 impl UnixListener {
-    /// Create a [`UnixListener`] with a given UNIX socket path.
+    /// Create a [`UnixListener`] with a given UNIX socket path. A path
+    /// starting with `@` binds an abstract socket instead of a file on Linux.
     ///
     /// # Errors
     ///
-    /// This function will return an error if it fails to bind to the socket.
+    /// This function will return an error if it fails to bind to the socket,
+    /// or if an abstract socket is requested on other platforms.
     pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, ListenError> {
-        match TokioUnixListener::bind(path.as_ref()) {
+        let path = path.as_ref();
+        let res = match stream::abstract_name(path) {
+            Some(name) => Self::bind_abstract(name),
+            None => TokioUnixListener::bind(path),
+        };
+
+        match res {
             Ok(listener) => Ok(Self { listener }),
             Err(err) => match err.kind() {
                 IoErrorKind::AddrInUse => InUseSnafu {
-                    endpoint: path.as_ref().to_string_lossy(),
+                    endpoint: path.to_string_lossy(),
+                }
+                .fail(),
+                IoErrorKind::Unsupported => UnsupportedSnafu {
+                    endpoint: path.to_string_lossy(),
                 }
                 .fail(),
                 _ => Err(err).context(BindSystemSnafu),
@@ -66,6 +84,22 @@ impl UnixListener {
         }
     }
 
+    #[cfg(target_os = "linux")]
+    fn bind_abstract(name: &[u8]) -> IoResult<TokioUnixListener> {
+        use std::os::linux::net::SocketAddrExt;
+        use std::os::unix::net::{SocketAddr, UnixListener as StdUnixListener};
+
+        let address = SocketAddr::from_abstract_name(name)?;
+        let listener = StdUnixListener::bind_addr(&address)?;
+        listener.set_nonblocking(true)?;
+        TokioUnixListener::from_std(listener)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn bind_abstract(_name: &[u8]) -> IoResult<TokioUnixListener> {
+        Err(IoErrorKind::Unsupported.into())
+    }
+
     /// Return its internal listener.
     pub fn into_inner(self) -> TokioUnixListener {
         self.listener
@@ -89,6 +123,61 @@ impl From<TokioUnixListener> for UnixListener {
     }
 }
 
+/// A [`Listener`] implementation which returns [`TcpStream`]s, so that a
+/// client on another host or container can reach the daemon.
+///
+/// [`TcpStream`]: tokio::net::TcpStream
+#[derive(Debug)]
+pub struct TcpListener {
+    listener: TokioTcpListener,
+}
+
+impl TcpListener {
+    /// Create a [`TcpListener`] bound to a given address.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it fails to bind to the address.
+    pub fn new(address: SocketAddr) -> Result<Self, ListenError> {
+        let listener = match StdTcpListener::bind(address) {
+            Ok(listener) => listener,
+            Err(err) => match err.kind() {
+                IoErrorKind::AddrInUse => {
+                    return InUseSnafu {
+                        endpoint: address.to_string(),
+                    }
+                    .fail()
+                }
+                _ => return Err(err).context(BindSystemSnafu),
+            },
+        };
+
+        listener.set_nonblocking(true).context(BindSystemSnafu)?;
+        let listener = TokioTcpListener::from_std(listener).context(BindSystemSnafu)?;
+        Ok(Self { listener })
+    }
+
+    /// Return the address it is bound to, which is useful if the port is 0.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the system fails to tell it.
+    pub fn local_addr(&self) -> Result<SocketAddr, ListenError> {
+        self.listener.local_addr().context(BindSystemSnafu)
+    }
+}
+
+#[async_trait::async_trait]
+impl Listener for TcpListener {
+    async fn accept(&self) -> Result<Box<dyn Stream>, ListenError> {
+        self.listener
+            .accept()
+            .await
+            .map(|(stream, _)| -> Box<dyn Stream> { Box::new(stream) })
+            .context(AcceptSystemSnafu)
+    }
+}
+
 /// A [`Listener`] implementation which returns [`DuplexStream`]s. This is
 /// typically used for testing purpose.
 #[derive(Debug)]
@@ -124,6 +213,37 @@ impl Listener for DuplexListener {
     }
 }
 
+/// A [`Listener`] implementation which accepts [`DuplexStream`]s from a
+/// channel, e.g. the one returned by
+/// [`DuplexConnector::new`](crate::client::app::connector::DuplexConnector::new),
+/// so that a client can talk to an in-process daemon.
+#[derive(Debug)]
+pub struct ChannelListener {
+    peer: Mutex<Receiver<DuplexStream>>,
+}
+
+impl ChannelListener {
+    /// Create a [`ChannelListener`] which accepts streams from `peer`.
+    pub fn new(peer: Receiver<DuplexStream>) -> Self {
+        Self {
+            peer: Mutex::new(peer),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Listener for ChannelListener {
+    async fn accept(&self) -> Result<Box<dyn Stream>, ListenError> {
+        let stream = self.peer.lock().await.recv().await.ok_or_else(|| {
+            BindUnknownSnafu {
+                message: "Peer already closed",
+            }
+            .build()
+        })?;
+        Ok(Box::new(stream))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -132,6 +252,8 @@ mod tests {
     use bytes::BytesMut;
     use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
+    use crate::client::app::connector::{Connector, DuplexConnector, TcpConnector};
+
     #[tokio::test]
     async fn unix_listener_error_in_use() {
         let tmp = TempDir::new().expect("Test environment should support temporary directories");
@@ -143,6 +265,57 @@ mod tests {
         ));
     }
 
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn unix_listener_abstract() {
+        use crate::client::app::connector::UnixConnector;
+
+        let path = format!("@to-concentrate-test-{}", std::process::id());
+        let listener = UnixListener::new(&path).unwrap();
+        assert!(matches!(
+            UnixListener::new(&path),
+            Err(ListenError::InUse { .. })
+        ));
+
+        let connector = UnixConnector::new(&path);
+        let mut local = connector.connect().await.unwrap();
+        let mut peer = listener.accept().await.unwrap();
+        local.write_all(b"bytes").await.unwrap();
+        drop(local);
+
+        let mut buf = BytesMut::new();
+        peer.read_buf(&mut buf).await.unwrap();
+        assert_eq!(&buf[..], b"bytes");
+
+        // Nothing is left behind once the listener is gone.
+        drop(listener);
+        assert!(!Path::new(&path).exists());
+        UnixListener::new(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn tcp_listener() {
+        let listener = TcpListener::new("127.0.0.1:0".parse().unwrap()).unwrap();
+        let connector = TcpConnector::new(listener.local_addr().unwrap());
+        let mut local = connector.connect().await.unwrap();
+        let mut peer = listener.accept().await.unwrap();
+        local.write_all(b"bytes").await.unwrap();
+        drop(local);
+
+        let mut buf = BytesMut::new();
+        peer.read_buf(&mut buf).await.unwrap();
+        assert_eq!(&buf[..], b"bytes");
+    }
+
+    #[tokio::test]
+    async fn tcp_listener_error_in_use() {
+        let listener = TcpListener::new("127.0.0.1:0".parse().unwrap()).unwrap();
+        assert!(matches!(
+            TcpListener::new(listener.local_addr().unwrap()),
+            Err(ListenError::InUse { .. })
+        ));
+    }
+
     #[tokio::test]
     async fn duplex_listener() {
         let (connector, mut peer) = DuplexListener::new(256);
@@ -165,4 +338,29 @@ mod tests {
             Err(ListenError::BindUnknown { .. })
         ));
     }
+
+    #[tokio::test]
+    async fn channel_listener() {
+        let (connector, peer) = DuplexConnector::new(256);
+        let listener = ChannelListener::new(peer);
+        let mut local = connector.connect().await.unwrap();
+        let mut peer = listener.accept().await.unwrap();
+        local.write_all(b"bytes").await.unwrap();
+        drop(local);
+
+        let mut buf = BytesMut::new();
+        peer.read_buf(&mut buf).await.unwrap();
+        assert_eq!(&buf[..], b"bytes");
+    }
+
+    #[tokio::test]
+    async fn channel_listener_error_bind_unknown() {
+        let (connector, peer) = DuplexConnector::new(256);
+        let listener = ChannelListener::new(peer);
+        drop(connector);
+        assert!(matches!(
+            listener.accept().await,
+            Err(ListenError::BindUnknown { .. })
+        ));
+    }
 }
@@ -1,5 +1,7 @@
+pub mod daemon;
 pub mod listener;
 pub mod server;
 
-pub use listener::UnixListener;
-pub use server::Server;
+pub use daemon::Daemon;
+pub use listener::{TcpListener, UnixListener};
+pub use server::{ConfigLoader, Server};
@@ -1,22 +1,103 @@
+use std::future;
+use std::io::Error as IoError;
 use std::sync::Arc;
+use std::task::Poll;
 
 use snafu::prelude::*;
+use tokio::signal::unix::{Signal, SignalKind};
+use tokio::sync::broadcast::{self, error::RecvError, Receiver as BroadcastReceiver};
+use tokio::sync::Notify;
+use tokio::task::JoinSet;
+use tokio::time::{Duration, Instant};
 use tracing::{field::Empty, Instrument, Span};
 
+use crate::daemon::config::{Configuration, LoadConfigurationError};
+use crate::daemon::repository::{
+    BehaviorConfiguration, DurationConfiguration, NotificationConfiguration, StageConfiguration,
+};
 use crate::domain::client::outbound::QueryResponse;
-use crate::domain::daemon::ApplicationCore;
+use crate::domain::daemon::DEFAULT_SESSION;
+use crate::domain::daemon::{ApplicationCore, ChangeStageError, SetupApplicationCoreError};
+use crate::domain::entity::duration::TryNewStageDurationError;
+use crate::domain::entity::{StageDuration, TimerEvent};
 use crate::protocol::connection::{ReceiveFrameError, SendFrameError};
-use crate::protocol::{Connection, Protocol, Request, Response};
+use crate::protocol::{Connection, Protocol, ProtocolVersion, Request, Response};
 use crate::tracing_report;
 use crate::utils::stream::Stream;
 
 use super::listener::{ListenError, Listener};
 
+/// Maximum time for in-flight connections to finish after the server stops
+/// accepting new ones.
+const GRACE_PERIOD: Duration = Duration::from_secs(1);
+
+/// How long an accepted connection may stay silent before it is closed, so
+/// that half-open connections don't tie up handlers.
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Number of events which can wait for a slow subscriber. It misses the
+/// oldest ones once more are pending, so that it never holds up the timer.
+const EVENT_CAPACITY: usize = 16;
+
+/// How long a health check waits for the timer, so that the daemon still
+/// answers while the timer is busy.
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A function which loads the configuration again for [`Server::with_reload`].
+pub type ConfigLoader =
+    Box<dyn Fn() -> Result<Configuration, LoadConfigurationError> + Send + Sync>;
+
+/// Statistics of a connection recorded in its `handle` span. The duration is
+/// recorded on drop, so it also covers connections aborted mid-request.
+struct ConnectionStats {
+    span: Span,
+    start: Instant,
+    requests: u64,
+}
+
+impl ConnectionStats {
+    fn new(span: Span) -> Self {
+        span.record("req_count", 0);
+        Self {
+            span,
+            start: Instant::now(),
+            requests: 0,
+        }
+    }
+
+    /// Count a received request, before handling it.
+    fn count(&mut self) {
+        self.requests += 1;
+        self.span.record("req_count", self.requests);
+    }
+}
+
+impl Drop for ConnectionStats {
+    fn drop(&mut self) {
+        let duration = self.start.elapsed();
+        self.span
+            .record("duration", tracing::field::debug(duration));
+        self.span.in_scope(|| {
+            tracing::info!(req_count = self.requests, ?duration, "Closed connection");
+        });
+    }
+}
+
 /// An dedicated server which listens on a UNIX socket and handles
 /// requests from clients.
 pub struct Server {
     listener: Box<dyn Listener>,
     core: Arc<ApplicationCore>,
+    shutdown: Arc<Notify>,
+    signals: Vec<SignalKind>,
+    loader: Option<ConfigLoader>,
+}
+
+/// What the server should do after handling a connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Handled {
+    Continue,
+    Shutdown,
 }
 
 impl Server {
@@ -25,19 +106,71 @@ impl Server {
         Self {
             listener,
             core: Arc::new(core),
+            shutdown: Arc::new(Notify::new()),
+            signals: Vec::new(),
+            loader: None,
         }
     }
 
+    /// Shut down gracefully on receiving any of `signals`, keeping the
+    /// progress of the timers.
+    pub fn with_signals(mut self, signals: Vec<SignalKind>) -> Self {
+        self.signals = signals;
+        self
+    }
+
+    /// Reload the configuration with `loader` on receiving `SIGHUP`, unless
+    /// it is one of the shutdown signals.
+    pub fn with_reload(mut self, loader: ConfigLoader) -> Self {
+        self.loader = Some(loader);
+        self
+    }
+
     /// Accept connections from a [`UnixListener`] and handle requests.
     ///
     /// # Errors
     ///
     /// This function will return an error if the server fails to accept
     /// connections or any unexpected error occurs during handling requests.
+    /// It returns normally after a [`Request::Stop`] is handled or one of the
+    /// configured signals is received, giving in-flight connections a brief
+    /// grace period to finish.
     #[tracing::instrument(skip(self))]
     pub async fn serve(&self) -> Result<(), ServerError> {
+        let mut signals = self
+            .signals
+            .iter()
+            .map(|&kind| tokio::signal::unix::signal(kind))
+            .collect::<Result<Vec<_>, _>>()
+            .context(SignalSnafu)?;
+        let mut hangup = match self.loader {
+            Some(_) if !self.signals.contains(&SignalKind::hangup()) => {
+                Some(tokio::signal::unix::signal(SignalKind::hangup()).context(SignalSnafu)?)
+            }
+            _ => None,
+        };
+        let mut handlers = JoinSet::new();
+
         loop {
-            let stream = match self.listener.accept().await {
+            let accepted = tokio::select! {
+                accepted = self.listener.accept() => accepted,
+                Some(_) = handlers.join_next() => continue,
+                _ = self.shutdown.notified() => {
+                    tracing::info!("Stopped serving");
+                    break;
+                }
+                _ = Self::recv_any(&mut signals) => {
+                    tracing::info!("Received shutdown signal");
+                    break;
+                }
+                _ = Self::recv_any(hangup.as_mut_slice()) => {
+                    tracing::info!("Received reload signal");
+                    self.reload().await;
+                    continue;
+                }
+            };
+
+            let stream = match accepted {
                 Ok(stream) => {
                     tracing::info!("Accepted connection");
                     stream
@@ -49,40 +182,264 @@ impl Server {
             };
 
             let core = Arc::clone(&self.core);
-            let connection = Connection::from(stream);
+            let shutdown = Arc::clone(&self.shutdown);
+            let connection = Connection::from(stream).with_read_timeout(READ_TIMEOUT);
 
-            let span = tracing::info_span!("handle", req = Empty).or_current();
-            tokio::spawn(
+            let span = tracing::info_span!(
+                "handle",
+                session = Empty,
+                req = Empty,
+                req_count = Empty,
+                duration = Empty,
+            )
+            .or_current();
+            handlers.spawn(
                 async move {
-                    if let Err(err) = Self::handle(core, connection).await {
-                        tracing_report!(err, format!("Could not handle requests"));
+                    match Self::handle(core, connection).await {
+                        Ok(Handled::Continue) => {}
+                        Ok(Handled::Shutdown) => shutdown.notify_one(),
+                        Err(err) => {
+                            tracing_report!(err, format!("Could not handle requests"));
+                        }
                     }
                 }
                 .instrument(span),
             );
         }
+
+        let drained = async { while handlers.join_next().await.is_some() {} };
+        if tokio::time::timeout(GRACE_PERIOD, drained).await.is_err() {
+            tracing::warn!(
+                connections = handlers.len(),
+                "Aborted connections after the grace period"
+            );
+            handlers.shutdown().await;
+        }
+
+        // Timers stopped by `Request::Stop` are already gone, so this only
+        // stops those left by a signal.
+        self.core.shutdown().await;
+        Ok(())
+    }
+
+    /// Load the configuration again and apply it to the timers. An invalid
+    /// configuration is reported and the old one stays in effect.
+    async fn reload(&self) {
+        let Some(loader) = &self.loader else {
+            return;
+        };
+
+        let config = match loader() {
+            Ok(config) => Arc::new(config),
+            Err(err) => {
+                tracing_report!(err, "Kept the old configuration");
+                return;
+            }
+        };
+
+        let res = self
+            .core
+            .reload(
+                Arc::new(DurationConfiguration::new(Arc::clone(&config))),
+                Arc::new(NotificationConfiguration::new(Arc::clone(&config))),
+                Arc::new(StageConfiguration::new(Arc::clone(&config))),
+                Arc::new(BehaviorConfiguration::new(config)),
+            )
+            .await;
+
+        match res {
+            Ok(()) => tracing::info!("Reloaded configuration"),
+            Err(err) => tracing::error!(
+                err = %snafu::Report::from_error(err),
+                "Kept the old configuration"
+            ),
+        }
+    }
+
+    /// Wait until any of `signals` is received. Never return if there is no
+    /// signal.
+    async fn recv_any(signals: &mut [Signal]) {
+        future::poll_fn(|cx| {
+            for signal in signals.iter_mut() {
+                if signal.poll_recv(cx).is_ready() {
+                    return Poll::Ready(());
+                }
+            }
+            Poll::Pending
+        })
+        .await
     }
 
-    /// Handle requests from an accepted connection.
+    /// Handle requests from an accepted connection. The connection is kept
+    /// alive for further requests until the client closes it or sends a
+    /// [`Protocol::Quit`].
     ///
     /// # Errors
     ///
-    /// This function will return an error if handling connection fails.
+    /// This function will return an error if handling connection fails. A
+    /// message other than a request is only reported, but a broken frame ends
+    /// the connection.
     async fn handle<S: Stream>(
         core: Arc<ApplicationCore>,
         mut connection: Connection<S>,
+    ) -> Result<Handled, ServerError> {
+        let mut stats = ConnectionStats::new(Span::current());
+        let mut protocol: Protocol = connection.receive().await.context(ReceiveSnafu)?.into();
+
+        // Clients before the handshake was introduced send the request
+        // directly, so the handshake is optional.
+        if let Protocol::Hello { version } = protocol {
+            let hello = Protocol::Hello {
+                version: ProtocolVersion::CURRENT,
+            };
+            connection.send(hello.into()).await.context(SendSnafu)?;
+            ensure!(
+                ProtocolVersion::CURRENT.is_compatible(&version),
+                IncompatibleClientSnafu { version }
+            );
+            protocol = match connection.receive().await {
+                Ok(frame) => frame.into(),
+                Err(ReceiveFrameError::Closed) => return Ok(Handled::Continue),
+                Err(err) => return Err(err).context(ReceiveSnafu),
+            };
+        }
+
+        loop {
+            match protocol {
+                Protocol::Request { session, request } => {
+                    stats.count();
+                    let handled =
+                        Self::handle_request(&core, &mut connection, session, request).await?;
+
+                    if handled == Handled::Shutdown {
+                        return Ok(handled);
+                    }
+                }
+                Protocol::Quit => break,
+                protocol => {
+                    let message = Self::report(BadRequestSnafu { protocol }.build());
+                    Self::send_error(&mut connection, message).await?;
+                }
+            }
+
+            protocol = match connection.receive().await {
+                Ok(frame) => frame.into(),
+                Err(ReceiveFrameError::Closed) => break,
+                Err(ReceiveFrameError::Timeout { .. }) => {
+                    tracing::info!("Closed idle connection");
+                    break;
+                }
+                Err(err) => return Err(err).context(ReceiveSnafu),
+            };
+        }
+
+        // Signal the client that no more data will be sent.
+        connection.shutdown_write().await.context(SendSnafu)?;
+        Ok(Handled::Continue)
+    }
+
+    /// Log `err` and describe it along with its sources for the client.
+    fn report(err: ServerError) -> String {
+        tracing_report!(err);
+        snafu::Report::from_error(err).to_string()
+    }
+
+    /// Tell the client that its message could not be handled, for the reason
+    /// in `message`, without ending the connection.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the response could not be sent.
+    async fn send_error<S: Stream>(
+        connection: &mut Connection<S>,
+        message: String,
     ) -> Result<(), ServerError> {
-        let request = match connection.receive().await {
-            Ok(frame) => match Protocol::from(frame) {
-                Protocol::Request(request) => request,
-                protocol => return BadRequestSnafu { protocol }.fail(),
-            },
-            Err(err) => return Err(err).context(ReceiveSnafu),
-        };
+        connection
+            .send(Protocol::Response(Response::Error { message }).into())
+            .await
+            .context(SendSnafu)
+            .inspect(|_| tracing::info!("Sent error response"))
+    }
+
+    /// Send the events of a subscription to the client until it closes the
+    /// connection, sends anything else, or the timer stops. A slow client
+    /// only misses some events rather than slowing the timer down.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if an event could not be sent.
+    async fn forward_events<S: Stream>(
+        connection: &mut Connection<S>,
+        mut events: BroadcastReceiver<TimerEvent>,
+    ) -> Result<(), ServerError> {
+        loop {
+            let event = tokio::select! {
+                event = events.recv() => event,
+                received = connection.receive() => match received {
+                    // A subscriber stays silent while it waits for events.
+                    Err(ReceiveFrameError::Timeout { .. }) => continue,
+                    _ => return Ok(()),
+                },
+            };
 
+            match event {
+                Ok(event) => connection
+                    .send(Protocol::Response(Response::Event { event }).into())
+                    .await
+                    .context(SendSnafu)?,
+                Err(RecvError::Lagged(missed)) => {
+                    tracing::warn!(missed, "Dropped events for a slow subscriber");
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            }
+        }
+    }
+
+    /// Handle a single request routed to `session`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the session could not be opened
+    /// or the response could not be sent.
+    async fn handle_request<S: Stream>(
+        core: &ApplicationCore,
+        connection: &mut Connection<S>,
+        session: Option<String>,
+        request: Request,
+    ) -> Result<Handled, ServerError> {
+        let session = session.as_deref().unwrap_or(DEFAULT_SESSION);
+        Span::current().record("session", session);
         Span::current().record("req", format!("{request:?}"));
 
-        match request {
+        // Stopping shuts down the whole daemon rather than a single session.
+        if request == Request::Stop {
+            tracing::info!("Received request");
+            core.stop().await;
+            tracing::info!("Handled request");
+            connection
+                .send(Protocol::Response(Response::Stop).into())
+                .await
+                .context(SendSnafu)
+                .inspect(|_| tracing::info!("Sent response"))?;
+            connection.shutdown_write().await.context(SendSnafu)?;
+            return Ok(Handled::Shutdown);
+        }
+
+        let uptime = core.uptime();
+        let core = core
+            .session(session)
+            .await
+            .context(SessionSnafu)
+            .map_err(Self::report);
+        let core = match core {
+            Ok(core) => core,
+            Err(message) => {
+                Self::send_error(connection, message).await?;
+                return Ok(Handled::Continue);
+            }
+        };
+
+        let res = match request {
             Request::Pause => {
                 tracing::info!("Received request");
                 core.pause.pause().await;
@@ -113,9 +470,54 @@ impl Server {
                     .context(SendSnafu)
                     .inspect(|_| tracing::info!("Sent response"))
             }
-            Request::Skip => {
+            Request::Schedule => {
+                tracing::info!("Received request");
+                let stages = core.schedule.schedule().await;
+                tracing::info!("Handled request");
+                connection
+                    .send(Protocol::Response(Response::Schedule { stages }).into())
+                    .await
+                    .context(SendSnafu)
+                    .inspect(|_| tracing::info!("Sent response"))
+            }
+            Request::Stats => {
                 tracing::info!("Received request");
-                core.skip.skip().await;
+                let stats = core.stats.stats().await;
+                tracing::info!("Handled request");
+                connection
+                    .send(Protocol::Response(Response::Stats { stats }).into())
+                    .await
+                    .context(SendSnafu)
+                    .inspect(|_| tracing::info!("Sent response"))
+            }
+            Request::Subscribe => {
+                tracing::info!("Received request");
+                let (sender, events) = broadcast::channel(EVENT_CAPACITY);
+                core.subscribe.subscribe(sender).await;
+                tracing::info!("Handled request");
+                Self::forward_events(connection, events)
+                    .await
+                    .inspect(|_| tracing::info!("Ended subscription"))
+            }
+            Request::Health => {
+                tracing::info!("Received request");
+                let status = tokio::time::timeout(HEALTH_TIMEOUT, core.ping.ping()).await;
+                tracing::info!("Handled request");
+                match status {
+                    Ok(status) => connection
+                        .send(Protocol::Response(Response::Health { uptime, status }).into())
+                        .await
+                        .context(SendSnafu)
+                        .inspect(|_| tracing::info!("Sent response")),
+                    Err(_) => {
+                        let message = Self::report(UnresponsiveSnafu.build());
+                        Self::send_error(connection, message).await
+                    }
+                }
+            }
+            Request::Skip { notify } => {
+                tracing::info!("Received request");
+                core.skip.skip(notify).await;
                 tracing::info!("Handled request");
                 connection
                     .send(Protocol::Response(Response::Skip).into())
@@ -123,7 +525,104 @@ impl Server {
                     .context(SendSnafu)
                     .inspect(|_| tracing::info!("Sent response"))
             }
-        }
+            Request::Reset => {
+                tracing::info!("Received request");
+                core.reset.reset().await;
+                tracing::info!("Handled request");
+                connection
+                    .send(Protocol::Response(Response::Reset).into())
+                    .await
+                    .context(SendSnafu)
+                    .inspect(|_| tracing::info!("Sent response"))
+            }
+            Request::Mute => {
+                tracing::info!("Received request");
+                core.mute.mute().await;
+                tracing::info!("Handled request");
+                connection
+                    .send(Protocol::Response(Response::Mute).into())
+                    .await
+                    .context(SendSnafu)
+                    .inspect(|_| tracing::info!("Sent response"))
+            }
+            Request::Unmute => {
+                tracing::info!("Received request");
+                core.unmute.unmute().await;
+                tracing::info!("Handled request");
+                connection
+                    .send(Protocol::Response(Response::Unmute).into())
+                    .await
+                    .context(SendSnafu)
+                    .inspect(|_| tracing::info!("Sent response"))
+            }
+            Request::Adjust { delta_secs } => {
+                tracing::info!("Received request");
+                let res = core.adjust.adjust(delta_secs).await;
+                tracing::info!("Handled request");
+                match res.context(StageSnafu).map_err(Self::report) {
+                    Ok(()) => connection
+                        .send(Protocol::Response(Response::Adjust).into())
+                        .await
+                        .context(SendSnafu)
+                        .inspect(|_| tracing::info!("Sent response")),
+                    Err(message) => Self::send_error(connection, message).await,
+                }
+            }
+            Request::SetDuration { total } => {
+                tracing::info!(?total, "Received request");
+                let total = StageDuration::try_from_duration(total)
+                    .context(StageDurationSnafu { total })
+                    .map_err(Self::report);
+                match total {
+                    Ok(total) => {
+                        core.set_duration.set_duration(total).await;
+                        tracing::info!("Handled request");
+                        connection
+                            .send(Protocol::Response(Response::SetDuration).into())
+                            .await
+                            .context(SendSnafu)
+                            .inspect(|_| tracing::info!("Sent response"))
+                    }
+                    Err(message) => Self::send_error(connection, message).await,
+                }
+            }
+            Request::SkipTo { stage } => {
+                tracing::info!("Received request");
+                let res = core.skip_to.skip_to(stage).await;
+                tracing::info!("Handled request");
+                match res.context(StageSnafu).map_err(Self::report) {
+                    Ok(()) => connection
+                        .send(Protocol::Response(Response::SkipTo).into())
+                        .await
+                        .context(SendSnafu)
+                        .inspect(|_| tracing::info!("Sent response")),
+                    Err(message) => Self::send_error(connection, message).await,
+                }
+            }
+            Request::Stop => unreachable!("Stop should have been handled for all sessions"),
+            Request::AutoPause { trigger } => {
+                tracing::info!("Received request");
+                core.auto_pause.auto_pause(trigger).await;
+                tracing::info!("Handled request");
+                connection
+                    .send(Protocol::Response(Response::AutoPause).into())
+                    .await
+                    .context(SendSnafu)
+                    .inspect(|_| tracing::info!("Sent response"))
+            }
+            Request::AutoResume { trigger } => {
+                tracing::info!("Received request");
+                core.auto_resume.auto_resume(trigger).await;
+                tracing::info!("Handled request");
+                connection
+                    .send(Protocol::Response(Response::AutoResume).into())
+                    .await
+                    .context(SendSnafu)
+                    .inspect(|_| tracing::info!("Sent response"))
+            }
+        };
+        res?;
+        Ok(Handled::Continue)
     }
 }
 
@@ -132,9 +631,13 @@ impl From<QueryResponse> for Response {
         Response::Query {
             current: value.current,
             stage: value.stage,
+            kind: value.kind,
             total: value.total,
             remaining: value.remaining,
             past: value.past,
+            long_break: value.long_break,
+            completed_cycles: value.completed_cycles,
+            muted: value.muted,
         }
     }
 }
@@ -145,10 +648,31 @@ impl From<QueryResponse> for Response {
 pub enum ServerError {
     #[snafu(display("Could not accept a connection"))]
     Listen { source: ListenError },
+    #[snafu(display("Could not listen for shutdown signals"))]
+    Signal {
+        #[snafu(source(from(IoError, Arc::new)))]
+        source: Arc<IoError>,
+    },
     #[snafu(display("Could not receive a request"))]
     Receive { source: ReceiveFrameError },
     #[snafu(display("Could not handle {protocol:?}"))]
     BadRequest { protocol: Protocol },
+    #[snafu(display("Could not talk with a client speaking protocol {version}"))]
+    IncompatibleClient { version: ProtocolVersion },
+    #[snafu(display("Could not open the requested session"))]
+    Session {
+        #[snafu(source(from(SetupApplicationCoreError, Arc::new)))]
+        source: Arc<SetupApplicationCoreError>,
+    },
+    #[snafu(display("Timer did not respond within {:?}", HEALTH_TIMEOUT))]
+    Unresponsive,
+    #[snafu(display("Could not change the current stage"))]
+    Stage { source: ChangeStageError },
+    #[snafu(display("Could not let the current stage last {total:?}"))]
+    StageDuration {
+        total: Duration,
+        source: TryNewStageDurationError,
+    },
     #[snafu(display("Could not send a response"))]
     Send { source: SendFrameError },
 }
@@ -157,49 +681,566 @@ pub enum ServerError {
 mod tests {
     use super::*;
 
-    use std::future;
-
-    use tokio::io::DuplexStream;
+    use tokio::io::{AsyncWriteExt, DuplexStream};
     use tokio::time::Duration;
 
+    use crate::client::app::connector::DuplexConnector;
+    use crate::daemon::app::listener::{ChannelListener, DuplexListener};
+    use crate::daemon::config;
+    use crate::daemon::outbound::{LogNotifyService, NoIdleDetector, NoStageLog, NoStateStore};
     use crate::domain::daemon::inbound::{
-        MockPausePort, MockQueryPort, MockResumePort, MockSkipPort,
+        MockAdjustPort, MockAutoPausePort, MockAutoResumePort, MockMutePort, MockPausePort,
+        MockPingPort, MockQueryPort, MockReloadPort, MockResetPort, MockResumePort,
+        MockSchedulePort, MockSetDurationPort, MockSkipPort, MockSkipToPort, MockStatsPort,
+        MockStopPort, MockSubscribePort, MockUnmutePort,
     };
+    use crate::domain::daemon::{MockSessionFactory, Session, SpawnWorkerError};
+    use crate::domain::entity::{StageState, TimerStats, TimerStatus};
 
     #[tokio::test]
     async fn server_handle() {
-        let core = new_core();
-        let (connection, mut client) = new_connection_with(Protocol::Request(Request::Query)).await;
+        let core = new_core().await;
+        let (connection, mut client) = new_connection_with(Protocol::request(Request::Query)).await;
         assert!(Server::handle(core, connection).await.is_ok());
         assert_eq!(
             client.receive().await.unwrap(),
             Protocol::Response(Response::Query {
-                current: "Running".to_owned(),
+                current: TimerStatus::Running,
                 stage: "Preparation".to_owned(),
+                kind: StageState::Preparation,
                 total: Duration::from_secs(20),
                 remaining: Duration::from_secs(15),
                 past: Duration::from_secs(5),
+                long_break: false,
+                completed_cycles: 0,
+                muted: false,
+            })
+            .into(),
+        );
+    }
+
+    #[tokio::test]
+    async fn server_handle_shutdown_write() {
+        let core = new_core().await;
+        let (connection, mut client) = new_connection_with(Protocol::request(Request::Pause)).await;
+        assert!(Server::handle(core, connection).await.is_ok());
+        assert_eq!(
+            client.receive().await.unwrap(),
+            Protocol::Response(Response::Pause).into(),
+        );
+        assert!(matches!(
+            client.receive().await,
+            Err(ReceiveFrameError::Closed)
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn server_handle_idle_timeout() {
+        let core = new_core().await;
+        let (server, client) = tokio::io::duplex(1024);
+        let connection = Connection::from(server).with_read_timeout(READ_TIMEOUT);
+        let mut client = Connection::from(client);
+        client
+            .send(Protocol::request(Request::Pause).into())
+            .await
+            .unwrap();
+
+        // The client keeps the connection open without sending anything else.
+        let start = tokio::time::Instant::now();
+        assert!(Server::handle(core, connection).await.is_ok());
+        assert!(start.elapsed() >= READ_TIMEOUT);
+        assert_eq!(
+            client.receive().await.unwrap(),
+            Protocol::Response(Response::Pause).into(),
+        );
+        assert!(matches!(
+            client.receive().await,
+            Err(ReceiveFrameError::Closed)
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn server_handle_subscribe() {
+        let core = new_core().await;
+        let (server, client) = tokio::io::duplex(1024);
+        let connection = Connection::from(server).with_read_timeout(READ_TIMEOUT);
+        let mut client = Connection::from(client);
+        client
+            .send(Protocol::request(Request::Subscribe).into())
+            .await
+            .unwrap();
+
+        // Events are forwarded until the timer ends the subscription.
+        assert!(Server::handle(core, connection).await.is_ok());
+        for current in [TimerStatus::Running, TimerStatus::Paused] {
+            assert_eq!(
+                client.receive().await.unwrap(),
+                Protocol::Response(Response::Event {
+                    event: new_event(current),
+                })
+                .into(),
+            );
+        }
+        assert!(matches!(
+            client.receive().await,
+            Err(ReceiveFrameError::Closed)
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn server_handle_health() {
+        let core = new_core().await;
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        let (connection, mut client) =
+            new_connection_with(Protocol::request(Request::Health)).await;
+        assert!(Server::handle(core, connection).await.is_ok());
+        assert_eq!(
+            client.receive().await.unwrap(),
+            Protocol::Response(Response::Health {
+                uptime: Duration::from_secs(5),
+                status: TimerStatus::Running,
             })
             .into(),
         );
     }
 
     #[tokio::test]
-    async fn server_handle_error_bad_request() {
-        let core = new_core();
-        let (connection, _) = new_connection_with(Protocol::Response(Response::Pause)).await;
+    async fn server_handle_stop() {
+        let core = new_core().await;
+        let (connection, mut client) = new_connection_with(Protocol::request(Request::Stop)).await;
+        assert_eq!(
+            Server::handle(core, connection).await.unwrap(),
+            Handled::Shutdown,
+        );
+        assert_eq!(
+            client.receive().await.unwrap(),
+            Protocol::Response(Response::Stop).into(),
+        );
+    }
+
+    #[tokio::test]
+    async fn server_handle_query_after_stop() {
+        let core = new_core().await;
+        let (server, client) = tokio::io::duplex(1024);
+        let mut client = Connection::from(client);
+
+        // A keep-alive connection, e.g. from `watch`, outlives the stop.
+        let watch = async {
+            let response = client.request(Protocol::request(Request::Query)).await;
+            assert!(matches!(
+                response.unwrap(),
+                Protocol::Response(Response::Query { .. })
+            ));
+            let (connection, _stopper) =
+                new_connection_with(Protocol::request(Request::Stop)).await;
+            assert_eq!(
+                Server::handle(Arc::clone(&core), connection).await.unwrap(),
+                Handled::Shutdown,
+            );
+
+            let response = client.request(Protocol::request(Request::Query)).await;
+            client.send(Protocol::Quit.into()).await.unwrap();
+            response.unwrap()
+        };
+
+        let (handled, response) = tokio::join!(
+            Server::handle(Arc::clone(&core), Connection::from(server)),
+            watch,
+        );
+        assert!(handled.is_ok());
+        assert!(matches!(
+            response,
+            Protocol::Response(Response::Error { message }) if message.contains("stopped")
+        ));
+    }
+
+    #[tokio::test]
+    async fn server_serve_stop() {
+        let (listener, mut peer) = DuplexListener::new(1024);
+        let server = Server::new(Box::new(listener), new_core_owned().await);
+
+        let client = async {
+            let mut client = Connection::from(peer.recv().await.unwrap());
+            let request = Protocol::request(Request::Stop);
+            client.send(request.into()).await.unwrap();
+            client.receive().await.unwrap()
+        };
+
+        // The server returns after the response is sent.
+        let (served, response) = tokio::join!(server.serve(), client);
+        assert!(served.is_ok());
+        assert_eq!(response, Protocol::Response(Response::Stop).into());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn server_serve_grace_period() {
+        let (listener, mut peer) = DuplexListener::new(1024);
+        let server = Server::new(Box::new(listener), new_core_owned().await);
+
+        let client = async {
+            // An idle connection is kept alive until the grace period ends.
+            let idle = peer.recv().await.unwrap();
+            let mut client = Connection::from(peer.recv().await.unwrap());
+            let request = Protocol::request(Request::Stop);
+            client.send(request.into()).await.unwrap();
+            client.receive().await.unwrap();
+            idle
+        };
+
+        let start = tokio::time::Instant::now();
+        let (served, _idle) = tokio::join!(server.serve(), client);
+        assert!(served.is_ok());
+        assert!(start.elapsed() >= GRACE_PERIOD);
+    }
+
+    #[tokio::test]
+    async fn server_serve_signal() {
+        // No client ever connects.
+        let (_connector, peer) = DuplexConnector::new(1024);
+        let listener = ChannelListener::new(peer);
+        let server = Server::new(Box::new(listener), new_core_owned().await)
+            .with_signals(vec![SignalKind::user_defined2()]);
+
+        let signal = async {
+            // Give `serve` a chance to install the signal handler.
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            std::process::Command::new("kill")
+                .args(["-USR2", &std::process::id().to_string()])
+                .status()
+                .unwrap();
+        };
+
+        let (served, _) = tokio::join!(server.serve(), signal);
+        assert!(served.is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn server_reload() {
+        let server = new_server_with_reload(Box::new(|| {
+            let mut config = config::load_default()?;
+            config.duration.concentration = 60;
+            Ok(config)
+        }))
+        .await;
+        server.reload().await;
+
+        let session = server.core.session(DEFAULT_SESSION).await.unwrap();
+        session.skip.skip(false).await;
+        let response = session.query.query().await;
+        assert_eq!(response.stage, StageState::Concentration.to_string());
+        assert_eq!(response.total, Duration::from_secs(60));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn server_reload_invalid() {
+        let server = new_server_with_reload(Box::new(|| {
+            let mut config = config::load_default()?;
+            config.duration.concentration = 0;
+            Ok(config)
+        }))
+        .await;
+        server.reload().await;
+
+        // The old configuration stays in effect.
+        let session = server.core.session(DEFAULT_SESSION).await.unwrap();
+        session.skip.skip(false).await;
+        let response = session.query.query().await;
+        assert_eq!(response.total, Duration::from_secs(2400));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn server_handle_adjust_beyond_limit() {
+        let server = new_server_with_reload(Box::new(config::load_default)).await;
+        let session = server.core.session(DEFAULT_SESSION).await.unwrap();
+        let total = session.query.query().await.total;
+        let (connection, mut client) = new_connection_with_all(vec![
+            Protocol::request(Request::Adjust {
+                delta_secs: 10000 * 60 * 60,
+            }),
+            Protocol::request(Request::Adjust { delta_secs: 60 }),
+        ])
+        .await;
+
+        // An over-large extension is refused, while a reasonable one works.
+        assert!(Server::handle(Arc::clone(&server.core), connection)
+            .await
+            .is_ok());
+        let Protocol::Response(Response::Error { message }) =
+            client.receive().await.unwrap().into()
+        else {
+            panic!("Server should have rejected the extension");
+        };
+        assert!(message.contains("Could not change the length of the current stage"));
+        assert!(message.contains("must not be longer than"));
+        assert_eq!(
+            client.receive().await.unwrap(),
+            Protocol::Response(Response::Adjust).into(),
+        );
+        assert_eq!(
+            session.query.query().await.total,
+            total + Duration::from_secs(60),
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn server_handle_skip_to_unknown_stage() {
+        let server = new_server_with_reload(Box::new(config::load_default)).await;
+        let (connection, mut client) = new_connection_with_all(vec![
+            Protocol::request(Request::SkipTo {
+                stage: StageState::Concentration,
+            }),
+            Protocol::request(Request::SkipTo {
+                stage: StageState::Preparation,
+            }),
+        ])
+        .await;
+
+        // The preparation never comes again once the timer has moved on.
+        assert!(Server::handle(Arc::clone(&server.core), connection)
+            .await
+            .is_ok());
+        assert_eq!(
+            client.receive().await.unwrap(),
+            Protocol::Response(Response::SkipTo).into(),
+        );
+        let Protocol::Response(Response::Error { message }) =
+            client.receive().await.unwrap().into()
+        else {
+            panic!("Server should have reported the unknown stage");
+        };
+        assert!(message.contains("Could not find a Preparation stage to skip to"));
+
+        let session = server.core.session(DEFAULT_SESSION).await.unwrap();
+        let response = session.query.query().await;
+        assert_eq!(response.kind, StageState::Concentration);
+    }
+
+    #[tokio::test]
+    async fn server_handle_session() {
+        let mut factory = MockSessionFactory::new();
+        factory
+            .expect_create()
+            .withf(|name| name == DEFAULT_SESSION)
+            .times(1)
+            .returning(|_| Box::pin(async { Ok(new_session()) }));
+        factory
+            .expect_create()
+            .withf(|name| name == "work")
+            .times(1)
+            .returning(|_| Box::pin(async { Ok(new_session()) }));
+        let core = Arc::new(
+            ApplicationCore::with_factory(Arc::new(factory))
+                .await
+                .unwrap(),
+        );
+
+        for _ in 0..2 {
+            let request = Protocol::Request {
+                session: Some("work".to_owned()),
+                request: Request::Pause,
+            };
+            let (connection, mut client) = new_connection_with(request).await;
+            assert!(Server::handle(Arc::clone(&core), connection).await.is_ok());
+            assert_eq!(
+                client.receive().await.unwrap(),
+                Protocol::Response(Response::Pause).into(),
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn server_handle_error_session() {
+        let mut factory = MockSessionFactory::new();
+        factory
+            .expect_create()
+            .withf(|name| name == DEFAULT_SESSION)
+            .returning(|_| Box::pin(async { Ok(new_session()) }));
+        factory
+            .expect_create()
+            .withf(|name| name == "work")
+            .returning(|name| {
+                let session = name.to_owned();
+                Box::pin(async move {
+                    Err(SetupApplicationCoreError::Worker {
+                        session,
+                        source: SpawnWorkerError::Startup,
+                    })
+                })
+            });
+        let core = Arc::new(
+            ApplicationCore::with_factory(Arc::new(factory))
+                .await
+                .unwrap(),
+        );
+
+        let request = Protocol::Request {
+            session: Some("work".to_owned()),
+            request: Request::Pause,
+        };
+        let (connection, mut client) = new_connection_with(request).await;
+
+        // The client learns why instead of seeing the connection close.
+        assert!(Server::handle(core, connection).await.is_ok());
+        let Protocol::Response(Response::Error { message }) =
+            client.receive().await.unwrap().into()
+        else {
+            panic!("Server should have reported the session error");
+        };
+        assert!(message.contains("Could not open the requested session"));
+        assert!(message.contains("exited before it is ready"));
+    }
+
+    #[tokio::test]
+    async fn server_handle_handshake() {
+        let core = new_core().await;
+        let hello = Protocol::Hello {
+            version: ProtocolVersion::CURRENT,
+        };
+        let (connection, mut client) =
+            new_connection_with_all(vec![hello.clone(), Protocol::request(Request::Pause)]).await;
+
+        assert!(Server::handle(core, connection).await.is_ok());
+        assert_eq!(client.receive().await.unwrap(), hello.into());
+        assert_eq!(
+            client.receive().await.unwrap(),
+            Protocol::Response(Response::Pause).into(),
+        );
+    }
+
+    #[tokio::test]
+    async fn server_handle_error_incompatible_client() {
+        let core = new_core().await;
+        let version = ProtocolVersion {
+            major: ProtocolVersion::CURRENT.major + 1,
+            minor: 0,
+        };
+        let (connection, mut client) = new_connection_with(Protocol::Hello { version }).await;
+
         assert!(matches!(
             Server::handle(core, connection).await,
-            Err(ServerError::BadRequest {
-                protocol: Protocol::Response(Response::Pause)
+            Err(ServerError::IncompatibleClient { .. }),
+        ));
+
+        // The client still learns the server's version to report a clear error.
+        assert_eq!(
+            client.receive().await.unwrap(),
+            Protocol::Hello {
+                version: ProtocolVersion::CURRENT
+            }
+            .into(),
+        );
+    }
+
+    #[tokio::test]
+    async fn server_handle_keep_alive() {
+        let core = new_core().await;
+        let (connection, mut client) = new_connection_with_all(vec![
+            Protocol::request(Request::Pause),
+            Protocol::request(Request::Resume),
+            Protocol::Quit,
+            Protocol::request(Request::Pause),
+        ])
+        .await;
+
+        assert!(Server::handle(core, connection).await.is_ok());
+        assert_eq!(
+            client.receive().await.unwrap(),
+            Protocol::Response(Response::Pause).into(),
+        );
+        assert_eq!(
+            client.receive().await.unwrap(),
+            Protocol::Response(Response::Resume).into(),
+        );
+
+        // Requests after quitting are ignored.
+        assert!(matches!(
+            client.receive().await,
+            Err(ReceiveFrameError::Closed)
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn connection_stats_count() {
+        let mut stats = ConnectionStats::new(Span::none());
+        stats.count();
+        stats.count();
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert_eq!(stats.requests, 2);
+        assert_eq!(stats.start.elapsed(), Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn server_handle_bad_request() {
+        let core = new_core().await;
+        let (connection, mut client) = new_connection_with_all(vec![
+            Protocol::Response(Response::Pause),
+            Protocol::request(Request::Pause),
+        ])
+        .await;
+
+        // A bad request is answered with an error without closing the
+        // connection.
+        assert!(Server::handle(core, connection).await.is_ok());
+        let Protocol::Response(Response::Error { message }) =
+            client.receive().await.unwrap().into()
+        else {
+            panic!("Server should have reported the bad request");
+        };
+        assert!(message.contains("Could not handle"));
+        assert_eq!(
+            client.receive().await.unwrap(),
+            Protocol::Response(Response::Pause).into(),
+        );
+    }
+
+    #[tokio::test]
+    async fn server_handle_set_duration_invalid() {
+        let core = new_core().await;
+        let (connection, mut client) = new_connection_with_all(vec![
+            Protocol::request(Request::SetDuration {
+                total: StageDuration::MAX + Duration::from_secs(1),
+            }),
+            Protocol::request(Request::SetDuration {
+                total: Duration::from_secs(3000),
+            }),
+        ])
+        .await;
+
+        // A total which no stage may last is rejected before the timer sees
+        // it.
+        assert!(Server::handle(core, connection).await.is_ok());
+        let Protocol::Response(Response::Error { message }) =
+            client.receive().await.unwrap().into()
+        else {
+            panic!("Server should have rejected the total");
+        };
+        assert!(message.contains("Could not let the current stage last"));
+        assert!(message.contains("must not be longer than"));
+        assert_eq!(
+            client.receive().await.unwrap(),
+            Protocol::Response(Response::SetDuration).into(),
+        );
+    }
+
+    #[tokio::test]
+    async fn server_handle_error_broken_frame() {
+        let core = new_core().await;
+        let (server, mut client) = tokio::io::duplex(1024);
+        client.write_all(b"?broken").await.unwrap();
+        drop(client);
+
+        assert!(matches!(
+            Server::handle(core, Connection::from(server)).await,
+            Err(ServerError::Receive {
+                source: ReceiveFrameError::Parse { .. }
             }),
         ))
     }
 
     #[tokio::test]
     async fn server_handle_error_send() {
-        let core = new_core();
-        let (connection, client) = new_connection_with(Protocol::Request(Request::Pause)).await;
+        let core = new_core().await;
+        let (connection, client) = new_connection_with(Protocol::request(Request::Pause)).await;
         drop(client);
         assert!(matches!(
             Server::handle(core, connection).await,
@@ -207,7 +1248,43 @@ mod tests {
         ))
     }
 
-    fn new_core() -> Arc<ApplicationCore> {
+    /// Create a server with a core over the default configuration, which is
+    /// replaced by `loader` on reload.
+    async fn new_server_with_reload(loader: ConfigLoader) -> Server {
+        let config = Arc::new(config::load_default().unwrap());
+        let core = ApplicationCore::setup(
+            Arc::new(LogNotifyService::new()),
+            Arc::new(NoIdleDetector::new()),
+            Arc::new(NoStateStore::new()),
+            Arc::new(NoStageLog::new()),
+            Arc::new(DurationConfiguration::new(Arc::clone(&config))),
+            Arc::new(NotificationConfiguration::new(Arc::clone(&config))),
+            Arc::new(StageConfiguration::new(Arc::clone(&config))),
+            Arc::new(BehaviorConfiguration::new(config)),
+        )
+        .await
+        .unwrap();
+
+        let (_connector, peer) = DuplexConnector::new(1024);
+        Server::new(Box::new(ChannelListener::new(peer)), core).with_reload(loader)
+    }
+
+    async fn new_core() -> Arc<ApplicationCore> {
+        Arc::new(new_core_owned().await)
+    }
+
+    async fn new_core_owned() -> ApplicationCore {
+        let mut factory = MockSessionFactory::new();
+        factory
+            .expect_create()
+            .returning(|_| Box::pin(async { Ok(new_session()) }));
+
+        ApplicationCore::with_factory(Arc::new(factory))
+            .await
+            .unwrap()
+    }
+
+    fn new_session() -> Session {
         let mut pause = MockPausePort::new();
         pause
             .expect_pause()
@@ -221,34 +1298,141 @@ mod tests {
         let mut query = MockQueryPort::new();
         query.expect_query().returning(|| {
             Box::pin(future::ready(QueryResponse {
-                current: "Running".to_owned(),
+                current: TimerStatus::Running,
                 stage: "Preparation".to_owned(),
+                kind: StageState::Preparation,
                 total: Duration::from_secs(20),
                 remaining: Duration::from_secs(15),
                 past: Duration::from_secs(5),
+                long_break: false,
+                completed_cycles: 0,
+                muted: false,
             }))
         });
 
+        let mut ping = MockPingPort::new();
+        ping.expect_ping()
+            .returning(|| Box::pin(future::ready(TimerStatus::Running)));
+
+        let mut schedule = MockSchedulePort::new();
+        schedule
+            .expect_schedule()
+            .returning(|| Box::pin(future::ready(Vec::new())));
+
+        let mut stats = MockStatsPort::new();
+        stats
+            .expect_stats()
+            .returning(|| Box::pin(future::ready(TimerStats::default())));
+
+        let mut subscribe = MockSubscribePort::new();
+        subscribe.expect_subscribe().returning(|sender| {
+            let _ = sender.send(new_event(TimerStatus::Running));
+            let _ = sender.send(new_event(TimerStatus::Paused));
+            Box::pin(future::ready(()))
+        });
+
         let mut skip = MockSkipPort::new();
-        skip.expect_skip().returning(|| Box::pin(future::ready(())));
+        skip.expect_skip()
+            .returning(|_| Box::pin(future::ready(())));
+
+        let mut reset = MockResetPort::new();
+        reset
+            .expect_reset()
+            .returning(|| Box::pin(future::ready(())));
+
+        let mut mute = MockMutePort::new();
+        mute.expect_mute().returning(|| Box::pin(future::ready(())));
+
+        let mut unmute = MockUnmutePort::new();
+        unmute
+            .expect_unmute()
+            .returning(|| Box::pin(future::ready(())));
+
+        let mut adjust = MockAdjustPort::new();
+        adjust
+            .expect_adjust()
+            .returning(|_| Box::pin(future::ready(Ok(()))));
+
+        let mut set_duration = MockSetDurationPort::new();
+        set_duration
+            .expect_set_duration()
+            .returning(|_| Box::pin(future::ready(())));
+
+        let mut skip_to = MockSkipToPort::new();
+        skip_to
+            .expect_skip_to()
+            .returning(|_| Box::pin(future::ready(Ok(()))));
+
+        let mut stop = MockStopPort::new();
+        stop.expect_stop()
+            .returning(|_| Box::pin(future::ready(())));
+
+        let mut auto_pause = MockAutoPausePort::new();
+        auto_pause
+            .expect_auto_pause()
+            .returning(|_| Box::pin(future::ready(())));
+
+        let mut auto_resume = MockAutoResumePort::new();
+        auto_resume
+            .expect_auto_resume()
+            .returning(|_| Box::pin(future::ready(())));
 
-        let core = ApplicationCore {
+        let mut reload = MockReloadPort::new();
+        reload
+            .expect_reload()
+            .returning(|_| Box::pin(future::ready(())));
+
+        Session {
             pause: Arc::new(pause),
             resume: Arc::new(resume),
             query: Arc::new(query),
+            ping: Arc::new(ping),
+            schedule: Arc::new(schedule),
+            stats: Arc::new(stats),
+            subscribe: Arc::new(subscribe),
             skip: Arc::new(skip),
-        };
+            reset: Arc::new(reset),
+            mute: Arc::new(mute),
+            unmute: Arc::new(unmute),
+            adjust: Arc::new(adjust),
+            set_duration: Arc::new(set_duration),
+            skip_to: Arc::new(skip_to),
+            stop: Arc::new(stop),
+            auto_pause: Arc::new(auto_pause),
+            auto_resume: Arc::new(auto_resume),
+            reload: Arc::new(reload),
+        }
+    }
 
-        Arc::new(core)
+    fn new_event(current: TimerStatus) -> TimerEvent {
+        TimerEvent {
+            current,
+            stage: "Preparation".to_owned(),
+            kind: StageState::Preparation,
+            remaining: Duration::from_secs(15),
+        }
     }
 
     async fn new_connection_with(
         data_recv: Protocol,
+    ) -> (Connection<DuplexStream>, Connection<DuplexStream>) {
+        new_connection_with_all(vec![data_recv]).await
+    }
+
+    /// Create a connection on which the client has sent all of `data_recv`
+    /// and then closed its write side.
+    async fn new_connection_with_all(
+        data_recv: Vec<Protocol>,
     ) -> (Connection<DuplexStream>, Connection<DuplexStream>) {
         let (server, client) = tokio::io::duplex(1024);
         let server = Connection::from(server);
         let mut client = Connection::from(client);
-        client.send(data_recv.into()).await.unwrap();
+
+        for data in data_recv {
+            client.send(data.into()).await.unwrap();
+        }
+
+        client.shutdown_write().await.unwrap();
         (server, client)
     }
 }
@@ -1,7 +1,10 @@
+use std::net::SocketAddr;
 use std::path::PathBuf;
 
 use serde::Deserialize;
 
+use super::seconds;
+
 /// Overall configuration structure in memory.
 #[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
 pub struct Configuration {
@@ -9,34 +12,252 @@ pub struct Configuration {
     pub notification: NotificationSection,
     #[serde(default)]
     pub runtime: RuntimeSection,
+    #[serde(default)]
+    pub auto_pause: AutoPauseSection,
+    #[serde(default)]
+    pub client: ClientSection,
+    /// A custom sequence of stages. Use the three default stages if empty.
+    #[serde(default)]
+    pub stages: Vec<StageSection>,
 }
 
+/// Durations in seconds. Each of them is written as either an integer of
+/// seconds or a string like `"25m"` or `"1h30m"`.
 #[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
 pub struct DurationSection {
+    #[serde(deserialize_with = "seconds::deserialize")]
     pub preparation: u64,
+    #[serde(deserialize_with = "seconds::deserialize")]
     pub concentration: u64,
+    #[serde(deserialize_with = "seconds::deserialize")]
     pub relaxation: u64,
+    /// Duration of a long break. Use `relaxation` if omitted.
+    #[serde(default, deserialize_with = "seconds::deserialize_option")]
+    pub long_break: Option<u64>,
+    /// Number of concentration stages before a long break. Long breaks are
+    /// disabled if it is zero.
+    #[serde(default)]
+    pub cycles_before_long_break: u32,
+    /// Kind of the stage which the timer starts from. Start from the first
+    /// stage if omitted.
+    #[serde(default)]
+    pub initial_stage: Option<StageKindOption>,
+    /// How the timer goes through the stages.
+    #[serde(default)]
+    pub mode: ModeOption,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ModeOption {
+    /// Go through every stage.
+    #[default]
+    Full,
+    /// Skip preparations, alternating concentrations and relaxations only.
+    Simple,
 }
 
 #[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
 pub struct NotificationSection {
+    /// How notifications are shown.
+    #[serde(default)]
+    pub backend: NotificationBackendOption,
+    /// Shell command run by the `command` backend.
+    #[serde(default)]
+    pub command: Option<String>,
+    /// Seconds within which a notification following another one is
+    /// dropped. Nothing is dropped if omitted or zero.
+    #[serde(default, deserialize_with = "seconds::deserialize_option")]
+    pub min_interval: Option<u64>,
+    /// Most characters in the summary or the body of any notification. There
+    /// is no limit if omitted.
+    #[serde(default)]
+    pub max_length: Option<usize>,
     pub preparation: MessageSection,
     pub concentration: MessageSection,
     pub relaxation: MessageSection,
 }
 
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationBackendOption {
+    #[default]
+    Desktop,
+    Command,
+}
+
 #[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
 pub struct MessageSection {
     pub summary: String,
     pub body: Option<String>,
+    #[serde(default)]
+    pub urgency: UrgencyOption,
+    #[serde(default)]
+    pub timeout_ms: Option<u32>,
+    #[serde(default)]
+    pub sound: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum UrgencyOption {
+    Low,
+    #[default]
+    Normal,
+    Critical,
+}
+
+/// One stage of a custom sequence, with its own duration and notification.
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+pub struct StageSection {
+    pub name: String,
+    pub kind: StageKindOption,
+    #[serde(deserialize_with = "seconds::deserialize")]
+    pub duration: u64,
+    pub summary: String,
+    pub body: Option<String>,
+    #[serde(default)]
+    pub urgency: UrgencyOption,
+    #[serde(default)]
+    pub timeout_ms: Option<u32>,
+    #[serde(default)]
+    pub sound: Option<String>,
+    /// Offsets before the end of the stage at which to notify.
+    #[serde(default, deserialize_with = "seconds::deserialize_vec")]
+    pub checkpoints: Vec<u64>,
+    #[serde(default)]
+    pub checkpoint_summary: Option<String>,
+    #[serde(default)]
+    pub checkpoint_body: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Clone, PartialEq, Eq, Default)]
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StageKindOption {
+    Preparation,
+    Concentration,
+    Relaxation,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
 pub struct RuntimeSection {
+    /// Path of the UNIX socket, or the name of an abstract one on Linux if it
+    /// starts with `@`.
     #[serde(default)]
     pub socket: Option<PathBuf>,
     #[serde(default)]
     pub pid: Option<PathBuf>,
+    /// File in which the progress of the timers is saved.
+    #[serde(default)]
+    pub state: Option<PathBuf>,
+    /// Address on which the daemon listens over TCP instead of `socket`.
+    #[serde(default)]
+    pub tcp_address: Option<SocketAddr>,
+    /// Signals on which the daemon shuts down gracefully.
+    #[serde(default = "RuntimeSection::default_shutdown_signals")]
+    pub shutdown_signals: Vec<SignalOption>,
+    /// File to which a line of CSV is appended for every completed stage.
+    #[serde(default)]
+    pub stats_file: Option<PathBuf>,
+    /// Permission bits of `socket`, which decide who may control the timer.
+    #[serde(default = "RuntimeSection::default_socket_mode")]
+    pub socket_mode: u32,
+    /// Whether a timer starting afresh waits in the paused state.
+    #[serde(default)]
+    pub start_paused: bool,
+}
+
+impl RuntimeSection {
+    fn default_shutdown_signals() -> Vec<SignalOption> {
+        vec![SignalOption::Sigterm, SignalOption::Sigint]
+    }
+
+    fn default_socket_mode() -> u32 {
+        0o600
+    }
+}
+
+impl Default for RuntimeSection {
+    fn default() -> Self {
+        Self {
+            socket: None,
+            pid: None,
+            state: None,
+            tcp_address: None,
+            shutdown_signals: Self::default_shutdown_signals(),
+            stats_file: None,
+            socket_mode: Self::default_socket_mode(),
+            start_paused: false,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum SignalOption {
+    Sigterm,
+    Sigint,
+    Sighup,
+    Sigquit,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+pub struct AutoPauseSection {
+    #[serde(default = "AutoPauseSection::default_enabled")]
+    pub screen_lock: bool,
+    #[serde(default = "AutoPauseSection::default_enabled")]
+    pub low_battery: bool,
+    /// Seconds without any input after which the timer is paused. Idle
+    /// detection is disabled if omitted or zero.
+    #[serde(default, deserialize_with = "seconds::deserialize_option")]
+    pub idle: Option<u64>,
+    /// Seconds after which a manual pause ends and the timer resumes by
+    /// itself. The timer stays paused until resumed if omitted or zero.
+    #[serde(default, deserialize_with = "seconds::deserialize_option")]
+    pub max_pause: Option<u64>,
+}
+
+impl AutoPauseSection {
+    fn default_enabled() -> bool {
+        true
+    }
+}
+
+impl Default for AutoPauseSection {
+    fn default() -> Self {
+        Self {
+            screen_lock: Self::default_enabled(),
+            low_battery: Self::default_enabled(),
+            idle: None,
+            max_pause: None,
+        }
+    }
+}
+
+/// Options read only by the client. Command line flags take precedence.
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq, Default)]
+pub struct ClientSection {
+    /// Maximum seconds to wait for a request. Wait forever if it is zero,
+    /// and the client picks a default if omitted.
+    #[serde(default)]
+    pub request_timeout: Option<u64>,
+    /// Number of extra attempts to connect to the daemon.
+    #[serde(default)]
+    pub retries: u32,
+    /// Maximum seconds to keep connecting to the daemon with growing waits.
+    /// Connect only once if omitted.
+    #[serde(default)]
+    pub connect_deadline: Option<u64>,
+    #[serde(default)]
+    pub default_format: Option<FormatOption>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FormatOption {
+    Plain,
+    Toml,
+    Json,
 }
 
 #[cfg(test)]
@@ -54,27 +275,274 @@ mod tests {
                 preparation: 900,
                 concentration: 2400,
                 relaxation: 600,
+                long_break: Some(1800),
+                cycles_before_long_break: 4,
+                initial_stage: None,
+                mode: ModeOption::Full,
             },
             notification: NotificationSection {
+                backend: NotificationBackendOption::Desktop,
+                command: None,
+                min_interval: None,
+                max_length: None,
                 preparation: MessageSection {
                     summary: "Preparation Stage End".to_owned(),
                     body: Some("It's time to start concentrating on learning.".to_owned()),
+                    urgency: UrgencyOption::Normal,
+                    timeout_ms: None,
+                    sound: None,
                 },
                 concentration: MessageSection {
                     summary: "Concentration Stage End".to_owned(),
                     body: Some("Well done! Remember to have a rest.".to_owned()),
+                    urgency: UrgencyOption::Normal,
+                    timeout_ms: None,
+                    sound: None,
                 },
                 relaxation: MessageSection {
                     summary: "Relaxation Stage End".to_owned(),
                     body: Some("Feel energetic now? Let's continue.".to_owned()),
+                    urgency: UrgencyOption::Normal,
+                    timeout_ms: None,
+                    sound: None,
                 },
             },
             runtime: RuntimeSection {
                 socket: None,
                 pid: None,
+                state: None,
+                tcp_address: None,
+                shutdown_signals: vec![SignalOption::Sigterm, SignalOption::Sigint],
+                stats_file: None,
+                socket_mode: 0o600,
+                start_paused: false,
             },
+            auto_pause: AutoPauseSection {
+                screen_lock: true,
+                low_battery: true,
+                idle: None,
+                max_pause: None,
+            },
+            client: ClientSection::default(),
+            stages: Vec::new(),
+        };
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn deserialize_auto_pause_default() {
+        let actual: AutoPauseSection = toml::from_str("").unwrap();
+        assert_eq!(actual, AutoPauseSection::default());
+
+        let actual: AutoPauseSection = toml::from_str("screen_lock = false").unwrap();
+        let expected = AutoPauseSection {
+            screen_lock: false,
+            low_battery: true,
+            idle: None,
+            max_pause: None,
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn deserialize_stages() {
+        let content = r#"
+            [duration]
+            preparation = 1
+            concentration = 2
+            relaxation = 3
+
+            [notification.preparation]
+            summary = "Preparation"
+
+            [notification.concentration]
+            summary = "Concentration"
+
+            [notification.relaxation]
+            summary = "Relaxation"
+
+            [[stages]]
+            name = "Deep Work"
+            kind = "concentration"
+            duration = "50m"
+            summary = "Deep Work End"
+            urgency = "critical"
+            checkpoints = ["10m", 60]
+            checkpoint_summary = "Deep Work Ending"
+
+            [[stages]]
+            name = "Break"
+            kind = "relaxation"
+            duration = 600
+            summary = "Break End"
+            body = "Let's continue."
+        "#;
+        let actual: Configuration = toml::from_str(content).unwrap();
+        let expected = vec![
+            StageSection {
+                name: "Deep Work".to_owned(),
+                kind: StageKindOption::Concentration,
+                duration: 3000,
+                summary: "Deep Work End".to_owned(),
+                body: None,
+                urgency: UrgencyOption::Critical,
+                timeout_ms: None,
+                sound: None,
+                checkpoints: vec![600, 60],
+                checkpoint_summary: Some("Deep Work Ending".to_owned()),
+                checkpoint_body: None,
+            },
+            StageSection {
+                name: "Break".to_owned(),
+                kind: StageKindOption::Relaxation,
+                duration: 600,
+                summary: "Break End".to_owned(),
+                body: Some("Let's continue.".to_owned()),
+                urgency: UrgencyOption::Normal,
+                timeout_ms: None,
+                sound: None,
+                checkpoints: Vec::new(),
+                checkpoint_summary: None,
+                checkpoint_body: None,
+            },
+        ];
+        assert_eq!(actual.stages, expected);
+    }
+
+    #[test]
+    fn deserialize_notification_backend() {
+        let content = r#"
+            backend = "command"
+            command = "notify.sh"
+
+            [preparation]
+            summary = "Preparation"
+
+            [concentration]
+            summary = "Concentration"
+
+            [relaxation]
+            summary = "Relaxation"
+        "#;
+        let actual: NotificationSection = toml::from_str(content).unwrap();
+        assert_eq!(actual.backend, NotificationBackendOption::Command);
+        assert_eq!(actual.command.as_deref(), Some("notify.sh"));
+    }
+
+    #[test]
+    fn deserialize_runtime_section() {
+        let content = DEFAULT_CONTENT
+            .replace("# [runtime]", "[runtime]")
+            .replace("# socket = ", "socket = ")
+            .replace("# pid = ", "pid = ");
+        let actual: Configuration = toml::from_str(&content).unwrap();
+        assert_eq!(
+            actual.runtime.socket,
+            Some(PathBuf::from("/path/to/unix/socket"))
+        );
+        assert_eq!(actual.runtime.pid, Some(PathBuf::from("/path/to/pid/file")));
+
+        let actual: Configuration = toml::from_str(DEFAULT_CONTENT).unwrap();
+        assert_eq!(actual.runtime, RuntimeSection::default());
+    }
+
+    #[test]
+    fn deserialize_runtime_shutdown_signals() {
+        let actual: RuntimeSection = toml::from_str("").unwrap();
+        assert_eq!(actual, RuntimeSection::default());
+
+        let actual: RuntimeSection = toml::from_str(r#"shutdown_signals = ["SIGHUP"]"#).unwrap();
+        assert_eq!(actual.shutdown_signals, vec![SignalOption::Sighup]);
+
+        let content = r#"shutdown_signals = ["SIGKILL"]"#;
+        assert!(toml::from_str::<RuntimeSection>(content).is_err());
+    }
+
+    #[test]
+    fn deserialize_runtime_socket_mode() {
+        let actual: RuntimeSection = toml::from_str("").unwrap();
+        assert_eq!(actual.socket_mode, 0o600);
+
+        let actual: RuntimeSection = toml::from_str("socket_mode = 0o660").unwrap();
+        assert_eq!(actual.socket_mode, 0o660);
+    }
+
+    #[test]
+    fn deserialize_client() {
+        let actual: ClientSection = toml::from_str("").unwrap();
+        let expected = ClientSection {
+            request_timeout: None,
+            retries: 0,
+            connect_deadline: None,
+            default_format: None,
         };
+        assert_eq!(actual, expected);
 
+        let content = r#"
+            request_timeout = 5
+            retries = 2
+            connect_deadline = 3
+            default_format = "toml"
+        "#;
+        let actual: ClientSection = toml::from_str(content).unwrap();
+        let expected = ClientSection {
+            request_timeout: Some(5),
+            retries: 2,
+            connect_deadline: Some(3),
+            default_format: Some(FormatOption::Toml),
+        };
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn deserialize_duration_without_long_break() {
+        let actual: DurationSection =
+            toml::from_str("preparation = 1\nconcentration = 2\nrelaxation = 3").unwrap();
+        assert_eq!(actual.long_break, None);
+        assert_eq!(actual.cycles_before_long_break, 0);
+        assert_eq!(actual.initial_stage, None);
+        assert_eq!(actual.mode, ModeOption::Full);
+    }
+
+    #[test]
+    fn deserialize_duration_initial_stage() {
+        let content = "preparation = 1\nconcentration = 2\nrelaxation = 3\n";
+        let actual: DurationSection =
+            toml::from_str(&format!("{content}initial_stage = \"concentration\"")).unwrap();
+        assert_eq!(actual.initial_stage, Some(StageKindOption::Concentration));
+
+        let actual =
+            toml::from_str::<DurationSection>(&format!("{content}initial_stage = \"nap\""));
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn deserialize_duration_mode() {
+        let content = "preparation = 1\nconcentration = 2\nrelaxation = 3\n";
+        let actual: DurationSection =
+            toml::from_str(&format!("{content}mode = \"simple\"")).unwrap();
+        assert_eq!(actual.mode, ModeOption::Simple);
+    }
+
+    #[test]
+    fn deserialize_duration_with_units() {
+        let content = r#"
+            preparation = "15m"
+            concentration = "1h30m"
+            relaxation = 600
+            long_break = "1800s"
+        "#;
+        let actual: DurationSection = toml::from_str(content).unwrap();
+        assert_eq!(actual.preparation, 900);
+        assert_eq!(actual.concentration, 5400);
+        assert_eq!(actual.relaxation, 600);
+        assert_eq!(actual.long_break, Some(1800));
+
+        let content = "preparation = \"15x\"\nconcentration = 1\nrelaxation = 1";
+        assert!(toml::from_str::<DurationSection>(content).is_err());
+
+        let content = "preparation = -1\nconcentration = 1\nrelaxation = 1";
+        assert!(toml::from_str::<DurationSection>(content).is_err());
+    }
 }
@@ -0,0 +1,259 @@
+use std::path::{Path, PathBuf};
+
+use snafu::prelude::*;
+
+use crate::daemon::config::content::{Configuration, MessageSection};
+use crate::domain::entity::StageState;
+
+/// Prefix of environment variables which override configuration values.
+pub const ENV_PREFIX: &str = "TO_CONCENTRATE";
+
+/// Environment variable holding the path of the configuration file.
+pub const CONFIG_ENV: &str = "TO_CONCENTRATE_CONFIG";
+
+/// Resolve the path of the configuration file, preferring `flag` to the
+/// [`CONFIG_ENV`] variable looked up by `lookup`. Return `None` to fall back
+/// to the XDG base directories. An empty variable counts as unset.
+pub fn config_path<F>(flag: Option<&Path>, lookup: F) -> Option<PathBuf>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    match flag {
+        Some(path) => Some(path.to_path_buf()),
+        None => lookup(CONFIG_ENV)
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from),
+    }
+}
+
+/// Override notification messages with values looked up by `lookup`. Keys are
+/// named `TO_CONCENTRATE_NOTIFICATION_<STAGE>_<FIELD>`, where `<STAGE>` is
+/// one of `PREPARATION`, `CONCENTRATION` and `RELAXATION`, and `<FIELD>` is
+/// either `SUMMARY` or `BODY`.
+///
+/// # Errors
+///
+/// This function will return an error if any override is an empty string.
+pub fn override_notification<F>(
+    config: &mut Configuration,
+    lookup: F,
+) -> Result<(), OverrideConfigurationError>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let sections = [
+        (
+            StageState::Preparation,
+            &mut config.notification.preparation,
+        ),
+        (
+            StageState::Concentration,
+            &mut config.notification.concentration,
+        ),
+        (StageState::Relaxation, &mut config.notification.relaxation),
+    ];
+
+    for (stage, section) in sections {
+        override_message(&stage.to_string().to_uppercase(), section, &lookup)?;
+    }
+
+    Ok(())
+}
+
+/// Expand environment variables looked up by `lookup` in the paths of the
+/// `runtime` section. A variable is written as `$NAME` or `${NAME}`, and a
+/// `$` followed by anything else is kept as it is.
+///
+/// # Errors
+///
+/// This function will return an error if a variable is undefined or a
+/// `${` is not closed.
+pub fn expand_runtime<F>(
+    config: &mut Configuration,
+    lookup: F,
+) -> Result<(), OverrideConfigurationError>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let runtime = &mut config.runtime;
+    let paths = [
+        &mut runtime.socket,
+        &mut runtime.pid,
+        &mut runtime.state,
+        &mut runtime.stats_file,
+    ];
+
+    for path in paths.into_iter().flatten() {
+        // A path which is not valid UTF-8 could not name any variable.
+        if let Some(raw) = path.to_str() {
+            *path = PathBuf::from(expand(raw, &lookup)?);
+        }
+    }
+
+    Ok(())
+}
+
+fn expand<F>(raw: &str, lookup: &F) -> Result<String, OverrideConfigurationError>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let is_name = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut expanded = String::with_capacity(raw.len());
+    let mut rest = raw;
+
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+
+        let (name, next) = if let Some(braced) = after.strip_prefix('{') {
+            let end = braced
+                .find('}')
+                .context(UnclosedVariableSnafu { path: raw })?;
+            (&braced[..end], &braced[end + 1..])
+        } else {
+            let end = after.find(|c| !is_name(c)).unwrap_or(after.len());
+            (&after[..end], &after[end..])
+        };
+
+        if name.is_empty() {
+            expanded.push('$');
+            rest = after;
+            continue;
+        }
+
+        let value = lookup(name).context(UndefinedVariableSnafu { name, path: raw })?;
+        expanded.push_str(&value);
+        rest = next;
+    }
+
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+fn override_message<F>(
+    stage: &str,
+    section: &mut MessageSection,
+    lookup: &F,
+) -> Result<(), OverrideConfigurationError>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let key = format!("{ENV_PREFIX}_NOTIFICATION_{stage}_SUMMARY");
+    if let Some(summary) = lookup(&key) {
+        ensure!(!summary.is_empty(), EmptyValueSnafu { key });
+        section.summary = summary;
+    }
+
+    let key = format!("{ENV_PREFIX}_NOTIFICATION_{stage}_BODY");
+    if let Some(body) = lookup(&key) {
+        ensure!(!body.is_empty(), EmptyValueSnafu { key });
+        section.body = Some(body);
+    }
+
+    Ok(())
+}
+
+/// An error type for overriding configuration values.
+#[derive(Debug, Snafu, Clone)]
+#[non_exhaustive]
+pub enum OverrideConfigurationError {
+    #[snafu(display("Could not override configuration with empty {key}"))]
+    EmptyValue { key: String },
+    #[snafu(display("Could not expand undefined variable {name} in {path}"))]
+    UndefinedVariable { name: String, path: String },
+    #[snafu(display("Could not expand unclosed variable in {path}"))]
+    UnclosedVariable { path: String },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    use crate::daemon::config::reader::DEFAULT_CONTENT;
+
+    #[test]
+    fn override_notification_values() {
+        let mut config: Configuration = toml::from_str(DEFAULT_CONTENT).unwrap();
+        let vars = HashMap::from([
+            (
+                "TO_CONCENTRATE_NOTIFICATION_CONCENTRATION_SUMMARY",
+                "summary",
+            ),
+            ("TO_CONCENTRATE_NOTIFICATION_RELAXATION_BODY", "body"),
+        ]);
+        let lookup = |key: &str| vars.get(key).map(|value| value.to_string());
+
+        override_notification(&mut config, lookup).unwrap();
+        assert_eq!(config.notification.concentration.summary, "summary");
+        assert_eq!(config.notification.relaxation.body.as_deref(), Some("body"));
+        assert_eq!(
+            config.notification.preparation.summary,
+            "Preparation Stage End"
+        );
+    }
+
+    #[test]
+    fn expand_runtime_paths() {
+        let mut config: Configuration = toml::from_str(DEFAULT_CONTENT).unwrap();
+        config.runtime.socket = Some(PathBuf::from("${XDG_RUNTIME_DIR}/tc.sock"));
+        config.runtime.pid = Some(PathBuf::from("$HOME/tc.pid"));
+        config.runtime.state = Some(PathBuf::from("/tmp/$/tc.state"));
+        let vars = HashMap::from([("XDG_RUNTIME_DIR", "/run/user/1000"), ("HOME", "/home/me")]);
+        let lookup = |key: &str| vars.get(key).map(|value| value.to_string());
+
+        expand_runtime(&mut config, lookup).unwrap();
+        let runtime = &config.runtime;
+        assert_eq!(
+            runtime.socket,
+            Some(PathBuf::from("/run/user/1000/tc.sock"))
+        );
+        assert_eq!(runtime.pid, Some(PathBuf::from("/home/me/tc.pid")));
+        assert_eq!(runtime.state, Some(PathBuf::from("/tmp/$/tc.state")));
+        assert_eq!(runtime.stats_file, None);
+    }
+
+    #[test]
+    fn expand_runtime_error() {
+        let mut config: Configuration = toml::from_str(DEFAULT_CONTENT).unwrap();
+        config.runtime.socket = Some(PathBuf::from("$MISSING/tc.sock"));
+        assert!(matches!(
+            expand_runtime(&mut config, |_| None),
+            Err(OverrideConfigurationError::UndefinedVariable { name, .. }) if name == "MISSING"
+        ));
+
+        config.runtime.socket = Some(PathBuf::from("${HOME/tc.sock"));
+        assert!(matches!(
+            expand_runtime(&mut config, |_| Some("/home/me".to_owned())),
+            Err(OverrideConfigurationError::UnclosedVariable { .. })
+        ));
+    }
+
+    #[test]
+    fn config_path_precedence() {
+        let flag = Path::new("/flag/config.toml");
+        let lookup = |key: &str| (key == CONFIG_ENV).then(|| "/env/config.toml".to_owned());
+
+        assert_eq!(config_path(Some(flag), lookup), Some(flag.to_path_buf()));
+        assert_eq!(
+            config_path(None, lookup),
+            Some(PathBuf::from("/env/config.toml"))
+        );
+        assert_eq!(config_path(None, |_| None), None);
+        assert_eq!(config_path(None, |_| Some(String::new())), None);
+    }
+
+    #[test]
+    fn override_notification_error_empty_value() {
+        let mut config: Configuration = toml::from_str(DEFAULT_CONTENT).unwrap();
+        let lookup = |key: &str| {
+            (key == "TO_CONCENTRATE_NOTIFICATION_PREPARATION_SUMMARY").then(String::new)
+        };
+
+        assert!(matches!(
+            override_notification(&mut config, lookup),
+            Err(OverrideConfigurationError::EmptyValue { .. })
+        ));
+    }
+}
@@ -1,10 +1,18 @@
 mod content;
+mod env;
 mod reader;
+mod seconds;
 
 use std::path::Path;
 
-pub use content::Configuration;
-pub use reader::ReadContentError;
+pub use content::{
+    AutoPauseSection, ClientSection, Configuration, DurationSection, FormatOption, MessageSection,
+    ModeOption, NotificationBackendOption, NotificationSection, RuntimeSection, SignalOption,
+    StageKindOption, StageSection, UrgencyOption,
+};
+pub use env::{config_path, OverrideConfigurationError, CONFIG_ENV, ENV_PREFIX};
+pub use reader::{ReadContentError, DEFAULT_CONTENT};
+pub use seconds::{parse as parse_seconds, ParseSecondsError};
 
 use snafu::prelude::*;
 use toml::de::Error as DeError;
@@ -23,10 +31,13 @@ pub enum LoadConfigurationError {
     Read { source: ReadContentError },
     #[snafu(display("Could not parse invalid configurations"))]
     Parse { source: DeError },
+    #[snafu(display("Could not override configurations with environment variables"))]
+    Override { source: OverrideConfigurationError },
 }
 
 /// Read configuration from given path. Optionally create one from default
-/// template if it doesn't exists.
+/// template if it doesn't exists. Values are then overridden by environment
+/// variables if present, and those in runtime paths are expanded.
 ///
 /// # Errors
 ///
@@ -36,10 +47,42 @@ pub fn load<P: AsRef<Path>>(
     path: P,
     create_new: bool,
 ) -> Result<Configuration, LoadConfigurationError> {
+    load_with_lookup(path, create_new, |key| std::env::var(key).ok())
+}
+
+/// Read configuration like [`load`], with environment variables looked up by
+/// `lookup`.
+fn load_with_lookup<P, F>(
+    path: P,
+    create_new: bool,
+    lookup: F,
+) -> Result<Configuration, LoadConfigurationError>
+where
+    P: AsRef<Path>,
+    F: Fn(&str) -> Option<String>,
+{
     let content = ContentReader::new(path.as_ref(), create_new)
         .read()
         .context(ReadSnafu)?;
-    toml::from_str(&content).context(ParseSnafu)
+    let mut config = toml::from_str(&content).context(ParseSnafu)?;
+    env::override_notification(&mut config, &lookup).context(OverrideSnafu)?;
+    env::expand_runtime(&mut config, &lookup).context(OverrideSnafu)?;
+    Ok(config)
+}
+
+/// Build configuration from the default template without touching any file.
+/// Values are then overridden by environment variables if present, and those
+/// in runtime paths are expanded.
+///
+/// # Errors
+///
+/// This function will return an error if overriding configuration fails.
+pub fn load_default() -> Result<Configuration, LoadConfigurationError> {
+    let mut config = toml::from_str(reader::DEFAULT_CONTENT).context(ParseSnafu)?;
+    env::override_notification(&mut config, |key| std::env::var(key).ok())
+        .context(OverrideSnafu)?;
+    env::expand_runtime(&mut config, |key| std::env::var(key).ok()).context(OverrideSnafu)?;
+    Ok(config)
 }
 
 /// Read configuration from a custom path. This won't create any new file by
@@ -53,16 +96,83 @@ pub fn load_with_path<P: AsRef<Path>>(path: P) -> Result<Configuration, LoadConf
     load(path, false)
 }
 
-/// Read configuration from XDG configuration directory. Create one from default
-/// template if it doesn't exists.
+/// Read configuration from XDG configuration directory. Optionally create one
+/// from default template if it doesn't exists.
 ///
 /// # Errors
 ///
 /// This function will return an error if reading content from file fails or
 /// parsing configuration fails.
-pub fn load_with_xdg(app_name: String) -> Result<Configuration, LoadConfigurationError> {
+pub fn load_with_xdg(
+    app_name: String,
+    create_new: bool,
+) -> Result<Configuration, LoadConfigurationError> {
+    // The directory is only created along with a new file.
     let path = Xdg::new(Path::new(&app_name))
-        .and_then(|xdg| xdg.resolve_create(XdgBaseKind::Config, "config.toml"))
+        .and_then(|xdg| xdg.resolve(XdgBaseKind::Config, "config.toml"))
         .context(XdgConfigSnafu)?;
-    load(path, true)
+    load(path, create_new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+
+    use assert_fs::prelude::*;
+    use assert_fs::TempDir;
+    use predicates::path as path_pred;
+
+    use crate::daemon::repository::NotificationConfiguration;
+    use crate::domain::repository::NotificationRepository;
+
+    #[test]
+    fn load_without_creation() {
+        let tmp = TempDir::new().expect("Test environment should support temporary directories");
+        let file = tmp.child("config.toml");
+        file.assert(path_pred::missing());
+
+        assert!(matches!(
+            load(&file, false),
+            Err(LoadConfigurationError::Read {
+                source: ReadContentError::NotFound { .. }
+            })
+        ));
+        file.assert(path_pred::missing());
+    }
+
+    #[test]
+    fn load_with_creation() {
+        let tmp = TempDir::new().expect("Test environment should support temporary directories");
+        let dir = tmp.child("to-concentrate");
+        let file = dir.child("config.toml");
+
+        // A missing file in a missing directory, as in a fresh XDG
+        // configuration directory, is left alone unless it should be created.
+        assert!(load(&file, false).is_err());
+        dir.assert(path_pred::missing());
+
+        let config = load(&file, true).unwrap();
+        file.assert(reader::DEFAULT_CONTENT);
+        assert_eq!(config, load(&file, false).unwrap());
+    }
+
+    #[tokio::test]
+    async fn load_with_environment_override() {
+        let tmp = TempDir::new().expect("Test environment should support temporary directories");
+        let file = tmp.child("config.toml");
+        file.write_str(reader::DEFAULT_CONTENT).unwrap();
+
+        let lookup = |key: &str| {
+            (key == "TO_CONCENTRATE_NOTIFICATION_RELAXATION_SUMMARY")
+                .then(|| "Overridden".to_owned())
+        };
+        let config = load_with_lookup(&file, false, lookup).unwrap();
+
+        let repository = NotificationConfiguration::new(Arc::new(config));
+        let message = repository.relaxation_notification().await.unwrap();
+        assert_eq!(message.summary(), "Overridden");
+        assert_eq!(message.body(), Some("Feel energetic now? Let's continue."));
+    }
 }
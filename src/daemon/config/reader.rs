@@ -1,22 +1,56 @@
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{Error as IoError, ErrorKind, Read, Seek, Write};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use snafu::prelude::*;
 
+/// The configuration template, which is written when no configuration file
+/// exists and printed by `config generate`.
 pub const DEFAULT_CONTENT: &str = r#"
 # This configuration file is generated automatically. Feel free to do some
 # modification.
 
-# The `duration` section specifies the duration of each stage in seconds.
+# The `duration` section specifies the duration of each stage in seconds, or
+# with units like "25m" and "1h30m".
+# Every `cycles_before_long_break` concentration stages, the relaxation stage
+# lasts for `long_break` instead. Set it to 0 to disable long breaks.
+# `initial_stage` is one of "preparation", "concentration" and "relaxation",
+# and makes the timer start from the first stage of that kind instead of the
+# very first stage, e.g. to skip the preparation.
+# `mode` is "full" to go through every stage, or "simple" to skip
+# preparations and alternate concentrations and relaxations only.
 [duration]
 preparation = 900
 concentration = 2400
 relaxation = 600
+long_break = 1800
+cycles_before_long_break = 4
+# initial_stage = "concentration"
+# mode = "full"
 
 # The `notification.<stage>` section specifies the message shown in desktop
-# notifications. `body` is optional.
+# notifications. `body` is optional. `urgency` is one of "low", "normal" and
+# "critical", and defaults to "normal". `timeout_ms` is how long the
+# notification stays on screen, where 0 keeps it until dismissed, and defaults
+# to the notification server's choice. `sound` names a sound from the XDG sound
+# theme, e.g. "complete", played if the notification server supports it.
+# `{next_stage}` and `{next_duration}` in `summary` and `body` are replaced
+# with the upcoming stage's name and length, e.g. "Next up: {next_stage}
+# ({next_duration})" shows "Next up: Relaxation (05:00)". `{cycle}` is replaced
+# with the number of the current round of stages, counting from 1, e.g.
+# "Concentration #{cycle} complete".
+# Set `notification.backend` to "command" to run a shell command instead,
+# which reads the stage's name, the summary and the body from the environment
+# variables `TC_STAGE`, `TC_SUMMARY`, `TC_BODY` and `TC_URGENCY`.
+# `min_interval` drops a notification which comes within that many seconds of
+# the last one shown, e.g. while skipping through stages. `max_length` rejects
+# a summary or body with more characters, which desktops may cut off.
+# [notification]
+# backend = "command"
+# command = "notify.sh"
+# min_interval = 10
+# max_length = 200
 [notification.preparation]
 summary = "Preparation Stage End"
 body = "It's time to start concentrating on learning."
@@ -24,17 +58,71 @@ body = "It's time to start concentrating on learning."
 [notification.concentration]
 summary = "Concentration Stage End"
 body = "Well done! Remember to have a rest."
+# timeout_ms = 0
+# sound = "complete"
 
 [notification.relaxation]
 summary = "Relaxation Stage End"
 body = "Feel energetic now? Let's continue."
 
+# The `stages` list replaces the three stages above with a custom sequence.
+# Each stage has a `kind` of "preparation", "concentration" or "relaxation",
+# and the timer starts over from the first stage which is not a preparation
+# after the last one. The durations and notifications above are ignored then,
+# except that long breaks still replace relaxation stages. A stage may list
+# `checkpoints`, offsets before its end at which `checkpoint_summary` and
+# `checkpoint_body` are shown.
+# [[stages]]
+# name = "Deep Work"
+# kind = "concentration"
+# duration = "50m"
+# summary = "Deep Work End"
+# body = "Take a short break."
+# urgency = "critical"
+# timeout_ms = 10000
+# checkpoints = ["10m", "1m"]
+# checkpoint_summary = "Deep Work is ending soon"
+# checkpoint_body = "Wrap up what you are doing."
+
+# The `auto_pause` section specifies which external events pause the timer
+# automatically. Send them with `to-concentrate on-lock` and
+# `to-concentrate on-unlock`, or `to-concentrate on-low-battery` and
+# `to-concentrate on-ac-power`. If `idle` is set, the timer is also paused
+# after the keyboard and mouse have not been touched for that long, and
+# resumed once they are used again. If `max_pause` is set, a manual pause
+# lasting that long ends and the timer resumes by itself.
+[auto_pause]
+screen_lock = true
+low_battery = true
+# idle = "5m"
+# max_pause = "30m"
+
 # The `runtime` section specifies the paths to some runtime files. Leave
-# them empty to use default settings. Currently environment variables is not
-# supported.
+# them empty to use default settings. Environment variables like `$HOME` or
+# `${XDG_RUNTIME_DIR}` in these paths are expanded. If `tcp_address` is set, the daemon listens on it instead of the
+# UNIX socket, e.g. for a client in another container. Both sides must use it.
+# `socket_mode` is the permission of the UNIX socket, which only lets its
+# owner control the timer by default. On Linux, a `socket` starting with `@`,
+# like "@to-concentrate", names an abstract socket instead, which leaves no
+# file behind and has no permission.
 # [runtime]
 # socket = "/path/to/unix/socket"
-# runtime = "/path/to/pid/file"
+# pid = "/path/to/pid/file"
+# state = "/path/to/state/file"
+# tcp_address = "127.0.0.1:7250"
+# shutdown_signals = ["SIGTERM", "SIGINT"]
+# socket_mode = 0o600
+# stats_file = "/path/to/stats.csv"
+# start_paused = false
+
+# The `client` section tunes the client only. Flags on the command line
+# override these options. `request_timeout` defaults to 5 seconds after
+# connecting, and 0 waits forever.
+# [client]
+# request_timeout = 5
+# retries = 0
+# connect_deadline = 3
+# default_format = "plain"
 "#;
 
 /// A reader which reads the configuration content and creates a default
@@ -80,6 +168,9 @@ impl ContentReader {
         match File::open(path.as_path()) {
             Ok(file) => Ok(file),
             Err(err) => match err.kind() {
+                ErrorKind::NotFound if Self::is_dangling_symlink(path.as_path()) => {
+                    DanglingSymlinkSnafu { path }.fail()
+                }
                 ErrorKind::NotFound => {
                     if create_new {
                         Self::create_configuration(path.as_path())
@@ -94,12 +185,24 @@ impl ContentReader {
         }
     }
 
-    /// Create a default configuration file.
+    /// Check whether the path is a symbolic link whose target doesn't exist.
+    fn is_dangling_symlink(path: &Path) -> bool {
+        fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_symlink())
+    }
+
+    /// Create a default configuration file, along with its leading
+    /// directories if they didn't exist before.
     ///
     /// # Errors
     ///
     /// This function will return an error if the creation fails.
     fn create_configuration<P: AsRef<Path>>(path: P) -> Result<File, ReadContentError> {
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent).context(FileSystemSnafu {
+                when: "Creating configuration directory",
+            })?;
+        }
+
         let mut file = File::options()
             .read(true)
             .write(true)
@@ -129,6 +232,11 @@ impl ContentReader {
 pub enum ReadContentError {
     #[snafu(display("Could not open inexistent file {}", path.display()))]
     NotFound { path: PathBuf },
+    #[snafu(display(
+        "Could not open {} which links to a missing target, fix or remove the link",
+        path.display()
+    ))]
+    DanglingSymlink { path: PathBuf },
     #[snafu(display("Could not create default configuration: {when}"))]
     FileSystem {
         when: String,
@@ -150,9 +258,9 @@ mod tests {
         let tmp = TempDir::new().expect("Test environment should support temporary directories");
         let file = tmp.child("config.toml");
         let content = "content for testing";
-        file.write_str(&content).unwrap();
+        file.write_str(content).unwrap();
 
-        let reader = ContentReader::new(file.to_path_buf(), false);
+        let reader = ContentReader::new(&file, false);
         assert_eq!(reader.read().unwrap(), content);
     }
 
@@ -167,6 +275,21 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn open_configuration_dangling_symlink() {
+        let tmp = TempDir::new().expect("Test environment should support temporary directories");
+        let target = tmp.child("missing.toml");
+        let file = tmp.child("config.toml");
+        file.symlink_to_file(target.path()).unwrap();
+        target.assert(path_pred::missing());
+
+        assert!(matches!(
+            ContentReader::open_configuration(file.to_path_buf(), true),
+            Err(ReadContentError::DanglingSymlink { .. })
+        ));
+        target.assert(path_pred::missing());
+    }
+
     #[test]
     fn create_configuration() {
         let tmp = TempDir::new().expect("Test environment should support temporary directories");
@@ -175,4 +298,12 @@ mod tests {
         assert!(ContentReader::open_configuration(file.to_path_buf(), true).is_ok());
         file.assert(DEFAULT_CONTENT);
     }
+
+    #[test]
+    fn create_configuration_with_directory() {
+        let tmp = TempDir::new().expect("Test environment should support temporary directories");
+        let file = tmp.child("to-concentrate").child("config.toml");
+        assert!(ContentReader::open_configuration(file.to_path_buf(), true).is_ok());
+        file.assert(DEFAULT_CONTENT);
+    }
 }
@@ -0,0 +1,147 @@
+use std::fmt::{Formatter, Result as FmtResult};
+
+use serde::de::{Error as DeError, Visitor};
+use serde::{Deserialize, Deserializer};
+use snafu::prelude::*;
+
+/// Parse a human-friendly duration such as `"25m"`, `"1500s"` or `"1h30m"`
+/// into seconds. Units are `h`, `m` and `s`, each used at most once and in
+/// this order. A bare number is taken as seconds.
+///
+/// # Errors
+///
+/// This function will return an error if the string is not a valid duration.
+pub fn parse(value: &str) -> Result<u64, ParseSecondsError> {
+    let value = value.trim();
+    ensure!(!value.is_empty(), EmptySnafu);
+
+    if let Ok(seconds) = value.parse() {
+        return Ok(seconds);
+    }
+
+    let mut total: u64 = 0;
+    let mut rest = value;
+    let mut units = ['h', 'm', 's'].iter();
+
+    while !rest.is_empty() {
+        let end = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .context(MissingUnitSnafu { value })?;
+        ensure!(end > 0, InvalidSnafu { value });
+
+        let number: u64 = rest[..end].parse().ok().context(OverflowSnafu { value })?;
+        let unit = rest[end..].chars().next().unwrap_or_default();
+        let scale = match units.find(|&&u| u == unit) {
+            Some('h') => 3600,
+            Some('m') => 60,
+            Some(_) => 1,
+            None => return UnitSnafu { value, unit }.fail(),
+        };
+
+        total = number
+            .checked_mul(scale)
+            .and_then(|seconds| total.checked_add(seconds))
+            .context(OverflowSnafu { value })?;
+        rest = &rest[end + unit.len_utf8()..];
+    }
+
+    Ok(total)
+}
+
+/// Deserialize seconds from either an integer or a human-friendly string.
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    deserializer.deserialize_any(SecondsVisitor)
+}
+
+/// Like [`deserialize`], but for an optional value.
+pub fn deserialize_option<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<u64>, D::Error> {
+    deserialize(deserializer).map(Some)
+}
+
+/// Like [`deserialize`], but for a list of values.
+pub fn deserialize_vec<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u64>, D::Error> {
+    #[derive(Deserialize)]
+    struct Seconds(#[serde(deserialize_with = "deserialize")] u64);
+
+    let seconds = Vec::<Seconds>::deserialize(deserializer)?;
+    Ok(seconds.into_iter().map(|Seconds(value)| value).collect())
+}
+
+struct SecondsVisitor;
+
+impl Visitor<'_> for SecondsVisitor {
+    type Value = u64;
+
+    fn expecting(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str("seconds as an integer or a duration like \"25m\" or \"1h30m\"")
+    }
+
+    fn visit_u64<E: DeError>(self, value: u64) -> Result<Self::Value, E> {
+        Ok(value)
+    }
+
+    fn visit_i64<E: DeError>(self, value: i64) -> Result<Self::Value, E> {
+        value
+            .try_into()
+            .map_err(|_| E::custom(format!("duration must not be negative, got {value}")))
+    }
+
+    fn visit_str<E: DeError>(self, value: &str) -> Result<Self::Value, E> {
+        parse(value).map_err(E::custom)
+    }
+}
+
+/// An error for parsing a human-friendly duration.
+#[derive(Debug, Snafu, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseSecondsError {
+    #[snafu(display("Duration must not be empty"))]
+    Empty,
+    #[snafu(display("Invalid duration {value:?}"))]
+    Invalid { value: String },
+    #[snafu(display("Missing unit at the end of duration {value:?}"))]
+    MissingUnit { value: String },
+    #[snafu(display("Unexpected unit {unit:?} in duration {value:?}, use h, m and s in order"))]
+    Unit { value: String, unit: char },
+    #[snafu(display("Duration {value:?} is too long"))]
+    Overflow { value: String },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_succeeded() {
+        assert_eq!(parse("90").unwrap(), 90);
+        assert_eq!(parse("1500s").unwrap(), 1500);
+        assert_eq!(parse("25m").unwrap(), 1500);
+        assert_eq!(parse("1h").unwrap(), 3600);
+        assert_eq!(parse("1h30m").unwrap(), 5400);
+        assert_eq!(parse("1h0m5s").unwrap(), 3605);
+    }
+
+    #[test]
+    fn parse_failed() {
+        assert!(matches!(parse(""), Err(ParseSecondsError::Empty)));
+        assert!(matches!(parse("m"), Err(ParseSecondsError::Invalid { .. })));
+        assert!(matches!(
+            parse("1h30"),
+            Err(ParseSecondsError::MissingUnit { .. })
+        ));
+        assert!(matches!(
+            parse("25x"),
+            Err(ParseSecondsError::Unit { unit: 'x', .. })
+        ));
+        assert!(matches!(
+            parse("30m1h"),
+            Err(ParseSecondsError::Unit { unit: 'h', .. })
+        ));
+        assert!(matches!(
+            parse("99999999999999999999h"),
+            Err(ParseSecondsError::Overflow { .. })
+        ));
+    }
+}
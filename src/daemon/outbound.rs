@@ -1,7 +1,24 @@
-use notify_rust::Notification;
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use notify_rust::{Notification, Timeout, Urgency};
 use snafu::prelude::*;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::sync::{Mutex, OnceCell};
+use tokio::time::Instant;
+use zbus::Connection;
 
+use crate::domain::daemon::outbound::{DetectIdleError, IdleDetector};
 use crate::domain::daemon::outbound::{NotifyError, NotifyPort, NotifyRequest};
+use crate::domain::daemon::outbound::{StageLog, StageLogError, StageRecord};
+use crate::domain::daemon::outbound::{StateStore, StateStoreError, WorkerSnapshot};
+use crate::domain::entity::{NotificationTimeout, NotificationUrgency};
+use crate::utils::local_time;
 
 /// A [`NotifyPort`] implementation based on XDG desktop notification.
 #[derive(Debug, Clone)]
@@ -21,11 +38,26 @@ impl NotifyPort for NotifyService {
         let mut notification = Notification::new();
         notification.appname(&self.app_name);
         notification.summary(&request.summary);
+        notification.urgency(match request.urgency {
+            NotificationUrgency::Low => Urgency::Low,
+            NotificationUrgency::Normal => Urgency::Normal,
+            NotificationUrgency::Critical => Urgency::Critical,
+        });
+        notification.timeout(match request.timeout {
+            NotificationTimeout::Default => Timeout::Default,
+            NotificationTimeout::Never => Timeout::Never,
+            NotificationTimeout::After(duration) => Timeout::from(duration),
+        });
 
         if let Some(body) = request.body {
             notification.body(&body);
         }
 
+        // Notification servers ignore hints they do not support.
+        if let Some(sound) = request.sound {
+            notification.sound_name(&sound);
+        }
+
         let _ = whatever!(
             notification.show_async().await,
             "Could not show notification",
@@ -34,3 +66,558 @@ impl NotifyPort for NotifyService {
         Ok(())
     }
 }
+
+/// A [`NotifyPort`] implementation which runs a shell command for every
+/// notification. The command gets the stage's name, the summary and the body
+/// from the environment variables `TC_STAGE`, `TC_SUMMARY` and `TC_BODY`, and
+/// the urgency from `TC_URGENCY` as `low`, `normal` or `critical`.
+#[derive(Debug, Clone)]
+pub struct CommandNotifyService {
+    command: String,
+}
+
+impl CommandNotifyService {
+    pub fn new(command: String) -> Self {
+        Self { command }
+    }
+}
+
+#[async_trait::async_trait]
+impl NotifyPort for CommandNotifyService {
+    async fn notify_impl(&self, request: NotifyRequest) -> Result<(), NotifyError> {
+        let urgency = match request.urgency {
+            NotificationUrgency::Low => "low",
+            NotificationUrgency::Normal => "normal",
+            NotificationUrgency::Critical => "critical",
+        };
+        let mut child = whatever!(
+            Command::new("sh")
+                .arg("-c")
+                .arg(&self.command)
+                .env("TC_STAGE", &request.stage)
+                .env("TC_SUMMARY", &request.summary)
+                .env("TC_BODY", request.body.as_deref().unwrap_or_default())
+                .env("TC_URGENCY", urgency)
+                .stdin(Stdio::null())
+                .spawn(),
+            "Could not run notification command",
+        );
+
+        // Don't hold the timer back while the command runs.
+        tokio::spawn(async move {
+            match child.wait().await {
+                Ok(status) if status.success() => {}
+                Ok(status) => tracing::warn!(%status, "Notification command failed"),
+                Err(err) => tracing::warn!(%err, "Could not wait for notification command"),
+            }
+        });
+
+        Ok(())
+    }
+}
+
+/// A [`NotifyPort`] implementation which only writes notifications to the log.
+#[derive(Debug, Clone, Default)]
+pub struct LogNotifyService;
+
+impl LogNotifyService {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait::async_trait]
+impl NotifyPort for LogNotifyService {
+    async fn notify_impl(&self, request: NotifyRequest) -> Result<(), NotifyError> {
+        match request.body {
+            Some(body) => tracing::info!(summary = request.summary, body, "Notified"),
+            None => tracing::info!(summary = request.summary, "Notified"),
+        }
+        Ok(())
+    }
+}
+
+/// A [`NotifyPort`] implementation which wraps another one, and drops
+/// notifications arriving within `interval` of the last one shown, so that a
+/// timer going through stages rapidly does not flood the notification server.
+pub struct RateLimitedNotifier {
+    inner: Arc<dyn NotifyPort>,
+    interval: Duration,
+    last: Mutex<Option<Instant>>,
+}
+
+impl RateLimitedNotifier {
+    pub fn new(inner: Arc<dyn NotifyPort>, interval: Duration) -> Self {
+        Self {
+            inner,
+            interval,
+            last: Mutex::new(None),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl NotifyPort for RateLimitedNotifier {
+    async fn notify_impl(&self, request: NotifyRequest) -> Result<(), NotifyError> {
+        let now = Instant::now();
+
+        {
+            let mut last = self.last.lock().await;
+            if last.is_some_and(|last| now.duration_since(last) < self.interval) {
+                tracing::warn!(
+                    stage = request.stage,
+                    summary = request.summary,
+                    "Dropped a notification too close to the last one"
+                );
+                return Ok(());
+            }
+            *last = Some(now);
+        }
+
+        self.inner.notify_impl(request).await
+    }
+}
+
+/// A [`StateStore`] implementation which keeps the states of all sessions in
+/// one JSON file.
+#[derive(Debug)]
+pub struct FileStateStore {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileStateStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Read all saved states. A missing file means nothing is saved.
+    async fn read(&self) -> Result<BTreeMap<String, WorkerSnapshot>, StateStoreError> {
+        let content = match tokio::fs::read(&self.path).await {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(BTreeMap::new()),
+            Err(err) => {
+                return Err(err)
+                    .with_whatever_context(|_| format!("Could not read {}", self.path.display()))
+            }
+        };
+
+        serde_json::from_slice(&content)
+            .with_whatever_context(|_| format!("Could not parse {}", self.path.display()))
+    }
+
+    /// Replace all saved states. The file is written aside and then renamed,
+    /// so that a crash never leaves a half-written file.
+    async fn write(
+        &self,
+        states: &BTreeMap<String, WorkerSnapshot>,
+    ) -> Result<(), StateStoreError> {
+        let content = whatever!(serde_json::to_vec(states), "Could not serialize states");
+        let temp = self.path.with_extension("tmp");
+
+        tokio::fs::write(&temp, content)
+            .await
+            .with_whatever_context(|_| format!("Could not write {}", temp.display()))?;
+        tokio::fs::rename(&temp, &self.path)
+            .await
+            .with_whatever_context(|_| format!("Could not replace {}", self.path.display()))
+    }
+
+    /// Read all saved states for modification. A corrupt file is replaced.
+    async fn read_for_update(&self) -> BTreeMap<String, WorkerSnapshot> {
+        self.read().await.unwrap_or_else(|err| {
+            tracing::warn!(err = %err, "Overwriting the unreadable state file");
+            BTreeMap::new()
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl StateStore for FileStateStore {
+    async fn load(&self, session: &str) -> Result<Option<WorkerSnapshot>, StateStoreError> {
+        let _guard = self.lock.lock().await;
+        Ok(self.read().await?.remove(session))
+    }
+
+    async fn save(&self, session: &str, snapshot: &WorkerSnapshot) -> Result<(), StateStoreError> {
+        let _guard = self.lock.lock().await;
+        let mut states = self.read_for_update().await;
+        states.insert(session.to_owned(), snapshot.clone());
+        self.write(&states).await
+    }
+
+    async fn clear(&self, session: &str) -> Result<(), StateStoreError> {
+        let _guard = self.lock.lock().await;
+        let mut states = self.read_for_update().await;
+
+        if states.remove(session).is_some() {
+            self.write(&states).await?;
+        }
+        Ok(())
+    }
+}
+
+/// A [`StateStore`] implementation which saves nothing, so that every session
+/// starts afresh.
+#[derive(Debug, Clone, Default)]
+pub struct NoStateStore;
+
+impl NoStateStore {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait::async_trait]
+impl StateStore for NoStateStore {
+    async fn load(&self, _session: &str) -> Result<Option<WorkerSnapshot>, StateStoreError> {
+        Ok(None)
+    }
+
+    async fn save(
+        &self,
+        _session: &str,
+        _snapshot: &WorkerSnapshot,
+    ) -> Result<(), StateStoreError> {
+        Ok(())
+    }
+
+    async fn clear(&self, _session: &str) -> Result<(), StateStoreError> {
+        Ok(())
+    }
+}
+
+/// A [`StageLog`] implementation which appends a line of CSV to a file for
+/// every completed stage. The header is written once the file is empty.
+#[derive(Debug)]
+pub struct CsvStageLog {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl CsvStageLog {
+    const HEADER: &'static str = "timestamp,session,stage,kind,seconds\n";
+
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn line(record: &StageRecord) -> String {
+        format!(
+            "{},{},{},{},{}\n",
+            local_time::format_utc(record.finished_at),
+            Self::field(&record.session),
+            Self::field(&record.stage),
+            record.kind,
+            record.duration.as_secs(),
+        )
+    }
+
+    /// Quote a field if it contains a comma, a quote or a line break.
+    fn field(value: &str) -> String {
+        if value.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_owned()
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl StageLog for CsvStageLog {
+    async fn append(&self, record: &StageRecord) -> Result<(), StageLogError> {
+        let _guard = self.lock.lock().await;
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .with_whatever_context(|_| format!("Could not open {}", self.path.display()))?;
+
+        let empty = file
+            .metadata()
+            .await
+            .map(|metadata| metadata.len() == 0)
+            .unwrap_or(false);
+        let mut content = if empty {
+            Self::HEADER.to_owned()
+        } else {
+            String::new()
+        };
+        content.push_str(&Self::line(record));
+
+        file.write_all(content.as_bytes())
+            .await
+            .with_whatever_context(|_| format!("Could not write {}", self.path.display()))?;
+        // A tokio file writes in the background, so wait for the line to
+        // land before the next record is appended.
+        file.flush()
+            .await
+            .with_whatever_context(|_| format!("Could not write {}", self.path.display()))
+    }
+}
+
+/// A [`StageLog`] implementation which records nothing.
+#[derive(Debug, Clone, Default)]
+pub struct NoStageLog;
+
+impl NoStageLog {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait::async_trait]
+impl StageLog for NoStageLog {
+    async fn append(&self, _record: &StageRecord) -> Result<(), StageLogError> {
+        Ok(())
+    }
+}
+
+/// An [`IdleDetector`] implementation for desktop sessions. It asks GNOME's
+/// idle monitor over D-Bus, which also works on Wayland, and falls back to
+/// `xprintidle` on other X11 desktops.
+#[derive(Debug, Default)]
+pub struct SessionIdleDetector {
+    connection: OnceCell<Connection>,
+}
+
+impl SessionIdleDetector {
+    pub fn new() -> Self {
+        Self {
+            connection: OnceCell::new(),
+        }
+    }
+
+    async fn mutter_idle_time(&self) -> Result<Duration, DetectIdleError> {
+        let connection = whatever!(
+            self.connection.get_or_try_init(Connection::session).await,
+            "Could not connect to the session bus",
+        );
+        let reply = whatever!(
+            connection
+                .call_method(
+                    Some("org.gnome.Mutter.IdleMonitor"),
+                    "/org/gnome/Mutter/IdleMonitor/Core",
+                    Some("org.gnome.Mutter.IdleMonitor"),
+                    "GetIdletime",
+                    &(),
+                )
+                .await,
+            "Could not query the idle monitor",
+        );
+        let millis = whatever!(
+            reply.body().deserialize::<u64>(),
+            "Could not parse the idle time from the idle monitor",
+        );
+        Ok(Duration::from_millis(millis))
+    }
+
+    async fn xprintidle_idle_time(&self) -> Result<Duration, DetectIdleError> {
+        let output = whatever!(
+            Command::new("xprintidle")
+                .stdin(Stdio::null())
+                .output()
+                .await,
+            "Could not run xprintidle",
+        );
+        ensure_whatever!(
+            output.status.success(),
+            "xprintidle failed with {}",
+            output.status,
+        );
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let millis = whatever!(
+            stdout.trim().parse::<u64>(),
+            "Could not parse the idle time from xprintidle",
+        );
+        Ok(Duration::from_millis(millis))
+    }
+}
+
+#[async_trait::async_trait]
+impl IdleDetector for SessionIdleDetector {
+    async fn idle_time(&self) -> Result<Duration, DetectIdleError> {
+        match self.mutter_idle_time().await {
+            Ok(idle) => Ok(idle),
+            Err(err) => {
+                tracing::trace!(err = %err, "Falling back to xprintidle");
+                self.xprintidle_idle_time().await
+            }
+        }
+    }
+}
+
+/// An [`IdleDetector`] implementation which takes the user as always active.
+#[derive(Debug, Clone, Default)]
+pub struct NoIdleDetector;
+
+impl NoIdleDetector {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait::async_trait]
+impl IdleDetector for NoIdleDetector {
+    async fn idle_time(&self) -> Result<Duration, DetectIdleError> {
+        Ok(Duration::ZERO)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::SystemTime;
+
+    use crate::domain::entity::StageState;
+
+    use assert_fs::{prelude::*, TempDir};
+
+    #[tokio::test]
+    async fn command_notify_service_notify() {
+        let tmp = TempDir::new().expect("Test environment should support temporary directories");
+        let file = tmp.child("notified");
+        let command = format!(
+            "printf '%s|%s|%s|%s' \"$TC_STAGE\" \"$TC_SUMMARY\" \"$TC_BODY\" \"$TC_URGENCY\" > {}",
+            file.display()
+        );
+        let service = CommandNotifyService::new(command);
+
+        let request = NotifyRequest {
+            stage: "Concentration".to_owned(),
+            summary: "Concentration Stage End".to_owned(),
+            body: None,
+            urgency: NotificationUrgency::Critical,
+            timeout: NotificationTimeout::Default,
+            sound: None,
+        };
+        service.notify_impl(request).await.unwrap();
+
+        // The command runs on background.
+        for _ in 0..100 {
+            match std::fs::read_to_string(&file) {
+                Ok(content) if !content.is_empty() => {
+                    assert_eq!(content, "Concentration|Concentration Stage End||critical");
+                    return;
+                }
+                _ => tokio::time::sleep(Duration::from_millis(20)).await,
+            }
+        }
+        panic!("Notification command should have run");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rate_limited_notifier_drop() {
+        let inner = Arc::new(CountNotifier::default());
+        let notifier = RateLimitedNotifier::new(Arc::clone(&inner) as _, Duration::from_secs(5));
+
+        notifier.notify_impl(new_request()).await.unwrap();
+        tokio::time::advance(Duration::from_secs(3)).await;
+        notifier.notify_impl(new_request()).await.unwrap();
+        assert_eq!(*inner.count.lock().await, 1);
+
+        // The window starts from the last notification shown.
+        tokio::time::advance(Duration::from_secs(2)).await;
+        notifier.notify_impl(new_request()).await.unwrap();
+        notifier.notify_impl(new_request()).await.unwrap();
+        assert_eq!(*inner.count.lock().await, 2);
+    }
+
+    #[tokio::test]
+    async fn file_state_store_save_load() {
+        let tmp = TempDir::new().expect("Test environment should support temporary directories");
+        let store = FileStateStore::new(tmp.child("state.json").to_path_buf());
+        assert_eq!(store.load("default").await.unwrap(), None);
+
+        let snapshot = new_snapshot();
+        store.save("default", &snapshot).await.unwrap();
+        assert_eq!(store.load("default").await.unwrap(), Some(snapshot.clone()));
+        assert_eq!(store.load("work").await.unwrap(), None);
+
+        store.clear("default").await.unwrap();
+        assert_eq!(store.load("default").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn file_state_store_corrupt() {
+        let tmp = TempDir::new().expect("Test environment should support temporary directories");
+        let file = tmp.child("state.json");
+        file.write_str("{ not json").unwrap();
+
+        let store = FileStateStore::new(file.to_path_buf());
+        assert!(store.load("default").await.is_err());
+
+        let snapshot = new_snapshot();
+        store.save("default", &snapshot).await.unwrap();
+        assert_eq!(store.load("default").await.unwrap(), Some(snapshot));
+    }
+
+    #[tokio::test]
+    async fn csv_stage_log_append() {
+        let tmp = TempDir::new().expect("Test environment should support temporary directories");
+        let file = tmp.child("stats.csv");
+        let log = CsvStageLog::new(file.to_path_buf());
+
+        let mut record = StageRecord {
+            session: "default".to_owned(),
+            stage: "Concentration".to_owned(),
+            kind: StageState::Concentration,
+            duration: Duration::from_secs(1500),
+            finished_at: SystemTime::UNIX_EPOCH + Duration::from_secs(1_723_714_200),
+        };
+        log.append(&record).await.unwrap();
+        record.stage = "Deep, \"Work\"".to_owned();
+        log.append(&record).await.unwrap();
+
+        file.assert(
+            "timestamp,session,stage,kind,seconds\n\
+             2024-08-15T09:30:00Z,default,Concentration,Concentration,1500\n\
+             2024-08-15T09:30:00Z,default,\"Deep, \"\"Work\"\"\",Concentration,1500\n",
+        );
+    }
+
+    fn new_snapshot() -> WorkerSnapshot {
+        WorkerSnapshot {
+            stage: 1,
+            concentrations: 2,
+            cycles: 0,
+            past: Duration::from_secs(300),
+            total: Duration::from_secs(1500),
+            paused: true,
+            saved_at: SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+        }
+    }
+
+    #[derive(Default)]
+    struct CountNotifier {
+        count: Mutex<u32>,
+    }
+
+    #[async_trait::async_trait]
+    impl NotifyPort for CountNotifier {
+        async fn notify_impl(&self, _request: NotifyRequest) -> Result<(), NotifyError> {
+            *self.count.lock().await += 1;
+            Ok(())
+        }
+    }
+
+    fn new_request() -> NotifyRequest {
+        NotifyRequest {
+            stage: "Concentration".to_owned(),
+            summary: "Concentration Stage End".to_owned(),
+            body: None,
+            urgency: NotificationUrgency::Normal,
+            timeout: NotificationTimeout::Default,
+            sound: None,
+        }
+    }
+}
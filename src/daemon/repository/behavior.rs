@@ -0,0 +1,138 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::daemon::config::Configuration;
+use crate::domain::entity::AutoPauseTrigger;
+use crate::domain::repository::{behavior::GetBehaviorError, BehaviorRepository};
+
+/// A [`BehaviorRepository`] implementation which reads configuration files.
+pub struct BehaviorConfiguration {
+    config: Arc<Configuration>,
+}
+
+impl BehaviorConfiguration {
+    /// Creates a new [`BehaviorConfiguration`].
+    pub fn new(config: Arc<Configuration>) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait::async_trait]
+impl BehaviorRepository for BehaviorConfiguration {
+    async fn auto_pause_enabled(
+        &self,
+        trigger: AutoPauseTrigger,
+    ) -> Result<bool, GetBehaviorError> {
+        match trigger {
+            AutoPauseTrigger::ScreenLock => Ok(self.config.auto_pause.screen_lock),
+            AutoPauseTrigger::LowBattery => Ok(self.config.auto_pause.low_battery),
+            AutoPauseTrigger::Idle => Ok(self.idle_threshold().await?.is_some()),
+        }
+    }
+
+    async fn idle_threshold(&self) -> Result<Option<Duration>, GetBehaviorError> {
+        let threshold = self.config.auto_pause.idle.filter(|&secs| secs > 0);
+        Ok(threshold.map(Duration::from_secs))
+    }
+
+    async fn start_paused(&self) -> Result<bool, GetBehaviorError> {
+        Ok(self.config.runtime.start_paused)
+    }
+
+    async fn max_pause(&self) -> Result<Option<Duration>, GetBehaviorError> {
+        let max_pause = self.config.auto_pause.max_pause.filter(|&secs| secs > 0);
+        Ok(max_pause.map(Duration::from_secs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = r#"
+        [duration]
+        preparation = 1
+        concentration = 2
+        relaxation = 3
+
+        [notification.preparation]
+        summary = "Preparation"
+
+        [notification.concentration]
+        summary = "Concentration"
+
+        [notification.relaxation]
+        summary = "Relaxation"
+    "#;
+
+    #[tokio::test]
+    async fn behavior_configuration_default() {
+        let config = Arc::new(toml::from_str::<Configuration>(CONTENT).unwrap());
+        let repository = BehaviorConfiguration::new(config);
+
+        assert!(repository
+            .auto_pause_enabled(AutoPauseTrigger::ScreenLock)
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn behavior_configuration_disabled() {
+        let content = format!("{CONTENT}\n[auto_pause]\nscreen_lock = false\n");
+        let config = Arc::new(toml::from_str::<Configuration>(&content).unwrap());
+        let repository = BehaviorConfiguration::new(config);
+
+        assert!(!repository
+            .auto_pause_enabled(AutoPauseTrigger::ScreenLock)
+            .await
+            .unwrap());
+        assert!(repository
+            .auto_pause_enabled(AutoPauseTrigger::LowBattery)
+            .await
+            .unwrap());
+        assert!(!repository
+            .auto_pause_enabled(AutoPauseTrigger::Idle)
+            .await
+            .unwrap());
+        assert_eq!(repository.idle_threshold().await.unwrap(), None);
+        assert_eq!(repository.max_pause().await.unwrap(), None);
+        assert!(!repository.start_paused().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn behavior_configuration_idle() {
+        let content = format!("{CONTENT}\n[auto_pause]\nidle = \"5m\"\n");
+        let config = Arc::new(toml::from_str::<Configuration>(&content).unwrap());
+        let repository = BehaviorConfiguration::new(config);
+
+        assert!(repository
+            .auto_pause_enabled(AutoPauseTrigger::Idle)
+            .await
+            .unwrap());
+        assert_eq!(
+            repository.idle_threshold().await.unwrap(),
+            Some(Duration::from_secs(300))
+        );
+    }
+
+    #[tokio::test]
+    async fn behavior_configuration_start_paused() {
+        let content = format!("{CONTENT}\n[runtime]\nstart_paused = true\n");
+        let config = Arc::new(toml::from_str::<Configuration>(&content).unwrap());
+        let repository = BehaviorConfiguration::new(config);
+
+        assert!(repository.start_paused().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn behavior_configuration_max_pause() {
+        let content = format!("{CONTENT}\n[auto_pause]\nmax_pause = \"30m\"\n");
+        let config = Arc::new(toml::from_str::<Configuration>(&content).unwrap());
+        let repository = BehaviorConfiguration::new(config);
+
+        assert_eq!(
+            repository.max_pause().await.unwrap(),
+            Some(Duration::from_secs(1800))
+        );
+    }
+}
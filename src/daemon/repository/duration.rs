@@ -1,7 +1,9 @@
 use std::sync::Arc;
 
 use crate::daemon::config::Configuration;
-use crate::domain::entity::StageDuration;
+use crate::daemon::config::ModeOption;
+use crate::daemon::repository::stage::stage_state;
+use crate::domain::entity::{StageDuration, StageState, TimerMode};
 use crate::domain::repository::{duration::GetDurationError, DurationRepository};
 
 /// A [`DurationRepository`] implementation which reads configuration files.
@@ -14,31 +16,45 @@ impl DurationConfiguration {
     pub fn new(config: Arc<Configuration>) -> Self {
         Self { config }
     }
+
+    /// Build a [`StageDuration`] from a raw value in seconds.
+    fn duration(raw: u64) -> Result<StageDuration, GetDurationError> {
+        raw.try_into()
+            .map_err(|err| GetDurationError::Invalid { source: err })
+    }
 }
 
 #[async_trait::async_trait]
 impl DurationRepository for DurationConfiguration {
     async fn preparation_duration(&self) -> Result<StageDuration, GetDurationError> {
-        let raw = self.config.duration.preparation;
-        let value = raw
-            .try_into()
-            .map_err(|err| GetDurationError::Invalid { source: err })?;
-        Ok(value)
+        Self::duration(self.config.duration.preparation)
     }
 
     async fn concentration_duration(&self) -> Result<StageDuration, GetDurationError> {
-        let raw = self.config.duration.concentration;
-        let value = raw
-            .try_into()
-            .map_err(|err| GetDurationError::Invalid { source: err })?;
-        Ok(value)
+        Self::duration(self.config.duration.concentration)
     }
 
     async fn relaxation_duration(&self) -> Result<StageDuration, GetDurationError> {
-        let raw = self.config.duration.relaxation;
-        let value = raw
-            .try_into()
-            .map_err(|err| GetDurationError::Invalid { source: err })?;
-        Ok(value)
+        Self::duration(self.config.duration.relaxation)
+    }
+
+    async fn long_break_duration(&self) -> Result<StageDuration, GetDurationError> {
+        let duration = &self.config.duration;
+        Self::duration(duration.long_break.unwrap_or(duration.relaxation))
+    }
+
+    async fn cycles_before_long_break(&self) -> Result<u32, GetDurationError> {
+        Ok(self.config.duration.cycles_before_long_break)
+    }
+
+    async fn initial_stage(&self) -> Result<Option<StageState>, GetDurationError> {
+        Ok(self.config.duration.initial_stage.map(stage_state))
+    }
+
+    async fn mode(&self) -> Result<TimerMode, GetDurationError> {
+        Ok(match self.config.duration.mode {
+            ModeOption::Full => TimerMode::Full,
+            ModeOption::Simple => TimerMode::Simple,
+        })
     }
 }
@@ -0,0 +1,266 @@
+use crate::domain::entity::{NotificationMessage, StageDuration, StageState, TimerMode};
+use crate::domain::repository::{duration::GetDurationError, DurationRepository};
+use crate::domain::repository::{notification::GetNotificationError, NotificationRepository};
+
+/// A [`DurationRepository`] implementation which holds the durations in
+/// memory. It starts with the same durations as the default configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InMemoryDurationRepository {
+    preparation: StageDuration,
+    concentration: StageDuration,
+    relaxation: StageDuration,
+    long_break: Option<StageDuration>,
+    cycles_before_long_break: u32,
+    initial_stage: Option<StageState>,
+    mode: TimerMode,
+}
+
+impl InMemoryDurationRepository {
+    /// Creates a new [`InMemoryDurationRepository`].
+    pub fn new() -> Self {
+        Self {
+            preparation: default_duration(900),
+            concentration: default_duration(2400),
+            relaxation: default_duration(600),
+            long_break: Some(default_duration(1800)),
+            cycles_before_long_break: 4,
+            initial_stage: None,
+            mode: TimerMode::Full,
+        }
+    }
+
+    /// Set the duration of the preparation stage.
+    pub fn with_preparation(mut self, duration: StageDuration) -> Self {
+        self.preparation = duration;
+        self
+    }
+
+    /// Set the duration of the concentration stage.
+    pub fn with_concentration(mut self, duration: StageDuration) -> Self {
+        self.concentration = duration;
+        self
+    }
+
+    /// Set the duration of the relaxation stage.
+    pub fn with_relaxation(mut self, duration: StageDuration) -> Self {
+        self.relaxation = duration;
+        self
+    }
+
+    /// Set the duration of a long break. Use the relaxation stage's one if it
+    /// is `None`.
+    pub fn with_long_break(mut self, duration: Option<StageDuration>) -> Self {
+        self.long_break = duration;
+        self
+    }
+
+    /// Set the number of concentration stages before a long break, where zero
+    /// disables long breaks.
+    pub fn with_cycles_before_long_break(mut self, cycles: u32) -> Self {
+        self.cycles_before_long_break = cycles;
+        self
+    }
+
+    /// Set the kind of the stage which the timer starts from, or start from
+    /// the first stage if it is `None`.
+    pub fn with_initial_stage(mut self, kind: Option<StageState>) -> Self {
+        self.initial_stage = kind;
+        self
+    }
+
+    /// Set how the timer goes through the stages.
+    pub fn with_mode(mut self, mode: TimerMode) -> Self {
+        self.mode = mode;
+        self
+    }
+}
+
+impl Default for InMemoryDurationRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn default_duration(seconds: u64) -> StageDuration {
+    match StageDuration::try_new(seconds) {
+        Ok(duration) => duration,
+        Err(_) => unreachable!("Default durations should be valid"),
+    }
+}
+
+#[async_trait::async_trait]
+impl DurationRepository for InMemoryDurationRepository {
+    async fn preparation_duration(&self) -> Result<StageDuration, GetDurationError> {
+        Ok(self.preparation.clone())
+    }
+
+    async fn concentration_duration(&self) -> Result<StageDuration, GetDurationError> {
+        Ok(self.concentration.clone())
+    }
+
+    async fn relaxation_duration(&self) -> Result<StageDuration, GetDurationError> {
+        Ok(self.relaxation.clone())
+    }
+
+    async fn long_break_duration(&self) -> Result<StageDuration, GetDurationError> {
+        Ok(self.long_break.as_ref().unwrap_or(&self.relaxation).clone())
+    }
+
+    async fn cycles_before_long_break(&self) -> Result<u32, GetDurationError> {
+        Ok(self.cycles_before_long_break)
+    }
+
+    async fn initial_stage(&self) -> Result<Option<StageState>, GetDurationError> {
+        Ok(self.initial_stage)
+    }
+
+    async fn mode(&self) -> Result<TimerMode, GetDurationError> {
+        Ok(self.mode)
+    }
+}
+
+/// A [`NotificationRepository`] implementation which holds the messages in
+/// memory. It starts with the same messages as the default configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InMemoryNotificationRepository {
+    preparation: NotificationMessage,
+    concentration: NotificationMessage,
+    relaxation: NotificationMessage,
+}
+
+impl InMemoryNotificationRepository {
+    /// Creates a new [`InMemoryNotificationRepository`].
+    pub fn new() -> Self {
+        Self {
+            preparation: default_message(
+                "Preparation Stage End",
+                "It's time to start concentrating on learning.",
+            ),
+            concentration: default_message(
+                "Concentration Stage End",
+                "Well done! Remember to have a rest.",
+            ),
+            relaxation: default_message(
+                "Relaxation Stage End",
+                "Feel energetic now? Let's continue.",
+            ),
+        }
+    }
+
+    /// Set the message shown after the preparation stage.
+    pub fn with_preparation(mut self, message: NotificationMessage) -> Self {
+        self.preparation = message;
+        self
+    }
+
+    /// Set the message shown after the concentration stage.
+    pub fn with_concentration(mut self, message: NotificationMessage) -> Self {
+        self.concentration = message;
+        self
+    }
+
+    /// Set the message shown after the relaxation stage.
+    pub fn with_relaxation(mut self, message: NotificationMessage) -> Self {
+        self.relaxation = message;
+        self
+    }
+}
+
+impl Default for InMemoryNotificationRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn default_message(summary: &str, body: &str) -> NotificationMessage {
+    match NotificationMessage::try_new(summary.to_owned(), Some(body.to_owned())) {
+        Ok(message) => message,
+        Err(_) => unreachable!("Default messages should be valid"),
+    }
+}
+
+#[async_trait::async_trait]
+impl NotificationRepository for InMemoryNotificationRepository {
+    async fn preparation_notification(&self) -> Result<NotificationMessage, GetNotificationError> {
+        Ok(self.preparation.clone())
+    }
+
+    async fn concentration_notification(
+        &self,
+    ) -> Result<NotificationMessage, GetNotificationError> {
+        Ok(self.concentration.clone())
+    }
+
+    async fn relaxation_notification(&self) -> Result<NotificationMessage, GetNotificationError> {
+        Ok(self.relaxation.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+
+    use crate::daemon::config;
+    use crate::daemon::repository::{DurationConfiguration, NotificationConfiguration};
+
+    #[tokio::test]
+    async fn in_memory_duration_repository() {
+        let repository = InMemoryDurationRepository::new()
+            .with_concentration(StageDuration::try_new(1500).unwrap())
+            .with_long_break(None)
+            .with_cycles_before_long_break(2);
+
+        assert_eq!(
+            repository.concentration_duration().await.unwrap(),
+            StageDuration::try_new(1500).unwrap(),
+        );
+        assert_eq!(
+            repository.long_break_duration().await.unwrap(),
+            repository.relaxation_duration().await.unwrap(),
+        );
+        assert_eq!(repository.cycles_before_long_break().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn in_memory_notification_repository() {
+        let message = NotificationMessage::try_new("Done".to_owned(), None).unwrap();
+        let repository = InMemoryNotificationRepository::new().with_relaxation(message.clone());
+        assert_eq!(repository.relaxation_notification().await.unwrap(), message);
+    }
+
+    #[tokio::test]
+    async fn in_memory_repository_default() {
+        // The defaults follow the default configuration.
+        let config = Arc::new(config::load_default().unwrap());
+        let durations = DurationConfiguration::new(Arc::clone(&config));
+        let notifications = NotificationConfiguration::new(config);
+        let memory_durations = InMemoryDurationRepository::default();
+        let memory_notifications = InMemoryNotificationRepository::default();
+
+        assert_eq!(
+            memory_durations.preparation_duration().await.unwrap(),
+            durations.preparation_duration().await.unwrap(),
+        );
+        assert_eq!(
+            memory_durations.concentration_duration().await.unwrap(),
+            durations.concentration_duration().await.unwrap(),
+        );
+        assert_eq!(
+            memory_durations.long_break_duration().await.unwrap(),
+            durations.long_break_duration().await.unwrap(),
+        );
+        assert_eq!(
+            memory_durations.cycles_before_long_break().await.unwrap(),
+            durations.cycles_before_long_break().await.unwrap(),
+        );
+        assert_eq!(
+            memory_notifications
+                .concentration_notification()
+                .await
+                .unwrap(),
+            notifications.concentration_notification().await.unwrap(),
+        );
+    }
+}
@@ -1,5 +1,11 @@
+mod behavior;
 mod duration;
+mod memory;
 mod notification;
+mod stage;
 
+pub use behavior::BehaviorConfiguration;
 pub use duration::DurationConfiguration;
+pub use memory::{InMemoryDurationRepository, InMemoryNotificationRepository};
 pub use notification::NotificationConfiguration;
+pub use stage::StageConfiguration;
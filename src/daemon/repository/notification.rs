@@ -1,7 +1,9 @@
 use std::sync::Arc;
+use std::time::Duration;
 
-use crate::daemon::config::Configuration;
-use crate::domain::entity::NotificationMessage;
+use crate::daemon::config::{Configuration, MessageSection, UrgencyOption};
+use crate::domain::entity::notification::TryNewNotificationMessageError;
+use crate::domain::entity::{NotificationMessage, NotificationTimeout, NotificationUrgency};
 use crate::domain::repository::{notification::GetNotificationError, NotificationRepository};
 
 /// A [`NotificationRepository`] implementation which reads configuration files.
@@ -14,30 +16,179 @@ impl NotificationConfiguration {
     pub fn new(config: Arc<Configuration>) -> Self {
         Self { config }
     }
+
+    /// Build a [`NotificationMessage`] from a borrowed section, copying only
+    /// the strings owned by the message.
+    fn message(
+        &self,
+        section: &MessageSection,
+    ) -> Result<NotificationMessage, GetNotificationError> {
+        NotificationMessage::try_new(section.summary.clone(), section.body.clone())
+            .and_then(|message| limit(message, self.config.notification.max_length))
+            .map(|message| message.with_urgency(urgency(section.urgency)))
+            .map(|message| message.with_timeout(timeout(section.timeout_ms)))
+            .map(|message| with_sound(message, section.sound.clone()))
+            .map_err(|err| GetNotificationError::Invalid { source: err })
+    }
+}
+
+/// Convert the configured urgency into the domain's one.
+pub(super) fn urgency(option: UrgencyOption) -> NotificationUrgency {
+    match option {
+        UrgencyOption::Low => NotificationUrgency::Low,
+        UrgencyOption::Normal => NotificationUrgency::Normal,
+        UrgencyOption::Critical => NotificationUrgency::Critical,
+    }
+}
+
+/// Convert the configured timeout into the domain's one, where `0` means
+/// that the notification never expires.
+pub(super) fn timeout(timeout_ms: Option<u32>) -> NotificationTimeout {
+    match timeout_ms {
+        None => NotificationTimeout::Default,
+        Some(0) => NotificationTimeout::Never,
+        Some(ms) => NotificationTimeout::After(Duration::from_millis(ms.into())),
+    }
+}
+
+/// Check `message` against the configured length limit, if any.
+pub(super) fn limit(
+    message: NotificationMessage,
+    max_length: Option<usize>,
+) -> Result<NotificationMessage, TryNewNotificationMessageError> {
+    match max_length {
+        Some(max) => message.try_limit(max),
+        None => Ok(message),
+    }
+}
+
+/// Attach the configured sound, if any, to `message`.
+pub(super) fn with_sound(
+    message: NotificationMessage,
+    sound: Option<String>,
+) -> NotificationMessage {
+    match sound {
+        Some(sound) => message.with_sound(sound),
+        None => message,
+    }
 }
 
 #[async_trait::async_trait]
 impl NotificationRepository for NotificationConfiguration {
     async fn preparation_notification(&self) -> Result<NotificationMessage, GetNotificationError> {
-        let section = self.config.notification.preparation.clone();
-        let value = NotificationMessage::try_new(section.summary, section.body)
-            .map_err(|err| GetNotificationError::Invalid { source: err })?;
-        Ok(value)
+        self.message(&self.config.notification.preparation)
     }
 
     async fn concentration_notification(
         &self,
     ) -> Result<NotificationMessage, GetNotificationError> {
-        let section = self.config.notification.concentration.clone();
-        let value = NotificationMessage::try_new(section.summary, section.body)
-            .map_err(|err| GetNotificationError::Invalid { source: err })?;
-        Ok(value)
+        self.message(&self.config.notification.concentration)
     }
 
     async fn relaxation_notification(&self) -> Result<NotificationMessage, GetNotificationError> {
-        let section = self.config.notification.relaxation.clone();
-        let value = NotificationMessage::try_new(section.summary, section.body)
-            .map_err(|err| GetNotificationError::Invalid { source: err })?;
-        Ok(value)
+        self.message(&self.config.notification.relaxation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = r#"
+        [duration]
+        preparation = 1
+        concentration = 2
+        relaxation = 3
+
+        [notification.preparation]
+        summary = "Preparation"
+        timeout_ms = 5000
+
+        [notification.concentration]
+        summary = "Concentration"
+        body = "body"
+        urgency = "critical"
+        timeout_ms = 0
+        sound = "complete"
+
+        [notification.relaxation]
+        summary = "Relaxation"
+    "#;
+
+    #[tokio::test]
+    async fn notification_configuration_repeated_read() {
+        let config = Arc::new(toml::from_str::<Configuration>(CONTENT).unwrap());
+        let repository = NotificationConfiguration::new(Arc::clone(&config));
+        let other = NotificationConfiguration::new(Arc::clone(&config));
+
+        // Every read through either repository sees the shared section.
+        let section = &config.notification.concentration;
+        for _ in 0..4 {
+            let message = repository.concentration_notification().await.unwrap();
+            assert_eq!(message.summary(), section.summary);
+            assert_eq!(message.body(), section.body.as_deref());
+            assert_eq!(message, other.concentration_notification().await.unwrap());
+        }
+
+        // Reads borrow the shared configuration instead of copying it.
+        assert_eq!(Arc::strong_count(&config), 3);
+        assert!(Arc::ptr_eq(&config, &repository.config));
+        assert!(Arc::ptr_eq(&config, &other.config));
+    }
+
+    #[tokio::test]
+    async fn notification_configuration_max_length() {
+        let content = CONTENT.replace(
+            "[notification.preparation]",
+            "[notification]\nmax_length = 12\n[notification.preparation]",
+        );
+        let config = Arc::new(toml::from_str::<Configuration>(&content).unwrap());
+        let repository = NotificationConfiguration::new(config);
+
+        assert!(repository.preparation_notification().await.is_ok());
+        assert!(matches!(
+            repository.concentration_notification().await,
+            Err(GetNotificationError::Invalid {
+                source: TryNewNotificationMessageError::SummaryTooLong { max: 12, .. }
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn notification_configuration_urgency() {
+        let config = Arc::new(toml::from_str::<Configuration>(CONTENT).unwrap());
+        let repository = NotificationConfiguration::new(config);
+
+        let message = repository.concentration_notification().await.unwrap();
+        assert_eq!(message.urgency(), NotificationUrgency::Critical);
+        let message = repository.relaxation_notification().await.unwrap();
+        assert_eq!(message.urgency(), NotificationUrgency::Normal);
+    }
+
+    #[tokio::test]
+    async fn notification_configuration_timeout() {
+        let config = Arc::new(toml::from_str::<Configuration>(CONTENT).unwrap());
+        let repository = NotificationConfiguration::new(config);
+
+        let message = repository.preparation_notification().await.unwrap();
+        assert_eq!(
+            message.timeout(),
+            NotificationTimeout::After(Duration::from_secs(5))
+        );
+        let message = repository.concentration_notification().await.unwrap();
+        assert_eq!(message.timeout(), NotificationTimeout::Never);
+        let message = repository.relaxation_notification().await.unwrap();
+        assert_eq!(message.timeout(), NotificationTimeout::Default);
+    }
+
+    #[tokio::test]
+    async fn notification_configuration_sound() {
+        let config = Arc::new(toml::from_str::<Configuration>(CONTENT).unwrap());
+        let repository = NotificationConfiguration::new(config);
+
+        let message = repository.concentration_notification().await.unwrap();
+        assert_eq!(message.sound(), Some("complete"));
+        let message = repository.relaxation_notification().await.unwrap();
+        assert_eq!(message.sound(), None);
     }
 }
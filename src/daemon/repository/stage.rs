@@ -0,0 +1,162 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::daemon::config::{Configuration, StageKindOption, StageSection};
+use crate::domain::entity::{NotificationMessage, Stage, StageDuration, StageState};
+use crate::domain::repository::{stage::GetStageError, StageRepository};
+
+use super::notification::{limit, timeout, urgency, with_sound};
+
+/// A [`StageRepository`] implementation which reads configuration files.
+pub struct StageConfiguration {
+    config: Arc<Configuration>,
+}
+
+impl StageConfiguration {
+    /// Creates a new [`StageConfiguration`].
+    pub fn new(config: Arc<Configuration>) -> Self {
+        Self { config }
+    }
+
+    /// Build a [`Stage`] from a borrowed section.
+    fn stage(&self, section: &StageSection) -> Result<Stage, GetStageError> {
+        let name = &section.name;
+        let max_length = self.config.notification.max_length;
+        let kind = stage_state(section.kind);
+        let duration = StageDuration::try_new(section.duration).map_err(|err| {
+            GetStageError::InvalidDuration {
+                name: name.clone(),
+                source: err,
+            }
+        })?;
+        let notification =
+            NotificationMessage::try_new(section.summary.clone(), section.body.clone())
+                .and_then(|message| limit(message, max_length))
+                .map(|message| message.with_urgency(urgency(section.urgency)))
+                .map(|message| message.with_timeout(timeout(section.timeout_ms)))
+                .map(|message| with_sound(message, section.sound.clone()))
+                .map_err(|err| GetStageError::InvalidNotification {
+                    name: name.clone(),
+                    source: err,
+                })?;
+        let stage = Stage::try_new(name.clone(), kind, duration, notification)
+            .map_err(|err| GetStageError::Invalid { source: err })?;
+
+        if section.checkpoints.is_empty() {
+            return Ok(stage);
+        }
+        let summary = (section.checkpoint_summary.clone())
+            .unwrap_or_else(|| format!("{name} is ending soon"));
+        let checkpoint = NotificationMessage::try_new(summary, section.checkpoint_body.clone())
+            .and_then(|message| limit(message, max_length))
+            .map(|message| message.with_urgency(urgency(section.urgency)))
+            .map(|message| message.with_timeout(timeout(section.timeout_ms)))
+            .map_err(|err| GetStageError::InvalidNotification {
+                name: name.clone(),
+                source: err,
+            })?;
+        let checkpoints = section
+            .checkpoints
+            .iter()
+            .map(|&secs| Duration::from_secs(secs))
+            .collect();
+        Ok(stage.with_checkpoints(checkpoints, checkpoint))
+    }
+}
+
+/// Convert the kind of a stage in the configuration.
+pub(super) fn stage_state(kind: StageKindOption) -> StageState {
+    match kind {
+        StageKindOption::Preparation => StageState::Preparation,
+        StageKindOption::Concentration => StageState::Concentration,
+        StageKindOption::Relaxation => StageState::Relaxation,
+    }
+}
+
+#[async_trait::async_trait]
+impl StageRepository for StageConfiguration {
+    async fn stages(&self) -> Result<Vec<Stage>, GetStageError> {
+        let stages = self.config.stages.iter();
+        stages.map(|section| self.stage(section)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = r#"
+        [duration]
+        preparation = 1
+        concentration = 2
+        relaxation = 3
+
+        [notification.preparation]
+        summary = "Preparation"
+
+        [notification.concentration]
+        summary = "Concentration"
+
+        [notification.relaxation]
+        summary = "Relaxation"
+    "#;
+
+    #[tokio::test]
+    async fn stage_configuration_default() {
+        let config = Arc::new(toml::from_str::<Configuration>(CONTENT).unwrap());
+        let repository = StageConfiguration::new(config);
+
+        assert!(repository.stages().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn stage_configuration_custom() {
+        let content = format!(
+            "{CONTENT}\n[[stages]]\nname = \"Deep Work\"\nkind = \"concentration\"\n\
+             duration = \"50m\"\nsummary = \"End\"\n"
+        );
+        let config = Arc::new(toml::from_str::<Configuration>(&content).unwrap());
+        let repository = StageConfiguration::new(config);
+
+        let stages = repository.stages().await.unwrap();
+        assert_eq!(stages.len(), 1);
+        assert_eq!(stages[0].name(), "Deep Work");
+        assert_eq!(stages[0].kind(), StageState::Concentration);
+        assert_eq!(stages[0].duration(), &StageDuration::try_new(3000).unwrap());
+        assert!(stages[0].checkpoints().is_empty());
+        assert!(stages[0].checkpoint_notification().is_none());
+    }
+
+    #[tokio::test]
+    async fn stage_configuration_checkpoints() {
+        let content = format!(
+            "{CONTENT}\n[[stages]]\nname = \"Deep Work\"\nkind = \"concentration\"\n\
+             duration = \"50m\"\nsummary = \"End\"\ncheckpoints = [\"10m\", 60]\n"
+        );
+        let config = Arc::new(toml::from_str::<Configuration>(&content).unwrap());
+        let repository = StageConfiguration::new(config);
+
+        let stages = repository.stages().await.unwrap();
+        assert_eq!(
+            stages[0].checkpoints(),
+            [Duration::from_secs(600), Duration::from_secs(60)],
+        );
+        let notification = stages[0].checkpoint_notification().unwrap();
+        assert_eq!(notification.summary(), "Deep Work is ending soon");
+    }
+
+    #[tokio::test]
+    async fn stage_configuration_invalid() {
+        let content = format!(
+            "{CONTENT}\n[[stages]]\nname = \"Deep Work\"\nkind = \"concentration\"\n\
+             duration = 0\nsummary = \"End\"\n"
+        );
+        let config = Arc::new(toml::from_str::<Configuration>(&content).unwrap());
+        let repository = StageConfiguration::new(config);
+
+        assert!(matches!(
+            repository.stages().await,
+            Err(GetStageError::InvalidDuration { .. })
+        ));
+    }
+}
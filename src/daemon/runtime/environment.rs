@@ -64,13 +64,16 @@ impl Environment {
     /// This function will return an error if any system error occurs.
     fn setup_permissions(permissions: &Vec<(PathBuf, u32)>) -> Result<(), SetupEnvironmentError> {
         for (path, permission) in permissions {
-            let metadata = fs::metadata(path).context(SetPermissionSnafu {
+            let context = SetPermissionSnafu {
                 path: path.as_path(),
                 permission: *permission,
-            })?;
+            };
+            let mut permissions = fs::metadata(path).context(context)?.permissions();
 
-            if metadata.permissions().mode() != *permission {
-                metadata.permissions().set_mode(*permission);
+            // The mode also carries the file type, which is left as it is.
+            if permissions.mode() & 0o7777 != *permission {
+                permissions.set_mode(*permission);
+                fs::set_permissions(path, permissions).context(context)?;
             }
         }
 
@@ -87,7 +90,7 @@ pub enum SetupEnvironmentError {
         #[snafu(source(from(IoError, Arc::new)))]
         source: Arc<IoError>,
     },
-    #[snafu(display("Could not set {}'s permission to {permission}", path.display()))]
+    #[snafu(display("Could not set {}'s permission to {permission:o}", path.display()))]
     SetPermission {
         path: PathBuf,
         permission: u32,
@@ -124,13 +127,28 @@ mod tests {
         let tmp = TempDir::new().expect("Test environment should support temporary directories");
         tmp.child("file").touch().unwrap();
         let file = tmp.child("file").to_path_buf();
-        println!("{}", file.display());
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o644)).unwrap();
 
         let mut env = Environment::new();
-        env.register_permission(&file, 0o644);
+        env.register_permission(&file, 0o600);
         env.setup().unwrap();
 
         let perm = fs::metadata(&file).unwrap().permissions().mode();
-        assert_eq!(perm & ((1 << 9) - 1), 0o644);
+        assert_eq!(perm & 0o7777, 0o600);
+    }
+
+    #[test]
+    fn environment_setup_permission_missing() {
+        let tmp = TempDir::new().expect("Test environment should support temporary directories");
+
+        let mut env = Environment::new();
+        env.register_permission(tmp.child("missing"), 0o600);
+        assert!(matches!(
+            env.setup(),
+            Err(SetupEnvironmentError::SetPermission {
+                permission: 0o600,
+                ..
+            })
+        ));
     }
 }
@@ -1,4 +1,4 @@
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{Error as IoError, ErrorKind as IoErrorKind, Read, Write};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -14,6 +14,7 @@ pub struct ProcessController {
     app_name: String,
     pid_file: PathBuf,
     daemonize: bool,
+    force: bool,
 }
 
 impl ProcessController {
@@ -23,9 +24,17 @@ impl ProcessController {
             app_name,
             pid_file,
             daemonize,
+            force: false,
         }
     }
 
+    /// Remove a stale PID file before starting, even if its content is
+    /// invalid, as long as no matching process is running.
+    pub fn with_force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
     /// Finish process-related work, such as daemonization and multiple instance
     /// detection.
     ///
@@ -34,7 +43,14 @@ impl ProcessController {
     /// This function will return an error if the preapration fails.
     pub fn start(self) -> Result<(), ControlProcessError> {
         let system = System::new_all();
-        Self::detect_instance(&system, &self.pid_file, &self.app_name)?;
+        match Self::detect_instance(&system, &self.pid_file, &self.app_name) {
+            Err(ControlProcessError::InvalidPidFile) if self.force => {}
+            res => res?,
+        }
+
+        if self.force {
+            Self::remove_pid(&self.pid_file)?;
+        }
 
         if self.daemonize {
             Daemonize::new()
@@ -55,10 +71,30 @@ impl ProcessController {
         pid_file: P,
         app_name: &str,
     ) -> Result<(), ControlProcessError> {
+        match Self::running_instance(system, pid_file, app_name)? {
+            Some(_) => MultipleProcessesSnafu.fail(),
+            None => Ok(()),
+        }
+    }
+
+    /// Get the PID stored in `pid_file` if the process is alive and named
+    /// after `app_name`. Return `None` if the PID file is missing or empty,
+    /// or the process has gone or is an unrelated one which reuses the PID,
+    /// e.g. after the daemon crashed.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the PID file could not be read
+    /// or is invalid.
+    pub fn running_instance<P: AsRef<Path>>(
+        system: &System,
+        pid_file: P,
+        app_name: &str,
+    ) -> Result<Option<Pid>, ControlProcessError> {
         let mut file = match File::open(pid_file) {
             Ok(file) => file,
             Err(err) => match err.kind() {
-                IoErrorKind::NotFound => return Ok(()),
+                IoErrorKind::NotFound => return Ok(None),
                 _ => {
                     return Err(err).context(FileSystemSnafu {
                         message: "Could not open PID file",
@@ -72,20 +108,33 @@ impl ProcessController {
             message: "Could not open PID file",
         })?;
 
+        // A daemon which crashed right after creating the file leaves it empty.
+        let content = content.trim();
+        if content.is_empty() {
+            return Ok(None);
+        }
+
         let pid = content
-            .trim()
             .parse::<Pid>()
             .map_err(|_| InvalidPidFileSnafu.build())?;
 
-        if let Some(proc) = system.process(pid) {
-            let name = proc.name().to_string_lossy();
-            if name.contains(app_name) {
-                MultipleProcessesSnafu.fail()
-            } else {
+        let running = system
+            .process(pid)
+            .is_some_and(|proc| proc.name().to_string_lossy().contains(app_name));
+        Ok(running.then_some(pid))
+    }
+
+    /// Remove a stale `pid_file`, if any.
+    fn remove_pid<P: AsRef<Path>>(pid_file: P) -> Result<(), ControlProcessError> {
+        match fs::remove_file(pid_file) {
+            Ok(()) => {
+                tracing::info!("Removed the stale PID file");
                 Ok(())
             }
-        } else {
-            Ok(())
+            Err(err) if err.kind() == IoErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err).context(FileSystemSnafu {
+                message: "Could not remove stale PID file",
+            }),
         }
     }
 
@@ -119,3 +168,74 @@ pub enum ControlProcessError {
     #[snafu(display("Could not daemonize the process"))]
     Daemonize { source: DaemonizeError },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use assert_fs::prelude::*;
+    use assert_fs::TempDir;
+
+    const APP_NAME: &str = "to-concentrate-test-daemon";
+
+    #[test]
+    fn running_instance_empty() {
+        let tmp = TempDir::new().expect("Test environment should support temporary directories");
+        let pid_file = tmp.child("daemon.pid");
+        pid_file.write_str(" \n").unwrap();
+
+        let system = System::new_all();
+        assert_eq!(
+            ProcessController::running_instance(&system, &pid_file, APP_NAME).unwrap(),
+            None,
+        );
+    }
+
+    #[test]
+    fn running_instance_non_numeric() {
+        let tmp = TempDir::new().expect("Test environment should support temporary directories");
+        let pid_file = tmp.child("daemon.pid");
+        pid_file.write_str("not a pid").unwrap();
+
+        let system = System::new_all();
+        assert!(matches!(
+            ProcessController::running_instance(&system, &pid_file, APP_NAME),
+            Err(ControlProcessError::InvalidPidFile),
+        ));
+    }
+
+    #[test]
+    fn running_instance_other_process() {
+        let tmp = TempDir::new().expect("Test environment should support temporary directories");
+        let pid_file = tmp.child("daemon.pid");
+        let pid = sysinfo::get_current_pid().unwrap();
+        pid_file.write_str(&pid.to_string()).unwrap();
+
+        // The test process is alive, but it is not a daemon.
+        let system = System::new_all();
+        assert_eq!(
+            ProcessController::running_instance(&system, &pid_file, APP_NAME).unwrap(),
+            None,
+        );
+        assert!(ProcessController::detect_instance(&system, &pid_file, APP_NAME).is_ok());
+    }
+
+    #[test]
+    fn process_controller_start_force() {
+        let tmp = TempDir::new().expect("Test environment should support temporary directories");
+        let pid_file = tmp.child("daemon.pid");
+        pid_file.write_str("not a pid").unwrap();
+
+        let controller = ProcessController::new(APP_NAME.to_owned(), pid_file.to_path_buf(), false);
+        assert!(matches!(
+            controller.start(),
+            Err(ControlProcessError::InvalidPidFile),
+        ));
+
+        let controller = ProcessController::new(APP_NAME.to_owned(), pid_file.to_path_buf(), false)
+            .with_force(true);
+        assert!(controller.start().is_ok());
+        let pid = sysinfo::get_current_pid().unwrap();
+        pid_file.assert(pid.to_string());
+    }
+}
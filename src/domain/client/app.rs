@@ -1,6 +1,10 @@
 use std::sync::Arc;
 
-use crate::domain::client::outbound::{InitPort, PausePort, QueryPort, ResumePort, SkipPort};
+use crate::domain::client::outbound::SubscribePort;
+use crate::domain::client::outbound::{AdjustPort, AutoPausePort, AutoResumePort, HealthPort};
+use crate::domain::client::outbound::{InitPort, PausePort, QueryPort, ResetPort, ResumePort};
+use crate::domain::client::outbound::{MutePort, SetDurationPort, StopPort, UnmutePort};
+use crate::domain::client::outbound::{SchedulePort, SkipPort, SkipToPort, StatsPort, StatusPort};
 
 /// Entrance to the domain logic, providing ports for external adapters.
 pub struct ApplicationCore {
@@ -8,25 +12,68 @@ pub struct ApplicationCore {
     pub pause: Arc<dyn PausePort>,
     pub resume: Arc<dyn ResumePort>,
     pub query: Arc<dyn QueryPort>,
+    pub schedule: Arc<dyn SchedulePort>,
+    pub stats: Arc<dyn StatsPort>,
+    pub subscribe: Arc<dyn SubscribePort>,
     pub skip: Arc<dyn SkipPort>,
+    pub reset: Arc<dyn ResetPort>,
+    pub mute: Arc<dyn MutePort>,
+    pub unmute: Arc<dyn UnmutePort>,
+    pub adjust: Arc<dyn AdjustPort>,
+    pub set_duration: Arc<dyn SetDurationPort>,
+    pub skip_to: Arc<dyn SkipToPort>,
+    pub stop: Arc<dyn StopPort>,
+    pub auto_pause: Arc<dyn AutoPausePort>,
+    pub auto_resume: Arc<dyn AutoResumePort>,
+    pub status: Arc<dyn StatusPort>,
+    pub health: Arc<dyn HealthPort>,
 }
 
 impl ApplicationCore {
     /// Create and initialize a new [`ApplicationCore`] by injecting external
     /// repositories and adapters.
+    #[allow(clippy::too_many_arguments)]
     pub fn setup(
         init: Arc<dyn InitPort>,
         pause: Arc<dyn PausePort>,
         resume: Arc<dyn ResumePort>,
         query: Arc<dyn QueryPort>,
+        schedule: Arc<dyn SchedulePort>,
+        stats: Arc<dyn StatsPort>,
+        subscribe: Arc<dyn SubscribePort>,
         skip: Arc<dyn SkipPort>,
+        reset: Arc<dyn ResetPort>,
+        mute: Arc<dyn MutePort>,
+        unmute: Arc<dyn UnmutePort>,
+        adjust: Arc<dyn AdjustPort>,
+        set_duration: Arc<dyn SetDurationPort>,
+        skip_to: Arc<dyn SkipToPort>,
+        stop: Arc<dyn StopPort>,
+        auto_pause: Arc<dyn AutoPausePort>,
+        auto_resume: Arc<dyn AutoResumePort>,
+        status: Arc<dyn StatusPort>,
+        health: Arc<dyn HealthPort>,
     ) -> ApplicationCore {
         Self {
             init,
             pause,
             resume,
             query,
+            schedule,
+            stats,
+            subscribe,
             skip,
+            reset,
+            mute,
+            unmute,
+            adjust,
+            set_duration,
+            skip_to,
+            stop,
+            auto_pause,
+            auto_resume,
+            status,
+            health,
         }
     }
 }
@@ -1,11 +1,17 @@
 use std::error::Error as StdError;
 
 use snafu::prelude::*;
+use tokio::sync::mpsc::Receiver;
+use tokio::time::Duration;
 
 pub use crate::domain::daemon::inbound::QueryResponse;
 
+use crate::domain::entity::TimerStatus;
+use crate::domain::entity::{AutoPauseTrigger, ScheduledStage, StageState, TimerEvent, TimerStats};
+
 /// A public port for launching and initializing a daemon.
 #[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
 pub trait InitPort: Send + Sync + 'static {
     /// Do the initialization operation.
     ///
@@ -34,6 +40,7 @@ pub enum InitDaemonError {
 
 /// A public port for requesting the daemon to suspend the tomato timer.
 #[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
 pub trait PausePort: Send + Sync + 'static {
     /// Do the pause operation.
     ///
@@ -45,6 +52,7 @@ pub trait PausePort: Send + Sync + 'static {
 
 /// A public port for requesting the daemon to resume the tomato timer.
 #[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
 pub trait ResumePort: Send + Sync + 'static {
     /// Do the resume operation.
     ///
@@ -56,20 +64,217 @@ pub trait ResumePort: Send + Sync + 'static {
 
 /// A public port for requesting the daemon to query the current state.
 #[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
 pub trait QueryPort: Send + Sync + 'static {
     /// Do the query operation.
     async fn query(&self) -> Result<QueryResponse, RequestDaemonError>;
 }
 
+/// A public port for requesting the daemon to list the upcoming stages.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait SchedulePort: Send + Sync + 'static {
+    /// Do the schedule operation, which returns the current stage with its
+    /// remaining time, followed by the stages of one round of the sequence.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the operation failed.
+    async fn schedule(&self) -> Result<Vec<ScheduledStage>, RequestDaemonError>;
+}
+
+/// A public port for requesting the daemon to count the completed stages.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait StatsPort: Send + Sync + 'static {
+    /// Do the stats operation.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the operation failed.
+    async fn stats(&self) -> Result<TimerStats, RequestDaemonError>;
+}
+
+/// A public port for following the changes of the timer's state.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait SubscribePort: Send + Sync + 'static {
+    /// Do the subscribe operation, which returns a receiver of the current
+    /// state followed by every change of it. The subscription goes on in the
+    /// background until the receiver is dropped.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the first subscription failed.
+    async fn subscribe(&self) -> Result<Receiver<TimerEvent>, RequestDaemonError>;
+}
+
 /// A public port for requesting the daemon to skip the current stage.
 #[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
 pub trait SkipPort: Send + Sync + 'static {
-    /// Do the skip operation.
+    /// Do the skip operation. Ask the daemon to emit the skipped stage's
+    /// notification if `notify` is set.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the operation failed.
+    async fn skip(&self, notify: bool) -> Result<(), RequestDaemonError>;
+}
+
+/// A public port for requesting the daemon to restart the tomato timer from
+/// the initial stage.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait ResetPort: Send + Sync + 'static {
+    /// Do the reset operation.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the operation failed.
+    async fn reset(&self) -> Result<(), RequestDaemonError>;
+}
+
+/// A public port for requesting the daemon to stop showing notifications.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait MutePort: Send + Sync + 'static {
+    /// Do the mute operation.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the operation failed.
+    async fn mute(&self) -> Result<(), RequestDaemonError>;
+}
+
+/// A public port for requesting the daemon to show notifications again.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait UnmutePort: Send + Sync + 'static {
+    /// Do the unmute operation.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the operation failed.
+    async fn unmute(&self) -> Result<(), RequestDaemonError>;
+}
+
+/// A public port for requesting the daemon to change the length of the
+/// current stage.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait AdjustPort: Send + Sync + 'static {
+    /// Do the adjusting operation. Positive `delta_secs` extends the current
+    /// stage and negative one shortens it.
     ///
     /// # Errors
     ///
     /// This function will return an error if the operation failed.
-    async fn skip(&self) -> Result<(), RequestDaemonError>;
+    async fn adjust(&self, delta_secs: i64) -> Result<(), RequestDaemonError>;
+}
+
+/// A public port for requesting the daemon to set the length of the current
+/// stage.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait SetDurationPort: Send + Sync + 'static {
+    /// Do the setting operation, so that the current stage lasts `total` in
+    /// all.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the operation failed.
+    async fn set_duration(&self, total: Duration) -> Result<(), RequestDaemonError>;
+}
+
+/// A public port for requesting the daemon to jump to a stage of a specific
+/// kind.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait SkipToPort: Send + Sync + 'static {
+    /// Do the skipping operation, which starts the next stage of the kind
+    /// `stage` afresh.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the operation failed.
+    async fn skip_to(&self, stage: StageState) -> Result<(), RequestDaemonError>;
+}
+
+/// A public port for requesting the daemon to stop and exit.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait StopPort: Send + Sync + 'static {
+    /// Do the stop operation.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the operation failed.
+    async fn stop(&self) -> Result<(), RequestDaemonError>;
+}
+
+/// A public port for requesting the daemon to suspend the tomato timer on
+/// behalf of an external event.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait AutoPausePort: Send + Sync + 'static {
+    /// Do the automatic pause operation.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the operation failed.
+    async fn auto_pause(&self, trigger: AutoPauseTrigger) -> Result<(), RequestDaemonError>;
+}
+
+/// A public port for requesting the daemon to resume the tomato timer on
+/// behalf of an external event.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait AutoResumePort: Send + Sync + 'static {
+    /// Do the automatic resume operation.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the operation failed.
+    async fn auto_resume(&self, trigger: AutoPauseTrigger) -> Result<(), RequestDaemonError>;
+}
+
+/// A public port for checking whether the daemon is running.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait StatusPort: Send + Sync + 'static {
+    /// Check the daemon's process and whether it accepts connections.
+    async fn status(&self) -> DaemonStatus;
+}
+
+/// A public port for checking whether the daemon and its timer respond.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait HealthPort: Send + Sync + 'static {
+    /// Do the health check operation.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the operation failed, including
+    /// when the timer does not respond in time.
+    async fn health(&self) -> Result<DaemonHealth, RequestDaemonError>;
+}
+
+/// Health of the daemon, which has been up for `uptime` and whose timer is
+/// in `status`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DaemonHealth {
+    pub uptime: Duration,
+    pub status: TimerStatus,
+}
+
+/// Status of the daemon. `pid` is the process recorded in the PID file if it
+/// is still alive, and `reachable` tells whether the daemon answered a
+/// connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DaemonStatus {
+    pub pid: Option<u32>,
+    pub reachable: bool,
 }
 
 /// An error type of sending requests to daemon.
@@ -81,6 +286,12 @@ pub enum RequestDaemonError {
     Unavailable { endpoint: String },
     #[snafu(display("Could not receive a valid response"))]
     BadResponse,
+    #[snafu(display(
+        "Client protocol {client} is incompatible with daemon protocol {server}, restart the daemon"
+    ))]
+    VersionMismatch { client: String, server: String },
+    #[snafu(display("Daemon could not handle the request: {message}"))]
+    Daemon { message: String },
     #[snafu(whatever, display("Request failed: {message}"))]
     Unknown {
         message: String,
@@ -1,58 +1,424 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use snafu::prelude::*;
+use tokio::sync::Mutex;
+use tokio::time::{Duration, Instant};
 
-use crate::domain::daemon::app::service::{PauseService, QueryService, ResumeService, SkipService};
-use crate::domain::daemon::inbound::{PausePort, QueryPort, ResumePort, SkipPort};
-use crate::domain::daemon::outbound::NotifyPort;
-use crate::domain::daemon::worker::{self, SpawnWorkerError};
-use crate::domain::repository::{DurationRepository, NotificationRepository};
+use crate::domain::daemon::app::session::DEFAULT_SESSION;
+use crate::domain::daemon::app::session::{Session, SessionFactory, WorkerSessionFactory};
+use crate::domain::daemon::outbound::{IdleDetector, NotifyPort, StageLog, StateStore};
+use crate::domain::daemon::worker::{LoadWorkerConfigError, SpawnWorkerError};
+use crate::domain::repository::{
+    BehaviorRepository, DurationRepository, NotificationRepository, StageRepository,
+};
 
-/// Entrance to the domain logic, providing ports for external adapters.
+/// Entrance to the domain logic, providing ports of named [`Session`]s for
+/// external adapters.
 pub struct ApplicationCore {
-    pub pause: Arc<dyn PausePort>,
-    pub resume: Arc<dyn ResumePort>,
-    pub query: Arc<dyn QueryPort>,
-    pub skip: Arc<dyn SkipPort>,
+    factory: Arc<dyn SessionFactory>,
+    sessions: Mutex<Sessions>,
+    started: Instant,
+}
+
+/// The [`Session`]s by their names, to which no session is added anymore
+/// once they are stopped.
+#[derive(Default)]
+struct Sessions {
+    opened: HashMap<String, Arc<Session>>,
+    stopped: bool,
 }
 
 impl ApplicationCore {
     /// Initialize the application by injecting external repositories and
-    /// adapters.
+    /// adapters. The [`DEFAULT_SESSION`] is started immediately.
     ///
     /// # Errors
     ///
     /// This function will return an error if initialization failed.
+    #[allow(clippy::too_many_arguments)]
     pub async fn setup(
         notify_port: Arc<dyn NotifyPort>,
+        idle_detector: Arc<dyn IdleDetector>,
+        state_store: Arc<dyn StateStore>,
+        stage_log: Arc<dyn StageLog>,
         duration_repository: Arc<dyn DurationRepository>,
         notification_repository: Arc<dyn NotificationRepository>,
+        stage_repository: Arc<dyn StageRepository>,
+        behavior_repository: Arc<dyn BehaviorRepository>,
     ) -> Result<ApplicationCore, SetupApplicationCoreError> {
-        let worker = worker::spawn(duration_repository, notification_repository, notify_port)
-            .await
-            .context(WorkerSnafu)?;
-        let worker = Arc::new(worker);
+        let factory = WorkerSessionFactory::new(
+            notify_port,
+            idle_detector,
+            state_store,
+            stage_log,
+            duration_repository,
+            notification_repository,
+            stage_repository,
+            behavior_repository,
+        );
 
-        let pause_port = Arc::new(PauseService::new(Arc::clone(&worker)));
-        let resume_port = Arc::new(ResumeService::new(Arc::clone(&worker)));
-        let query_port = Arc::new(QueryService::new(Arc::clone(&worker)));
-        let skip_port = Arc::new(SkipService::new(Arc::clone(&worker)));
+        Self::with_factory(Arc::new(factory)).await
+    }
 
+    /// Initialize the application with a custom [`SessionFactory`]. The
+    /// [`DEFAULT_SESSION`] is started immediately.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if initialization failed.
+    pub async fn with_factory(
+        factory: Arc<dyn SessionFactory>,
+    ) -> Result<ApplicationCore, SetupApplicationCoreError> {
         let app = ApplicationCore {
-            pause: pause_port,
-            resume: resume_port,
-            query: query_port,
-            skip: skip_port,
+            factory,
+            sessions: Mutex::new(Sessions::default()),
+            started: Instant::now(),
         };
 
+        app.session(DEFAULT_SESSION).await?;
         Ok(app)
     }
+
+    /// Get the [`Session`] with the given name, creating it if it does not
+    /// exist yet.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a new session could not be
+    /// initialized, or the sessions have been stopped.
+    pub async fn session(&self, name: &str) -> Result<Arc<Session>, SetupApplicationCoreError> {
+        // Hold the lock while creating, so that concurrent requests for the
+        // same name never spawn two workers.
+        let mut sessions = self.sessions.lock().await;
+        ensure!(!sessions.stopped, StoppedSnafu { session: name });
+
+        if let Some(session) = sessions.opened.get(name) {
+            return Ok(Arc::clone(session));
+        }
+
+        let session = Arc::new(self.factory.create(name).await?);
+        sessions
+            .opened
+            .insert(name.to_owned(), Arc::clone(&session));
+        Ok(session)
+    }
+
+    /// Get how long it has been since the application was initialized.
+    pub fn uptime(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Stop the timers of all sessions and clear their saved progress. No
+    /// session is opened afterwards, so a request arriving later never starts
+    /// a fresh timer.
+    pub async fn stop(&self) {
+        self.stop_all(false).await;
+    }
+
+    /// Stop the timers of all sessions like [`ApplicationCore::stop`], but
+    /// keep their saved progress for the next start of the daemon.
+    pub async fn shutdown(&self) {
+        self.stop_all(true).await;
+    }
+
+    /// Replace the configuration with the one loaded from the given
+    /// repositories. Running sessions keep the length of their current
+    /// stages, and sessions created afterwards start with the new one.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the new configuration is
+    /// invalid, in which case the old one stays in effect.
+    pub async fn reload(
+        &self,
+        duration_repository: Arc<dyn DurationRepository>,
+        notification_repository: Arc<dyn NotificationRepository>,
+        stage_repository: Arc<dyn StageRepository>,
+        behavior_repository: Arc<dyn BehaviorRepository>,
+    ) -> Result<(), ReloadApplicationCoreError> {
+        // Hold the lock, so that no session is created with the old
+        // configuration in the meantime.
+        let sessions = self.sessions.lock().await;
+        let config = self
+            .factory
+            .reload(
+                duration_repository,
+                notification_repository,
+                stage_repository,
+                behavior_repository,
+            )
+            .await?;
+
+        for session in sessions.opened.values() {
+            session.reload.reload(config.clone()).await;
+        }
+        Ok(())
+    }
+
+    async fn stop_all(&self, keep_progress: bool) {
+        let sessions = {
+            let mut sessions = self.sessions.lock().await;
+            sessions.stopped = true;
+            std::mem::take(&mut sessions.opened)
+        };
+
+        for session in sessions.values() {
+            session.stop.stop(keep_progress).await;
+        }
+    }
 }
 
 /// An error for initializing the application.
 #[derive(Debug, Snafu)]
+#[snafu(visibility(pub(super)))]
 #[non_exhaustive]
 pub enum SetupApplicationCoreError {
-    #[snafu(display("Could not spawn a background worker"))]
-    Worker { source: SpawnWorkerError },
+    #[snafu(display("Could not spawn a background worker for session {session:?}"))]
+    Worker {
+        session: String,
+        source: SpawnWorkerError,
+    },
+    #[snafu(display("Could not open session {session:?} after the timers have stopped"))]
+    Stopped { session: String },
+}
+
+/// An error for reloading the configuration of the application.
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(super)))]
+#[non_exhaustive]
+pub enum ReloadApplicationCoreError {
+    #[snafu(display("Could not load the new configuration"))]
+    Config { source: LoadWorkerConfigError },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::future;
+
+    use crate::domain::daemon::outbound::{MockIdleDetector, MockStageLog, MockStateStore};
+    use crate::domain::daemon::outbound::{NotifyError, NotifyRequest};
+    use crate::domain::entity::sequence::TryNewStageError;
+    use crate::domain::entity::{NotificationMessage, StageDuration, TimerMode, TimerStatus};
+    use crate::domain::repository::behavior::MockBehaviorRepository;
+    use crate::domain::repository::duration::MockDurationRepository;
+    use crate::domain::repository::notification::MockNotificationRepository;
+    use crate::domain::repository::stage::{GetStageError, MockStageRepository};
+
+    struct SilentNotifier;
+
+    #[async_trait::async_trait]
+    impl NotifyPort for SilentNotifier {
+        async fn notify_impl(&self, _request: NotifyRequest) -> Result<(), NotifyError> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn application_core_independent_sessions() {
+        let core = new_core().await;
+
+        let default = core.session(DEFAULT_SESSION).await.unwrap();
+        let work = core.session("work").await.unwrap();
+        assert!(Arc::ptr_eq(&work, &core.session("work").await.unwrap()));
+
+        work.pause.pause().await;
+        tokio::time::sleep(Duration::from_secs(3)).await;
+
+        let response = default.query.query().await;
+        assert_eq!(response.current, TimerStatus::Running);
+        assert_eq!(response.past, Duration::from_secs(3));
+
+        let response = work.query.query().await;
+        assert_eq!(response.current, TimerStatus::Paused);
+        assert_eq!(response.past, Duration::from_secs(0));
+
+        default.skip.skip(false).await;
+        assert_eq!(default.query.query().await.stage, "Concentration");
+        assert_eq!(work.query.query().await.stage, "Preparation");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn application_core_uptime() {
+        let core = new_core().await;
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        assert_eq!(core.uptime(), Duration::from_secs(5));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn application_core_shutdown() {
+        let core = new_core_with_clears(0).await;
+        let work = core.session("work").await.unwrap();
+        work.pause.pause().await;
+
+        core.shutdown().await;
+        assert!(core.sessions.lock().await.opened.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn application_core_stop() {
+        let core = new_core().await;
+        let default = core.session(DEFAULT_SESSION).await.unwrap();
+        let work = core.session("work").await.unwrap();
+        work.pause.pause().await;
+
+        core.stop().await;
+        assert!(core.sessions.lock().await.opened.is_empty());
+        assert_eq!(default.query.query().await.current, TimerStatus::Stopped);
+
+        // Addressing a session afterwards never starts a fresh timer.
+        for name in [DEFAULT_SESSION, "work"] {
+            assert!(matches!(
+                core.session(name).await,
+                Err(SetupApplicationCoreError::Stopped { session }) if session == name
+            ));
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn application_core_reload() {
+        let core = new_core().await;
+        let default = core.session(DEFAULT_SESSION).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(3)).await;
+
+        let (duration, notification, stage, behavior) = new_repositories(900);
+        core.reload(duration, notification, stage, behavior)
+            .await
+            .unwrap();
+
+        // The current stage keeps its length, but the next one is reloaded.
+        let response = default.query.query().await;
+        assert_eq!(response.total, Duration::from_secs(600));
+        assert_eq!(response.past, Duration::from_secs(3));
+        default.skip.skip(false).await;
+        assert_eq!(default.query.query().await.total, Duration::from_secs(900));
+
+        // So is a session created afterwards.
+        let work = core.session("work").await.unwrap();
+        assert_eq!(work.query.query().await.total, Duration::from_secs(900));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn application_core_reload_invalid() {
+        let core = new_core().await;
+        let default = core.session(DEFAULT_SESSION).await.unwrap();
+
+        let (duration, notification, _, behavior) = new_repositories(900);
+        let mut stage = MockStageRepository::new();
+        stage.expect_stages().returning(|| {
+            Err(GetStageError::Invalid {
+                source: TryNewStageError::EmptyName,
+            })
+        });
+        assert!(matches!(
+            core.reload(duration, notification, Arc::new(stage), behavior)
+                .await,
+            Err(ReloadApplicationCoreError::Config { .. })
+        ));
+
+        default.skip.skip(false).await;
+        assert_eq!(default.query.query().await.total, Duration::from_secs(600));
+    }
+
+    async fn new_core() -> ApplicationCore {
+        new_core_with_clears(0..).await
+    }
+
+    /// Create an [`ApplicationCore`] whose saved progress is cleared for the
+    /// given number of times.
+    async fn new_core_with_clears(clears: impl Into<mockall::TimesRange>) -> ApplicationCore {
+        let clears = clears.into();
+        let (duration, notification, stage, behavior) = new_repositories(600);
+
+        let mut store = MockStateStore::new();
+        store
+            .expect_load()
+            .returning(|_| Box::pin(future::ready(Ok(None))));
+        store
+            .expect_save()
+            .returning(|_, _| Box::pin(future::ready(Ok(()))));
+        store
+            .expect_clear()
+            .times(clears)
+            .returning(|_| Box::pin(future::ready(Ok(()))));
+
+        let mut stage_log = MockStageLog::new();
+        stage_log
+            .expect_append()
+            .returning(|_| Box::pin(future::ready(Ok(()))));
+
+        ApplicationCore::setup(
+            Arc::new(SilentNotifier),
+            Arc::new(MockIdleDetector::new()),
+            Arc::new(store),
+            Arc::new(stage_log),
+            duration,
+            notification,
+            stage,
+            behavior,
+        )
+        .await
+        .unwrap()
+    }
+
+    type Repositories = (
+        Arc<dyn DurationRepository>,
+        Arc<dyn NotificationRepository>,
+        Arc<dyn StageRepository>,
+        Arc<dyn BehaviorRepository>,
+    );
+
+    /// Create repositories of the default stages, each lasting `secs`.
+    fn new_repositories(secs: u64) -> Repositories {
+        let new_duration = move || Ok(StageDuration::try_new(secs).unwrap());
+        let new_message = || Ok(NotificationMessage::try_new("Stage".to_owned(), None).unwrap());
+
+        let mut duration = MockDurationRepository::new();
+        duration
+            .expect_preparation_duration()
+            .returning(new_duration);
+        duration
+            .expect_concentration_duration()
+            .returning(new_duration);
+        duration
+            .expect_relaxation_duration()
+            .returning(new_duration);
+        duration
+            .expect_long_break_duration()
+            .returning(new_duration);
+        duration
+            .expect_cycles_before_long_break()
+            .returning(|| Ok(0));
+        duration.expect_initial_stage().returning(|| Ok(None));
+        duration.expect_mode().returning(|| Ok(TimerMode::Full));
+
+        let mut notification = MockNotificationRepository::new();
+        notification
+            .expect_preparation_notification()
+            .returning(new_message);
+        notification
+            .expect_concentration_notification()
+            .returning(new_message);
+        notification
+            .expect_relaxation_notification()
+            .returning(new_message);
+
+        let mut stage = MockStageRepository::new();
+        stage.expect_stages().returning(|| Ok(Vec::new()));
+
+        let mut behavior = MockBehaviorRepository::new();
+        behavior.expect_auto_pause_enabled().returning(|_| Ok(true));
+        behavior.expect_idle_threshold().returning(|| Ok(None));
+        behavior.expect_start_paused().returning(|| Ok(false));
+        behavior.expect_max_pause().returning(|| Ok(None));
+
+        (
+            Arc::new(duration),
+            Arc::new(notification),
+            Arc::new(stage),
+            Arc::new(behavior),
+        )
+    }
 }
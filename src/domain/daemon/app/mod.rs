@@ -1,4 +1,9 @@
 mod core;
 mod service;
+mod session;
 
-pub use core::{ApplicationCore, SetupApplicationCoreError};
+pub use core::{ApplicationCore, ReloadApplicationCoreError, SetupApplicationCoreError};
+pub use session::{Session, SessionFactory, DEFAULT_SESSION};
+
+#[cfg(test)]
+pub use session::MockSessionFactory;
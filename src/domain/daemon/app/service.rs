@@ -1,7 +1,17 @@
 use std::sync::Arc;
 
-use crate::domain::daemon::inbound::{PausePort, QueryPort, QueryResponse, ResumePort, SkipPort};
+use tokio::sync::broadcast::Sender;
+
+use crate::domain::daemon::inbound::{AdjustPort, ResetPort, SkipToPort, StopPort};
+use crate::domain::daemon::inbound::{AutoPausePort, AutoResumePort, ReloadPort};
+use crate::domain::daemon::inbound::{MutePort, SetDurationPort, SubscribePort, UnmutePort};
+use crate::domain::daemon::inbound::{PausePort, PingPort, QueryPort, QueryResponse};
+use crate::domain::daemon::inbound::{ResumePort, SchedulePort, SkipPort, StatsPort};
+use crate::domain::daemon::worker::{ChangeStageError, WorkerConfig};
 use crate::domain::daemon::worker::{QueryResponse as WorkerQueryResponse, WorkerHandle};
+use crate::domain::entity::TimerStats;
+use crate::domain::entity::{AutoPauseTrigger, ScheduledStage, StageDuration, StageState};
+use crate::domain::entity::{TimerEvent, TimerStatus};
 
 #[derive(Debug)]
 pub struct PauseService {
@@ -57,18 +67,45 @@ impl QueryPort for QueryService {
             current,
             total,
             past,
+            remaining,
             stage,
+            kind,
+            long_break,
+            completed_cycles,
+            muted,
         } = self.worker.query().await;
         QueryResponse {
             current,
-            stage: stage.to_string(),
+            stage,
+            kind,
             total,
-            remaining: total - past,
+            remaining,
             past,
+            long_break,
+            completed_cycles,
+            muted,
         }
     }
 }
 
+#[derive(Debug)]
+pub struct SubscribeService {
+    worker: Arc<WorkerHandle>,
+}
+
+impl SubscribeService {
+    pub fn new(worker: Arc<WorkerHandle>) -> Self {
+        Self { worker }
+    }
+}
+
+#[async_trait::async_trait]
+impl SubscribePort for SubscribeService {
+    async fn subscribe(&self, sender: Sender<TimerEvent>) {
+        self.worker.subscribe(sender).await
+    }
+}
+
 #[derive(Debug)]
 pub struct SkipService {
     worker: Arc<WorkerHandle>,
@@ -82,7 +119,241 @@ impl SkipService {
 
 #[async_trait::async_trait]
 impl SkipPort for SkipService {
-    async fn skip(&self) {
-        self.worker.skip().await
+    async fn skip(&self, notify: bool) {
+        self.worker.skip(notify).await
+    }
+}
+
+#[derive(Debug)]
+pub struct ResetService {
+    worker: Arc<WorkerHandle>,
+}
+
+impl ResetService {
+    pub fn new(worker: Arc<WorkerHandle>) -> Self {
+        Self { worker }
+    }
+}
+
+#[async_trait::async_trait]
+impl ResetPort for ResetService {
+    async fn reset(&self) {
+        self.worker.reset().await
+    }
+}
+
+#[derive(Debug)]
+pub struct MuteService {
+    worker: Arc<WorkerHandle>,
+}
+
+impl MuteService {
+    pub fn new(worker: Arc<WorkerHandle>) -> Self {
+        Self { worker }
+    }
+}
+
+#[async_trait::async_trait]
+impl MutePort for MuteService {
+    async fn mute(&self) {
+        self.worker.mute().await
+    }
+}
+
+#[derive(Debug)]
+pub struct UnmuteService {
+    worker: Arc<WorkerHandle>,
+}
+
+impl UnmuteService {
+    pub fn new(worker: Arc<WorkerHandle>) -> Self {
+        Self { worker }
+    }
+}
+
+#[async_trait::async_trait]
+impl UnmutePort for UnmuteService {
+    async fn unmute(&self) {
+        self.worker.unmute().await
+    }
+}
+
+#[derive(Debug)]
+pub struct AdjustService {
+    worker: Arc<WorkerHandle>,
+}
+
+impl AdjustService {
+    pub fn new(worker: Arc<WorkerHandle>) -> Self {
+        Self { worker }
+    }
+}
+
+#[async_trait::async_trait]
+impl AdjustPort for AdjustService {
+    async fn adjust(&self, delta_secs: i64) -> Result<(), ChangeStageError> {
+        self.worker.adjust(delta_secs).await
+    }
+}
+
+#[derive(Debug)]
+pub struct SetDurationService {
+    worker: Arc<WorkerHandle>,
+}
+
+impl SetDurationService {
+    pub fn new(worker: Arc<WorkerHandle>) -> Self {
+        Self { worker }
+    }
+}
+
+#[async_trait::async_trait]
+impl SetDurationPort for SetDurationService {
+    async fn set_duration(&self, total: StageDuration) {
+        self.worker.set_duration(total).await
+    }
+}
+
+#[derive(Debug)]
+pub struct PingService {
+    worker: Arc<WorkerHandle>,
+}
+
+impl PingService {
+    pub fn new(worker: Arc<WorkerHandle>) -> Self {
+        Self { worker }
+    }
+}
+
+#[async_trait::async_trait]
+impl PingPort for PingService {
+    async fn ping(&self) -> TimerStatus {
+        self.worker.ping().await
+    }
+}
+
+#[derive(Debug)]
+pub struct ScheduleService {
+    worker: Arc<WorkerHandle>,
+}
+
+impl ScheduleService {
+    pub fn new(worker: Arc<WorkerHandle>) -> Self {
+        Self { worker }
+    }
+}
+
+#[async_trait::async_trait]
+impl SchedulePort for ScheduleService {
+    async fn schedule(&self) -> Vec<ScheduledStage> {
+        self.worker.schedule().await
+    }
+}
+
+#[derive(Debug)]
+pub struct StatsService {
+    worker: Arc<WorkerHandle>,
+}
+
+impl StatsService {
+    pub fn new(worker: Arc<WorkerHandle>) -> Self {
+        Self { worker }
+    }
+}
+
+#[async_trait::async_trait]
+impl StatsPort for StatsService {
+    async fn stats(&self) -> TimerStats {
+        self.worker.stats().await
+    }
+}
+
+#[derive(Debug)]
+pub struct SkipToService {
+    worker: Arc<WorkerHandle>,
+}
+
+impl SkipToService {
+    pub fn new(worker: Arc<WorkerHandle>) -> Self {
+        Self { worker }
+    }
+}
+
+#[async_trait::async_trait]
+impl SkipToPort for SkipToService {
+    async fn skip_to(&self, stage: StageState) -> Result<(), ChangeStageError> {
+        self.worker.skip_to(stage).await
+    }
+}
+
+#[derive(Debug)]
+pub struct StopService {
+    worker: Arc<WorkerHandle>,
+}
+
+impl StopService {
+    pub fn new(worker: Arc<WorkerHandle>) -> Self {
+        Self { worker }
+    }
+}
+
+#[async_trait::async_trait]
+impl StopPort for StopService {
+    async fn stop(&self, keep_progress: bool) {
+        self.worker.stop(keep_progress).await
+    }
+}
+
+#[derive(Debug)]
+pub struct AutoPauseService {
+    worker: Arc<WorkerHandle>,
+}
+
+impl AutoPauseService {
+    pub fn new(worker: Arc<WorkerHandle>) -> Self {
+        Self { worker }
+    }
+}
+
+#[async_trait::async_trait]
+impl AutoPausePort for AutoPauseService {
+    async fn auto_pause(&self, trigger: AutoPauseTrigger) {
+        self.worker.auto_pause(trigger).await
+    }
+}
+
+#[derive(Debug)]
+pub struct AutoResumeService {
+    worker: Arc<WorkerHandle>,
+}
+
+impl AutoResumeService {
+    pub fn new(worker: Arc<WorkerHandle>) -> Self {
+        Self { worker }
+    }
+}
+
+#[async_trait::async_trait]
+impl AutoResumePort for AutoResumeService {
+    async fn auto_resume(&self, trigger: AutoPauseTrigger) {
+        self.worker.auto_resume(trigger).await
+    }
+}
+
+#[derive(Debug)]
+pub struct ReloadService {
+    worker: Arc<WorkerHandle>,
+}
+
+impl ReloadService {
+    pub fn new(worker: Arc<WorkerHandle>) -> Self {
+        Self { worker }
+    }
+}
+
+#[async_trait::async_trait]
+impl ReloadPort for ReloadService {
+    async fn reload(&self, config: WorkerConfig) {
+        self.worker.reload(config).await
     }
 }
@@ -0,0 +1,206 @@
+use std::sync::{Arc, RwLock};
+
+use snafu::prelude::*;
+
+use crate::domain::daemon::app::service::SetDurationService;
+use crate::domain::daemon::app::service::SubscribeService;
+use crate::domain::daemon::app::service::{
+    AdjustService, ResetService, SkipToService, StopService,
+};
+use crate::domain::daemon::app::service::{AutoPauseService, AutoResumeService, ReloadService};
+use crate::domain::daemon::app::service::{MuteService, StatsService, UnmuteService};
+use crate::domain::daemon::app::service::{PauseService, PingService, QueryService};
+use crate::domain::daemon::app::service::{ResumeService, ScheduleService, SkipService};
+use crate::domain::daemon::app::{ReloadApplicationCoreError, SetupApplicationCoreError};
+use crate::domain::daemon::inbound::{AdjustPort, AutoPausePort, AutoResumePort, ReloadPort};
+use crate::domain::daemon::inbound::{MutePort, SetDurationPort, SubscribePort, UnmutePort};
+use crate::domain::daemon::inbound::{
+    PausePort, PingPort, QueryPort, ResetPort, ResumePort, SchedulePort, SkipPort, SkipToPort,
+    StatsPort, StopPort,
+};
+use crate::domain::daemon::outbound::{IdleDetector, NotifyPort, StageLog, StateStore};
+use crate::domain::daemon::worker::{self, WorkerConfig};
+use crate::domain::repository::{
+    BehaviorRepository, DurationRepository, NotificationRepository, StageRepository,
+};
+
+use super::core::{ConfigSnafu, WorkerSnafu};
+
+/// Name of the session used when a request does not specify one.
+pub const DEFAULT_SESSION: &str = "default";
+
+/// Ports of one named timer, which are backed by a dedicated worker.
+pub struct Session {
+    pub pause: Arc<dyn PausePort>,
+    pub resume: Arc<dyn ResumePort>,
+    pub query: Arc<dyn QueryPort>,
+    pub ping: Arc<dyn PingPort>,
+    pub schedule: Arc<dyn SchedulePort>,
+    pub stats: Arc<dyn StatsPort>,
+    pub subscribe: Arc<dyn SubscribePort>,
+    pub skip: Arc<dyn SkipPort>,
+    pub reset: Arc<dyn ResetPort>,
+    pub mute: Arc<dyn MutePort>,
+    pub unmute: Arc<dyn UnmutePort>,
+    pub adjust: Arc<dyn AdjustPort>,
+    pub set_duration: Arc<dyn SetDurationPort>,
+    pub skip_to: Arc<dyn SkipToPort>,
+    pub stop: Arc<dyn StopPort>,
+    pub auto_pause: Arc<dyn AutoPausePort>,
+    pub auto_resume: Arc<dyn AutoResumePort>,
+    pub reload: Arc<dyn ReloadPort>,
+}
+
+/// A factory creating a [`Session`] on demand.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait SessionFactory: Send + Sync + 'static {
+    /// Create a new [`Session`] with the given name.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the session could not be
+    /// initialized.
+    async fn create(&self, name: &str) -> Result<Session, SetupApplicationCoreError>;
+
+    /// Load a new configuration from the given repositories, which are also
+    /// used by the sessions created afterwards.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the configuration is invalid,
+    /// in which case the old repositories stay in use.
+    async fn reload(
+        &self,
+        duration_repository: Arc<dyn DurationRepository>,
+        notification_repository: Arc<dyn NotificationRepository>,
+        stage_repository: Arc<dyn StageRepository>,
+        behavior_repository: Arc<dyn BehaviorRepository>,
+    ) -> Result<WorkerConfig, ReloadApplicationCoreError>;
+}
+
+/// A [`SessionFactory`] implementation which spawns a background worker for
+/// each session, all sharing the same repositories, [`StateStore`] and
+/// [`StageLog`].
+pub struct WorkerSessionFactory {
+    notify_port: Arc<dyn NotifyPort>,
+    idle_detector: Arc<dyn IdleDetector>,
+    state_store: Arc<dyn StateStore>,
+    stage_log: Arc<dyn StageLog>,
+    repositories: RwLock<Repositories>,
+}
+
+/// Repositories which the configuration of workers is loaded from.
+#[derive(Clone)]
+struct Repositories {
+    duration: Arc<dyn DurationRepository>,
+    notification: Arc<dyn NotificationRepository>,
+    stage: Arc<dyn StageRepository>,
+    behavior: Arc<dyn BehaviorRepository>,
+}
+
+impl WorkerSessionFactory {
+    /// Creates a new [`WorkerSessionFactory`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        notify_port: Arc<dyn NotifyPort>,
+        idle_detector: Arc<dyn IdleDetector>,
+        state_store: Arc<dyn StateStore>,
+        stage_log: Arc<dyn StageLog>,
+        duration_repository: Arc<dyn DurationRepository>,
+        notification_repository: Arc<dyn NotificationRepository>,
+        stage_repository: Arc<dyn StageRepository>,
+        behavior_repository: Arc<dyn BehaviorRepository>,
+    ) -> Self {
+        Self {
+            notify_port,
+            idle_detector,
+            state_store,
+            stage_log,
+            repositories: RwLock::new(Repositories {
+                duration: duration_repository,
+                notification: notification_repository,
+                stage: stage_repository,
+                behavior: behavior_repository,
+            }),
+        }
+    }
+
+    fn repositories(&self) -> Repositories {
+        match self.repositories.read() {
+            Ok(repositories) => repositories.clone(),
+            Err(_) => unreachable!("Repositories should not be poisoned"),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl SessionFactory for WorkerSessionFactory {
+    async fn create(&self, name: &str) -> Result<Session, SetupApplicationCoreError> {
+        let repositories = self.repositories();
+        let worker = worker::spawn(
+            name,
+            Arc::clone(&self.state_store),
+            Arc::clone(&self.stage_log),
+            repositories.duration,
+            repositories.notification,
+            repositories.stage,
+            repositories.behavior,
+            Arc::clone(&self.notify_port),
+            Arc::clone(&self.idle_detector),
+        )
+        .await
+        .context(WorkerSnafu { session: name })?;
+        let worker = Arc::new(worker);
+
+        Ok(Session {
+            pause: Arc::new(PauseService::new(Arc::clone(&worker))),
+            resume: Arc::new(ResumeService::new(Arc::clone(&worker))),
+            query: Arc::new(QueryService::new(Arc::clone(&worker))),
+            ping: Arc::new(PingService::new(Arc::clone(&worker))),
+            schedule: Arc::new(ScheduleService::new(Arc::clone(&worker))),
+            stats: Arc::new(StatsService::new(Arc::clone(&worker))),
+            subscribe: Arc::new(SubscribeService::new(Arc::clone(&worker))),
+            skip: Arc::new(SkipService::new(Arc::clone(&worker))),
+            reset: Arc::new(ResetService::new(Arc::clone(&worker))),
+            mute: Arc::new(MuteService::new(Arc::clone(&worker))),
+            unmute: Arc::new(UnmuteService::new(Arc::clone(&worker))),
+            adjust: Arc::new(AdjustService::new(Arc::clone(&worker))),
+            set_duration: Arc::new(SetDurationService::new(Arc::clone(&worker))),
+            skip_to: Arc::new(SkipToService::new(Arc::clone(&worker))),
+            stop: Arc::new(StopService::new(Arc::clone(&worker))),
+            auto_pause: Arc::new(AutoPauseService::new(Arc::clone(&worker))),
+            auto_resume: Arc::new(AutoResumeService::new(Arc::clone(&worker))),
+            reload: Arc::new(ReloadService::new(Arc::clone(&worker))),
+        })
+    }
+
+    async fn reload(
+        &self,
+        duration_repository: Arc<dyn DurationRepository>,
+        notification_repository: Arc<dyn NotificationRepository>,
+        stage_repository: Arc<dyn StageRepository>,
+        behavior_repository: Arc<dyn BehaviorRepository>,
+    ) -> Result<WorkerConfig, ReloadApplicationCoreError> {
+        let repositories = Repositories {
+            duration: duration_repository,
+            notification: notification_repository,
+            stage: stage_repository,
+            behavior: behavior_repository,
+        };
+        let config = worker::load_config(
+            Arc::clone(&repositories.duration),
+            Arc::clone(&repositories.notification),
+            Arc::clone(&repositories.stage),
+            Arc::clone(&repositories.behavior),
+        )
+        .await
+        .context(ConfigSnafu)?;
+
+        match self.repositories.write() {
+            Ok(mut current) => *current = repositories,
+            Err(_) => unreachable!("Repositories should not be poisoned"),
+        }
+        Ok(config)
+    }
+}
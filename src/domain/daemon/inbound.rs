@@ -1,5 +1,11 @@
+use tokio::sync::broadcast::Sender;
 use tokio::time::Duration;
 
+use crate::domain::daemon::{ChangeStageError, WorkerConfig};
+use crate::domain::entity::TimerStats;
+use crate::domain::entity::{AutoPauseTrigger, ScheduledStage, StageDuration, StageState};
+use crate::domain::entity::{TimerEvent, TimerStatus};
+
 /// A public port for suspending the tomato timer.
 #[async_trait::async_trait]
 #[cfg_attr(test, mockall::automock)]
@@ -27,17 +33,162 @@ pub trait QueryPort: Send + Sync + 'static {
 /// The state of this daemon.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct QueryResponse {
-    pub current: String,
+    pub current: TimerStatus,
+    /// Name of the current stage, which may be customized in the
+    /// configuration.
     pub stage: String,
+    /// Kind of the current stage.
+    pub kind: StageState,
     pub total: Duration,
     pub remaining: Duration,
     pub past: Duration,
+    /// Whether the current break, or the upcoming one, is a long break.
+    pub long_break: bool,
+    /// Number of rounds of the stage sequence completed since the timer
+    /// started.
+    pub completed_cycles: u32,
+    /// Whether notifications are muted.
+    pub muted: bool,
+}
+
+/// A public port for checking whether the timer is running.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait PingPort: Send + Sync + 'static {
+    /// Do the ping operation, which is cheaper than a full query. It also
+    /// works once the timer has stopped.
+    async fn ping(&self) -> TimerStatus;
+}
+
+/// A public port for listing the upcoming stages.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait SchedulePort: Send + Sync + 'static {
+    /// Do the schedule operation, which returns the current stage with its
+    /// remaining time, followed by the stages of one round of the sequence.
+    async fn schedule(&self) -> Vec<ScheduledStage>;
+}
+
+/// A public port for counting the completed stages.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait StatsPort: Send + Sync + 'static {
+    /// Do the stats operation, which returns the counts of the completed
+    /// concentration stages.
+    async fn stats(&self) -> TimerStats;
+}
+
+/// A public port for following the changes of the timer's state.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait SubscribePort: Send + Sync + 'static {
+    /// Do the subscribe operation. The current state is sent through `sender`
+    /// right away, followed by an event whenever the stage changes or the
+    /// timer is paused, resumed or stopped. It lasts until all receivers of
+    /// `sender` are dropped or the timer stops.
+    async fn subscribe(&self, sender: Sender<TimerEvent>);
 }
 
 /// A public port for skip the current stage.
 #[async_trait::async_trait]
 #[cfg_attr(test, mockall::automock)]
 pub trait SkipPort: Send + Sync + 'static {
-    /// Do the skipping operation.
-    async fn skip(&self);
+    /// Do the skipping operation. Emit the skipped stage's notification if
+    /// `notify` is set.
+    async fn skip(&self, notify: bool);
+}
+
+/// A public port for restarting the tomato timer from the initial stage.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait ResetPort: Send + Sync + 'static {
+    /// Do the reset operation.
+    async fn reset(&self);
+}
+
+/// A public port for muting the notifications of the tomato timer.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait MutePort: Send + Sync + 'static {
+    /// Do the mute operation. The timer goes on, but shows no notification
+    /// until it is unmuted.
+    async fn mute(&self);
+}
+
+/// A public port for showing the notifications of the tomato timer again.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait UnmutePort: Send + Sync + 'static {
+    /// Do the unmute operation.
+    async fn unmute(&self);
+}
+
+/// A public port for changing the length of the current stage.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait AdjustPort: Send + Sync + 'static {
+    /// Do the adjusting operation. Positive `delta_secs` extends the current
+    /// stage and negative one shortens it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the stage would become too long.
+    async fn adjust(&self, delta_secs: i64) -> Result<(), ChangeStageError>;
+}
+
+/// A public port for setting the length of the current stage.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait SetDurationPort: Send + Sync + 'static {
+    /// Do the setting operation, so that the current stage lasts `total` in
+    /// all. It only applies to the current stage.
+    async fn set_duration(&self, total: StageDuration);
+}
+
+/// A public port for jumping to a stage of a specific kind.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait SkipToPort: Send + Sync + 'static {
+    /// Do the skipping operation, which starts the next stage of the kind
+    /// `stage` afresh.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if no such stage comes again.
+    async fn skip_to(&self, stage: StageState) -> Result<(), ChangeStageError>;
+}
+
+/// A public port for stopping the tomato timer.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait StopPort: Send + Sync + 'static {
+    /// Do the stop operation. The timer handles no more requests afterwards.
+    /// Its saved progress is cleared unless `keep_progress` is set.
+    async fn stop(&self, keep_progress: bool);
+}
+
+/// A public port for suspending the tomato timer on behalf of an external
+/// event.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait AutoPausePort: Send + Sync + 'static {
+    /// Do the automatic pause operation.
+    async fn auto_pause(&self, trigger: AutoPauseTrigger);
+}
+
+/// A public port for resuming the tomato timer on behalf of an external event.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait AutoResumePort: Send + Sync + 'static {
+    /// Do the automatic resume operation. This only takes effect if the timer
+    /// was paused by the same trigger.
+    async fn auto_resume(&self, trigger: AutoPauseTrigger);
+}
+
+/// A public port for replacing the tomato timer's configuration.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait ReloadPort: Send + Sync + 'static {
+    /// Do the reload operation. The current stage keeps its length.
+    async fn reload(&self, config: WorkerConfig);
 }
@@ -4,4 +4,12 @@ pub mod outbound;
 mod app;
 mod worker;
 
-pub use app::{ApplicationCore, SetupApplicationCoreError};
+pub use app::{ApplicationCore, ReloadApplicationCoreError, SetupApplicationCoreError};
+pub use app::{Session, SessionFactory, DEFAULT_SESSION};
+pub use worker::{ChangeStageError, LoadWorkerConfigError, SpawnWorkerError};
+pub use worker::{WorkerConfig, WorkerConfigBuilder, WorkerHandle};
+
+pub(crate) use worker::spawn as spawn_worker;
+
+#[cfg(test)]
+pub use app::MockSessionFactory;
@@ -1,22 +1,29 @@
 use std::error::Error as StdError;
+use std::time::{Duration, SystemTime};
 
+use serde::{Deserialize, Serialize};
 use snafu::prelude::*;
 
-use crate::domain::entity::NotificationMessage;
+use crate::domain::entity::StageState;
+use crate::domain::entity::{NotificationMessage, NotificationTimeout, NotificationUrgency};
 
 /// A public port for emitting a notification.
 #[async_trait::async_trait]
 pub trait NotifyPort: Send + Sync + 'static {
-    /// Do the notification operation. This method is not intended to be
-    /// implemented by adapters directly.
+    /// Do the notification operation for the stage named `stage`. This method
+    /// is not intended to be implemented by adapters directly.
     ///
     /// # Errors
     ///
     /// This function will return an error if failed to make a notification.
-    async fn notify(&self, request: &NotificationMessage) -> Result<(), NotifyError> {
+    async fn notify(&self, stage: &str, request: &NotificationMessage) -> Result<(), NotifyError> {
         let request = NotifyRequest {
+            stage: stage.to_owned(),
             summary: request.summary().to_owned(),
             body: request.body().map(|body| body.to_owned()),
+            urgency: request.urgency(),
+            timeout: request.timeout(),
+            sound: request.sound().map(|sound| sound.to_owned()),
         };
         self.notify_impl(request).await
     }
@@ -29,11 +36,18 @@ pub trait NotifyPort: Send + Sync + 'static {
     async fn notify_impl(&self, request: NotifyRequest) -> Result<(), NotifyError>;
 }
 
-/// A structure that stores required data.
+/// A structure that stores required data. `stage` is the name of the stage
+/// which has just ended.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct NotifyRequest {
+    pub stage: String,
     pub summary: String,
     pub body: Option<String>,
+    pub urgency: NotificationUrgency,
+    pub timeout: NotificationTimeout,
+    /// Name of the sound to play, following the XDG sound naming
+    /// specification.
+    pub sound: Option<String>,
 }
 
 /// An error type of the notification operation.
@@ -47,3 +61,123 @@ pub enum NotifyError {
         source: Option<Box<dyn StdError>>,
     },
 }
+
+/// A public port for detecting how long the user has been away from the
+/// keyboard and mouse.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait IdleDetector: Send + Sync + 'static {
+    /// Get the time since the last input of the user.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the idle time is unavailable,
+    /// e.g. the desktop environment does not support it.
+    async fn idle_time(&self) -> Result<Duration, DetectIdleError>;
+}
+
+/// An error type of the idle detection.
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum DetectIdleError {
+    #[snafu(whatever, display("Idle detection failed: {message}"))]
+    Unknown {
+        message: String,
+        #[snafu(source(from(Box<dyn StdError + Send + Sync>, Some)))]
+        source: Option<Box<dyn StdError + Send + Sync>>,
+    },
+}
+
+/// A public port for keeping the progress of the timers across daemon
+/// restarts. Each named session has its own state.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait StateStore: Send + Sync + 'static {
+    /// Load the last saved state of the session. Return `None` if nothing was
+    /// saved.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the saved state could not be
+    /// read, e.g. it is corrupt.
+    async fn load(&self, session: &str) -> Result<Option<WorkerSnapshot>, StateStoreError>;
+
+    /// Save the current state of the session, replacing the previous one.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the state could not be written.
+    async fn save(&self, session: &str, snapshot: &WorkerSnapshot) -> Result<(), StateStoreError>;
+
+    /// Remove the saved state of the session, so that it starts afresh next
+    /// time.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the state could not be removed.
+    async fn clear(&self, session: &str) -> Result<(), StateStoreError>;
+}
+
+/// Progress of a timer at the moment it is saved. `past` does not include
+/// the time after `saved_at`, even if the timer is running.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkerSnapshot {
+    /// Index of the current stage in the stage sequence.
+    pub stage: usize,
+    pub concentrations: u32,
+    /// Number of completed rounds of the stage sequence. Missing in states
+    /// saved by older versions.
+    #[serde(default)]
+    pub cycles: u32,
+    pub past: Duration,
+    pub total: Duration,
+    pub paused: bool,
+    pub saved_at: SystemTime,
+}
+
+/// An error type of the state storing operations.
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum StateStoreError {
+    #[snafu(whatever, display("State storing failed: {message}"))]
+    Unknown {
+        message: String,
+        #[snafu(source(from(Box<dyn StdError + Send + Sync>, Some)))]
+        source: Option<Box<dyn StdError + Send + Sync>>,
+    },
+}
+
+/// A public port for keeping a record of every completed stage, e.g. for a
+/// productivity dashboard.
+#[async_trait::async_trait]
+#[cfg_attr(test, mockall::automock)]
+pub trait StageLog: Send + Sync + 'static {
+    /// Append the record of a stage which has just ended.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the record could not be written.
+    async fn append(&self, record: &StageRecord) -> Result<(), StageLogError>;
+}
+
+/// A stage which ended at `finished_at` after lasting for `duration`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StageRecord {
+    pub session: String,
+    pub stage: String,
+    pub kind: StageState,
+    pub duration: Duration,
+    pub finished_at: SystemTime,
+}
+
+/// An error type of the stage logging operation.
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum StageLogError {
+    #[snafu(whatever, display("Stage logging failed: {message}"))]
+    Unknown {
+        message: String,
+        #[snafu(source(from(Box<dyn StdError + Send + Sync>, Some)))]
+        source: Option<Box<dyn StdError + Send + Sync>>,
+    },
+}
@@ -1,16 +1,34 @@
+use tokio::sync::broadcast::Sender as BroadcastSender;
 use tokio::sync::mpsc::Sender;
 use tokio::sync::oneshot::{self, Sender as OneshotSender};
 use tokio::time::Duration;
 
-use crate::domain::entity::StageState;
+use crate::domain::daemon::worker::routine::WorkerConfig;
+use crate::domain::daemon::worker::ChangeStageError;
+use crate::domain::entity::TimerStats;
+use crate::domain::entity::{AutoPauseTrigger, ScheduledStage, StageDuration, StageState};
+use crate::domain::entity::{TimerEvent, TimerStatus};
 
 /// Result of one query of the current state.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct QueryResponse {
-    pub current: String,
+    pub current: TimerStatus,
     pub total: Duration,
     pub past: Duration,
-    pub stage: StageState,
+    /// How long remains of the current stage, which is zero rather than
+    /// negative if `past` has gone beyond `total`.
+    pub remaining: Duration,
+    /// Name of the current stage.
+    pub stage: String,
+    /// Kind of the current stage.
+    pub kind: StageState,
+    /// Whether the current break, or the upcoming one, is a long break.
+    pub long_break: bool,
+    /// Number of rounds of the stage sequence completed since the timer
+    /// started.
+    pub completed_cycles: u32,
+    /// Whether notifications are muted.
+    pub muted: bool,
 }
 
 /// Actions that a [`WorkerRoutine`] runs.
@@ -18,10 +36,51 @@ pub struct QueryResponse {
 pub enum Command {
     Pause,
     Resume,
-    Skip,
+    Skip {
+        notify: bool,
+    },
+    Reset,
+    Adjust {
+        delta_secs: i64,
+        responder: OneshotSender<Result<(), ChangeStageError>>,
+    },
+    SetDuration {
+        total: StageDuration,
+    },
+    SkipTo {
+        stage: StageState,
+        responder: OneshotSender<Result<(), ChangeStageError>>,
+    },
     Query {
         responder: OneshotSender<QueryResponse>,
     },
+    Ping {
+        responder: OneshotSender<TimerStatus>,
+    },
+    Schedule {
+        responder: OneshotSender<Vec<ScheduledStage>>,
+    },
+    Stats {
+        responder: OneshotSender<TimerStats>,
+    },
+    AutoPause {
+        trigger: AutoPauseTrigger,
+    },
+    AutoResume {
+        trigger: AutoPauseTrigger,
+    },
+    Reload {
+        config: WorkerConfig,
+    },
+    Mute,
+    Unmute,
+    Subscribe {
+        sender: BroadcastSender<TimerEvent>,
+    },
+    Stop {
+        keep_progress: bool,
+        responder: OneshotSender<()>,
+    },
 }
 
 /// Handle that controls a [`WorkerRoutine`].
@@ -52,15 +111,102 @@ impl WorkerHandle {
         };
     }
 
+    /// Send [`Command::Mute`] to the background worker and stop showing
+    /// notifications, while the timer goes on as usual.
+    pub async fn mute(&self) {
+        match self.requester.send(Command::Mute).await {
+            Ok(_) => {}
+            Err(_) => unreachable!("Worker should not be shutted down"),
+        };
+    }
+
+    /// Send [`Command::Unmute`] to the background worker and show
+    /// notifications again.
+    pub async fn unmute(&self) {
+        match self.requester.send(Command::Unmute).await {
+            Ok(_) => {}
+            Err(_) => unreachable!("Worker should not be shutted down"),
+        };
+    }
+
+    /// Send [`Command::Subscribe`] to the background worker, which sends the
+    /// current state through `sender` right away and then every change of it,
+    /// until all receivers of `sender` are dropped.
+    pub async fn subscribe(&self, sender: BroadcastSender<TimerEvent>) {
+        match self.requester.send(Command::Subscribe { sender }).await {
+            Ok(_) => {}
+            Err(_) => unreachable!("Worker should not be shutted down"),
+        };
+    }
+
     /// Send [`Command::Skip`] to the background worker and skip to the next
-    /// stage.
-    pub async fn skip(&self) {
-        match self.requester.send(Command::Skip).await {
+    /// stage. The skipped stage's notification is emitted if `notify` is set.
+    pub async fn skip(&self, notify: bool) {
+        match self.requester.send(Command::Skip { notify }).await {
+            Ok(_) => {}
+            Err(_) => unreachable!("Worker should not be shutted down"),
+        };
+    }
+
+    /// Send [`Command::Reset`] to the background worker and restart the timer
+    /// from the initial stage.
+    pub async fn reset(&self) {
+        match self.requester.send(Command::Reset).await {
             Ok(_) => {}
             Err(_) => unreachable!("Worker should not be shutted down"),
         };
     }
 
+    /// Send [`Command::Adjust`] to the background worker and lengthen the
+    /// current stage by `delta_secs`, or shorten it if it is negative.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the stage would become longer
+    /// than [`StageDuration::MAX`], in which case it is left unchanged.
+    pub async fn adjust(&self, delta_secs: i64) -> Result<(), ChangeStageError> {
+        let (responder, receiver) = oneshot::channel();
+        let command = Command::Adjust {
+            delta_secs,
+            responder,
+        };
+        match self.requester.send(command).await {
+            // A stopped worker ignores the command without replying.
+            Ok(_) => receiver.await.unwrap_or(Ok(())),
+            Err(_) => unreachable!("Worker should not be shutted down"),
+        }
+    }
+
+    /// Send [`Command::SetDuration`] to the background worker and make the
+    /// current stage last `total` in all. The next stage follows the
+    /// configuration again.
+    pub async fn set_duration(&self, total: StageDuration) {
+        match self.requester.send(Command::SetDuration { total }).await {
+            Ok(_) => {}
+            Err(_) => unreachable!("Worker should not be shutted down"),
+        };
+    }
+
+    /// Send [`Command::SkipTo`] to the background worker and jump to the
+    /// next stage of the kind `stage`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the sequence never comes to a
+    /// stage of the kind, in which case the timer is left unchanged.
+    pub async fn skip_to(&self, stage: StageState) -> Result<(), ChangeStageError> {
+        let (responder, receiver) = oneshot::channel();
+        match self
+            .requester
+            .send(Command::SkipTo { stage, responder })
+            .await
+        {
+            // A stopped worker ignores the command without replying.
+            Ok(_) => receiver.await.unwrap_or(Ok(())),
+            Err(_) => unreachable!("Worker should not be shutted down"),
+        }
+    }
+
     /// Send [`Command::Query`] to the background worker to get the current
     /// state.
     pub async fn query(&self) -> QueryResponse {
@@ -73,4 +219,88 @@ impl WorkerHandle {
             Err(_) => unreachable!("Worker should not be shutted down"),
         }
     }
+
+    /// Send [`Command::Ping`] to the background worker to get only whether
+    /// the timer is running. Unlike other requests, this is still valid after
+    /// the worker has stopped.
+    pub async fn ping(&self) -> TimerStatus {
+        let (responder, receiver) = oneshot::channel();
+        match self.requester.send(Command::Ping { responder }).await {
+            Ok(_) => receiver.await.unwrap_or(TimerStatus::Stopped),
+            Err(_) => TimerStatus::Stopped,
+        }
+    }
+
+    /// Send [`Command::Schedule`] to the background worker to get the current
+    /// stage and those following it for one round of the sequence.
+    pub async fn schedule(&self) -> Vec<ScheduledStage> {
+        let (responder, receiver) = oneshot::channel();
+        match self.requester.send(Command::Schedule { responder }).await {
+            Ok(_) => match receiver.await {
+                Ok(res) => res,
+                Err(_) => unreachable!("Worker should not be shutted down"),
+            },
+            Err(_) => unreachable!("Worker should not be shutted down"),
+        }
+    }
+
+    /// Send [`Command::Stats`] to the background worker to get the statistics
+    /// of the completed stages.
+    pub async fn stats(&self) -> TimerStats {
+        let (responder, receiver) = oneshot::channel();
+        match self.requester.send(Command::Stats { responder }).await {
+            Ok(_) => match receiver.await {
+                Ok(res) => res,
+                Err(_) => unreachable!("Worker should not be shutted down"),
+            },
+            Err(_) => unreachable!("Worker should not be shutted down"),
+        }
+    }
+
+    /// Send [`Command::AutoPause`] to the background worker and pause the
+    /// timer on behalf of the trigger.
+    pub async fn auto_pause(&self, trigger: AutoPauseTrigger) {
+        match self.requester.send(Command::AutoPause { trigger }).await {
+            Ok(_) => {}
+            Err(_) => unreachable!("Worker should not be shutted down"),
+        };
+    }
+
+    /// Send [`Command::AutoResume`] to the background worker and resume the
+    /// timer if it was paused by the same trigger.
+    pub async fn auto_resume(&self, trigger: AutoPauseTrigger) {
+        match self.requester.send(Command::AutoResume { trigger }).await {
+            Ok(_) => {}
+            Err(_) => unreachable!("Worker should not be shutted down"),
+        };
+    }
+
+    /// Send [`Command::Reload`] to the background worker and replace its
+    /// configuration. The current stage keeps its length, while the stages
+    /// afterwards follow `config`.
+    pub async fn reload(&self, config: WorkerConfig) {
+        match self.requester.send(Command::Reload { config }).await {
+            Ok(_) => {}
+            Err(_) => unreachable!("Worker should not be shutted down"),
+        };
+    }
+
+    /// Send [`Command::Stop`] to the background worker and wait until it
+    /// stops. Afterwards, queries report the stopped timer while commands
+    /// which change it are ignored. The saved progress is cleared unless
+    /// `keep_progress` is set.
+    pub async fn stop(&self, keep_progress: bool) {
+        let (responder, receiver) = oneshot::channel();
+        let command = Command::Stop {
+            keep_progress,
+            responder,
+        };
+        match self.requester.send(command).await {
+            Ok(_) => match receiver.await {
+                Ok(_) => {}
+                Err(_) => unreachable!("Worker should not be shutted down"),
+            },
+            Err(_) => unreachable!("Worker should not be shutted down"),
+        }
+    }
 }
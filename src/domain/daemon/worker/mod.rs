@@ -1,97 +1,541 @@
 mod handle;
 mod routine;
 mod state;
+mod stats;
 
 pub use handle::{QueryResponse, WorkerHandle};
+pub use routine::{WorkerConfig, WorkerConfigBuilder};
 
 use std::sync::Arc;
 
 use snafu::prelude::*;
 
-use crate::domain::daemon::outbound::NotifyPort;
-use crate::domain::entity::StageState;
+use crate::domain::daemon::outbound::{IdleDetector, NotifyPort, StageLog, StateStore};
+use crate::domain::entity::duration::TryNewStageDurationError;
+use crate::domain::entity::sequence::{TryNewStageError, TryNewStageSequenceError};
+use crate::domain::entity::{AutoPauseTrigger, Stage, StageSequence, StageState};
+use crate::domain::repository::behavior::{BehaviorRepository, GetBehaviorError};
 use crate::domain::repository::duration::{DurationRepository, GetDurationError};
 use crate::domain::repository::notification::{GetNotificationError, NotificationRepository};
+use crate::domain::repository::stage::{GetStageError, StageRepository};
 
-use routine::{WorkerConfig, WorkerRoutine};
+use routine::WorkerRoutine;
 
+/// Number of commands which can wait for a worker. Senders only block once
+/// this many commands are pending, so that bursts of queries, e.g. from
+/// `watch`, do not serialize behind the worker's ticks. A larger capacity
+/// costs memory only for commands which are actually waiting, but lets more
+/// commands queue up behind a slow one instead of pushing back on clients.
+const COMMAND_CAPACITY: usize = 32;
+
+/// Spawn a background worker for the session named `session`, which continues
+/// with the progress saved in `store` if possible and appends every completed
+/// stage to `stage_log`.
+#[allow(clippy::too_many_arguments)]
 pub async fn spawn(
+    session: &str,
+    store: Arc<dyn StateStore>,
+    stage_log: Arc<dyn StageLog>,
     duration_repository: Arc<dyn DurationRepository>,
     notification_repository: Arc<dyn NotificationRepository>,
+    stage_repository: Arc<dyn StageRepository>,
+    behavior_repository: Arc<dyn BehaviorRepository>,
     notifier: Arc<dyn NotifyPort>,
+    idle_detector: Arc<dyn IdleDetector>,
 ) -> Result<WorkerHandle, SpawnWorkerError> {
-    let (requester, commands) = tokio::sync::mpsc::channel(1);
-    let config = load_config(duration_repository, notification_repository).await?;
-    WorkerRoutine::spawn(config, commands, notifier);
+    let (requester, commands) = tokio::sync::mpsc::channel(COMMAND_CAPACITY);
+    let (ready, ready_receiver) = tokio::sync::oneshot::channel();
+    let config = load_config(
+        duration_repository,
+        notification_repository,
+        stage_repository,
+        behavior_repository,
+    )
+    .await
+    .context(ConfigSnafu)?;
+    WorkerRoutine::spawn(
+        config,
+        commands,
+        notifier,
+        idle_detector,
+        session.to_owned(),
+        store,
+        stage_log,
+        ready,
+    );
+
+    // Wait for the timer to start, so that no request is handled by a worker
+    // which is still initializing.
+    ready_receiver.await.ok().context(StartupSnafu)?;
     Ok(WorkerHandle::new(requester))
 }
 
-async fn load_config(
+/// Load a [`WorkerConfig`] from the repositories.
+///
+/// # Errors
+///
+/// This function will return an error if any repository fails or the stages
+/// are invalid.
+pub async fn load_config(
     duration_repository: Arc<dyn DurationRepository>,
     notification_repository: Arc<dyn NotificationRepository>,
-) -> Result<WorkerConfig, SpawnWorkerError> {
-    let preparation_duration =
-        duration_repository
-            .preparation_duration()
-            .await
-            .context(DurationConfigSnafu {
-                key: StageState::Preparation,
-            })?;
-    let concentration_duration =
-        duration_repository
-            .concentration_duration()
-            .await
-            .context(DurationConfigSnafu {
-                key: StageState::Concentration,
-            })?;
-    let relaxation_duration =
-        duration_repository
-            .relaxation_duration()
-            .await
-            .context(DurationConfigSnafu {
-                key: StageState::Relaxation,
-            })?;
-    let preparation_notification = notification_repository
-        .preparation_notification()
+    stage_repository: Arc<dyn StageRepository>,
+    behavior_repository: Arc<dyn BehaviorRepository>,
+) -> Result<WorkerConfig, LoadWorkerConfigError> {
+    let mut stages = stage_repository.stages().await.context(StageConfigSnafu)?;
+    if stages.is_empty() {
+        stages = load_default_stages(&*duration_repository, &*notification_repository).await?;
+    }
+    let stages = StageSequence::try_new(stages).context(StageSequenceSnafu)?;
+
+    let initial_stage = duration_repository
+        .initial_stage()
         .await
-        .context(NotificationConfigSnafu {
-            key: StageState::Preparation,
+        .context(InitialStageConfigSnafu)?;
+    let initial_stage = match initial_stage {
+        Some(kind) => (0..stages.len())
+            .find(|&index| stages[index].kind() == kind)
+            .context(InitialStageSnafu { kind })?,
+        None => stages.initial(),
+    };
+    let mode = duration_repository.mode().await.context(ModeConfigSnafu)?;
+
+    let long_break_duration = duration_repository
+        .long_break_duration()
+        .await
+        .context(LongBreakConfigSnafu)?;
+    let cycles_before_long_break = duration_repository
+        .cycles_before_long_break()
+        .await
+        .context(CyclesConfigSnafu)?;
+    let screen_lock_auto_pause = behavior_repository
+        .auto_pause_enabled(AutoPauseTrigger::ScreenLock)
+        .await
+        .context(BehaviorOptionSnafu {
+            key: "auto_pause.screen_lock",
         })?;
-    let concentration_notification = notification_repository
-        .concentration_notification()
+    let low_battery_auto_pause = behavior_repository
+        .auto_pause_enabled(AutoPauseTrigger::LowBattery)
         .await
-        .context(NotificationConfigSnafu {
-            key: StageState::Concentration,
+        .context(BehaviorOptionSnafu {
+            key: "auto_pause.low_battery",
         })?;
-    let relaxation_notification = notification_repository
-        .relaxation_notification()
+    let idle_threshold =
+        behavior_repository
+            .idle_threshold()
+            .await
+            .context(BehaviorOptionSnafu {
+                key: "auto_pause.idle",
+            })?;
+    let start_paused = behavior_repository
+        .start_paused()
+        .await
+        .context(BehaviorOptionSnafu {
+            key: "runtime.start_paused",
+        })?;
+    let max_pause = behavior_repository
+        .max_pause()
         .await
-        .context(NotificationConfigSnafu {
-            key: StageState::Relaxation,
+        .context(BehaviorOptionSnafu {
+            key: "auto_pause.max_pause",
         })?;
 
-    Ok(WorkerConfig {
-        preparation_duration,
-        concentration_duration,
-        relaxation_duration,
-        preparation_notification,
-        concentration_notification,
-        relaxation_notification,
-    })
+    let config = WorkerConfig::builder(stages)
+        .initial_stage(initial_stage)
+        .mode(mode)
+        .long_break_duration(long_break_duration)
+        .cycles_before_long_break(cycles_before_long_break)
+        .screen_lock_auto_pause(screen_lock_auto_pause)
+        .low_battery_auto_pause(low_battery_auto_pause)
+        .idle_threshold(idle_threshold)
+        .start_paused(start_paused)
+        .max_pause(max_pause)
+        .build();
+    Ok(config)
+}
+
+/// Build the default stages: a preparation, followed by concentrations and
+/// relaxations in turn.
+async fn load_default_stages(
+    duration_repository: &dyn DurationRepository,
+    notification_repository: &dyn NotificationRepository,
+) -> Result<Vec<Stage>, LoadWorkerConfigError> {
+    let mut stages = Vec::new();
+
+    for kind in [
+        StageState::Preparation,
+        StageState::Concentration,
+        StageState::Relaxation,
+    ] {
+        let duration = match kind {
+            StageState::Preparation => duration_repository.preparation_duration().await,
+            StageState::Concentration => duration_repository.concentration_duration().await,
+            StageState::Relaxation => duration_repository.relaxation_duration().await,
+        }
+        .context(DurationConfigSnafu { key: kind })?;
+        let notification = match kind {
+            StageState::Preparation => notification_repository.preparation_notification().await,
+            StageState::Concentration => notification_repository.concentration_notification().await,
+            StageState::Relaxation => notification_repository.relaxation_notification().await,
+        }
+        .context(NotificationConfigSnafu { key: kind })?;
+        let stage = Stage::try_new(kind.to_string(), kind, duration, notification)
+            .context(DefaultStageSnafu)?;
+        stages.push(stage);
+    }
+
+    Ok(stages)
 }
 
 /// An error for spawning the background worker.
 #[derive(Debug, Snafu)]
 #[non_exhaustive]
 pub enum SpawnWorkerError {
-    #[snafu(display("Could not load duration configration for {key:?} from repository"))]
+    #[snafu(display("Could not load the worker configuration"))]
+    Config { source: LoadWorkerConfigError },
+    #[snafu(display("Background worker exited before it is ready"))]
+    Startup,
+}
+
+/// An error for changing the current stage of a running worker.
+#[derive(Debug, Clone, Snafu, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ChangeStageError {
+    #[snafu(display("Could not change the length of the current stage"))]
+    Length { source: TryNewStageDurationError },
+    #[snafu(display("Could not find a {kind} stage to skip to"))]
+    NoStage { kind: StageState },
+}
+
+/// An error for loading a [`WorkerConfig`].
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum LoadWorkerConfigError {
+    #[snafu(display("Could not load duration configration for {key} from repository"))]
     DurationConfig {
         key: StageState,
         source: GetDurationError,
     },
-    #[snafu(display("Could not load notification configration for {key:?} from repository"))]
+    #[snafu(display("Could not load notification configration for {key} from repository"))]
     NotificationConfig {
         key: StageState,
         source: GetNotificationError,
     },
+    #[snafu(display("Could not load custom stages from repository"))]
+    StageConfig { source: GetStageError },
+    #[snafu(display("Could not create the default stages"))]
+    DefaultStage { source: TryNewStageError },
+    #[snafu(display("Could not create an invalid stage sequence"))]
+    StageSequence { source: TryNewStageSequenceError },
+    #[snafu(display("Could not load the initial stage from repository"))]
+    InitialStageConfig { source: GetDurationError },
+    #[snafu(display("Could not start from {kind}, which is not in the stage sequence"))]
+    InitialStage { kind: StageState },
+    #[snafu(display("Could not load the timer mode from repository"))]
+    ModeConfig { source: GetDurationError },
+    #[snafu(display("Could not load the long break duration from repository"))]
+    LongBreakConfig { source: GetDurationError },
+    #[snafu(display("Could not load the number of cycles before a long break from repository"))]
+    CyclesConfig { source: GetDurationError },
+    #[snafu(display("Could not load behavior option {key} from repository"))]
+    BehaviorOption {
+        key: String,
+        source: GetBehaviorError,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::future;
+
+    use tokio::time::Duration;
+
+    use crate::domain::daemon::outbound::{MockIdleDetector, MockStageLog, MockStateStore};
+    use crate::domain::daemon::outbound::{NotifyError, NotifyRequest};
+    use crate::domain::entity::{NotificationMessage, StageDuration, TimerMode, TimerStatus};
+    use crate::domain::repository::behavior::MockBehaviorRepository;
+    use crate::domain::repository::duration::MockDurationRepository;
+    use crate::domain::repository::notification::MockNotificationRepository;
+    use crate::domain::repository::stage::MockStageRepository;
+
+    struct SilentNotifier;
+
+    #[async_trait::async_trait]
+    impl NotifyPort for SilentNotifier {
+        async fn notify_impl(&self, _request: NotifyRequest) -> Result<(), NotifyError> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn spawn_ready_before_request() {
+        let worker = new_worker().await;
+
+        // The very first request already sees a running timer.
+        let response = worker.query().await;
+        assert_eq!(response.current, TimerStatus::Running);
+        assert_eq!(response.stage, "Preparation");
+        assert_eq!(response.past, Duration::from_secs(0));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn spawn_concurrent_requests() {
+        let worker = Arc::new(new_worker().await);
+
+        let queries: Vec<_> = (0..2 * COMMAND_CAPACITY)
+            .map(|_| {
+                let worker = Arc::clone(&worker);
+                tokio::spawn(async move { worker.query().await })
+            })
+            .collect();
+
+        for query in queries {
+            let response = query.await.unwrap();
+            assert_eq!(response.current, TimerStatus::Running);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn spawn_ping_after_stop() {
+        let worker = new_worker().await;
+        assert_eq!(worker.ping().await, TimerStatus::Running);
+
+        worker.stop(true).await;
+        assert_eq!(worker.ping().await, TimerStatus::Stopped);
+        assert_eq!(worker.query().await.current, TimerStatus::Stopped);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn spawn_idle_after_stop() {
+        let worker = new_worker().await;
+        worker.stop(true).await;
+
+        // The paused clock only moves on once every task is idle, so this
+        // would never return if the stopped worker kept running.
+        tokio::time::sleep(Duration::from_secs(3600)).await;
+        assert_eq!(worker.query().await.current, TimerStatus::Stopped);
+    }
+
+    #[tokio::test]
+    async fn load_config_initial_stage() {
+        let load = |kind| {
+            let mut duration = MockDurationRepository::new();
+            duration
+                .expect_long_break_duration()
+                .returning(|| Ok(StageDuration::try_new(5).unwrap()));
+            duration
+                .expect_cycles_before_long_break()
+                .returning(|| Ok(0));
+            duration.expect_initial_stage().returning(move || Ok(kind));
+            duration.expect_mode().returning(|| Ok(TimerMode::Full));
+
+            let mut stage = MockStageRepository::new();
+            stage.expect_stages().returning(|| {
+                let new_stage = |name: &str, kind| {
+                    let duration = StageDuration::try_new(5).unwrap();
+                    let message = NotificationMessage::try_new(name.to_owned(), None).unwrap();
+                    Stage::try_new(name.to_owned(), kind, duration, message).unwrap()
+                };
+                Ok(vec![
+                    new_stage("Concentration", StageState::Concentration),
+                    new_stage("Relaxation", StageState::Relaxation),
+                ])
+            });
+
+            let mut behavior = MockBehaviorRepository::new();
+            behavior.expect_auto_pause_enabled().returning(|_| Ok(true));
+            behavior.expect_idle_threshold().returning(|| Ok(None));
+            behavior.expect_start_paused().returning(|| Ok(false));
+            behavior.expect_max_pause().returning(|| Ok(None));
+
+            load_config(
+                Arc::new(duration),
+                Arc::new(MockNotificationRepository::new()),
+                Arc::new(stage),
+                Arc::new(behavior),
+            )
+        };
+
+        assert_eq!(load(None).await.unwrap().initial_stage, 0);
+        let config = load(Some(StageState::Relaxation)).await.unwrap();
+        assert_eq!(config.initial_stage, 1);
+        assert!(matches!(
+            load(Some(StageState::Preparation)).await,
+            Err(LoadWorkerConfigError::InitialStage {
+                kind: StageState::Preparation
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn load_config_long_break_error() {
+        let load = |long_break_fails: bool| {
+            let invalid = || GetDurationError::Invalid {
+                source: StageDuration::try_new(0).unwrap_err(),
+            };
+            let mut duration = MockDurationRepository::new();
+            duration.expect_long_break_duration().returning(move || {
+                if long_break_fails {
+                    Err(invalid())
+                } else {
+                    Ok(StageDuration::try_new(5).unwrap())
+                }
+            });
+            duration
+                .expect_cycles_before_long_break()
+                .returning(move || Err(invalid()));
+            duration.expect_initial_stage().returning(|| Ok(None));
+            duration.expect_mode().returning(|| Ok(TimerMode::Full));
+
+            let mut stage = MockStageRepository::new();
+            stage.expect_stages().returning(|| {
+                let duration = StageDuration::try_new(5).unwrap();
+                let message = NotificationMessage::try_new("Concentration".to_owned(), None);
+                let stage = Stage::try_new(
+                    "Concentration".to_owned(),
+                    StageState::Concentration,
+                    duration,
+                    message.unwrap(),
+                );
+                Ok(vec![stage.unwrap()])
+            });
+
+            load_config(
+                Arc::new(duration),
+                Arc::new(MockNotificationRepository::new()),
+                Arc::new(stage),
+                Arc::new(MockBehaviorRepository::new()),
+            )
+        };
+
+        // Each option is named by its own error rather than by a stage.
+        assert!(matches!(
+            load(true).await,
+            Err(LoadWorkerConfigError::LongBreakConfig { .. })
+        ));
+        assert!(matches!(
+            load(false).await,
+            Err(LoadWorkerConfigError::CyclesConfig { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn load_config_simple_mode() {
+        let mut duration = MockDurationRepository::new();
+        duration
+            .expect_long_break_duration()
+            .returning(|| Ok(StageDuration::try_new(5).unwrap()));
+        duration
+            .expect_cycles_before_long_break()
+            .returning(|| Ok(0));
+        duration.expect_initial_stage().returning(|| Ok(None));
+        duration.expect_mode().returning(|| Ok(TimerMode::Simple));
+
+        let mut stage = MockStageRepository::new();
+        stage.expect_stages().returning(|| {
+            let new_stage = |name: &str, kind| {
+                let duration = StageDuration::try_new(5).unwrap();
+                let message = NotificationMessage::try_new(name.to_owned(), None).unwrap();
+                Stage::try_new(name.to_owned(), kind, duration, message).unwrap()
+            };
+            Ok(vec![
+                new_stage("Preparation", StageState::Preparation),
+                new_stage("Concentration", StageState::Concentration),
+                new_stage("Warm-up", StageState::Preparation),
+                new_stage("Relaxation", StageState::Relaxation),
+            ])
+        });
+
+        let mut behavior = MockBehaviorRepository::new();
+        behavior.expect_auto_pause_enabled().returning(|_| Ok(true));
+        behavior.expect_idle_threshold().returning(|| Ok(None));
+        behavior.expect_start_paused().returning(|| Ok(false));
+        behavior.expect_max_pause().returning(|| Ok(None));
+
+        let config = load_config(
+            Arc::new(duration),
+            Arc::new(MockNotificationRepository::new()),
+            Arc::new(stage),
+            Arc::new(behavior),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(config.initial_stage, 1);
+        assert_eq!(config.advance(1, 0), (3, 1));
+        assert_eq!(config.advance(3, 1), (1, 1));
+    }
+
+    async fn new_worker() -> WorkerHandle {
+        let new_duration = || Ok(StageDuration::try_new(5).unwrap());
+        let new_message = || Ok(NotificationMessage::try_new("Stage".to_owned(), None).unwrap());
+
+        let mut duration = MockDurationRepository::new();
+        duration
+            .expect_preparation_duration()
+            .returning(new_duration);
+        duration
+            .expect_concentration_duration()
+            .returning(new_duration);
+        duration
+            .expect_relaxation_duration()
+            .returning(new_duration);
+        duration
+            .expect_long_break_duration()
+            .returning(new_duration);
+        duration
+            .expect_cycles_before_long_break()
+            .returning(|| Ok(0));
+        duration.expect_initial_stage().returning(|| Ok(None));
+        duration.expect_mode().returning(|| Ok(TimerMode::Full));
+
+        let mut notification = MockNotificationRepository::new();
+        notification
+            .expect_preparation_notification()
+            .returning(new_message);
+        notification
+            .expect_concentration_notification()
+            .returning(new_message);
+        notification
+            .expect_relaxation_notification()
+            .returning(new_message);
+
+        let mut stage = MockStageRepository::new();
+        stage.expect_stages().returning(|| Ok(Vec::new()));
+
+        let mut behavior = MockBehaviorRepository::new();
+        behavior.expect_auto_pause_enabled().returning(|_| Ok(true));
+        behavior.expect_idle_threshold().returning(|| Ok(None));
+        behavior.expect_start_paused().returning(|| Ok(false));
+        behavior.expect_max_pause().returning(|| Ok(None));
+
+        let mut store = MockStateStore::new();
+        store
+            .expect_load()
+            .returning(|_| Box::pin(future::ready(Ok(None))));
+        store
+            .expect_save()
+            .returning(|_, _| Box::pin(future::ready(Ok(()))));
+
+        let mut stage_log = MockStageLog::new();
+        stage_log
+            .expect_append()
+            .returning(|_| Box::pin(future::ready(Ok(()))));
+
+        spawn(
+            "default",
+            Arc::new(store),
+            Arc::new(stage_log),
+            Arc::new(duration),
+            Arc::new(notification),
+            Arc::new(stage),
+            Arc::new(behavior),
+            Arc::new(SilentNotifier),
+            Arc::new(MockIdleDetector::new()),
+        )
+        .await
+        .unwrap()
+    }
 }
@@ -1,51 +1,341 @@
 use std::sync::Arc;
+use std::time::Duration;
 
+use tokio::sync::broadcast::Sender as BroadcastSender;
 use tokio::sync::mpsc::Receiver;
+use tokio::sync::oneshot::Sender as OneshotSender;
 use tokio::task::JoinHandle;
+use tokio::time::Instant;
 
-use crate::domain::daemon::outbound::NotifyPort;
+use crate::domain::daemon::outbound::{IdleDetector, NotifyPort, StageLog, StateStore};
 use crate::domain::daemon::worker::handle::Command;
 use crate::domain::daemon::worker::state::WorkerState;
-use crate::domain::entity::{NotificationMessage, StageDuration, StageState};
+use crate::domain::daemon::worker::stats::WorkerStats;
+use crate::domain::entity::{
+    AutoPauseTrigger, NotificationMessage, ScheduledStage, Stage, StageDuration, StageSequence,
+    StageState, TimerEvent, TimerMode,
+};
+
+/// Length of a long break in seconds if none is set.
+const DEFAULT_LONG_BREAK: u64 = 1800;
+/// Number of concentration stages before a long break if none is set.
+const DEFAULT_CYCLES_BEFORE_LONG_BREAK: u32 = 4;
 
 /// A type that stores configurations required by [`WorkerRoutine`]
-/// initialization.
+/// initialization. Stages are referred to by their indices in `stages`.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct WorkerConfig {
-    pub preparation_duration: StageDuration,
-    pub concentration_duration: StageDuration,
-    pub relaxation_duration: StageDuration,
-    pub preparation_notification: NotificationMessage,
-    pub concentration_notification: NotificationMessage,
-    pub relaxation_notification: NotificationMessage,
+    pub stages: StageSequence,
+    /// Index of the stage which the timer starts from.
+    pub initial_stage: usize,
+    /// How the timer goes through `stages`.
+    pub mode: TimerMode,
+    pub long_break_duration: StageDuration,
+    pub cycles_before_long_break: u32,
+    pub screen_lock_auto_pause: bool,
+    pub low_battery_auto_pause: bool,
+    /// How long the user may be idle before the timer is paused. Idle
+    /// detection is disabled if it is `None`.
+    pub idle_threshold: Option<Duration>,
+    /// Whether the timer waits in the paused state when it starts afresh
+    /// rather than from a saved state.
+    pub start_paused: bool,
+    /// How long a manual pause lasts before the timer resumes by itself. It
+    /// stays paused until resumed if it is `None`.
+    pub max_pause: Option<Duration>,
+    /// How often a running timer wakes up within a stage, for anything which
+    /// goes on during it. It only wakes up at the end of each stage if it is
+    /// `None`.
+    pub tick_interval: Option<Duration>,
 }
 
 impl WorkerConfig {
-    /// Get the duration corresponding to stage.
-    pub fn duration(&self, stage: StageState) -> &StageDuration {
-        match stage {
-            StageState::Preparation => &self.preparation_duration,
-            StageState::Concentration => &self.concentration_duration,
-            StageState::Relaxation => &self.relaxation_duration,
+    /// Start building a [`WorkerConfig`] which goes through `stages`. The
+    /// other fields follow the default configuration until they are set.
+    pub fn builder(stages: StageSequence) -> WorkerConfigBuilder {
+        WorkerConfigBuilder {
+            config: Self {
+                initial_stage: stages.initial(),
+                stages,
+                mode: TimerMode::Full,
+                long_break_duration: StageDuration::try_new(DEFAULT_LONG_BREAK)
+                    .expect("Default long break should be a valid duration"),
+                cycles_before_long_break: DEFAULT_CYCLES_BEFORE_LONG_BREAK,
+                screen_lock_auto_pause: true,
+                low_battery_auto_pause: true,
+                idle_threshold: None,
+                start_paused: false,
+                max_pause: None,
+                tick_interval: None,
+            },
+        }
+    }
+
+    /// Get the stage at `index`.
+    pub fn stage(&self, index: usize) -> &Stage {
+        &self.stages[index]
+    }
+
+    /// Get the duration of the stage at `index`, in which `concentrations`
+    /// concentration stages have been completed since the last long break.
+    pub fn stage_duration(&self, index: usize, concentrations: u32) -> &StageDuration {
+        let stage = self.stage(index);
+        match stage.kind() {
+            StageState::Relaxation if self.is_long_break(concentrations) => {
+                &self.long_break_duration
+            }
+            _ => stage.duration(),
+        }
+    }
+
+    /// Check whether a break after `concentrations` concentration stages is a
+    /// long one.
+    pub fn is_long_break(&self, concentrations: u32) -> bool {
+        self.cycles_before_long_break > 0 && concentrations >= self.cycles_before_long_break
+    }
+
+    /// Check whether the current break or the upcoming one is a long one.
+    pub fn next_break_long(&self, index: usize, concentrations: u32) -> bool {
+        match self.stage(index).kind() {
+            StageState::Relaxation => self.is_long_break(concentrations),
+            _ => self.is_long_break(concentrations + 1),
+        }
+    }
+
+    /// Move on from the stage at `index` and return the index of the next
+    /// stage along with the updated number of concentration stages since the
+    /// last long break.
+    pub fn advance(&self, index: usize, concentrations: u32) -> (usize, u32) {
+        let concentrations = match self.stage(index).kind() {
+            StageState::Concentration => concentrations + 1,
+            StageState::Relaxation if self.is_long_break(concentrations) => 0,
+            _ => concentrations,
+        };
+        (self.resolve(self.stages.next(index)), concentrations)
+    }
+
+    /// Get the index of the first stage from the one at `index` on which the
+    /// timer may stay in its mode. [`Preparation`]s are passed over in the
+    /// simple mode.
+    ///
+    /// [`Preparation`]: StageState::Preparation
+    pub fn resolve(&self, index: usize) -> usize {
+        let mut index = index;
+        if self.mode == TimerMode::Simple {
+            // A sequence always has a stage other than preparations.
+            for _ in 0..self.stages.len() {
+                if self.stage(index).kind() != StageState::Preparation {
+                    break;
+                }
+                index = self.stages.next(index);
+            }
+        }
+        index
+    }
+
+    /// Get the number of completed rounds of the sequence after moving on from
+    /// the stage at `index`, where `cycles` rounds had been completed. A round
+    /// completes once the last stage is left.
+    pub fn cycles_after(&self, index: usize, cycles: u32) -> u32 {
+        if self.stages.is_last(index) {
+            cycles.saturating_add(1)
+        } else {
+            cycles
+        }
+    }
+
+    /// Move on from the stage at `index` until a stage of `kind` is reached,
+    /// as if the stages in between were skipped. Return the index of that
+    /// stage along with the updated numbers of concentration stages and
+    /// completed rounds, or `None` if the sequence never comes to `kind`.
+    pub fn advance_to(
+        &self,
+        index: usize,
+        concentrations: u32,
+        cycles: u32,
+        kind: StageState,
+    ) -> Option<(usize, u32, u32)> {
+        let (mut index, mut concentrations, mut cycles) = (index, concentrations, cycles);
+
+        for _ in 0..self.stages.len() {
+            cycles = self.cycles_after(index, cycles);
+            (index, concentrations) = self.advance(index, concentrations);
+            if self.stage(index).kind() == kind {
+                return Some((index, concentrations, cycles));
+            }
+        }
+
+        None
+    }
+
+    /// Get the stages from the one at `index`, of which `remaining` is left,
+    /// for one round of the sequence until a stage would come again.
+    /// `concentrations` concentration stages have been completed since the
+    /// last long break.
+    pub fn schedule(
+        &self,
+        index: usize,
+        concentrations: u32,
+        remaining: Duration,
+    ) -> Vec<ScheduledStage> {
+        let mut visited = vec![false; self.stages.len()];
+        let mut schedule = vec![self.scheduled_stage(index, concentrations, remaining)];
+        let (mut index, mut concentrations) = (index, concentrations);
+        visited[index] = true;
+
+        loop {
+            (index, concentrations) = self.advance(index, concentrations);
+            if visited[index] {
+                return schedule;
+            }
+            visited[index] = true;
+            let duration = *self.stage_duration(index, concentrations).inner();
+            schedule.push(self.scheduled_stage(index, concentrations, duration));
+        }
+    }
+
+    fn scheduled_stage(
+        &self,
+        index: usize,
+        concentrations: u32,
+        duration: Duration,
+    ) -> ScheduledStage {
+        let stage = self.stage(index);
+        ScheduledStage {
+            name: stage.name().to_owned(),
+            kind: stage.kind(),
+            duration,
+            long_break: stage.kind() == StageState::Relaxation
+                && self.is_long_break(concentrations),
         }
     }
 
-    /// Get the notification message corresponding to stage.
-    pub fn notification(&self, stage: StageState) -> &NotificationMessage {
-        match stage {
-            StageState::Preparation => &self.preparation_notification,
-            StageState::Concentration => &self.concentration_notification,
-            StageState::Relaxation => &self.relaxation_notification,
+    /// Get the notification message shown after the stage at `index`.
+    pub fn notification(&self, index: usize) -> &NotificationMessage {
+        self.stage(index).notification()
+    }
+
+    /// Check whether the trigger is allowed to pause the timer automatically.
+    pub fn auto_pause_enabled(&self, trigger: AutoPauseTrigger) -> bool {
+        match trigger {
+            AutoPauseTrigger::ScreenLock => self.screen_lock_auto_pause,
+            AutoPauseTrigger::LowBattery => self.low_battery_auto_pause,
+            AutoPauseTrigger::Idle => self.idle_threshold.is_some(),
         }
     }
 }
 
+/// A builder of [`WorkerConfig`], created by [`WorkerConfig::builder`] or
+/// from an existing configuration to override some of its fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkerConfigBuilder {
+    config: WorkerConfig,
+}
+
+impl WorkerConfigBuilder {
+    /// Replace the stages to go through.
+    pub fn stages(mut self, stages: StageSequence) -> Self {
+        self.config.stages = stages;
+        self
+    }
+
+    /// Set the index of the stage which the timer starts from.
+    pub fn initial_stage(mut self, initial_stage: usize) -> Self {
+        self.config.initial_stage = initial_stage;
+        self
+    }
+
+    /// Set how the timer goes through the stages.
+    pub fn mode(mut self, mode: TimerMode) -> Self {
+        self.config.mode = mode;
+        self
+    }
+
+    /// Set the duration of long breaks.
+    pub fn long_break_duration(mut self, long_break_duration: StageDuration) -> Self {
+        self.config.long_break_duration = long_break_duration;
+        self
+    }
+
+    /// Set the number of concentration stages before a long break, where 0
+    /// disables long breaks.
+    pub fn cycles_before_long_break(mut self, cycles_before_long_break: u32) -> Self {
+        self.config.cycles_before_long_break = cycles_before_long_break;
+        self
+    }
+
+    /// Set whether locking the screen pauses the timer.
+    pub fn screen_lock_auto_pause(mut self, screen_lock_auto_pause: bool) -> Self {
+        self.config.screen_lock_auto_pause = screen_lock_auto_pause;
+        self
+    }
+
+    /// Set whether a low battery pauses the timer.
+    pub fn low_battery_auto_pause(mut self, low_battery_auto_pause: bool) -> Self {
+        self.config.low_battery_auto_pause = low_battery_auto_pause;
+        self
+    }
+
+    /// Set how long the user may be idle before the timer is paused.
+    pub fn idle_threshold(mut self, idle_threshold: Option<Duration>) -> Self {
+        self.config.idle_threshold = idle_threshold;
+        self
+    }
+
+    /// Set whether the timer waits in the paused state when it starts afresh.
+    pub fn start_paused(mut self, start_paused: bool) -> Self {
+        self.config.start_paused = start_paused;
+        self
+    }
+
+    /// Set how long a manual pause lasts before the timer resumes by itself.
+    pub fn max_pause(mut self, max_pause: Option<Duration>) -> Self {
+        self.config.max_pause = max_pause;
+        self
+    }
+
+    /// Set how often a running timer wakes up within a stage.
+    pub fn tick_interval(mut self, tick_interval: Option<Duration>) -> Self {
+        self.config.tick_interval = tick_interval;
+        self
+    }
+
+    /// Build the [`WorkerConfig`]. The initial stage is moved on to one on
+    /// which the timer may stay in its mode, as [`WorkerConfig::resolve`]
+    /// does.
+    pub fn build(self) -> WorkerConfig {
+        let mut config = self.config;
+        config.initial_stage = config.resolve(config.initial_stage);
+        config
+    }
+}
+
+impl From<WorkerConfig> for WorkerConfigBuilder {
+    fn from(config: WorkerConfig) -> Self {
+        Self { config }
+    }
+}
+
 /// A [`WorkerContext`] stores all objects relavent to the [`WorkerRoutine`]
-/// and the business logic.
+/// and the business logic. `store` keeps the progress of the session named
+/// `session`, and `stage_log` is told about every completed stage, which is
+/// also counted in `stats`.
 pub struct WorkerContext {
     pub config: WorkerConfig,
     pub commands: Receiver<Command>,
     pub notifier: Arc<dyn NotifyPort>,
+    pub idle_detector: Arc<dyn IdleDetector>,
+    pub session: String,
+    pub store: Arc<dyn StateStore>,
+    pub stage_log: Arc<dyn StageLog>,
+    pub stats: WorkerStats,
+    /// Whether notifications are muted for now.
+    pub muted: bool,
+    /// Senders of the events to the subscribers.
+    pub subscribers: Vec<BroadcastSender<TimerEvent>>,
+    /// When the idle time of the user is checked next. It is kept here rather
+    /// than in a state, so that frequent commands never put the check off.
+    pub idle_poll: Instant,
 }
 
 /// A type responsible for the daemon's main business logic. A [`WorkerRoutine`]
@@ -53,14 +343,22 @@ pub struct WorkerContext {
 pub struct WorkerRoutine {
     context: WorkerContext,
     state: WorkerState,
+    ready: Option<OneshotSender<()>>,
 }
 
 impl WorkerRoutine {
-    /// Spawn a running [`WorkerRoutine`] on background.
+    /// Spawn a running [`WorkerRoutine`] on background. `ready` is signaled
+    /// once the timer has started, before any [`Command`] is handled.
+    #[allow(clippy::too_many_arguments)]
     pub fn spawn(
         config: WorkerConfig,
         commands: Receiver<Command>,
         notifier: Arc<dyn NotifyPort>,
+        idle_detector: Arc<dyn IdleDetector>,
+        session: String,
+        store: Arc<dyn StateStore>,
+        stage_log: Arc<dyn StageLog>,
+        ready: OneshotSender<()>,
     ) -> JoinHandle<()> {
         tokio::spawn(async {
             let mut worker = Self {
@@ -68,17 +366,94 @@ impl WorkerRoutine {
                     config,
                     commands,
                     notifier,
+                    idle_detector,
+                    session,
+                    store,
+                    stage_log,
+                    stats: WorkerStats::new(),
+                    muted: false,
+                    subscribers: Vec::new(),
+                    idle_poll: Instant::now(),
                 },
                 state: WorkerState::new(),
+                ready: Some(ready),
             };
             worker.run().await;
         })
     }
 
-    /// Main part of its business logic.
+    /// Main part of its business logic, which lasts until the worker stops
+    /// and every handle is dropped. Each run of a state waits for a command
+    /// or a timer, so that the worker never spins, even once it has stopped.
     async fn run(&mut self) {
-        loop {
-            self.state.run(&mut self.context).await;
+        while !self.state.is_stopped() {
+            if self.state.run(&mut self.context).await {
+                self.persist().await;
+            }
+
+            // The first transition always leaves the initial ready state.
+            if let Some(ready) = self.ready.take() {
+                let _ = ready.send(());
+            }
         }
+
+        // Let the stopped state answer queries until every handle is gone.
+        self.state.run(&mut self.context).await;
+    }
+
+    /// Save the progress after it has changed. A worker stopped without
+    /// keeping its progress clears it, so that the session starts afresh next
+    /// time.
+    async fn persist(&self) {
+        let store = &self.context.store;
+        let session = &self.context.session;
+
+        let res = match self.state.snapshot() {
+            Some(snapshot) => store.save(session, &snapshot).await,
+            None if self.state.discards_progress() => store.clear(session).await,
+            None => Ok(()),
+        };
+
+        if let Err(err) = res {
+            tracing::warn!(session, err = %err, "Could not save the worker state");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn worker_config_builder() {
+        let new_stage = |name: &str, kind| {
+            let duration = StageDuration::try_new(10).unwrap();
+            let message = NotificationMessage::try_new(name.to_owned(), None).unwrap();
+            Stage::try_new(name.to_owned(), kind, duration, message).unwrap()
+        };
+        let stages = StageSequence::try_new(vec![
+            new_stage("Preparation", StageState::Preparation),
+            new_stage("Concentration", StageState::Concentration),
+        ])
+        .unwrap();
+
+        let config = WorkerConfig::builder(stages).build();
+        assert_eq!(config.initial_stage, 0);
+        assert_eq!(config.mode, TimerMode::Full);
+        assert_eq!(
+            config.long_break_duration,
+            StageDuration::try_new(1800).unwrap()
+        );
+        assert_eq!(config.cycles_before_long_break, 4);
+        assert!(config.screen_lock_auto_pause);
+        assert_eq!(config.max_pause, None);
+
+        // Overriding the mode moves the initial stage past the preparation.
+        let config = WorkerConfigBuilder::from(config)
+            .mode(TimerMode::Simple)
+            .max_pause(Some(Duration::from_secs(60)))
+            .build();
+        assert_eq!(config.initial_stage, 1);
+        assert_eq!(config.max_pause, Some(Duration::from_secs(60)));
     }
 }
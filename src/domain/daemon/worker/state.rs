@@ -1,9 +1,23 @@
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use snafu::prelude::*;
+use tokio::sync::broadcast::Sender as BroadcastSender;
 use tokio::sync::oneshot::Sender;
-use tokio::time::{Duration, Instant, Interval};
+use tokio::time::{Duration, Instant, Interval, MissedTickBehavior};
 
+use crate::domain::daemon::outbound::{IdleDetector, StageRecord, WorkerSnapshot};
 use crate::domain::daemon::worker::handle::{Command, QueryResponse};
-use crate::domain::daemon::worker::routine::WorkerContext;
-use crate::domain::entity::StageState;
+use crate::domain::daemon::worker::routine::{WorkerConfig, WorkerContext};
+use crate::domain::daemon::worker::{ChangeStageError, LengthSnafu, NoStageSnafu};
+use crate::domain::entity::NotificationMessage;
+use crate::domain::entity::{AutoPauseTrigger, StageDuration, StageState, TimerEvent};
+use crate::domain::entity::{TimerStats, TimerStatus};
+use crate::utils::clock::format_clock;
+use crate::utils::local_time;
+
+/// How often the idle time of the user is checked.
+const IDLE_POLL_INTERVAL: Duration = Duration::from_secs(5);
 
 #[derive(Debug)]
 #[repr(transparent)]
@@ -19,12 +33,68 @@ impl WorkerState {
         }
     }
 
-    /// Do the business logic based on its inner state.
-    pub async fn run(&mut self, context: &mut WorkerContext) {
-        self.inner = match self.inner.take() {
-            Some(inner) => Some(inner.run(context).await),
+    /// Check whether the worker has stopped, after which it runs only once
+    /// more, waiting for queries until the channel closes.
+    pub fn is_stopped(&self) -> bool {
+        matches!(self.inner, Some(WorkerStateInner::Stopped(_)))
+    }
+
+    /// Check whether the worker has stopped and the saved progress should be
+    /// cleared.
+    pub fn discards_progress(&self) -> bool {
+        matches!(
+            self.inner,
+            Some(WorkerStateInner::Stopped(StoppedState {
+                keep_progress: false,
+                ..
+            }))
+        )
+    }
+
+    /// Take a snapshot of the progress for saving. Return `None` if no timer
+    /// is working.
+    pub fn snapshot(&self) -> Option<WorkerSnapshot> {
+        match &self.inner {
+            Some(WorkerStateInner::Running(state)) => Some(state.snapshot()),
+            Some(WorkerStateInner::Paused(state)) => Some(state.snapshot()),
+            _ => None,
+        }
+    }
+
+    /// Do the business logic based on its inner state, and tell the
+    /// subscribers if the timer has moved on to another stage or has been
+    /// paused, resumed or stopped. Return whether the progress has changed,
+    /// which is never the case for commands that only ask for something.
+    pub async fn run(&mut self, context: &mut WorkerContext) -> bool {
+        let (inner, before, progress, from) = match self.inner.take() {
+            Some(inner) => {
+                let (before, progress, from) = (inner.position(), inner.progress(), inner.name());
+                (inner.run(context).await, before, progress, from)
+            }
             None => unreachable!("`WorkerState`'s inner should not be `None`"),
         };
+        let changed = inner.position() != before || inner.progress() != progress;
+
+        if let Some((_, stage, _, _)) = inner.position().filter(|&after| Some(after) != before) {
+            tracing::debug!(
+                session = context.session,
+                from,
+                to = inner.name(),
+                stage = context.config.stage(stage).name(),
+                past = ?inner.past(),
+                "Changed state"
+            );
+            if let Some(event) = inner.event(context) {
+                publish(context, event);
+            }
+        }
+
+        // Nothing happens to a stopped timer anymore, so its subscriptions end.
+        if matches!(inner, WorkerStateInner::Stopped(_)) {
+            context.subscribers.clear();
+        }
+        self.inner = Some(inner);
+        changed
     }
 }
 
@@ -40,12 +110,77 @@ enum WorkerStateInner {
     Ready(ReadyState),
     Running(RunningState),
     Paused(PausedState),
+    Stopped(StoppedState),
 }
 
 impl WorkerStateInner {
     pub fn new() -> Self {
         Self::Ready(ReadyState)
     }
+
+    /// Get the status, the stage, and the counts of concentrations and
+    /// cycles, which together change on every transition worth an event.
+    /// Return `None` if the timer has not started.
+    fn position(&self) -> Option<(TimerStatus, usize, u32, u32)> {
+        match self {
+            Self::Ready(_) => None,
+            Self::Running(state) => Some((
+                TimerStatus::Running,
+                state.stage,
+                state.concentrations,
+                state.cycles,
+            )),
+            Self::Paused(state) => Some((
+                TimerStatus::Paused,
+                state.stage,
+                state.concentrations,
+                state.cycles,
+            )),
+            Self::Stopped(state) => Some((TimerStatus::Stopped, state.stage, 0, state.cycles)),
+        }
+    }
+
+    /// Get when the current stage went on from how long it had gone on, and
+    /// its total length. They stay the same while only time goes on, unlike
+    /// the saved snapshot. Return `None` if no timer is working.
+    fn progress(&self) -> Option<(Option<Instant>, Duration, StageDuration)> {
+        match self {
+            Self::Running(state) => Some((Some(state.start), state.past, state.total.clone())),
+            Self::Paused(state) => Some((None, state.past, state.total.clone())),
+            Self::Ready(_) | Self::Stopped(_) => None,
+        }
+    }
+
+    /// Get the name of the state, for logging.
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Ready(_) => "Ready",
+            Self::Running(_) => "Running",
+            Self::Paused(_) => "Paused",
+            Self::Stopped(_) => "Stopped",
+        }
+    }
+
+    /// Get how long the current stage has gone on, which is zero if no timer
+    /// is working.
+    fn past(&self) -> Duration {
+        match self {
+            Self::Running(state) => state.past + (Instant::now() - state.start),
+            Self::Paused(state) => state.past,
+            Self::Ready(_) | Self::Stopped(_) => Duration::ZERO,
+        }
+    }
+
+    /// Describe the current state for the subscribers. Return `None` if the
+    /// timer has not started.
+    fn event(&self, context: &WorkerContext) -> Option<TimerEvent> {
+        match self {
+            Self::Ready(_) => None,
+            Self::Running(state) => Some(state.event(context)),
+            Self::Paused(state) => Some(state.event(context)),
+            Self::Stopped(state) => Some(state.event(context)),
+        }
+    }
 }
 
 /// A state which indicates that the [`WorkerRoutine`] is ready to run.
@@ -54,62 +189,249 @@ struct ReadyState;
 
 impl StateRun for ReadyState {
     async fn run(self, context: &mut WorkerContext) -> WorkerStateInner {
-        let stage = StageState::initial();
-        let duration = *context.config.duration(stage).inner();
-        let (start, timer) = spawn_timer(duration).await;
+        match self.restore(context).await {
+            Some(state) => state,
+            None if context.config.start_paused => self.wait(context),
+            None => self.start(context).await,
+        }
+    }
+}
+
+impl ReadyState {
+    /// Start the timer from the initial stage.
+    async fn start(self, context: &mut WorkerContext) -> WorkerStateInner {
+        let stage = context.config.initial_stage;
+        let duration = context.config.stage_duration(stage, 0).clone();
+        let (start, deadline) = start_timer(*duration.inner());
 
         RunningState {
             start,
             past: Duration::from_secs(0),
-            timer,
+            total: duration,
+            deadline,
+            stage,
+            concentrations: 0,
+            cycles: 0,
+        }
+        .into()
+    }
+
+    /// Wait at the beginning of the initial stage until the timer is resumed.
+    fn wait(self, context: &WorkerContext) -> WorkerStateInner {
+        let stage = context.config.initial_stage;
+        let duration = context.config.stage_duration(stage, 0).clone();
+
+        PausedState {
+            past: Duration::from_secs(0),
+            total: duration,
             stage,
+            concentrations: 0,
+            cycles: 0,
+            trigger: None,
+            resume_at: None,
         }
         .into()
     }
+
+    /// Continue with the progress saved by a previous worker. A running timer
+    /// is considered to have kept going while no worker was alive. Return
+    /// `None` if nothing usable is saved, e.g. it is corrupt, its stage has
+    /// already ended or is no longer in the configured sequence.
+    async fn restore(&self, context: &mut WorkerContext) -> Option<WorkerStateInner> {
+        let snapshot = match context.store.load(&context.session).await {
+            Ok(snapshot) => snapshot?,
+            Err(err) => {
+                tracing::warn!(session = context.session, err = %err, "Discarded the saved worker state");
+                return None;
+            }
+        };
+
+        let mut past = snapshot.past;
+        if !snapshot.paused {
+            past += SystemTime::now()
+                .duration_since(snapshot.saved_at)
+                .unwrap_or_default();
+        }
+
+        let unknown_stage = context.config.stages.get(snapshot.stage).is_none();
+        let total = match StageDuration::try_from_duration(snapshot.total) {
+            Ok(total) if !unknown_stage && past < *total.inner() => total,
+            _ => {
+                tracing::info!(
+                    session = context.session,
+                    "Discarded the stale worker state"
+                );
+                return None;
+            }
+        };
+
+        if snapshot.paused {
+            return Some(
+                PausedState {
+                    past,
+                    total,
+                    stage: snapshot.stage,
+                    concentrations: snapshot.concentrations,
+                    cycles: snapshot.cycles,
+                    trigger: None,
+                    resume_at: None,
+                }
+                .into(),
+            );
+        }
+
+        let (start, deadline) = start_timer(*total.inner() - past);
+        Some(
+            RunningState {
+                start,
+                past,
+                total,
+                deadline,
+                stage: snapshot.stage,
+                concentrations: snapshot.concentrations,
+                cycles: snapshot.cycles,
+            }
+            .into(),
+        )
+    }
 }
 
 /// A state which indicates that the [`WorkerRoutine`] is running, with a timer working
-/// internally.
+/// internally. `total` is the length of the current stage, which may differ
+/// from the configured one after an adjustment. `stage` is an index into the
+/// configured stage sequence. `concentrations` counts the concentration stages
+/// since the last long break, and `cycles` counts the rounds of the sequence
+/// completed since the timer started. The stage ends exactly at `deadline`.
 #[derive(Debug)]
 struct RunningState {
     start: Instant,
     past: Duration,
-    timer: Interval,
-    stage: StageState,
+    total: StageDuration,
+    deadline: Instant,
+    stage: usize,
+    concentrations: u32,
+    cycles: u32,
 }
 
 impl StateRun for RunningState {
-    async fn run(mut self, context: &mut WorkerContext) -> WorkerStateInner {
-        tokio::select! {
-            _ = self.timer.tick() => self.handle_tick(context).await,
-            Some(command) = context.commands.recv() => match command {
-                Command::Pause => self.handle_pause(),
-                Command::Resume => self.handle_resume(),
-                Command::Skip => self.handle_skip(context).await,
-                Command::Query { responder } => self.handle_query(context, responder),
-            },
-            else => self.into(),
+    async fn run(self, context: &mut WorkerContext) -> WorkerStateInner {
+        // Being away only matters while concentrating.
+        let threshold = context
+            .config
+            .idle_threshold
+            .filter(|_| context.config.stage(self.stage).kind() == StageState::Concentration);
+        let detector = Arc::clone(&context.idle_detector);
+        let past = self.past + (Instant::now() - self.start);
+        let checkpoint = next_checkpoint(&context.config, self.stage, *self.total.inner(), past);
+
+        // Ticks in between only wake the worker up, without leaving the state,
+        // so that nothing is saved for them.
+        let mut tick = context.config.tick_interval.map(|period| {
+            let mut tick = tokio::time::interval_at(self.start + period, period);
+            tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+            tick
+        });
+        let checkpoint = wait_checkpoint(checkpoint);
+        tokio::pin!(checkpoint);
+
+        loop {
+            tokio::select! {
+                () = tokio::time::sleep_until(self.deadline) => break self.handle_tick(context).await,
+                _ = wait_tick(tick.as_mut()) => {
+                    tracing::trace!(session = context.session, "Ticked");
+                }
+                idle = poll_idle(threshold, &*detector, context.idle_poll) => {
+                    context.idle_poll = Instant::now() + IDLE_POLL_INTERVAL;
+                    if let Some(idle) = idle {
+                        break self.handle_idle(idle);
+                    }
+                }
+                () = &mut checkpoint => {
+                    notify_checkpoint(context, self.stage, self.concentrations, self.cycles).await;
+                    break self.into();
+                }
+                Some(command) = context.commands.recv() => break match command {
+                    Command::Pause => self.handle_pause(context),
+                    Command::Resume => self.handle_resume(),
+                    Command::Skip { notify } => self.handle_skip(context, notify).await,
+                    Command::Reset => self.handle_reset(context).await,
+                    Command::Adjust {
+                        delta_secs,
+                        responder,
+                    } => self.handle_adjust(context, delta_secs, responder).await,
+                    Command::SetDuration { total } => {
+                        self.handle_set_duration(context, total).await
+                    }
+                    Command::SkipTo { stage, responder } => {
+                        self.handle_skip_to(context, stage, responder).await
+                    }
+                    Command::Query { responder } => {
+                        self.handle_query(context, responder);
+                        self.into()
+                    }
+                    Command::Ping { responder } => {
+                        let _ = responder.send(TimerStatus::Running);
+                        self.into()
+                    }
+                    Command::Schedule { responder } => {
+                        let schedule =
+                            context
+                                .config
+                                .schedule(self.stage, self.concentrations, self.remaining());
+                        let _ = responder.send(schedule);
+                        self.into()
+                    }
+                    Command::Stats { responder } => {
+                        let _ = responder.send(stats(context));
+                        self.into()
+                    }
+                    Command::AutoPause { trigger } => self.handle_auto_pause(context, trigger),
+                    Command::AutoResume { .. } => self.into(),
+                    Command::Reload { config } => {
+                        handle_reload(context, self.stage, config);
+                        self.into()
+                    }
+                    Command::Mute => {
+                        context.muted = true;
+                        self.into()
+                    }
+                    Command::Unmute => {
+                        context.muted = false;
+                        self.into()
+                    }
+                    Command::Subscribe { sender } => {
+                        subscribe(context, sender, self.event(context));
+                        self.into()
+                    }
+                    Command::Stop {
+                        keep_progress,
+                        responder,
+                    } => handle_stop(keep_progress, responder, self.stage, self.cycles),
+                },
+                else => break self.into(),
+            }
         }
     }
 }
 
 impl RunningState {
     async fn handle_tick(self, context: &mut WorkerContext) -> WorkerStateInner {
-        let notification = context.config.notification(self.stage);
-
-        if let Err(err) = context.notifier.notify(notification).await {
-            tracing::error!(err = %err);
-        }
+        notify_stage(context, self.stage, self.concentrations, self.cycles).await;
+        record_stage(context, self.stage, *self.total.inner()).await;
 
-        let stage = self.stage.next();
-        let duration = *context.config.duration(stage).inner();
-        let (start, timer) = spawn_timer(duration).await;
+        let (stage, concentrations) = context.config.advance(self.stage, self.concentrations);
+        let cycles = context.config.cycles_after(self.stage, self.cycles);
+        let duration = context.config.stage_duration(stage, concentrations).clone();
+        let (start, deadline) = start_timer(*duration.inner());
 
         RunningState {
             start,
             past: Duration::from_secs(0),
-            timer,
+            total: duration,
+            deadline,
             stage,
+            concentrations,
+            cycles,
         }
         .into()
     }
@@ -118,222 +440,1791 @@ impl RunningState {
         self.into()
     }
 
-    fn handle_pause(self) -> WorkerStateInner {
+    fn handle_pause(self, context: &WorkerContext) -> WorkerStateInner {
         PausedState {
             past: self.past + (Instant::now() - self.start),
+            total: self.total,
             stage: self.stage,
+            concentrations: self.concentrations,
+            cycles: self.cycles,
+            trigger: None,
+            resume_at: resume_at(context),
         }
         .into()
     }
 
-    async fn handle_skip(self, context: &mut WorkerContext) -> WorkerStateInner {
-        let stage = self.stage.next();
-        let duration = *context.config.duration(stage).inner();
-        let (start, timer) = spawn_timer(duration).await;
+    fn handle_auto_pause(
+        self,
+        context: &mut WorkerContext,
+        trigger: AutoPauseTrigger,
+    ) -> WorkerStateInner {
+        if !context.config.auto_pause_enabled(trigger) {
+            return self.into();
+        }
+
+        PausedState {
+            past: self.past + (Instant::now() - self.start),
+            total: self.total,
+            stage: self.stage,
+            concentrations: self.concentrations,
+            cycles: self.cycles,
+            trigger: Some(trigger),
+            resume_at: None,
+        }
+        .into()
+    }
+
+    /// Pause because the user has been idle for `idle`. That time is not
+    /// counted, as nobody has concentrated during it.
+    fn handle_idle(self, idle: Duration) -> WorkerStateInner {
+        let elapsed = Instant::now() - self.start;
+
+        PausedState {
+            past: self.past + elapsed.saturating_sub(idle),
+            total: self.total,
+            stage: self.stage,
+            concentrations: self.concentrations,
+            cycles: self.cycles,
+            trigger: Some(AutoPauseTrigger::Idle),
+            resume_at: None,
+        }
+        .into()
+    }
+
+    async fn handle_skip(self, context: &mut WorkerContext, notify: bool) -> WorkerStateInner {
+        if notify {
+            notify_stage(context, self.stage, self.concentrations, self.cycles).await;
+        }
+
+        let (stage, concentrations) = context.config.advance(self.stage, self.concentrations);
+        let cycles = context.config.cycles_after(self.stage, self.cycles);
+        let duration = context.config.stage_duration(stage, concentrations).clone();
+        let (start, deadline) = start_timer(*duration.inner());
 
         RunningState {
             start,
             past: Duration::from_secs(0),
-            timer,
+            total: duration,
+            deadline,
             stage,
+            concentrations,
+            cycles,
         }
         .into()
     }
 
-    fn handle_query(
+    async fn handle_reset(self, context: &mut WorkerContext) -> WorkerStateInner {
+        ReadyState.start(context).await
+    }
+
+    async fn handle_skip_to(
+        self,
+        context: &mut WorkerContext,
+        kind: StageState,
+        responder: Sender<Result<(), ChangeStageError>>,
+    ) -> WorkerStateInner {
+        match skip_to(context, self.stage, self.concentrations, self.cycles, kind).await {
+            Ok(state) => {
+                let _ = responder.send(Ok(()));
+                state.into()
+            }
+            Err(err) => {
+                let _ = responder.send(Err(err));
+                self.into()
+            }
+        }
+    }
+
+    /// Lengthen or shorten the current stage by `delta_secs`, and tell the
+    /// requester whether it is done. The stage ends immediately if no time
+    /// remains.
+    async fn handle_adjust(
+        self,
+        context: &mut WorkerContext,
+        delta_secs: i64,
+        responder: Sender<Result<(), ChangeStageError>>,
+    ) -> WorkerStateInner {
+        let past = self.past + (Instant::now() - self.start);
+        match adjust_total(&self.total, past, delta_secs) {
+            Ok(total) => {
+                let _ = responder.send(Ok(()));
+                self.change_total(context, past, total).await
+            }
+            Err(err) => {
+                let _ = responder.send(Err(err));
+                self.into()
+            }
+        }
+    }
+
+    async fn handle_set_duration(
+        self,
+        context: &mut WorkerContext,
+        total: StageDuration,
+    ) -> WorkerStateInner {
+        let past = self.past + (Instant::now() - self.start);
+        self.change_total(context, past, Some(total)).await
+    }
+
+    /// Let the current stage, of which `past` has gone by, last `total`. It
+    /// ends at once if `total` is `None` or not beyond `past`.
+    async fn change_total(
         self,
         context: &mut WorkerContext,
-        responder: Sender<QueryResponse>,
+        past: Duration,
+        total: Option<StageDuration>,
     ) -> WorkerStateInner {
+        let Some(total) = total.filter(|total| *total.inner() > past) else {
+            return self.handle_tick(context).await;
+        };
+
+        let (start, deadline) = start_timer(*total.inner() - past);
+        RunningState {
+            start,
+            past,
+            total,
+            deadline,
+            ..self
+        }
+        .into()
+    }
+
+    fn snapshot(&self) -> WorkerSnapshot {
+        WorkerSnapshot {
+            stage: self.stage,
+            concentrations: self.concentrations,
+            cycles: self.cycles,
+            past: self.past + (Instant::now() - self.start),
+            total: *self.total.inner(),
+            paused: false,
+            saved_at: SystemTime::now(),
+        }
+    }
+
+    /// Get how long remains of the current stage, which is never negative.
+    fn remaining(&self) -> Duration {
+        let past = self.past + (Instant::now() - self.start);
+        self.total.remaining_from(past)
+    }
+
+    fn event(&self, context: &WorkerContext) -> TimerEvent {
+        TimerEvent {
+            current: TimerStatus::Running,
+            stage: context.config.stage(self.stage).name().to_owned(),
+            kind: context.config.stage(self.stage).kind(),
+            remaining: self.remaining(),
+        }
+    }
+
+    /// Reply a snapshot of the current state. Querying never mutates the
+    /// state, so successive queries observe a non-decreasing `past` within
+    /// one stage.
+    fn handle_query(&self, context: &WorkerContext, responder: Sender<QueryResponse>) {
         let _ = responder.send(QueryResponse {
-            current: "Running".to_owned(),
-            total: *context.config.duration(self.stage).inner(),
+            current: TimerStatus::Running,
+            total: *self.total.inner(),
             past: self.past + (Instant::now() - self.start),
-            stage: self.stage,
+            remaining: self.remaining(),
+            stage: context.config.stage(self.stage).name().to_owned(),
+            kind: context.config.stage(self.stage).kind(),
+            long_break: context
+                .config
+                .next_break_long(self.stage, self.concentrations),
+            completed_cycles: self.cycles,
+            muted: context.muted,
         });
-
-        self.into()
     }
 }
 
 /// A state which indicates that the [`WorkerRoutine`] is paused. The time duration
-/// goes by in this stage is stored for future resuming.
+/// goes by in this stage is stored for future resuming. `trigger` is `None` if
+/// it is paused manually. The timer resumes by itself at `resume_at` if it is
+/// set.
 #[derive(Debug)]
 struct PausedState {
     past: Duration,
-    stage: StageState,
+    total: StageDuration,
+    stage: usize,
+    concentrations: u32,
+    cycles: u32,
+    trigger: Option<AutoPauseTrigger>,
+    resume_at: Option<Instant>,
 }
 
 impl StateRun for PausedState {
     async fn run(self, context: &mut WorkerContext) -> WorkerStateInner {
-        match context.commands.recv().await {
-            Some(Command::Pause) => self.handle_pause(),
+        let idle = self.trigger == Some(AutoPauseTrigger::Idle);
+        let detector = Arc::clone(&context.idle_detector);
+        let command = loop {
+            tokio::select! {
+                command = context.commands.recv() => break command,
+                active = poll_active(&*detector, context.idle_poll), if idle => {
+                    context.idle_poll = Instant::now() + IDLE_POLL_INTERVAL;
+                    if active {
+                        return self.handle_resume(context).await;
+                    }
+                }
+                () = wait_until(self.resume_at) => {
+                    tracing::info!(session = context.session, "Resumed after pausing for too long");
+                    return self.handle_resume(context).await;
+                }
+            }
+        };
+
+        match command {
+            Some(Command::Pause) => self.handle_pause(context),
             Some(Command::Resume) => self.handle_resume(context).await,
-            Some(Command::Skip) => self.handle_skip(context).await,
-            Some(Command::Query { responder }) => self.handle_query(context, responder),
+            Some(Command::Skip { notify }) => self.handle_skip(context, notify).await,
+            Some(Command::Reset) => self.handle_reset(context).await,
+            Some(Command::Adjust {
+                delta_secs,
+                responder,
+            }) => self.handle_adjust(context, delta_secs, responder).await,
+            Some(Command::SetDuration { total }) => self.handle_set_duration(context, total).await,
+            Some(Command::SkipTo { stage, responder }) => {
+                self.handle_skip_to(context, stage, responder).await
+            }
+            Some(Command::Query { responder }) => {
+                self.handle_query(context, responder);
+                self.into()
+            }
+            Some(Command::Ping { responder }) => {
+                let _ = responder.send(TimerStatus::Paused);
+                self.into()
+            }
+            Some(Command::Schedule { responder }) => {
+                let schedule =
+                    context
+                        .config
+                        .schedule(self.stage, self.concentrations, self.remaining());
+                let _ = responder.send(schedule);
+                self.into()
+            }
+            Some(Command::Stats { responder }) => {
+                let _ = responder.send(stats(context));
+                self.into()
+            }
+            Some(Command::AutoPause { .. }) => self.into(),
+            Some(Command::AutoResume { trigger }) => {
+                self.handle_auto_resume(context, trigger).await
+            }
+            Some(Command::Reload { config }) => {
+                handle_reload(context, self.stage, config);
+                self.into()
+            }
+            Some(Command::Mute) => {
+                context.muted = true;
+                self.into()
+            }
+            Some(Command::Unmute) => {
+                context.muted = false;
+                self.into()
+            }
+            Some(Command::Subscribe { sender }) => {
+                subscribe(context, sender, self.event(context));
+                self.into()
+            }
+            Some(Command::Stop {
+                keep_progress,
+                responder,
+            }) => handle_stop(keep_progress, responder, self.stage, self.cycles),
             None => self.into(),
         }
     }
 }
 
 impl PausedState {
-    fn handle_pause(self) -> WorkerStateInner {
-        self.into()
+    fn handle_pause(self, context: &WorkerContext) -> WorkerStateInner {
+        // A manual pause takes over an automatic one, so that the trigger can
+        // no longer resume the timer.
+        let resume_at = match self.trigger {
+            Some(_) => resume_at(context),
+            None => self.resume_at,
+        };
+        PausedState {
+            trigger: None,
+            resume_at,
+            ..self
+        }
+        .into()
+    }
+
+    async fn handle_auto_resume(
+        self,
+        context: &mut WorkerContext,
+        trigger: AutoPauseTrigger,
+    ) -> WorkerStateInner {
+        if self.trigger == Some(trigger) {
+            self.handle_resume(context).await
+        } else {
+            self.into()
+        }
     }
 
-    async fn handle_resume(self, context: &mut WorkerContext) -> WorkerStateInner {
-        let duration = *context.config.duration(self.stage).inner();
-        let (start, timer) = spawn_timer(duration - self.past).await;
+    async fn handle_resume(self, _context: &mut WorkerContext) -> WorkerStateInner {
+        let (start, deadline) = start_timer(self.remaining());
         RunningState {
             start,
             past: self.past,
-            timer,
+            total: self.total,
+            deadline,
             stage: self.stage,
+            concentrations: self.concentrations,
+            cycles: self.cycles,
         }
         .into()
     }
 
-    async fn handle_skip(self, context: &mut WorkerContext) -> WorkerStateInner {
-        let stage = self.stage.next();
-        let duration = *context.config.duration(stage).inner();
-        let (start, timer) = spawn_timer(duration).await;
+    async fn handle_skip(self, context: &mut WorkerContext, notify: bool) -> WorkerStateInner {
+        if notify {
+            notify_stage(context, self.stage, self.concentrations, self.cycles).await;
+        }
+
+        let (stage, concentrations) = context.config.advance(self.stage, self.concentrations);
+        let cycles = context.config.cycles_after(self.stage, self.cycles);
+        let duration = context.config.stage_duration(stage, concentrations).clone();
+        let (start, deadline) = start_timer(*duration.inner());
         RunningState {
             start,
             past: Duration::from_secs(0),
-            timer,
+            total: duration,
+            deadline,
             stage,
+            concentrations,
+            cycles,
         }
         .into()
     }
 
-    fn handle_query(
+    /// Restart from the initial stage. The timer runs immediately, even if it
+    /// was paused.
+    async fn handle_reset(self, context: &mut WorkerContext) -> WorkerStateInner {
+        ReadyState.start(context).await
+    }
+
+    /// Jump to the next stage of `kind`. The timer runs immediately, even if
+    /// it was paused.
+    async fn handle_skip_to(
         self,
         context: &mut WorkerContext,
-        responder: Sender<QueryResponse>,
+        kind: StageState,
+        responder: Sender<Result<(), ChangeStageError>>,
     ) -> WorkerStateInner {
-        let _ = responder.send(QueryResponse {
-            current: "Paused".to_owned(),
-            total: *context.config.duration(self.stage).inner(),
-            past: self.past,
-            stage: self.stage,
-        });
-        self.into()
+        match skip_to(context, self.stage, self.concentrations, self.cycles, kind).await {
+            Ok(state) => {
+                let _ = responder.send(Ok(()));
+                state.into()
+            }
+            Err(err) => {
+                let _ = responder.send(Err(err));
+                self.into()
+            }
+        }
     }
-}
 
-async fn spawn_timer(duration: Duration) -> (Instant, Interval) {
-    let mut timer = tokio::time::interval(duration);
-    let start = timer.tick().await;
-    (start, timer)
-}
+    /// Lengthen or shorten the current stage by `delta_secs`, and tell the
+    /// requester whether it is done. If no time remains, the stage ends as if
+    /// the timer ticked, and the next stage stays paused.
+    async fn handle_adjust(
+        self,
+        context: &mut WorkerContext,
+        delta_secs: i64,
+        responder: Sender<Result<(), ChangeStageError>>,
+    ) -> WorkerStateInner {
+        match adjust_total(&self.total, self.past, delta_secs) {
+            Ok(total) => {
+                let _ = responder.send(Ok(()));
+                self.change_total(context, total).await
+            }
+            Err(err) => {
+                let _ = responder.send(Err(err));
+                self.into()
+            }
+        }
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Make the current stage last `total` in all, which ends it like
+    /// [`handle_adjust`] if no time remains.
+    ///
+    /// [`handle_adjust`]: Self::handle_adjust
+    async fn handle_set_duration(
+        self,
+        context: &mut WorkerContext,
+        total: StageDuration,
+    ) -> WorkerStateInner {
+        self.change_total(context, Some(total)).await
+    }
 
-    use std::sync::{Arc, Mutex};
+    /// Make the current stage last `total` in all. It ends at once if `total`
+    /// is `None` or not beyond the past duration.
+    async fn change_total(
+        self,
+        context: &mut WorkerContext,
+        total: Option<StageDuration>,
+    ) -> WorkerStateInner {
+        if let Some(total) = total.filter(|total| *total.inner() > self.past) {
+            return PausedState { total, ..self }.into();
+        }
 
-    use tokio::sync::mpsc::Sender;
+        notify_stage(context, self.stage, self.concentrations, self.cycles).await;
 
-    use crate::domain::daemon::outbound::{NotifyError, NotifyPort, NotifyRequest};
-    use crate::domain::daemon::worker::routine::WorkerConfig;
-    use crate::domain::entity::{NotificationMessage, StageDuration};
+        let (stage, concentrations) = context.config.advance(self.stage, self.concentrations);
+        let cycles = context.config.cycles_after(self.stage, self.cycles);
+        PausedState {
+            past: Duration::from_secs(0),
+            total: context.config.stage_duration(stage, concentrations).clone(),
+            stage,
+            concentrations,
+            cycles,
+            ..self
+        }
+        .into()
+    }
 
-    #[tokio::test(start_paused = true)]
-    async fn timer_operation() {
-        let duration = Duration::from_secs(3);
-        let (start, mut timer) = spawn_timer(duration.clone()).await;
-        let now = timer.tick().await;
-        assert_eq!(now - start, duration);
+    fn snapshot(&self) -> WorkerSnapshot {
+        WorkerSnapshot {
+            stage: self.stage,
+            concentrations: self.concentrations,
+            cycles: self.cycles,
+            past: self.past,
+            total: *self.total.inner(),
+            paused: true,
+            saved_at: SystemTime::now(),
+        }
     }
 
-    #[tokio::test(start_paused = true)]
-    async fn ready_state_run() {
-        let (_, mut context, _) = new_worker_context();
-        let now = Instant::now();
-        let state = ReadyState;
-        let state = state.run(&mut context).await;
+    /// Get how long remains of the current stage, which is never negative.
+    fn remaining(&self) -> Duration {
+        self.total.remaining_from(self.past)
+    }
 
-        match state {
-            WorkerStateInner::Running(state) => {
-                assert_eq!(state.start, now);
-                assert_eq!(state.past, Duration::from_secs(0));
-                assert_eq!(state.stage, StageState::Preparation);
-            }
-            _ => unreachable!(),
+    fn event(&self, context: &WorkerContext) -> TimerEvent {
+        TimerEvent {
+            current: TimerStatus::Paused,
+            stage: context.config.stage(self.stage).name().to_owned(),
+            kind: context.config.stage(self.stage).kind(),
+            remaining: self.remaining(),
         }
     }
 
-    #[tokio::test(start_paused = true)]
-    async fn running_state_handle_tick() {
-        let (_, mut context, notifier) = new_worker_context();
-        let (start, state) = new_running_state().await;
-        let state = state.handle_tick(&mut context).await;
+    /// Reply a snapshot of the current state without mutating it.
+    fn handle_query(&self, context: &WorkerContext, responder: Sender<QueryResponse>) {
+        let _ = responder.send(QueryResponse {
+            current: TimerStatus::Paused,
+            total: *self.total.inner(),
+            past: self.past,
+            remaining: self.remaining(),
+            stage: context.config.stage(self.stage).name().to_owned(),
+            kind: context.config.stage(self.stage).kind(),
+            long_break: context
+                .config
+                .next_break_long(self.stage, self.concentrations),
+            completed_cycles: self.cycles,
+            muted: context.muted,
+        });
+    }
+}
 
-        match state {
-            WorkerStateInner::Running(state) => {
-                assert_eq!(state.start, start);
-                assert_eq!(state.past, Duration::from_secs(0));
-                assert_eq!(state.stage, StageState::Concentration);
+/// A state which indicates that the [`WorkerRoutine`] has stopped at the stage
+/// at `stage`, after `cycles` rounds of the sequence. It still answers queries
+/// until every requester is gone. The saved progress is kept for the next
+/// worker if `keep_progress` is set.
+#[derive(Debug)]
+struct StoppedState {
+    keep_progress: bool,
+    stage: usize,
+    cycles: u32,
+}
+
+impl StateRun for StoppedState {
+    /// Answer the commands which ask for something until the channel closes,
+    /// so that no requester waits for a reply forever. Any other command is
+    /// ignored, since nothing else is going to happen.
+    async fn run(self, context: &mut WorkerContext) -> WorkerStateInner {
+        while let Some(command) = context.commands.recv().await {
+            match command {
+                Command::Query { responder } => self.handle_query(context, responder),
+                Command::Ping { responder } => {
+                    let _ = responder.send(TimerStatus::Stopped);
+                }
+                Command::Schedule { responder } => {
+                    let _ = responder.send(Vec::new());
+                }
+                Command::Stats { responder } => {
+                    let _ = responder.send(stats(context));
+                }
+                Command::Stop { responder, .. } => {
+                    let _ = responder.send(());
+                }
+                // The subscription ends right after the current state.
+                Command::Subscribe { sender } => {
+                    let _ = sender.send(self.event(context));
+                }
+                command => {
+                    tracing::debug!(
+                        session = context.session,
+                        ?command,
+                        "Ignored a command after stopping"
+                    );
+                }
             }
-            _ => unreachable!(),
         }
 
-        let request = notifier.lock().unwrap().first().unwrap().clone();
-        assert_eq!(request.summary, "Preparation");
-        assert_eq!(request.body, None);
+        self.into()
     }
+}
 
-    #[tokio::test(start_paused = true)]
-    async fn running_state_handle_pause() {
-        let (_, _, notifier) = new_worker_context();
-        let (_, state) = new_running_state().await;
-
-        tokio::time::sleep(Duration::from_secs(1)).await;
-        let state = state.handle_pause();
+impl StoppedState {
+    /// Report the stage at which the timer stopped, with nothing left of it.
+    fn handle_query(&self, context: &WorkerContext, responder: Sender<QueryResponse>) {
+        let _ = responder.send(QueryResponse {
+            current: TimerStatus::Stopped,
+            total: Duration::from_secs(0),
+            past: Duration::from_secs(0),
+            remaining: Duration::from_secs(0),
+            stage: context.config.stage(self.stage).name().to_owned(),
+            kind: context.config.stage(self.stage).kind(),
+            long_break: false,
+            completed_cycles: self.cycles,
+            muted: context.muted,
+        });
+    }
 
-        match state {
-            WorkerStateInner::Paused(state) => {
-                assert_eq!(state.past, Duration::from_secs(1));
-                assert_eq!(state.stage, StageState::Preparation);
-            }
-            _ => unreachable!(),
+    fn event(&self, context: &WorkerContext) -> TimerEvent {
+        TimerEvent {
+            current: TimerStatus::Stopped,
+            stage: context.config.stage(self.stage).name().to_owned(),
+            kind: context.config.stage(self.stage).kind(),
+            remaining: Duration::from_secs(0),
         }
+    }
+}
 
-        assert!(notifier.lock().unwrap().is_empty());
+/// Stop the timer in whichever state it is, at the stage at `stage` after
+/// `cycles` rounds, and tell the requester that the worker has stopped.
+fn handle_stop(
+    keep_progress: bool,
+    responder: Sender<()>,
+    stage: usize,
+    cycles: u32,
+) -> WorkerStateInner {
+    let _ = responder.send(());
+    StoppedState {
+        keep_progress,
+        stage,
+        cycles,
+    }
+    .into()
+}
+
+/// Replace the configuration while the timer stays in the stage at `stage`,
+/// whose length is already fixed. The old configuration is kept if `config`
+/// no longer has that stage.
+fn handle_reload(context: &mut WorkerContext, stage: usize, config: WorkerConfig) {
+    if config.stages.get(stage).is_none() {
+        tracing::warn!(
+            session = context.session,
+            stage,
+            "Kept the old configuration without the current stage"
+        );
+        return;
+    }
+
+    context.config = config;
+    tracing::info!(session = context.session, "Reloaded configuration");
+}
+
+/// Start a fresh timer for the next stage of `kind` after the stage at
+/// `stage`, without any notification.
+///
+/// # Errors
+///
+/// This function will return an error if the sequence never comes to such a
+/// stage again.
+async fn skip_to(
+    context: &WorkerContext,
+    stage: usize,
+    concentrations: u32,
+    cycles: u32,
+    kind: StageState,
+) -> Result<RunningState, ChangeStageError> {
+    let (stage, concentrations, cycles) = context
+        .config
+        .advance_to(stage, concentrations, cycles, kind)
+        .context(NoStageSnafu { kind })?;
+
+    let duration = context.config.stage_duration(stage, concentrations).clone();
+    let (start, deadline) = start_timer(*duration.inner());
+    Ok(RunningState {
+        start,
+        past: Duration::from_secs(0),
+        total: duration,
+        deadline,
+        stage,
+        concentrations,
+        cycles,
+    })
+}
+
+/// Apply `delta_secs` to the `total` duration of a stage, of which `past` has
+/// gone by. Return `None` if no time would remain.
+///
+/// # Errors
+///
+/// This function will return an error if the stage would become longer than
+/// [`StageDuration::MAX`].
+fn adjust_total(
+    total: &StageDuration,
+    past: Duration,
+    delta_secs: i64,
+) -> Result<Option<StageDuration>, ChangeStageError> {
+    let delta = Duration::from_secs(delta_secs.unsigned_abs());
+
+    if delta_secs >= 0 {
+        return total.checked_extend(delta).map(Some).context(LengthSnafu);
+    }
+
+    match total.inner().checked_sub(delta) {
+        Some(total) if total > past => StageDuration::try_from_duration(total)
+            .map(Some)
+            .context(LengthSnafu),
+        _ => Ok(None),
+    }
+}
+
+/// Check the idle time of the user at `at`, and return it if the user has
+/// been idle for at least `threshold`. Never complete if `threshold` is
+/// `None`.
+async fn poll_idle(
+    threshold: Option<Duration>,
+    detector: &dyn IdleDetector,
+    at: Instant,
+) -> Option<Duration> {
+    let Some(threshold) = threshold else {
+        return std::future::pending().await;
+    };
+
+    tokio::time::sleep_until(at).await;
+    match detector.idle_time().await {
+        Ok(idle) => Some(idle).filter(|&idle| idle >= threshold),
+        Err(err) => {
+            tracing::debug!(err = %err, "Could not detect the idle time");
+            None
+        }
+    }
+}
+
+/// Check at `at` whether the user has become active again.
+async fn poll_active(detector: &dyn IdleDetector, at: Instant) -> bool {
+    tokio::time::sleep_until(at).await;
+    match detector.idle_time().await {
+        Ok(idle) => idle < IDLE_POLL_INTERVAL,
+        Err(err) => {
+            tracing::debug!(err = %err, "Could not detect the idle time");
+            false
+        }
+    }
+}
+
+/// Get how long it is from `past` until the next checkpoint of the stage at
+/// `stage`, which lasts for `total`. Checkpoints which are not within the
+/// stage are ignored. Return `None` if no checkpoint is left.
+fn next_checkpoint(
+    config: &WorkerConfig,
+    stage: usize,
+    total: Duration,
+    past: Duration,
+) -> Option<Duration> {
+    let stage = config.stage(stage);
+    stage.checkpoint_notification()?;
+    stage
+        .checkpoints()
+        .iter()
+        .filter(|&&offset| !offset.is_zero() && offset < total)
+        .map(|&offset| total - offset)
+        .filter(|&point| point > past)
+        .min()
+        .map(|point| point - past)
+}
+
+/// Get when a manual pause starting now ends by itself, or `None` if it lasts
+/// until the timer is resumed.
+fn resume_at(context: &WorkerContext) -> Option<Instant> {
+    context
+        .config
+        .max_pause
+        .map(|max_pause| Instant::now() + max_pause)
+}
+
+/// Wait until `deadline`. Never complete if it is `None`.
+async fn wait_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Wait for `checkpoint` to pass. Never complete if it is `None`.
+async fn wait_checkpoint(checkpoint: Option<Duration>) {
+    match checkpoint {
+        Some(checkpoint) => tokio::time::sleep(checkpoint).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Emit the checkpoint notification of the stage at `stage`, unless
+/// notifications are muted.
+async fn notify_checkpoint(
+    context: &WorkerContext,
+    stage: usize,
+    concentrations: u32,
+    cycles: u32,
+) {
+    if context.muted {
+        return;
+    }
+    let Some(notification) = context.config.stage(stage).checkpoint_notification() else {
+        return;
+    };
+    let notification =
+        with_stage_variables(&context.config, notification, stage, concentrations, cycles);
+    let name = context.config.stage(stage).name();
+
+    if let Err(err) = context.notifier.notify(name, &notification).await {
+        tracing::error!(err = %err);
+    }
+}
+
+/// Count the stage at `stage`, which has just ended after lasting for
+/// `duration`, and append it to the stage log.
+async fn record_stage(context: &mut WorkerContext, stage: usize, duration: Duration) {
+    let finished_at = SystemTime::now();
+    let stage = context.config.stage(stage);
+    let kind = stage.kind();
+    context
+        .stats
+        .record(kind, duration, local_time::local_day(finished_at));
+
+    let record = StageRecord {
+        session: context.session.clone(),
+        stage: stage.name().to_owned(),
+        kind,
+        duration,
+        finished_at,
+    };
+    if let Err(err) = context.stage_log.append(&record).await {
+        tracing::warn!(session = context.session, err = %err, "Could not log the stage");
+    }
+}
+
+/// Add a subscriber, which gets `event` describing the current state first.
+fn subscribe(context: &mut WorkerContext, sender: BroadcastSender<TimerEvent>, event: TimerEvent) {
+    if sender.send(event).is_ok() {
+        context.subscribers.push(sender);
+    }
+}
+
+/// Send `event` to every subscriber. A subscriber which has gone is dropped,
+/// while a slow one only misses the events it could not keep up with, so that
+/// the worker never waits for any of them.
+fn publish(context: &mut WorkerContext, event: TimerEvent) {
+    context
+        .subscribers
+        .retain(|sender| sender.send(event.clone()).is_ok());
+}
+
+/// Get the statistics of the completed stages as of now.
+fn stats(context: &WorkerContext) -> TimerStats {
+    context.stats.get(local_time::local_day(SystemTime::now()))
+}
+
+/// Emit the notification corresponding to the stage which has just ended,
+/// unless notifications are muted.
+async fn notify_stage(context: &WorkerContext, stage: usize, concentrations: u32, cycles: u32) {
+    if context.muted {
+        return;
+    }
+    let name = context.config.stage(stage).name();
+    let notification = context.config.notification(stage);
+    let notification =
+        with_stage_variables(&context.config, notification, stage, concentrations, cycles);
+
+    if let Err(err) = context.notifier.notify(name, &notification).await {
+        tracing::error!(err = %err);
+    }
+}
+
+/// Fill `{next_stage}` and `{next_duration}` in `notification` with the stage
+/// following the one at `stage`, in which `concentrations` concentration
+/// stages have been completed since the last long break. `{cycle}` is filled
+/// with the number of the current round, counting from 1 after `cycles`
+/// completed ones.
+fn with_stage_variables(
+    config: &WorkerConfig,
+    notification: &NotificationMessage,
+    stage: usize,
+    concentrations: u32,
+    cycles: u32,
+) -> NotificationMessage {
+    let (next, concentrations) = config.advance(stage, concentrations);
+    let duration = *config.stage_duration(next, concentrations).inner();
+    notification.with_variables(&[
+        ("next_stage", config.stage(next).name()),
+        ("next_duration", &format_clock(duration)),
+        ("cycle", &cycles.saturating_add(1).to_string()),
+    ])
+}
+
+/// Start timing a stage which lasts for `duration` from now, and return when
+/// it starts along with when it ends.
+fn start_timer(duration: Duration) -> (Instant, Instant) {
+    let start = Instant::now();
+    (start, start + duration)
+}
+
+/// Wait for the next tick of `tick`, or forever if there is none.
+async fn wait_tick(tick: Option<&mut Interval>) -> Instant {
+    match tick {
+        Some(tick) => tick.tick().await,
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::future;
+    use std::sync::{Arc, Mutex};
+
+    use tokio::sync::mpsc::Sender;
+
+    use crate::domain::daemon::outbound::StateStoreError;
+    use crate::domain::daemon::outbound::{MockIdleDetector, MockStageLog, MockStateStore};
+    use crate::domain::daemon::outbound::{NotifyError, NotifyPort, NotifyRequest};
+    use crate::domain::daemon::worker::routine::WorkerConfigBuilder;
+    use crate::domain::daemon::worker::stats::WorkerStats;
+    use crate::domain::entity::duration::TryNewStageDurationError;
+    use crate::domain::entity::{NotificationMessage, Stage, StageDuration};
+    use crate::domain::entity::{ScheduledStage, StageSequence, StageState};
+
+    /// Indices of the stages in the sequence of [`new_worker_context`].
+    const PREPARATION: usize = 0;
+    const CONCENTRATION: usize = 1;
+    const RELAXATION: usize = 2;
+
+    #[tokio::test(start_paused = true)]
+    async fn timer_operation() {
+        let duration = Duration::from_secs(3);
+        let (start, deadline) = start_timer(duration);
+        assert_eq!(start, Instant::now());
+        assert_eq!(deadline - start, duration);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn ready_state_run() {
+        let (_, mut context, _) = new_worker_context();
+        let now = Instant::now();
+        let state = ReadyState;
+        let state = state.run(&mut context).await;
+
+        match state {
+            WorkerStateInner::Running(state) => {
+                assert_eq!(state.start, now);
+                assert_eq!(state.past, Duration::from_secs(0));
+                assert_eq!(state.stage, PREPARATION);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn ready_state_run_initial_stage() {
+        let (_, mut context, _) = new_worker_context();
+        context.config.initial_stage = CONCENTRATION;
+
+        match ReadyState.run(&mut context).await {
+            WorkerStateInner::Running(state) => {
+                assert_eq!(state.stage, CONCENTRATION);
+                assert_eq!(*state.total.inner(), Duration::from_secs(20));
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn ready_state_run_start_paused() {
+        let (sender, mut context, _) = new_worker_context();
+        context.config.start_paused = true;
+        let state = ReadyState.run(&mut context).await;
+        tokio::time::advance(Duration::from_secs(10)).await;
+
+        let (responder, receiver) = tokio::sync::oneshot::channel();
+        sender.send(Command::Query { responder }).await.unwrap();
+        let state = match state {
+            WorkerStateInner::Paused(state) => state.run(&mut context).await,
+            _ => unreachable!(),
+        };
+
+        let response = receiver.await.unwrap();
+        assert_eq!(response.current, TimerStatus::Paused);
+        assert_eq!(response.kind, StageState::Preparation);
+        assert_eq!(response.past, Duration::from_secs(0));
+        assert_eq!(response.total, Duration::from_secs(5));
+        assert!(matches!(state, WorkerStateInner::Paused(_)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn ready_state_run_restore_running() {
+        let (_, mut context, _) = new_worker_context();
+        context.store = new_store(|| Ok(Some(new_snapshot(5, false, 3))));
+        let state = ReadyState.run(&mut context).await;
+
+        match state {
+            WorkerStateInner::Running(state) => {
+                assert_eq!(state.past.as_secs(), 8);
+                assert_eq!(*state.total.inner(), Duration::from_secs(20));
+                assert_eq!(state.stage, CONCENTRATION);
+                assert_eq!(state.concentrations, 1);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn ready_state_run_restore_paused() {
+        let (_, mut context, _) = new_worker_context();
+        context.store = new_store(|| Ok(Some(new_snapshot(5, true, 3))));
+        let state = ReadyState.run(&mut context).await;
+
+        match state {
+            WorkerStateInner::Paused(state) => {
+                assert_eq!(state.past, Duration::from_secs(5));
+                assert_eq!(state.stage, CONCENTRATION);
+                assert_eq!(state.trigger, None);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn ready_state_run_restore_stale() {
+        let (_, mut context, _) = new_worker_context();
+        context.store = new_store(|| Ok(Some(new_snapshot(5, false, 60))));
+        let state = ReadyState.run(&mut context).await;
+
+        match state {
+            WorkerStateInner::Running(state) => {
+                assert_eq!(state.past, Duration::from_secs(0));
+                assert_eq!(state.stage, PREPARATION);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn ready_state_run_restore_unknown_stage() {
+        let (_, mut context, _) = new_worker_context();
+        context.store = new_store(|| {
+            Ok(Some(WorkerSnapshot {
+                stage: 3,
+                ..new_snapshot(5, true, 0)
+            }))
+        });
+        let state = ReadyState.run(&mut context).await;
+
+        match state {
+            WorkerStateInner::Running(state) => {
+                assert_eq!(state.past, Duration::from_secs(0));
+                assert_eq!(state.stage, PREPARATION);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn ready_state_run_restore_corrupt() {
+        let (_, mut context, _) = new_worker_context();
+        context.store = new_store(|| {
+            Err(StateStoreError::Unknown {
+                message: "corrupt".to_owned(),
+                source: None,
+            })
+        });
+        let state = ReadyState.run(&mut context).await;
+
+        match state {
+            WorkerStateInner::Running(state) => {
+                assert_eq!(state.past, Duration::from_secs(0));
+                assert_eq!(state.stage, PREPARATION);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn running_state_handle_tick() {
+        let (_, mut context, notifier) = new_worker_context();
+        let (start, state) = new_running_state().await;
+        let state = state.handle_tick(&mut context).await;
+
+        match state {
+            WorkerStateInner::Running(state) => {
+                assert_eq!(state.start, start);
+                assert_eq!(state.past, Duration::from_secs(0));
+                assert_eq!(state.stage, CONCENTRATION);
+            }
+            _ => unreachable!(),
+        }
+
+        let request = notifier.lock().unwrap().first().unwrap().clone();
+        assert_eq!(request.summary, "Preparation");
+        assert_eq!(request.body, None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn running_state_run_mute() {
+        let (sender, mut context, notifier) = new_worker_context();
+        let (_, state) = new_running_state().await;
+
+        sender.send(Command::Mute).await.unwrap();
+        let WorkerStateInner::Running(state) = state.run(&mut context).await else {
+            unreachable!()
+        };
+        assert!(context.muted);
+
+        // The stage still ends, but silently.
+        let WorkerStateInner::Running(state) = state.run(&mut context).await else {
+            unreachable!()
+        };
+        assert_eq!(state.stage, CONCENTRATION);
+        assert!(notifier.lock().unwrap().is_empty());
+
+        let (responder, receiver) = tokio::sync::oneshot::channel();
+        sender.send(Command::Query { responder }).await.unwrap();
+        let WorkerStateInner::Running(state) = state.run(&mut context).await else {
+            unreachable!()
+        };
+        assert!(receiver.await.unwrap().muted);
+
+        sender.send(Command::Unmute).await.unwrap();
+        state.run(&mut context).await;
+        assert!(!context.muted);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn running_state_run_tick_interval() {
+        let (_sender, mut context, notifier) = new_worker_context();
+        context.config.tick_interval = Some(Duration::from_millis(300));
+        let (start, state) = new_running_state().await;
+
+        // Ticking within the stage neither ends it early nor late.
+        match state.run(&mut context).await {
+            WorkerStateInner::Running(state) => {
+                assert_eq!(state.start - start, Duration::from_secs(5));
+                assert_eq!(state.stage, CONCENTRATION);
+            }
+            _ => unreachable!(),
+        }
+        assert_eq!(notifier.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn running_state_handle_pause() {
+        let (_, context, notifier) = new_worker_context();
+        let (_, state) = new_running_state().await;
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let state = state.handle_pause(&context);
+
+        match state {
+            WorkerStateInner::Paused(state) => {
+                assert_eq!(state.past, Duration::from_secs(1));
+                assert_eq!(state.stage, PREPARATION);
+            }
+            _ => unreachable!(),
+        }
+
+        assert!(notifier.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn running_state_handle_skip() {
+        let (_, mut context, notifier) = new_worker_context();
+        let (start, state) = new_running_state().await;
+        let state = state.handle_skip(&mut context, false).await;
+
+        match state {
+            WorkerStateInner::Running(state) => {
+                assert_eq!(state.start, start);
+                assert_eq!(state.past, Duration::from_secs(0));
+                assert_eq!(state.stage, CONCENTRATION);
+            }
+            _ => unreachable!(),
+        }
+
+        assert!(notifier.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn running_state_handle_skip_to() {
+        let (_, mut context, notifier) = new_worker_context();
+        let (_, state) = new_running_state().await;
+        let (responder, receiver) = tokio::sync::oneshot::channel();
+        let state = state
+            .handle_skip_to(&mut context, StageState::Relaxation, responder)
+            .await;
+
+        let WorkerStateInner::Running(state) = state else {
+            unreachable!();
+        };
+        assert_eq!(receiver.await.unwrap(), Ok(()));
+        assert_eq!(state.past, Duration::from_secs(0));
+        assert_eq!(*state.total.inner(), Duration::from_secs(10));
+        assert_eq!(state.stage, RELAXATION);
+        assert_eq!(state.concentrations, 1);
+
+        // Jumping to the same kind of stage goes to the next one of it.
+        let (responder, _) = tokio::sync::oneshot::channel();
+        let state = state
+            .handle_skip_to(&mut context, StageState::Relaxation, responder)
+            .await;
+        let WorkerStateInner::Running(state) = state else {
+            unreachable!();
+        };
+        assert_eq!(state.stage, RELAXATION);
+        assert_eq!(state.concentrations, 2);
+        assert_eq!(state.cycles, 1);
+        assert!(notifier.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn running_state_handle_skip_to_unknown_stage() {
+        let (_, mut context, _) = new_worker_context();
+        let (_, state) = new_running_state().await;
+        tokio::time::sleep(Duration::from_secs(2)).await;
+
+        // Preparations never come again, which the requester learns.
+        let (responder, receiver) = tokio::sync::oneshot::channel();
+        let state = state
+            .handle_skip_to(&mut context, StageState::Preparation, responder)
+            .await;
+        let WorkerStateInner::Running(state) = state else {
+            unreachable!();
+        };
+        assert_eq!(
+            receiver.await.unwrap(),
+            Err(ChangeStageError::NoStage {
+                kind: StageState::Preparation,
+            }),
+        );
+        assert_eq!(state.stage, PREPARATION);
+        assert_eq!(state.remaining(), Duration::from_secs(3));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn paused_state_handle_skip_to() {
+        let (_, mut context, _) = new_worker_context();
+        let (_, state) = new_paused_state().await;
+        let (responder, receiver) = tokio::sync::oneshot::channel();
+        let state = state
+            .handle_skip_to(&mut context, StageState::Concentration, responder)
+            .await;
+
+        assert_eq!(receiver.await.unwrap(), Ok(()));
+        match state {
+            WorkerStateInner::Running(state) => {
+                assert_eq!(state.past, Duration::from_secs(0));
+                assert_eq!(*state.total.inner(), Duration::from_secs(20));
+                assert_eq!(state.stage, CONCENTRATION);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn running_state_handle_skip_notify() {
+        let (_, mut context, notifier) = new_worker_context();
+        let (_, state) = new_running_state().await;
+        let state = state.handle_skip(&mut context, true).await;
+
+        match state {
+            WorkerStateInner::Running(state) => {
+                assert_eq!(state.past, Duration::from_secs(0));
+                assert_eq!(state.stage, CONCENTRATION);
+            }
+            _ => unreachable!(),
+        }
+
+        let request = notifier.lock().unwrap().first().unwrap().clone();
+        assert_eq!(request.summary, "Preparation");
+        assert_eq!(notifier.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn running_state_handle_query_overrun() {
+        let (_, context, _) = new_worker_context();
+        let (start, deadline) = start_timer(Duration::from_secs(5));
+        let state = RunningState {
+            start,
+            past: Duration::from_secs(7),
+            total: StageDuration::try_new(5).unwrap(),
+            deadline,
+            stage: PREPARATION,
+            concentrations: 0,
+            cycles: 0,
+        };
+
+        // A stage which has run over its length has nothing left.
+        let (responder, receiver) = tokio::sync::oneshot::channel();
+        state.handle_query(&context, responder);
+        let response = receiver.await.unwrap();
+        assert_eq!(response.past, Duration::from_secs(7));
+        assert_eq!(response.remaining, Duration::ZERO);
+        assert_eq!(state.event(&context).remaining, Duration::ZERO);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn running_state_handle_query_consistent() {
+        let (_, context, _) = new_worker_context();
+        let (_, state) = new_running_state().await;
+        let query = |state: &RunningState| {
+            let (responder, mut receiver) = tokio::sync::oneshot::channel();
+            state.handle_query(&context, responder);
+            receiver.try_recv().unwrap()
+        };
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let first = query(&state);
+        let second = query(&state);
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let third = query(&state);
+
+        assert_eq!(first, second);
+        assert!(second.past < third.past);
+        assert_eq!(first.past, Duration::from_secs(1));
+        assert_eq!(third.past, Duration::from_secs(2));
+        assert_eq!(state.past, Duration::from_secs(0));
+        assert!(first.total - first.past >= third.total - third.past);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn paused_state_handle_resume() {
+        let (_, mut context, notifier) = new_worker_context();
+        let (start, state) = new_paused_state().await;
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let state = state.handle_resume(&mut context).await;
+
+        match state {
+            WorkerStateInner::Running(state) => {
+                assert_eq!(state.start, start + Duration::from_secs(1));
+                assert_eq!(state.past, Duration::from_secs(0));
+                assert_eq!(state.stage, PREPARATION);
+            }
+            _ => unreachable!(),
+        }
+
+        assert!(notifier.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn paused_state_handle_skip() {
+        let (_, mut context, notifier) = new_worker_context();
+        let (start, state) = new_paused_state().await;
+        let state = state.handle_skip(&mut context, false).await;
+
+        match state {
+            WorkerStateInner::Running(state) => {
+                assert_eq!(state.start, start);
+                assert_eq!(state.past, Duration::from_secs(0));
+                assert_eq!(state.stage, CONCENTRATION);
+            }
+            _ => unreachable!(),
+        }
+
+        assert!(notifier.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn paused_state_handle_skip_notify() {
+        let (_, mut context, notifier) = new_worker_context();
+        let (_, state) = new_paused_state().await;
+        let state = state.handle_skip(&mut context, true).await;
+
+        match state {
+            WorkerStateInner::Running(state) => {
+                assert_eq!(state.past, Duration::from_secs(0));
+                assert_eq!(state.stage, CONCENTRATION);
+            }
+            _ => unreachable!(),
+        }
+
+        let request = notifier.lock().unwrap().first().unwrap().clone();
+        assert_eq!(request.summary, "Preparation");
+        assert_eq!(notifier.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn running_state_handle_auto_pause() {
+        let (_, mut context, _) = new_worker_context();
+        let (_, state) = new_running_state().await;
+        let state = state.handle_auto_pause(&mut context, AutoPauseTrigger::ScreenLock);
+
+        match state {
+            WorkerStateInner::Paused(state) => {
+                assert_eq!(state.trigger, Some(AutoPauseTrigger::ScreenLock));
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn running_state_handle_auto_pause_disabled() {
+        let (_, mut context, _) = new_worker_context();
+        context.config.screen_lock_auto_pause = false;
+        let (_, state) = new_running_state().await;
+        let state = state.handle_auto_pause(&mut context, AutoPauseTrigger::ScreenLock);
+        assert!(matches!(state, WorkerStateInner::Running(_)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn paused_state_handle_auto_resume() {
+        let (_, mut context, _) = new_worker_context();
+        let (_, state) = new_paused_state().await;
+        let state = PausedState {
+            trigger: Some(AutoPauseTrigger::ScreenLock),
+            ..state
+        };
+        let state = state
+            .handle_auto_resume(&mut context, AutoPauseTrigger::ScreenLock)
+            .await;
+        assert!(matches!(state, WorkerStateInner::Running(_)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn paused_state_handle_auto_resume_after_manual_pause() {
+        let (_, mut context, _) = new_worker_context();
+        let (_, state) = new_running_state().await;
+
+        // A manual pause is never clobbered by an automatic resume.
+        let state = state.handle_pause(&context);
+        let WorkerStateInner::Paused(state) = state else {
+            unreachable!()
+        };
+        let state = state
+            .handle_auto_resume(&mut context, AutoPauseTrigger::ScreenLock)
+            .await;
+        let WorkerStateInner::Paused(state) = state else {
+            unreachable!()
+        };
+
+        // A manual pause during an automatic one takes it over.
+        let state = PausedState {
+            trigger: Some(AutoPauseTrigger::ScreenLock),
+            ..state
+        };
+        let WorkerStateInner::Paused(state) = state.handle_pause(&context) else {
+            unreachable!()
+        };
+        assert_eq!(state.trigger, None);
+        let state = state
+            .handle_auto_resume(&mut context, AutoPauseTrigger::ScreenLock)
+            .await;
+        assert!(matches!(state, WorkerStateInner::Paused(_)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn paused_state_handle_auto_resume_other_trigger() {
+        let (_, mut context, _) = new_worker_context();
+        let (_, state) = new_running_state().await;
+
+        // Reconnecting AC power does not undo a pause caused by a screen lock.
+        let state = state.handle_auto_pause(&mut context, AutoPauseTrigger::ScreenLock);
+        let WorkerStateInner::Paused(state) = state else {
+            unreachable!()
+        };
+        let state = state
+            .handle_auto_resume(&mut context, AutoPauseTrigger::LowBattery)
+            .await;
+        assert!(matches!(state, WorkerStateInner::Paused(_)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn paused_state_handle_low_battery_after_manual_pause() {
+        let (_, mut context, _) = new_worker_context();
+        let (_, state) = new_running_state().await;
+
+        let state = state.handle_auto_pause(&mut context, AutoPauseTrigger::LowBattery);
+        let WorkerStateInner::Paused(state) = state else {
+            unreachable!()
+        };
+        assert_eq!(state.trigger, Some(AutoPauseTrigger::LowBattery));
+
+        // The user pauses manually while on battery, then plugs in AC power.
+        let WorkerStateInner::Paused(state) = state.handle_pause(&context) else {
+            unreachable!()
+        };
+        let state = state
+            .handle_auto_resume(&mut context, AutoPauseTrigger::LowBattery)
+            .await;
+        assert!(matches!(state, WorkerStateInner::Paused(_)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn running_state_long_break() {
+        let (_, mut context, _) = new_worker_context();
+        let (_, state) = new_running_state().await;
+        let query = |state: &RunningState, context: &WorkerContext| {
+            let (responder, mut receiver) = tokio::sync::oneshot::channel();
+            state.handle_query(context, responder);
+            receiver.try_recv().unwrap()
+        };
+
+        // Preparation, then two cycles of concentration and relaxation.
+        let mut state = state;
+        let mut breaks = Vec::new();
+        for _ in 0..4 {
+            let WorkerStateInner::Running(next) = state.handle_tick(&mut context).await else {
+                unreachable!()
+            };
+            state = next;
+
+            if state.stage == RELAXATION {
+                let response = query(&state, &context);
+                breaks.push((response.total, response.long_break));
+            }
+        }
+        assert_eq!(
+            breaks,
+            [
+                (Duration::from_secs(10), false),
+                (Duration::from_secs(30), true),
+            ],
+        );
+
+        // The counter starts over after the long break.
+        let WorkerStateInner::Running(state) = state.handle_tick(&mut context).await else {
+            unreachable!()
+        };
+        assert_eq!(state.stage, CONCENTRATION);
+        assert_eq!(state.concentrations, 0);
+        assert!(!query(&state, &context).long_break);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn state_run_schedule() {
+        let (sender, mut context, _) = new_worker_context();
+        let (_, state) = new_running_state().await;
+        let entry = |name: &str, kind, secs, long_break| ScheduledStage {
+            name: name.to_owned(),
+            kind,
+            duration: Duration::from_secs(secs),
+            long_break,
+        };
+
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        let (responder, receiver) = tokio::sync::oneshot::channel();
+        sender.send(Command::Schedule { responder }).await.unwrap();
+        let state = state.run(&mut context).await;
+        assert_eq!(
+            receiver.await.unwrap(),
+            [
+                entry("Preparation", StageState::Preparation, 3, false),
+                entry("Concentration", StageState::Concentration, 20, false),
+                entry("Relaxation", StageState::Relaxation, 10, false),
+            ],
+        );
+
+        // A round from a paused long break ends before it comes again.
+        assert!(matches!(state, WorkerStateInner::Running(_)));
+        let state = PausedState {
+            past: Duration::from_secs(27),
+            total: StageDuration::try_new(30).unwrap(),
+            stage: RELAXATION,
+            concentrations: 2,
+            cycles: 0,
+            trigger: None,
+            resume_at: None,
+        };
+        let (responder, receiver) = tokio::sync::oneshot::channel();
+        sender.send(Command::Schedule { responder }).await.unwrap();
+        state.run(&mut context).await;
+        assert_eq!(
+            receiver.await.unwrap(),
+            [
+                entry("Relaxation", StageState::Relaxation, 3, true),
+                entry("Concentration", StageState::Concentration, 20, false),
+            ],
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn running_state_completed_cycles() {
+        let (_, mut context, _) = new_worker_context();
+        let (_, state) = new_running_state().await;
+        let query = |state: &RunningState, context: &WorkerContext| {
+            let (responder, mut receiver) = tokio::sync::oneshot::channel();
+            state.handle_query(context, responder);
+            receiver.try_recv().unwrap().completed_cycles
+        };
+
+        // Preparation and concentration do not complete a round.
+        let WorkerStateInner::Running(state) = state.handle_tick(&mut context).await else {
+            unreachable!()
+        };
+        let WorkerStateInner::Running(state) = state.handle_skip(&mut context, false).await else {
+            unreachable!()
+        };
+        assert_eq!(state.stage, RELAXATION);
+        assert_eq!(query(&state, &context), 0);
+
+        // Pausing and resuming keep the count.
+        let WorkerStateInner::Paused(state) = state.handle_pause(&context) else {
+            unreachable!()
+        };
+        let WorkerStateInner::Running(state) = state.handle_resume(&mut context).await else {
+            unreachable!()
+        };
+        assert_eq!(query(&state, &context), 0);
+
+        // Leaving the last stage completes a round, whether it is skipped.
+        let WorkerStateInner::Running(state) = state.handle_skip(&mut context, false).await else {
+            unreachable!()
+        };
+        assert_eq!(state.stage, CONCENTRATION);
+        assert_eq!(query(&state, &context), 1);
+        assert_eq!(state.snapshot().cycles, 1);
+
+        let WorkerStateInner::Running(state) = state.handle_tick(&mut context).await else {
+            unreachable!()
+        };
+        let WorkerStateInner::Running(state) = state.handle_tick(&mut context).await else {
+            unreachable!()
+        };
+        assert_eq!(query(&state, &context), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn running_state_handle_adjust() {
+        let (_, mut context, notifier) = new_worker_context();
+        let (_, state) = new_running_state().await;
+        tokio::time::sleep(Duration::from_secs(2)).await;
+
+        let (responder, receiver) = tokio::sync::oneshot::channel();
+        let WorkerStateInner::Running(state) =
+            state.handle_adjust(&mut context, 60, responder).await
+        else {
+            unreachable!()
+        };
+        assert_eq!(receiver.await.unwrap(), Ok(()));
+        assert_eq!(*state.total.inner(), Duration::from_secs(65));
+        assert_eq!(state.past, Duration::from_secs(2));
+
+        let (responder, _) = tokio::sync::oneshot::channel();
+        let WorkerStateInner::Running(state) =
+            state.handle_adjust(&mut context, -30, responder).await
+        else {
+            unreachable!()
+        };
+        assert_eq!(*state.total.inner(), Duration::from_secs(35));
+
+        // The new timer fires once the adjusted stage is over.
+        let start = Instant::now();
+        tokio::time::sleep_until(state.deadline).await;
+        assert_eq!(Instant::now() - start, Duration::from_secs(33));
+        assert!(notifier.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn running_state_handle_adjust_to_end() {
+        let (_, mut context, notifier) = new_worker_context();
+        let (_, state) = new_running_state().await;
+        tokio::time::sleep(Duration::from_secs(2)).await;
+
+        // Shortening beyond the past duration behaves like a tick.
+        let (responder, receiver) = tokio::sync::oneshot::channel();
+        let state = state.handle_adjust(&mut context, -600, responder).await;
+        assert_eq!(receiver.await.unwrap(), Ok(()));
+        match state {
+            WorkerStateInner::Running(state) => {
+                assert_eq!(state.stage, CONCENTRATION);
+                assert_eq!(state.past, Duration::from_secs(0));
+                assert_eq!(*state.total.inner(), Duration::from_secs(20));
+            }
+            _ => unreachable!(),
+        }
+        assert_eq!(notifier.lock().unwrap().len(), 1);
     }
 
     #[tokio::test(start_paused = true)]
-    async fn running_state_handle_skip() {
+    async fn running_state_handle_adjust_beyond_limit() {
+        let (_, mut context, _) = new_worker_context();
+        let (_, state) = new_running_state().await;
+        let too_long = Err(ChangeStageError::Length {
+            source: TryNewStageDurationError::TooLong {
+                max: StageDuration::MAX,
+            },
+        });
+
+        // The requester learns that nothing has changed.
+        let delta = StageDuration::MAX.as_secs() as i64;
+        let (responder, receiver) = tokio::sync::oneshot::channel();
+        let WorkerStateInner::Running(state) =
+            state.handle_adjust(&mut context, delta, responder).await
+        else {
+            unreachable!()
+        };
+        assert_eq!(receiver.await.unwrap(), too_long);
+        assert_eq!(*state.total.inner(), Duration::from_secs(5));
+
+        let (responder, receiver) = tokio::sync::oneshot::channel();
+        let WorkerStateInner::Running(state) =
+            state.handle_adjust(&mut context, i64::MAX, responder).await
+        else {
+            unreachable!()
+        };
+        assert_eq!(receiver.await.unwrap(), too_long);
+        assert_eq!(*state.total.inner(), Duration::from_secs(5));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn paused_state_handle_adjust() {
         let (_, mut context, notifier) = new_worker_context();
-        let (start, state) = new_running_state().await;
-        let state = state.handle_skip(&mut context).await;
+        let state = PausedState {
+            past: Duration::from_secs(3),
+            ..new_paused_state().await.1
+        };
+
+        let (responder, receiver) = tokio::sync::oneshot::channel();
+        let WorkerStateInner::Paused(state) =
+            state.handle_adjust(&mut context, 10, responder).await
+        else {
+            unreachable!()
+        };
+        assert_eq!(receiver.await.unwrap(), Ok(()));
+        assert_eq!(*state.total.inner(), Duration::from_secs(15));
+
+        // Resuming continues with the adjusted remaining time.
+        let WorkerStateInner::Running(state) = state.handle_resume(&mut context).await else {
+            unreachable!()
+        };
+        let start = Instant::now();
+        tokio::time::sleep_until(state.deadline).await;
+        assert_eq!(Instant::now() - start, Duration::from_secs(12));
+        assert!(notifier.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn paused_state_handle_adjust_beyond_limit() {
+        let (_, mut context, _) = new_worker_context();
+        let (_, state) = new_paused_state().await;
+
+        let (responder, receiver) = tokio::sync::oneshot::channel();
+        let WorkerStateInner::Paused(state) =
+            state.handle_adjust(&mut context, i64::MAX, responder).await
+        else {
+            unreachable!()
+        };
+        assert!(receiver.await.unwrap().is_err());
+        assert_eq!(*state.total.inner(), Duration::from_secs(5));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn paused_state_handle_adjust_to_end() {
+        let (_, mut context, notifier) = new_worker_context();
+        let (_, state) = new_paused_state().await;
 
+        let (responder, _) = tokio::sync::oneshot::channel();
+        let state = state.handle_adjust(&mut context, -5, responder).await;
         match state {
-            WorkerStateInner::Running(state) => {
-                assert_eq!(state.start, start);
+            WorkerStateInner::Paused(state) => {
+                assert_eq!(state.stage, CONCENTRATION);
                 assert_eq!(state.past, Duration::from_secs(0));
-                assert_eq!(state.stage, StageState::Concentration);
+                assert_eq!(*state.total.inner(), Duration::from_secs(20));
             }
             _ => unreachable!(),
         }
+        assert_eq!(notifier.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn running_state_handle_set_duration() {
+        let (_, mut context, notifier) = new_worker_context();
+        let (_, state) = new_running_state().await;
+        tokio::time::sleep(Duration::from_secs(2)).await;
+
+        let WorkerStateInner::Running(state) = state
+            .handle_set_duration(&mut context, StageDuration::try_new(60).unwrap())
+            .await
+        else {
+            unreachable!()
+        };
+        assert_eq!(*state.total.inner(), Duration::from_secs(60));
+        assert_eq!(state.past, Duration::from_secs(2));
+        let start = Instant::now();
+        tokio::time::sleep_until(state.deadline).await;
+        assert_eq!(Instant::now() - start, Duration::from_secs(58));
+
+        // A total already gone by ends the stage, and the next one follows
+        // the configuration.
+        let state = state
+            .handle_set_duration(&mut context, StageDuration::try_new(1).unwrap())
+            .await;
+        let WorkerStateInner::Running(state) = state else {
+            unreachable!()
+        };
+        assert_eq!(state.stage, CONCENTRATION);
+        assert_eq!(*state.total.inner(), Duration::from_secs(20));
+        assert_eq!(notifier.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn paused_state_handle_set_duration() {
+        let (_, mut context, notifier) = new_worker_context();
+        let state = PausedState {
+            past: Duration::from_secs(3),
+            ..new_paused_state().await.1
+        };
 
+        let WorkerStateInner::Paused(state) = state
+            .handle_set_duration(&mut context, StageDuration::try_new(50).unwrap())
+            .await
+        else {
+            unreachable!()
+        };
+        assert_eq!(*state.total.inner(), Duration::from_secs(50));
         assert!(notifier.lock().unwrap().is_empty());
+
+        let WorkerStateInner::Paused(state) = state
+            .handle_set_duration(&mut context, StageDuration::try_new(3).unwrap())
+            .await
+        else {
+            unreachable!()
+        };
+        assert_eq!(state.stage, CONCENTRATION);
+        assert_eq!(state.past, Duration::from_secs(0));
+        assert_eq!(*state.total.inner(), Duration::from_secs(20));
+        assert_eq!(notifier.lock().unwrap().len(), 1);
     }
 
     #[tokio::test(start_paused = true)]
-    async fn paused_state_handle_resume() {
+    async fn running_state_handle_reset() {
         let (_, mut context, notifier) = new_worker_context();
-        let (start, state) = new_paused_state().await;
+        let (_, state) = new_running_state().await;
+        let WorkerStateInner::Running(state) = state.handle_skip(&mut context, false).await else {
+            unreachable!()
+        };
 
-        tokio::time::sleep(Duration::from_secs(1)).await;
-        let state = state.handle_resume(&mut context).await;
+        tokio::time::sleep(Duration::from_secs(3)).await;
+        let now = Instant::now();
+        let state = state.handle_reset(&mut context).await;
 
         match state {
             WorkerStateInner::Running(state) => {
-                assert_eq!(state.start, start + Duration::from_secs(1));
+                assert_eq!(state.start, now);
                 assert_eq!(state.past, Duration::from_secs(0));
-                assert_eq!(state.stage, StageState::Preparation);
+                assert_eq!(state.stage, PREPARATION);
             }
             _ => unreachable!(),
         }
@@ -342,28 +2233,559 @@ mod tests {
     }
 
     #[tokio::test(start_paused = true)]
-    async fn paused_state_handle_skip() {
-        let (_, mut context, notifier) = new_worker_context();
-        let (start, state) = new_paused_state().await;
-        let state = state.handle_skip(&mut context).await;
+    async fn paused_state_handle_reset() {
+        let (_, mut context, _) = new_worker_context();
+        let state = PausedState {
+            past: Duration::from_secs(8),
+            total: StageDuration::try_new(20).unwrap(),
+            stage: CONCENTRATION,
+            concentrations: 1,
+            cycles: 0,
+            trigger: Some(AutoPauseTrigger::ScreenLock),
+            resume_at: None,
+        };
+
+        let now = Instant::now();
+        let state = state.handle_reset(&mut context).await;
 
         match state {
             WorkerStateInner::Running(state) => {
-                assert_eq!(state.start, start);
+                assert_eq!(state.start, now);
                 assert_eq!(state.past, Duration::from_secs(0));
-                assert_eq!(state.stage, StageState::Concentration);
+                assert_eq!(state.stage, PREPARATION);
             }
             _ => unreachable!(),
         }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn worker_state_run_subscribe() {
+        let (sender, mut context, _) = new_worker_context();
+        let (_, state) = new_running_state().await;
+        let mut state = WorkerState {
+            inner: Some(state.into()),
+        };
+        let (events, mut receiver) = tokio::sync::broadcast::channel(4);
+
+        // The current state comes first.
+        sender
+            .send(Command::Subscribe { sender: events })
+            .await
+            .unwrap();
+        state.run(&mut context).await;
+        let event = receiver.recv().await.unwrap();
+        assert_eq!(event.current, TimerStatus::Running);
+        assert_eq!(event.stage, "Preparation");
+        assert_eq!(event.remaining, Duration::from_secs(5));
+
+        sender.send(Command::Pause).await.unwrap();
+        state.run(&mut context).await;
+        assert_eq!(receiver.recv().await.unwrap().current, TimerStatus::Paused);
+
+        // Nothing is sent if the state stays the same.
+        sender.send(Command::Mute).await.unwrap();
+        state.run(&mut context).await;
+        assert!(receiver.is_empty());
+
+        let (responder, _) = tokio::sync::oneshot::channel();
+        let command = Command::Stop {
+            keep_progress: false,
+            responder,
+        };
+        sender.send(command).await.unwrap();
+        state.run(&mut context).await;
+        assert_eq!(receiver.recv().await.unwrap().current, TimerStatus::Stopped);
+        assert!(receiver.recv().await.is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn worker_state_run_changed() {
+        let (sender, mut context, _) = new_worker_context();
+        let (_, state) = new_running_state().await;
+        let mut state = WorkerState {
+            inner: Some(state.into()),
+        };
+
+        // Asking for something leaves the progress as it is.
+        tokio::time::advance(Duration::from_secs(1)).await;
+        let (responder, _) = tokio::sync::oneshot::channel();
+        sender.send(Command::Query { responder }).await.unwrap();
+        assert!(!state.run(&mut context).await);
+        let (responder, _) = tokio::sync::oneshot::channel();
+        sender.send(Command::Ping { responder }).await.unwrap();
+        assert!(!state.run(&mut context).await);
+
+        sender.send(Command::Pause).await.unwrap();
+        assert!(state.run(&mut context).await);
+        sender.send(Command::Mute).await.unwrap();
+        assert!(!state.run(&mut context).await);
+
+        // A paused timer still changes with its length.
+        let (responder, _) = tokio::sync::oneshot::channel();
+        let command = Command::Adjust {
+            delta_secs: 60,
+            responder,
+        };
+        sender.send(command).await.unwrap();
+        assert!(state.run(&mut context).await);
 
+        sender.send(Command::Resume).await.unwrap();
+        assert!(state.run(&mut context).await);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn running_state_run_stop() {
+        let (sender, mut context, notifier) = new_worker_context();
+        let (_, state) = new_running_state().await;
+        let (responder, receiver) = tokio::sync::oneshot::channel();
+        let command = Command::Stop {
+            keep_progress: false,
+            responder,
+        };
+        sender.send(command).await.unwrap();
+
+        let state = state.run(&mut context).await;
+        assert!(matches!(
+            state,
+            WorkerStateInner::Stopped(StoppedState {
+                keep_progress: false,
+                ..
+            })
+        ));
+        assert!(receiver.await.is_ok());
         assert!(notifier.lock().unwrap().is_empty());
     }
 
+    #[tokio::test(start_paused = true)]
+    async fn paused_state_run_stop() {
+        let (sender, mut context, _) = new_worker_context();
+        let (_, state) = new_paused_state().await;
+        let (responder, receiver) = tokio::sync::oneshot::channel();
+        let command = Command::Stop {
+            keep_progress: true,
+            responder,
+        };
+        sender.send(command).await.unwrap();
+
+        let state = state.run(&mut context).await;
+        assert!(matches!(
+            state,
+            WorkerStateInner::Stopped(StoppedState {
+                keep_progress: true,
+                ..
+            })
+        ));
+        assert!(receiver.await.is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn state_run_ping() {
+        let (sender, mut context, _) = new_worker_context();
+        let (_, running) = new_running_state().await;
+        let (_, paused) = new_paused_state().await;
+        let states: [(WorkerStateInner, _); 2] = [
+            (running.into(), TimerStatus::Running),
+            (paused.into(), TimerStatus::Paused),
+        ];
+
+        for (state, expected) in states {
+            let (responder, receiver) = tokio::sync::oneshot::channel();
+            sender.send(Command::Ping { responder }).await.unwrap();
+            let _ = state.run(&mut context).await;
+            assert_eq!(receiver.await.unwrap(), expected);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stopped_state_run_query() {
+        let (sender, mut context, _) = new_worker_context();
+        let state = StoppedState {
+            keep_progress: false,
+            stage: RELAXATION,
+            cycles: 2,
+        };
+
+        let requests = async move {
+            let (responder, receiver) = tokio::sync::oneshot::channel();
+            sender.send(Command::Query { responder }).await.unwrap();
+            let response = receiver.await.unwrap();
+
+            let (responder, receiver) = tokio::sync::oneshot::channel();
+            sender.send(Command::Ping { responder }).await.unwrap();
+            assert_eq!(receiver.await.unwrap(), TimerStatus::Stopped);
+
+            let (responder, receiver) = tokio::sync::oneshot::channel();
+            let command = Command::Stop {
+                keep_progress: true,
+                responder,
+            };
+            sender.send(command).await.unwrap();
+            assert!(receiver.await.is_ok());
+
+            // Dropping the sender afterwards lets the stopped state finish.
+            sender.send(Command::Pause).await.unwrap();
+            response
+        };
+
+        let (state, response) = tokio::join!(state.run(&mut context), requests);
+        assert!(matches!(
+            state,
+            WorkerStateInner::Stopped(StoppedState {
+                keep_progress: false,
+                ..
+            })
+        ));
+        assert_eq!(response.current, TimerStatus::Stopped);
+        assert_eq!(response.kind, StageState::Relaxation);
+        assert_eq!(response.total, Duration::from_secs(0));
+        assert_eq!(response.past, Duration::from_secs(0));
+        assert_eq!(response.completed_cycles, 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn running_state_run_idle() {
+        let (_sender, mut context, _) = new_worker_context();
+        context.config.idle_threshold = Some(Duration::from_secs(10));
+        let since = Instant::now() + Duration::from_secs(3);
+        context.idle_detector =
+            new_idle_detector(move || Instant::now().saturating_duration_since(since));
+        let (start, deadline) = start_timer(Duration::from_secs(20));
+        let state = RunningState {
+            start,
+            past: Duration::from_secs(0),
+            total: StageDuration::try_new(20).unwrap(),
+            deadline,
+            stage: CONCENTRATION,
+            concentrations: 0,
+            cycles: 0,
+        };
+
+        match state.run(&mut context).await {
+            WorkerStateInner::Paused(state) => {
+                assert_eq!(Instant::now() - start, Duration::from_secs(15));
+                assert_eq!(state.past, Duration::from_secs(3));
+                assert_eq!(state.trigger, Some(AutoPauseTrigger::Idle));
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn running_state_run_idle_while_queried() {
+        let (sender, mut context, _) = new_worker_context();
+        context.config.idle_threshold = Some(Duration::from_secs(10));
+        let since = Instant::now();
+        context.idle_detector = new_idle_detector(move || Instant::now() - since);
+        let (start, deadline) = start_timer(Duration::from_secs(20));
+        let mut state = WorkerStateInner::from(RunningState {
+            start,
+            past: Duration::from_secs(0),
+            total: StageDuration::try_new(20).unwrap(),
+            deadline,
+            stage: CONCENTRATION,
+            concentrations: 0,
+            cycles: 0,
+        });
+
+        // Queries every second, e.g. from `watch`, don't put off the check.
+        spawn_queries(sender);
+        let state = loop {
+            state = match state {
+                WorkerStateInner::Running(state) => state.run(&mut context).await,
+                state => break state,
+            };
+        };
+
+        let WorkerStateInner::Paused(state) = state else {
+            unreachable!()
+        };
+        assert_eq!(Instant::now() - start, Duration::from_secs(10));
+        assert_eq!(state.trigger, Some(AutoPauseTrigger::Idle));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn running_state_run_idle_relaxation() {
+        let (_sender, mut context, _) = new_worker_context();
+        context.config.idle_threshold = Some(Duration::from_secs(10));
+        let (start, mut state) = new_running_state().await;
+        state.stage = RELAXATION;
+
+        match state.run(&mut context).await {
+            WorkerStateInner::Running(state) => {
+                assert_eq!(Instant::now() - start, Duration::from_secs(5));
+                assert_eq!(state.stage, CONCENTRATION);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn paused_state_run_active() {
+        let (_sender, mut context, _) = new_worker_context();
+        let (start, mut state) = new_paused_state().await;
+        state.trigger = Some(AutoPauseTrigger::Idle);
+        let active_at = start + Duration::from_secs(8);
+        context.idle_detector = new_idle_detector(move || {
+            if Instant::now() < active_at {
+                Duration::from_secs(30)
+            } else {
+                Duration::from_secs(0)
+            }
+        });
+
+        match state.run(&mut context).await {
+            WorkerStateInner::Running(state) => {
+                assert_eq!(Instant::now() - start, Duration::from_secs(10));
+                assert_eq!(state.past, Duration::from_secs(0));
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn paused_state_run_active_while_queried() {
+        let (sender, mut context, _) = new_worker_context();
+        let (start, mut state) = new_paused_state().await;
+        state.trigger = Some(AutoPauseTrigger::Idle);
+        let active_at = start + Duration::from_secs(8);
+        context.idle_detector = new_idle_detector(move || {
+            if Instant::now() < active_at {
+                Duration::from_secs(30)
+            } else {
+                Duration::from_secs(0)
+            }
+        });
+
+        spawn_queries(sender);
+        let mut state = WorkerStateInner::from(state);
+        let state = loop {
+            state = match state {
+                WorkerStateInner::Paused(state) => state.run(&mut context).await,
+                state => break state,
+            };
+        };
+
+        assert!(matches!(state, WorkerStateInner::Running(_)));
+        assert_eq!(Instant::now() - start, Duration::from_secs(10));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn paused_state_run_max_pause() {
+        let (sender, mut context, _) = new_worker_context();
+        context.config.max_pause = Some(Duration::from_secs(60));
+        let (start, state) = new_running_state().await;
+        let WorkerStateInner::Paused(state) = state.handle_pause(&context) else {
+            unreachable!()
+        };
+
+        // Commands in between do not extend the pause.
+        tokio::time::sleep(Duration::from_secs(30)).await;
+        let (responder, _receiver) = tokio::sync::oneshot::channel();
+        sender.send(Command::Query { responder }).await.unwrap();
+        let WorkerStateInner::Paused(state) = state.run(&mut context).await else {
+            unreachable!()
+        };
+
+        match state.run(&mut context).await {
+            WorkerStateInner::Running(state) => {
+                assert_eq!(Instant::now() - start, Duration::from_secs(60));
+                assert_eq!(state.past, Duration::from_secs(0));
+            }
+            _ => unreachable!(),
+        }
+
+        // An automatic pause is left to its trigger.
+        let (_, state) = new_running_state().await;
+        let state = state.handle_auto_pause(&mut context, AutoPauseTrigger::ScreenLock);
+        let WorkerStateInner::Paused(state) = state else {
+            unreachable!()
+        };
+        assert_eq!(state.resume_at, None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn running_state_run_next_stage() {
+        let (_sender, mut context, notifications) = new_worker_context();
+        let message = NotificationMessage::try_new(
+            "Concentration #{cycle} complete, {next_stage} is coming".to_owned(),
+            Some("Next up: {next_stage} ({next_duration}), {other}".to_owned()),
+        )
+        .unwrap();
+        let duration = StageDuration::try_new(20).unwrap();
+        context.config.stages = StageSequence::try_new(vec![
+            new_stage("Preparation", StageState::Preparation, 5),
+            Stage::try_new(
+                "Concentration".to_owned(),
+                StageState::Concentration,
+                duration,
+                message,
+            )
+            .unwrap(),
+            new_stage("Relaxation", StageState::Relaxation, 10),
+        ])
+        .unwrap();
+        let (start, deadline) = start_timer(Duration::from_secs(20));
+        let state = RunningState {
+            start,
+            past: Duration::from_secs(0),
+            total: StageDuration::try_new(20).unwrap(),
+            deadline,
+            stage: CONCENTRATION,
+            concentrations: 1,
+            cycles: 2,
+        };
+
+        let _ = state.run(&mut context).await;
+        let notifications = notifications.lock().unwrap();
+        // The upcoming relaxation is a long break.
+        assert_eq!(
+            notifications[0].summary,
+            "Concentration #3 complete, Relaxation is coming"
+        );
+        assert_eq!(
+            notifications[0].body.as_deref(),
+            Some("Next up: Relaxation (00:30), {other}")
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn running_state_run_checkpoint() {
+        let (_sender, mut context, notifications) = new_worker_context();
+        let message = NotificationMessage::try_new("Almost done".to_owned(), None).unwrap();
+        let checkpoints = [15, 5, 0, 30].map(Duration::from_secs).to_vec();
+        context.config.stages = StageSequence::try_new(vec![
+            new_stage("Preparation", StageState::Preparation, 5),
+            new_stage("Concentration", StageState::Concentration, 20)
+                .with_checkpoints(checkpoints, message),
+            new_stage("Relaxation", StageState::Relaxation, 10),
+        ])
+        .unwrap();
+        let (start, deadline) = start_timer(Duration::from_secs(20));
+        let state = RunningState {
+            start,
+            past: Duration::from_secs(0),
+            total: StageDuration::try_new(20).unwrap(),
+            deadline,
+            stage: CONCENTRATION,
+            concentrations: 0,
+            cycles: 0,
+        };
+
+        let WorkerStateInner::Running(state) = state.run(&mut context).await else {
+            unreachable!()
+        };
+        assert_eq!(Instant::now() - start, Duration::from_secs(5));
+        let WorkerStateInner::Running(state) = state.run(&mut context).await else {
+            unreachable!()
+        };
+        assert_eq!(Instant::now() - start, Duration::from_secs(15));
+        let WorkerStateInner::Running(state) = state.run(&mut context).await else {
+            unreachable!()
+        };
+        assert_eq!(Instant::now() - start, Duration::from_secs(20));
+        assert_eq!(state.stage, RELAXATION);
+
+        let summaries: Vec<_> = notifications
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|request| request.summary.clone())
+            .collect();
+        assert_eq!(summaries, ["Almost done", "Almost done", "Concentration"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn state_run_stats() {
+        let (sender, mut context, _) = new_worker_context();
+        let mut stage_log = MockStageLog::new();
+        stage_log
+            .expect_append()
+            .withf(|record| record.stage == "Concentration" && record.session == "default")
+            .times(1)
+            .returning(|_| Box::pin(future::ready(Ok(()))));
+        context.stage_log = Arc::new(stage_log);
+
+        let (_, deadline) = start_timer(Duration::from_secs(20));
+        let state = RunningState {
+            start: Instant::now(),
+            past: Duration::from_secs(0),
+            total: StageDuration::try_new(20).unwrap(),
+            deadline,
+            stage: CONCENTRATION,
+            concentrations: 0,
+            cycles: 0,
+        };
+        let WorkerStateInner::Running(state) = state.handle_tick(&mut context).await else {
+            unreachable!()
+        };
+
+        let (responder, receiver) = tokio::sync::oneshot::channel();
+        sender.send(Command::Stats { responder }).await.unwrap();
+        let state = state.run(&mut context).await;
+        assert!(matches!(state, WorkerStateInner::Running(_)));
+
+        let stats = receiver.await.unwrap();
+        assert_eq!(stats.completed_concentrations, 1);
+        assert_eq!(stats.concentration_time, Duration::from_secs(20));
+        assert_eq!(stats.concentrations_today, 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn running_state_run_reload() {
+        let (sender, mut context, _) = new_worker_context();
+        let (_, state) = new_running_state().await;
+
+        let config = WorkerConfigBuilder::from(context.config.clone())
+            .stages(
+                StageSequence::try_new(vec![
+                    new_stage("Warmup", StageState::Preparation, 7),
+                    new_stage("Focus", StageState::Concentration, 40),
+                ])
+                .unwrap(),
+            )
+            .build();
+        sender.send(Command::Reload { config }).await.unwrap();
+
+        // The current stage keeps its length.
+        let WorkerStateInner::Running(state) = state.run(&mut context).await else {
+            unreachable!()
+        };
+        assert_eq!(*state.total.inner(), Duration::from_secs(5));
+        assert_eq!(context.config.stage(PREPARATION).name(), "Warmup");
+
+        // The next stage follows the new configuration.
+        let WorkerStateInner::Running(state) = state.handle_tick(&mut context).await else {
+            unreachable!()
+        };
+        assert_eq!(*state.total.inner(), Duration::from_secs(40));
+        assert_eq!(context.config.stage(state.stage).name(), "Focus");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn paused_state_run_reload_unknown_stage() {
+        let (sender, mut context, _) = new_worker_context();
+        let state = PausedState {
+            stage: RELAXATION,
+            ..new_paused_state().await.1
+        };
+
+        let mut config = context.config.clone();
+        config.stages =
+            StageSequence::try_new(vec![new_stage("Focus", StageState::Concentration, 40)])
+                .unwrap();
+        sender.send(Command::Reload { config }).await.unwrap();
+
+        let state = state.run(&mut context).await;
+        assert!(matches!(state, WorkerStateInner::Paused(_)));
+        assert_eq!(context.config.stage(RELAXATION).name(), "Relaxation");
+    }
+
     struct MockNotifier {
         notifications: Arc<Mutex<Vec<NotifyRequest>>>,
     }
 
     impl MockNotifier {
+        #[allow(clippy::new_ret_no_self)]
         fn new() -> (Arc<dyn NotifyPort>, Arc<Mutex<Vec<NotifyRequest>>>) {
             let notifier = Arc::new(Mutex::new(Vec::new()));
             let res = Self {
@@ -388,32 +2810,112 @@ mod tests {
     ) {
         let (sender, receiver) = tokio::sync::mpsc::channel(1);
         let new_duration = |d| StageDuration::try_new(d).unwrap();
-        let new_message = |s: &str| NotificationMessage::try_new(s.to_owned(), None).unwrap();
         let (mock, data) = MockNotifier::new();
 
         let context = WorkerContext {
-            config: WorkerConfig {
-                preparation_duration: new_duration(5),
-                concentration_duration: new_duration(20),
-                relaxation_duration: new_duration(10),
-                preparation_notification: new_message("Preparation"),
-                concentration_notification: new_message("Concentration"),
-                relaxation_notification: new_message("Relaxation"),
-            },
+            config: WorkerConfig::builder(
+                StageSequence::try_new(vec![
+                    new_stage("Preparation", StageState::Preparation, 5),
+                    new_stage("Concentration", StageState::Concentration, 20),
+                    new_stage("Relaxation", StageState::Relaxation, 10),
+                ])
+                .unwrap(),
+            )
+            .long_break_duration(new_duration(30))
+            .cycles_before_long_break(2)
+            .build(),
             commands: receiver,
             notifier: mock,
+            idle_detector: Arc::new(MockIdleDetector::new()),
+            session: "default".to_owned(),
+            store: new_store(|| Ok(None)),
+            stage_log: new_stage_log(),
+            stats: WorkerStats::new(),
+            muted: false,
+            subscribers: Vec::new(),
+            idle_poll: Instant::now() + IDLE_POLL_INTERVAL,
         };
 
         (sender, context, data)
     }
 
+    /// Send a query through `sender` every second until the worker is gone.
+    fn spawn_queries(sender: Sender<Command>) {
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                let (responder, _) = tokio::sync::oneshot::channel();
+                if sender.send(Command::Query { responder }).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    fn new_idle_detector<F>(idle_time: F) -> Arc<MockIdleDetector>
+    where
+        F: Fn() -> Duration + Send + 'static,
+    {
+        let mut detector = MockIdleDetector::new();
+        detector
+            .expect_idle_time()
+            .returning(move || Box::pin(future::ready(Ok(idle_time()))));
+        Arc::new(detector)
+    }
+
+    fn new_stage(name: &str, kind: StageState, secs: u64) -> Stage {
+        let duration = StageDuration::try_new(secs).unwrap();
+        let message = NotificationMessage::try_new(name.to_owned(), None).unwrap();
+        Stage::try_new(name.to_owned(), kind, duration, message).unwrap()
+    }
+
+    fn new_store<F>(load: F) -> Arc<MockStateStore>
+    where
+        F: Fn() -> Result<Option<WorkerSnapshot>, StateStoreError> + Send + 'static,
+    {
+        let mut store = MockStateStore::new();
+        store
+            .expect_load()
+            .returning(move |_| Box::pin(future::ready(load())));
+        store
+            .expect_save()
+            .returning(|_, _| Box::pin(future::ready(Ok(()))));
+        store
+            .expect_clear()
+            .returning(|_| Box::pin(future::ready(Ok(()))));
+        Arc::new(store)
+    }
+
+    fn new_stage_log() -> Arc<MockStageLog> {
+        let mut stage_log = MockStageLog::new();
+        stage_log
+            .expect_append()
+            .returning(|_| Box::pin(future::ready(Ok(()))));
+        Arc::new(stage_log)
+    }
+
+    fn new_snapshot(past: u64, paused: bool, saved_ago: u64) -> WorkerSnapshot {
+        WorkerSnapshot {
+            stage: CONCENTRATION,
+            concentrations: 1,
+            cycles: 0,
+            past: Duration::from_secs(past),
+            total: Duration::from_secs(20),
+            paused,
+            saved_at: SystemTime::now() - Duration::from_secs(saved_ago),
+        }
+    }
+
     async fn new_running_state() -> (Instant, RunningState) {
-        let (start, timer) = spawn_timer(Duration::from_secs(5)).await;
+        let (start, deadline) = start_timer(Duration::from_secs(5));
         let state = RunningState {
             start,
             past: Duration::from_secs(0),
-            timer,
-            stage: StageState::Preparation,
+            total: StageDuration::try_new(5).unwrap(),
+            deadline,
+            stage: PREPARATION,
+            concentrations: 0,
+            cycles: 0,
         };
         (start, state)
     }
@@ -421,7 +2923,12 @@ mod tests {
     async fn new_paused_state() -> (Instant, PausedState) {
         let state = PausedState {
             past: Duration::from_secs(0),
-            stage: StageState::Preparation,
+            total: StageDuration::try_new(5).unwrap(),
+            stage: PREPARATION,
+            concentrations: 0,
+            cycles: 0,
+            trigger: None,
+            resume_at: None,
         };
         (Instant::now(), state)
     }
@@ -0,0 +1,91 @@
+use std::time::Duration;
+
+use crate::domain::entity::{StageState, TimerStats};
+
+/// Counters of the stages completed by a worker. Days are numbered in the
+/// local time zone, and the counters of today start over on a new day.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorkerStats {
+    stats: TimerStats,
+    day: i64,
+}
+
+impl WorkerStats {
+    /// Creates a new [`WorkerStats`] without any completed stage.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a stage of `kind` which has lasted for `duration` and ended on
+    /// `day`.
+    pub fn record(&mut self, kind: StageState, duration: Duration, day: i64) {
+        self.stats = self.get(day);
+        self.day = day;
+
+        if kind == StageState::Concentration {
+            let stats = &mut self.stats;
+            stats.completed_concentrations = stats.completed_concentrations.saturating_add(1);
+            stats.concentration_time += duration;
+            stats.concentrations_today = stats.concentrations_today.saturating_add(1);
+            stats.concentration_time_today += duration;
+        }
+    }
+
+    /// Get the counters as of `day`.
+    pub fn get(&self, day: i64) -> TimerStats {
+        if day == self.day {
+            return self.stats;
+        }
+
+        TimerStats {
+            concentrations_today: 0,
+            concentration_time_today: Duration::ZERO,
+            ..self.stats
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn worker_stats_record() {
+        let mut stats = WorkerStats::new();
+        stats.record(StageState::Preparation, Duration::from_secs(60), 10);
+        stats.record(StageState::Concentration, Duration::from_secs(1500), 10);
+        stats.record(StageState::Relaxation, Duration::from_secs(300), 10);
+        stats.record(StageState::Concentration, Duration::from_secs(1200), 10);
+
+        let expected = TimerStats {
+            completed_concentrations: 2,
+            concentration_time: Duration::from_secs(2700),
+            concentrations_today: 2,
+            concentration_time_today: Duration::from_secs(2700),
+        };
+        assert_eq!(stats.get(10), expected);
+    }
+
+    #[test]
+    fn worker_stats_new_day() {
+        let mut stats = WorkerStats::new();
+        stats.record(StageState::Concentration, Duration::from_secs(1500), 10);
+
+        let expected = TimerStats {
+            completed_concentrations: 1,
+            concentration_time: Duration::from_secs(1500),
+            concentrations_today: 0,
+            concentration_time_today: Duration::ZERO,
+        };
+        assert_eq!(stats.get(11), expected);
+
+        stats.record(StageState::Concentration, Duration::from_secs(600), 11);
+        let expected = TimerStats {
+            completed_concentrations: 2,
+            concentration_time: Duration::from_secs(2100),
+            concentrations_today: 1,
+            concentration_time_today: Duration::from_secs(600),
+        };
+        assert_eq!(stats.get(11), expected);
+    }
+}
@@ -7,14 +7,54 @@ use snafu::prelude::*;
 pub struct StageDuration(Duration);
 
 impl StageDuration {
+    /// The longest duration a stage may last, so that a typo can't make a
+    /// stage effectively never end.
+    pub const MAX: Duration = Duration::from_secs(24 * 60 * 60);
+
     /// Try to create a [`StageDuration`] from a u64 integer.
     ///
     /// # Errors
     ///
-    /// This function will return an error if the integer is zero.
+    /// This function will return an error if the integer is zero or longer than
+    /// [`StageDuration::MAX`].
     pub fn try_new(seconds: u64) -> Result<Self, TryNewStageDurationError> {
         ensure!(seconds > 0, ZeroSnafu);
-        Ok(Self(Duration::from_secs(seconds)))
+        Self::bounded(Duration::from_secs(seconds))
+    }
+
+    /// Try to create a [`StageDuration`] from a [`Duration`]. Stages last for
+    /// whole seconds, so a fraction of a second is rejected rather than
+    /// rounded.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the duration is zero, has a
+    /// fraction of a second or is longer than [`StageDuration::MAX`].
+    pub fn try_from_duration(duration: Duration) -> Result<Self, TryNewStageDurationError> {
+        ensure!(duration.subsec_nanos() == 0, FractionalSnafu { duration });
+        Self::try_new(duration.as_secs())
+    }
+
+    /// Try to lengthen this [`StageDuration`] by `delta`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the resulting duration is longer
+    /// than [`StageDuration::MAX`].
+    pub fn checked_extend(&self, delta: Duration) -> Result<Self, TryNewStageDurationError> {
+        let duration = self.0.checked_add(delta).unwrap_or(Duration::MAX);
+        Self::bounded(duration)
+    }
+
+    fn bounded(duration: Duration) -> Result<Self, TryNewStageDurationError> {
+        ensure!(duration <= Self::MAX, TooLongSnafu { max: Self::MAX });
+        Ok(Self(duration))
+    }
+
+    /// Get how long remains of this [`StageDuration`] once `past` has gone
+    /// by, which is zero instead of underflowing if `past` is longer.
+    pub fn remaining_from(&self, past: Duration) -> Duration {
+        self.0.saturating_sub(past)
     }
 
     /// Returns a reference to the inner of this [`StageDuration`].
@@ -31,6 +71,14 @@ impl TryFrom<u64> for StageDuration {
     }
 }
 
+impl TryFrom<Duration> for StageDuration {
+    type Error = TryNewStageDurationError;
+
+    fn try_from(value: Duration) -> Result<Self, Self::Error> {
+        Self::try_from_duration(value)
+    }
+}
+
 /// An error type of creating a [`StageDuration`].
 #[derive(Debug, Clone, Snafu, PartialEq, Eq)]
 #[non_exhaustive]
@@ -38,6 +86,12 @@ pub enum TryNewStageDurationError {
     #[snafu(display("Duration must be greater than zero"))]
     #[non_exhaustive]
     Zero,
+    #[snafu(display("Duration must not be longer than {} seconds", max.as_secs()))]
+    #[non_exhaustive]
+    TooLong { max: Duration },
+    #[snafu(display("Duration must be whole seconds, got {duration:?}"))]
+    #[non_exhaustive]
+    Fractional { duration: Duration },
 }
 
 #[cfg(test)]
@@ -54,6 +108,12 @@ mod tests {
             StageDuration::try_new(0),
             Err(TryNewStageDurationError::Zero),
         );
+        assert_eq!(
+            StageDuration::try_new(24 * 60 * 60 + 1),
+            Err(TryNewStageDurationError::TooLong {
+                max: StageDuration::MAX
+            }),
+        );
     }
 
     #[test]
@@ -64,4 +124,73 @@ mod tests {
             Err::<StageDuration, TryNewStageDurationError>(TryNewStageDurationError::Zero)
         );
     }
+
+    #[test]
+    fn stage_duration_try_from_duration() {
+        assert_eq!(
+            StageDuration::try_from_duration(Duration::from_secs(1500)),
+            Ok(StageDuration(Duration::from_secs(1500))),
+        );
+        assert_eq!(
+            Duration::from_secs(10).try_into(),
+            Ok(StageDuration(Duration::from_secs(10))),
+        );
+        assert_eq!(
+            StageDuration::try_from_duration(Duration::ZERO),
+            Err(TryNewStageDurationError::Zero),
+        );
+        assert_eq!(
+            StageDuration::try_from_duration(StageDuration::MAX + Duration::from_secs(1)),
+            Err(TryNewStageDurationError::TooLong {
+                max: StageDuration::MAX
+            }),
+        );
+
+        // Sub-second durations are rejected instead of being rounded.
+        for duration in [Duration::from_millis(500), Duration::from_millis(1500)] {
+            assert_eq!(
+                StageDuration::try_from_duration(duration),
+                Err(TryNewStageDurationError::Fractional { duration }),
+            );
+        }
+    }
+
+    #[test]
+    fn stage_duration_remaining_from() {
+        let duration = StageDuration::try_new(1500).unwrap();
+        assert_eq!(
+            duration.remaining_from(Duration::from_secs(600)),
+            Duration::from_secs(900),
+        );
+        assert_eq!(
+            duration.remaining_from(Duration::from_secs(1500)),
+            Duration::ZERO,
+        );
+        assert_eq!(
+            duration.remaining_from(Duration::from_secs(1501)),
+            Duration::ZERO,
+        );
+        assert_eq!(duration.remaining_from(Duration::MAX), Duration::ZERO);
+    }
+
+    #[test]
+    fn stage_duration_checked_extend() {
+        let duration = StageDuration::try_new(1500).unwrap();
+        assert_eq!(
+            duration.checked_extend(Duration::from_secs(300)),
+            Ok(StageDuration(Duration::from_secs(1800))),
+        );
+        assert_eq!(
+            duration.checked_extend(Duration::from_secs(10000 * 60 * 60)),
+            Err(TryNewStageDurationError::TooLong {
+                max: StageDuration::MAX
+            }),
+        );
+        assert_eq!(
+            duration.checked_extend(Duration::MAX),
+            Err(TryNewStageDurationError::TooLong {
+                max: StageDuration::MAX
+            }),
+        );
+    }
 }
@@ -0,0 +1,17 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::domain::entity::{StageState, TimerStatus};
+
+/// A change of a timer's state, which is sent to its subscribers as soon as
+/// the stage changes or the timer is paused, resumed or stopped.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimerEvent {
+    pub current: TimerStatus,
+    /// Name of the stage which the timer is in.
+    pub stage: String,
+    pub kind: StageState,
+    /// How long remains of the stage.
+    pub remaining: Duration,
+}
@@ -1,7 +1,21 @@
 pub mod duration;
+pub mod event;
+pub mod mode;
 pub mod notification;
+pub mod pause;
+pub mod schedule;
+pub mod sequence;
 pub mod state;
+pub mod stats;
+pub mod status;
 
 pub use duration::StageDuration;
-pub use notification::NotificationMessage;
+pub use event::TimerEvent;
+pub use mode::TimerMode;
+pub use notification::{NotificationMessage, NotificationTimeout, NotificationUrgency};
+pub use pause::AutoPauseTrigger;
+pub use schedule::ScheduledStage;
+pub use sequence::{Stage, StageSequence};
 pub use state::StageState;
+pub use stats::TimerStats;
+pub use status::TimerStatus;
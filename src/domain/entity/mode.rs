@@ -0,0 +1,23 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// How the timer goes through its stage sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimerMode {
+    /// Go through every stage.
+    #[default]
+    Full,
+    /// Skip [`Preparation`]s, so that only concentrations and relaxations
+    /// alternate.
+    ///
+    /// [`Preparation`]: crate::domain::entity::StageState::Preparation
+    Simple,
+}
+
+impl Display for TimerMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Full => f.write_str("Full"),
+            Self::Simple => f.write_str("Simple"),
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use snafu::prelude::*;
 
 /// Essential information in one XDG desktop notification.
@@ -5,6 +7,9 @@ use snafu::prelude::*;
 pub struct NotificationMessage {
     summary: String,
     body: Option<String>,
+    urgency: NotificationUrgency,
+    timeout: NotificationTimeout,
+    sound: Option<String>,
 }
 
 impl NotificationMessage {
@@ -12,13 +17,77 @@ impl NotificationMessage {
     ///
     /// # Errors
     ///
-    /// This function will return an error if the summary is empty.
+    /// This function will return an error if the summary is empty or only
+    /// has whitespace.
     pub fn try_new(
         summary: String,
         body: Option<String>,
     ) -> Result<Self, TryNewNotificationMessageError> {
-        ensure!(!summary.is_empty(), EmptySummarySnafu);
-        Ok(Self { summary, body })
+        ensure!(!summary.trim().is_empty(), EmptySummarySnafu);
+        Ok(Self {
+            summary,
+            body,
+            urgency: NotificationUrgency::default(),
+            timeout: NotificationTimeout::default(),
+            sound: None,
+        })
+    }
+
+    /// Set the urgency of this [`NotificationMessage`], which is
+    /// [`NotificationUrgency::Normal`] by default.
+    pub fn with_urgency(mut self, urgency: NotificationUrgency) -> Self {
+        self.urgency = urgency;
+        self
+    }
+
+    /// Set how long this [`NotificationMessage`] stays on screen, which is
+    /// [`NotificationTimeout::Default`] by default.
+    pub fn with_timeout(mut self, timeout: NotificationTimeout) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the name of the sound played along with this
+    /// [`NotificationMessage`], which is silent by default.
+    pub fn with_sound(mut self, sound: String) -> Self {
+        self.sound = Some(sound);
+        self
+    }
+
+    /// Check that neither the summary nor the body of this
+    /// [`NotificationMessage`] has more than `max` characters, beyond which
+    /// desktops may cut it off.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if either of them is too long.
+    pub fn try_limit(self, max: usize) -> Result<Self, TryNewNotificationMessageError> {
+        let length = self.summary.chars().count();
+        ensure!(length <= max, SummaryTooLongSnafu { length, max });
+        if let Some(body) = self.body.as_deref() {
+            let length = body.chars().count();
+            ensure!(length <= max, BodyTooLongSnafu { length, max });
+        }
+        Ok(self)
+    }
+
+    /// Replace every `{name}` in the summary and the body with the value of
+    /// the variable `name` in `variables`. Any other text, including unknown
+    /// placeholders, is kept as it is.
+    pub fn with_variables(&self, variables: &[(&str, &str)]) -> Self {
+        let expand = |text: &str| {
+            variables
+                .iter()
+                .fold(text.to_owned(), |text, (name, value)| {
+                    text.replace(&format!("{{{name}}}"), value)
+                })
+        };
+
+        Self {
+            summary: expand(&self.summary),
+            body: self.body.as_deref().map(expand),
+            ..self.clone()
+        }
     }
 
     /// Returns a reference to the summary of this [`NotificationMessage`].
@@ -30,6 +99,43 @@ impl NotificationMessage {
     pub fn body(&self) -> Option<&str> {
         self.body.as_deref()
     }
+
+    /// Returns the urgency of this [`NotificationMessage`].
+    pub fn urgency(&self) -> NotificationUrgency {
+        self.urgency
+    }
+
+    /// Returns the timeout of this [`NotificationMessage`].
+    pub fn timeout(&self) -> NotificationTimeout {
+        self.timeout
+    }
+
+    /// Returns the sound name of this [`NotificationMessage`].
+    pub fn sound(&self) -> Option<&str> {
+        self.sound.as_deref()
+    }
+}
+
+/// How urgent a notification is. A critical one is shown even if the desktop
+/// suppresses the others, e.g. in do-not-disturb mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NotificationUrgency {
+    Low,
+    #[default]
+    Normal,
+    Critical,
+}
+
+/// How long a notification stays on screen before it expires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NotificationTimeout {
+    /// Leave it to the notification server.
+    #[default]
+    Default,
+    /// Keep it until it is dismissed.
+    Never,
+    /// Expire after the duration.
+    After(Duration),
 }
 
 impl From<NotificationMessage> for (String, Option<String>) {
@@ -45,6 +151,12 @@ pub enum TryNewNotificationMessageError {
     #[snafu(display("Summary of a notification must be non-empty."))]
     #[non_exhaustive]
     EmptySummary,
+    #[snafu(display("Summary of a notification has {length} characters, more than {max}."))]
+    #[non_exhaustive]
+    SummaryTooLong { length: usize, max: usize },
+    #[snafu(display("Body of a notification has {length} characters, more than {max}."))]
+    #[non_exhaustive]
+    BodyTooLong { length: usize, max: usize },
 }
 
 #[cfg(test)]
@@ -57,13 +169,45 @@ mod tests {
             NotificationMessage::try_new("summary".into(), Some("body".into())),
             Ok(NotificationMessage {
                 summary: "summary".into(),
-                body: Some("body".into())
+                body: Some("body".into()),
+                urgency: NotificationUrgency::Normal,
+                timeout: NotificationTimeout::Default,
+                sound: None,
             })
         );
         assert_eq!(
             NotificationMessage::try_new("".into(), Some("whatever".into())),
             Err(TryNewNotificationMessageError::EmptySummary)
         );
+        assert_eq!(
+            NotificationMessage::try_new(" \t".into(), None),
+            Err(TryNewNotificationMessageError::EmptySummary)
+        );
+    }
+
+    #[test]
+    fn notification_message_try_limit() {
+        let msg = NotificationMessage::try_new("summary".into(), Some("ä body".into())).unwrap();
+        assert_eq!(msg.clone().try_limit(7), Ok(msg.clone()));
+        assert!(matches!(
+            msg.clone().try_limit(6),
+            Err(TryNewNotificationMessageError::SummaryTooLong {
+                length: 7,
+                max: 6,
+                ..
+            })
+        ));
+
+        let msg =
+            NotificationMessage::try_new("summary".into(), Some("a long body".into())).unwrap();
+        assert!(matches!(
+            msg.try_limit(8),
+            Err(TryNewNotificationMessageError::BodyTooLong {
+                length: 11,
+                max: 8,
+                ..
+            })
+        ));
     }
 
     #[test]
@@ -71,8 +215,34 @@ mod tests {
         let msg = NotificationMessage::try_new("summary".into(), Some("body".into())).unwrap();
         assert_eq!(msg.summary(), "summary");
         assert_eq!(msg.body(), Some("body"));
+        assert_eq!(msg.urgency(), NotificationUrgency::Normal);
+        let msg = msg.with_urgency(NotificationUrgency::Critical);
+        assert_eq!(msg.urgency(), NotificationUrgency::Critical);
+        assert_eq!(msg.timeout(), NotificationTimeout::Default);
+        let msg = msg.with_timeout(NotificationTimeout::Never);
+        assert_eq!(msg.timeout(), NotificationTimeout::Never);
+        assert_eq!(msg.sound(), None);
+        let msg = msg.with_sound("complete".into());
+        assert_eq!(msg.sound(), Some("complete"));
         let (inner_summary, inner_body) = msg.into();
         assert_eq!(inner_summary, "summary");
         assert_eq!(inner_body, Some("body".into()));
     }
+
+    #[test]
+    fn notification_message_with_variables() {
+        let msg = NotificationMessage::try_new(
+            "{stage} ends".into(),
+            Some("Next up: {stage} ({duration}), {unknown}".into()),
+        )
+        .unwrap()
+        .with_urgency(NotificationUrgency::Critical);
+        let msg = msg.with_variables(&[("stage", "Relaxation"), ("duration", "5:00")]);
+        assert_eq!(msg.summary(), "Relaxation ends");
+        assert_eq!(msg.body(), Some("Next up: Relaxation (5:00), {unknown}"));
+        assert_eq!(msg.urgency(), NotificationUrgency::Critical);
+
+        let msg = NotificationMessage::try_new("summary".into(), None).unwrap();
+        assert_eq!(msg.with_variables(&[("stage", "Relaxation")]), msg);
+    }
 }
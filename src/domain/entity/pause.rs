@@ -0,0 +1,23 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use serde::{Deserialize, Serialize};
+
+/// An external event which pauses the timer automatically. Unlike a manual
+/// pause, an automatic pause is only undone by the same kind of event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AutoPauseTrigger {
+    ScreenLock,
+    LowBattery,
+    /// The user has not touched the keyboard or mouse for a while.
+    Idle,
+}
+
+impl Display for AutoPauseTrigger {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::ScreenLock => f.write_str("ScreenLock"),
+            Self::LowBattery => f.write_str("LowBattery"),
+            Self::Idle => f.write_str("Idle"),
+        }
+    }
+}
@@ -0,0 +1,17 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::domain::entity::StageState;
+
+/// One upcoming stage in the timer's schedule.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduledStage {
+    pub name: String,
+    pub kind: StageState,
+    /// How long the stage lasts, or how long remains of the current one.
+    pub duration: Duration,
+    /// Whether the stage is a long break.
+    #[serde(default)]
+    pub long_break: bool,
+}
@@ -0,0 +1,254 @@
+use std::time::Duration;
+
+use snafu::prelude::*;
+
+use crate::domain::entity::{NotificationMessage, StageDuration, StageState};
+
+/// One stage in a [`StageSequence`]. Its `kind` decides how it takes part in
+/// long breaks, while `notification` is shown once it ends. The
+/// `checkpoint_notification` is shown as each of the `checkpoints`, offsets
+/// before the stage ends, is reached.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stage {
+    name: String,
+    kind: StageState,
+    duration: StageDuration,
+    notification: NotificationMessage,
+    checkpoints: Vec<Duration>,
+    checkpoint_notification: Option<NotificationMessage>,
+}
+
+impl Stage {
+    /// Try to create a [`Stage`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the name is empty.
+    pub fn try_new(
+        name: String,
+        kind: StageState,
+        duration: StageDuration,
+        notification: NotificationMessage,
+    ) -> Result<Self, TryNewStageError> {
+        ensure!(!name.is_empty(), EmptyNameSnafu);
+        Ok(Self {
+            name,
+            kind,
+            duration,
+            notification,
+            checkpoints: Vec::new(),
+            checkpoint_notification: None,
+        })
+    }
+
+    /// Show `notification` at each of the `checkpoints` before this stage
+    /// ends.
+    pub fn with_checkpoints(
+        mut self,
+        checkpoints: Vec<Duration>,
+        notification: NotificationMessage,
+    ) -> Self {
+        self.checkpoints = checkpoints;
+        self.checkpoint_notification = Some(notification);
+        self
+    }
+
+    /// Returns the name of this [`Stage`].
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the kind of this [`Stage`].
+    pub fn kind(&self) -> StageState {
+        self.kind
+    }
+
+    /// Returns a reference to the duration of this [`Stage`].
+    pub fn duration(&self) -> &StageDuration {
+        &self.duration
+    }
+
+    /// Returns a reference to the notification of this [`Stage`].
+    pub fn notification(&self) -> &NotificationMessage {
+        &self.notification
+    }
+
+    /// Returns the checkpoints of this [`Stage`], as offsets before it ends.
+    pub fn checkpoints(&self) -> &[Duration] {
+        &self.checkpoints
+    }
+
+    /// Returns the notification shown at each checkpoint of this [`Stage`],
+    /// or `None` if it has no checkpoint.
+    pub fn checkpoint_notification(&self) -> Option<&NotificationMessage> {
+        self.checkpoint_notification.as_ref()
+    }
+}
+
+/// An error type of creating a [`Stage`].
+#[derive(Debug, Clone, Snafu, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TryNewStageError {
+    #[snafu(display("Name of a stage must be non-empty"))]
+    #[non_exhaustive]
+    EmptyName,
+}
+
+/// An ordered list of [`Stage`]s, identified by their indices. The timer goes
+/// through them in order. After the last one, it starts over from the first
+/// stage which is not a [`Preparation`], so that preparations only happen once.
+///
+/// [`Preparation`]: StageState::Preparation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StageSequence {
+    stages: Vec<Stage>,
+    restart: usize,
+}
+
+impl StageSequence {
+    /// Try to create a [`StageSequence`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there is no stage other than
+    /// [`Preparation`]s, after which the timer could not continue.
+    ///
+    /// [`Preparation`]: StageState::Preparation
+    pub fn try_new(stages: Vec<Stage>) -> Result<Self, TryNewStageSequenceError> {
+        let restart = stages
+            .iter()
+            .position(|stage| stage.kind != StageState::Preparation)
+            .context(NoCycleSnafu)?;
+        Ok(Self { stages, restart })
+    }
+
+    /// Get the index of the first stage.
+    pub fn initial(&self) -> usize {
+        0
+    }
+
+    /// Get the index of the stage following the one at `index`.
+    pub fn next(&self, index: usize) -> usize {
+        if index + 1 < self.stages.len() {
+            index + 1
+        } else {
+            self.restart
+        }
+    }
+
+    /// Check whether the stage at `index` is the last one, after which the
+    /// sequence starts over.
+    pub fn is_last(&self, index: usize) -> bool {
+        index + 1 >= self.stages.len()
+    }
+
+    /// Get the number of stages.
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// Check whether there is no stage, which never holds for a successfully
+    /// created [`StageSequence`].
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// Get the stage at `index`, or `None` if it is out of range.
+    pub fn get(&self, index: usize) -> Option<&Stage> {
+        self.stages.get(index)
+    }
+}
+
+impl std::ops::Index<usize> for StageSequence {
+    type Output = Stage;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.stages[index]
+    }
+}
+
+/// An error type of creating a [`StageSequence`].
+#[derive(Debug, Clone, Snafu, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TryNewStageSequenceError {
+    #[snafu(display("Stage sequence must contain a stage other than preparations"))]
+    #[non_exhaustive]
+    NoCycle,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stage_try_new() {
+        assert!(matches!(
+            Stage::try_new(
+                "".to_owned(),
+                StageState::Concentration,
+                new_duration(),
+                new_message(),
+            ),
+            Err(TryNewStageError::EmptyName),
+        ));
+    }
+
+    #[test]
+    fn stage_sequence_next() {
+        let sequence = StageSequence::try_new(vec![
+            new_stage("Preparation", StageState::Preparation),
+            new_stage("Concentration", StageState::Concentration),
+            new_stage("Relaxation", StageState::Relaxation),
+        ])
+        .unwrap();
+
+        let index = sequence.initial();
+        assert_eq!(sequence[index].name(), "Preparation");
+        let index = sequence.next(index);
+        assert_eq!(sequence[index].name(), "Concentration");
+        let index = sequence.next(index);
+        assert_eq!(sequence[index].name(), "Relaxation");
+        let index = sequence.next(index);
+        assert_eq!(sequence[index].name(), "Concentration");
+        assert!(sequence.get(3).is_none());
+        assert_eq!(sequence.len(), 3);
+        assert!(!sequence.is_last(1));
+        assert!(sequence.is_last(2));
+    }
+
+    #[test]
+    fn stage_sequence_without_preparation() {
+        let sequence = StageSequence::try_new(vec![
+            new_stage("Short", StageState::Concentration),
+            new_stage("Long", StageState::Concentration),
+        ])
+        .unwrap();
+
+        assert_eq!(sequence.next(0), 1);
+        assert_eq!(sequence.next(1), 0);
+    }
+
+    #[test]
+    fn stage_sequence_try_new() {
+        assert_eq!(
+            StageSequence::try_new(vec![]),
+            Err(TryNewStageSequenceError::NoCycle),
+        );
+        assert_eq!(
+            StageSequence::try_new(vec![new_stage("Preparation", StageState::Preparation)]),
+            Err(TryNewStageSequenceError::NoCycle),
+        );
+    }
+
+    fn new_stage(name: &str, kind: StageState) -> Stage {
+        Stage::try_new(name.to_owned(), kind, new_duration(), new_message()).unwrap()
+    }
+
+    fn new_duration() -> StageDuration {
+        StageDuration::try_new(10).unwrap()
+    }
+
+    fn new_message() -> NotificationMessage {
+        NotificationMessage::try_new("summary".to_owned(), None).unwrap()
+    }
+}
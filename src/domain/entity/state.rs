@@ -1,29 +1,22 @@
 use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
 
-/// The state of the working procedure.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use clap::builder::PossibleValue;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use snafu::prelude::*;
+
+/// The kind of a stage in the working procedure. The order of stages is
+/// decided by a [`StageSequence`].
+///
+/// [`StageSequence`]: crate::domain::entity::StageSequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum StageState {
     Preparation,
     Concentration,
     Relaxation,
 }
 
-impl StageState {
-    /// Get an initialized [`StageState`].
-    pub fn initial() -> Self {
-        Self::Preparation
-    }
-
-    /// Get the next [`StageState`] based on the current one.
-    pub fn next(self) -> Self {
-        match self {
-            Self::Preparation => Self::Concentration,
-            Self::Concentration => Self::Relaxation,
-            Self::Relaxation => Self::Concentration,
-        }
-    }
-}
-
 impl Display for StageState {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         match self {
@@ -34,21 +27,71 @@ impl Display for StageState {
     }
 }
 
+impl FromStr for StageState {
+    type Err = ParseStageStateError;
+
+    /// Parse a stage's name case-insensitively.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "preparation" => Ok(Self::Preparation),
+            "concentration" => Ok(Self::Concentration),
+            "relaxation" => Ok(Self::Relaxation),
+            _ => UnknownSnafu { input: s }.fail(),
+        }
+    }
+}
+
+/// Let command line flags take a [`StageState`], so that the possible values
+/// are listed in help messages and errors. They are the lowercase names.
+impl ValueEnum for StageState {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Self::Preparation, Self::Concentration, Self::Relaxation]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        let name = match self {
+            Self::Preparation => "preparation",
+            Self::Concentration => "concentration",
+            Self::Relaxation => "relaxation",
+        };
+        Some(PossibleValue::new(name))
+    }
+}
+
+/// An error type for parsing a [`StageState`].
+#[derive(Debug, Snafu, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseStageStateError {
+    #[snafu(display(
+        "Unknown stage {input:?}, expect one of preparation, concentration and relaxation"
+    ))]
+    Unknown { input: String },
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn state_next() {
-        let state = StageState::initial();
-        assert_eq!(state, StageState::Preparation);
-        let state = state.next();
-        assert_eq!(state, StageState::Concentration);
-        let state = state.next();
-        assert_eq!(state, StageState::Relaxation);
-        let state = state.next();
-        assert_eq!(state, StageState::Concentration);
-        let state = state.next();
-        assert_eq!(state, StageState::Relaxation);
+    fn state_from_str() {
+        assert_eq!("concentration".parse(), Ok(StageState::Concentration));
+        assert_eq!("Relaxation".parse(), Ok(StageState::Relaxation));
+        assert!(matches!(
+            "lunch".parse::<StageState>(),
+            Err(ParseStageStateError::Unknown { .. })
+        ));
+    }
+
+    #[test]
+    fn state_display_round_trip() {
+        for state in [
+            StageState::Preparation,
+            StageState::Concentration,
+            StageState::Relaxation,
+        ] {
+            assert_eq!(state.to_string().parse(), Ok(state));
+            assert_eq!(state.to_string().to_uppercase().parse(), Ok(state));
+        }
+        assert_eq!(StageState::Concentration.to_string(), "Concentration");
     }
 }
@@ -0,0 +1,13 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Counts of the concentration stages which a timer has completed, both since
+/// it started and since the last local midnight.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimerStats {
+    pub completed_concentrations: u32,
+    pub concentration_time: Duration,
+    pub concentrations_today: u32,
+    pub concentration_time_today: Duration,
+}
@@ -0,0 +1,21 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use serde::{Deserialize, Serialize};
+
+/// Whether the timer is counting down, paused, or no longer running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimerStatus {
+    Running,
+    Paused,
+    Stopped,
+}
+
+impl Display for TimerStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Running => f.write_str("Running"),
+            Self::Paused => f.write_str("Paused"),
+            Self::Stopped => f.write_str("Stopped"),
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use std::error::Error as StdError;
+use std::time::Duration;
+
+use snafu::prelude::*;
+
+use crate::domain::entity::AutoPauseTrigger;
+
+/// An abstract interface for accessing options which control the timer's
+/// behavior.
+#[cfg_attr(test, mockall::automock)]
+#[async_trait::async_trait]
+pub trait BehaviorRepository: Send + Sync + 'static {
+    /// Check whether the timer should be paused automatically by the given
+    /// [`AutoPauseTrigger`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if failed to get the option.
+    async fn auto_pause_enabled(&self, trigger: AutoPauseTrigger)
+        -> Result<bool, GetBehaviorError>;
+
+    /// Get how long the user may be idle before the timer is paused. Return
+    /// `None` if idle detection is disabled.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if failed to get the option.
+    async fn idle_threshold(&self) -> Result<Option<Duration>, GetBehaviorError>;
+
+    /// Check whether the timer should wait in the paused state when it starts
+    /// afresh.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if failed to get the option.
+    async fn start_paused(&self) -> Result<bool, GetBehaviorError>;
+
+    /// Get how long a manual pause may last before the timer resumes by
+    /// itself. Return `None` if the timer stays paused until resumed.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if failed to get the option.
+    async fn max_pause(&self) -> Result<Option<Duration>, GetBehaviorError>;
+}
+
+/// An error type of accessing the repository of behavior options.
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum GetBehaviorError {
+    #[snafu(whatever, display("Load behavior option failed: {message}"))]
+    #[non_exhaustive]
+    Unknown {
+        message: String,
+        #[snafu(source(from(Box<dyn StdError>, Some)))]
+        source: Option<Box<dyn StdError>>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn behavior_repository_get() {
+        let mut mock = MockBehaviorRepository::new();
+        mock.expect_auto_pause_enabled()
+            .returning(|trigger| Ok(trigger == AutoPauseTrigger::ScreenLock));
+
+        assert!(mock
+            .auto_pause_enabled(AutoPauseTrigger::ScreenLock)
+            .await
+            .unwrap());
+    }
+}
@@ -3,6 +3,7 @@ use std::error::Error as StdError;
 use snafu::prelude::*;
 
 use crate::domain::entity::duration::{StageDuration, TryNewStageDurationError};
+use crate::domain::entity::{StageState, TimerMode};
 
 /// An abstract interface for accessing duration data.
 #[cfg_attr(test, mockall::automock)]
@@ -34,6 +35,42 @@ pub trait DurationRepository: Send + Sync + 'static {
     ///
     /// This function will return an error if failed to get the duration.
     async fn relaxation_duration(&self) -> Result<StageDuration, GetDurationError>;
+
+    /// Get duration of a long break, which replaces the [`Relaxation`] stage
+    /// after several [`Concentration`] stages.
+    ///
+    /// [`Relaxation`]: crate::domain::entity::StageState::Relaxation
+    /// [`Concentration`]: crate::domain::entity::StageState::Concentration
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if failed to get the duration.
+    async fn long_break_duration(&self) -> Result<StageDuration, GetDurationError>;
+
+    /// Get the number of [`Concentration`] stages before a long break. Long
+    /// breaks are disabled if it is zero.
+    ///
+    /// [`Concentration`]: crate::domain::entity::StageState::Concentration
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if failed to get the number.
+    async fn cycles_before_long_break(&self) -> Result<u32, GetDurationError>;
+
+    /// Get the kind of the stage which the timer starts from. Return `None`
+    /// to start from the first stage.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if failed to get the kind.
+    async fn initial_stage(&self) -> Result<Option<StageState>, GetDurationError>;
+
+    /// Get how the timer goes through its stages.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if failed to get the mode.
+    async fn mode(&self) -> Result<TimerMode, GetDurationError>;
 }
 
 /// An error type of accessing the repository of [`StageDuration`]s.
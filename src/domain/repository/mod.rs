@@ -1,5 +1,9 @@
+pub mod behavior;
 pub mod duration;
 pub mod notification;
+pub mod stage;
 
+pub use behavior::BehaviorRepository;
 pub use duration::DurationRepository;
 pub use notification::NotificationRepository;
+pub use stage::StageRepository;
@@ -0,0 +1,61 @@
+use std::error::Error as StdError;
+
+use snafu::prelude::*;
+
+use crate::domain::entity::duration::TryNewStageDurationError;
+use crate::domain::entity::notification::TryNewNotificationMessageError;
+use crate::domain::entity::sequence::{Stage, TryNewStageError};
+
+/// An abstract interface for accessing a user-defined sequence of stages.
+#[cfg_attr(test, mockall::automock)]
+#[async_trait::async_trait]
+pub trait StageRepository: Send + Sync + 'static {
+    /// Get the custom [`Stage`]s in order. Return an empty list if the
+    /// default stages should be used.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if failed to get the stages.
+    async fn stages(&self) -> Result<Vec<Stage>, GetStageError>;
+}
+
+/// An error type of accessing the repository of [`Stage`]s.
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum GetStageError {
+    #[snafu(display("Could not create stage {name:?} with an invalid duration"))]
+    #[non_exhaustive]
+    InvalidDuration {
+        name: String,
+        source: TryNewStageDurationError,
+    },
+    #[snafu(display("Could not create stage {name:?} with an invalid notification message"))]
+    #[non_exhaustive]
+    InvalidNotification {
+        name: String,
+        source: TryNewNotificationMessageError,
+    },
+    #[snafu(display("Could not create an invalid stage"))]
+    #[non_exhaustive]
+    Invalid { source: TryNewStageError },
+    #[snafu(whatever, display("Load stages failed: {message}"))]
+    #[non_exhaustive]
+    Unknown {
+        message: String,
+        #[snafu(source(from(Box<dyn StdError>, Some)))]
+        source: Option<Box<dyn StdError>>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn stage_repository_get() {
+        let mut mock = MockStageRepository::new();
+        mock.expect_stages().returning(|| Ok(Vec::new()));
+
+        assert!(mock.stages().await.unwrap().is_empty());
+    }
+}
@@ -3,4 +3,5 @@ pub mod client;
 pub mod daemon;
 pub mod domain;
 pub mod protocol;
+pub mod timer;
 pub mod utils;
@@ -4,19 +4,40 @@ use bytes::{Buf, BytesMut};
 use snafu::prelude::*;
 use tokio::io::{AsyncReadExt, AsyncWriteExt, Error};
 use tokio::sync::Semaphore;
+use tokio::time::Duration;
 
-use crate::protocol::frame::{Frame, ParseFrameError, WriteFrameError};
+use crate::protocol::frame::{Frame, FrameCodec, ParseFrameError, WriteFrameError};
+use crate::protocol::Protocol;
 use crate::utils::stream::Stream;
 
 /// A wrapper of a stream (typically a socket), which handles sending and
 /// receiving frames through the stream.
+///
+/// Frames are sent in the [`FrameCodec`] of the connection, which follows the
+/// frames received, so that the peer is always answered in its own codec.
 pub struct Connection<S: Stream> {
     stream: S,
     buffer: BytesMut,
     semaphore: Semaphore,
+    read_timeout: Option<Duration>,
+    codec: FrameCodec,
 }
 
 impl<S: Stream> Connection<S> {
+    /// Give up receiving a [`Frame`] if it is not complete within `timeout`.
+    /// Receiving waits forever by default.
+    pub fn with_read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Send frames in the given [`FrameCodec`] until a frame in another one
+    /// is received. JSON is used by default.
+    pub fn with_codec(mut self, codec: FrameCodec) -> Self {
+        self.codec = codec;
+        self
+    }
+
     /// Serialize a [`Frame`] to bytes and send it through the wrapped stream.
     ///
     /// # Errors
@@ -30,6 +51,7 @@ impl<S: Stream> Connection<S> {
         };
 
         let mut buffer = BytesMut::with_capacity(256);
+        let frame = frame.with_codec(self.codec);
         frame.write(&mut buffer).context(WriteSnafuS)?;
 
         self.stream
@@ -40,31 +62,75 @@ impl<S: Stream> Connection<S> {
         Ok(())
     }
 
+    /// Shut down the write side of the wrapped stream, signaling the peer that
+    /// no more data will be sent. Receiving is still possible afterwards.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if network IO fails.
+    pub async fn shutdown_write(&mut self) -> Result<(), SendFrameError> {
+        let Ok(_permit) = self.semaphore.acquire().await else {
+            unreachable!("Semaphore should not be closed");
+        };
+
+        self.stream.shutdown().await.context(NetworkSnafuS)
+    }
+
     /// Receive bytes from the wrapped stream and then deserialize the
     /// [`Frame`].
+    ///
     /// # Errors
     ///
-    /// This function will return an error if deserialization fails or network
-    /// IO fails.
+    /// This function will return an error if deserialization fails, network
+    /// IO fails or the read timeout expires.
     pub async fn receive(&mut self) -> Result<Frame, ReceiveFrameError> {
         // Use a semaphore here to make this function can be called atomically.
         let Ok(_permit) = self.semaphore.acquire().await else {
             unreachable!("Semaphore should not be closed");
         };
 
+        let receiving = Self::receive_frame(&mut self.stream, &mut self.buffer);
+        let frame = match self.read_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, receiving)
+                .await
+                .ok()
+                .context(TimeoutSnafuR { timeout })??,
+            None => receiving.await?,
+        };
+
+        self.codec = frame.codec();
+        Ok(frame)
+    }
+
+    /// Send `request` and then receive the peer's reply to it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if either sending or receiving
+    /// fails.
+    pub async fn request(&mut self, request: Protocol) -> Result<Protocol, RequestFrameError> {
+        self.send(request.into()).await.context(SendSnafuQ)?;
+        let response = self.receive().await.context(ReceiveSnafuQ)?;
+        Ok(response.into())
+    }
+
+    async fn receive_frame(
+        stream: &mut S,
+        buffer: &mut BytesMut,
+    ) -> Result<Frame, ReceiveFrameError> {
         loop {
-            let tmp_buffer = &self.buffer[..];
+            let tmp_buffer = &buffer[..];
 
             match Frame::parse(tmp_buffer) {
                 Ok((frame, offset)) => {
-                    self.buffer.advance(offset);
+                    buffer.advance(offset);
                     return Ok(frame);
                 }
                 Err(ParseFrameError::Incomplete) => {}
                 Err(err) => return Err(err).context(ParseSnafuR),
             }
 
-            match self.stream.read_buf(&mut self.buffer).await {
+            match stream.read_buf(buffer).await {
                 Ok(0) => return ClosedSnafuR.fail(),
                 Err(err) => return Err(err).context(NetworkSnafuR),
                 _ => {}
@@ -79,6 +145,8 @@ impl<S: Stream> From<S> for Connection<S> {
             stream: value,
             buffer: BytesMut::with_capacity(1024),
             semaphore: Semaphore::new(1),
+            read_timeout: None,
+            codec: FrameCodec::default(),
         }
     }
 }
@@ -102,6 +170,8 @@ pub enum ReceiveFrameError {
     Parse { source: ParseFrameError },
     #[snafu(display("Connection is closed by the peer"))]
     Closed,
+    #[snafu(display("No complete frame is received within {} seconds", timeout.as_secs_f64()))]
+    Timeout { timeout: Duration },
     #[snafu(display("Could not receive bytes through inner stream"))]
     Network {
         #[snafu(source(from(Error, Arc::new)))]
@@ -109,14 +179,23 @@ pub enum ReceiveFrameError {
     },
 }
 
+#[derive(Debug, Snafu, Clone)]
+#[snafu(context(suffix(SnafuQ)))]
+pub enum RequestFrameError {
+    #[snafu(display("Could not send request"))]
+    Send { source: SendFrameError },
+    #[snafu(display("Could not receive response"))]
+    Receive { source: ReceiveFrameError },
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     use bytes::BufMut;
-    use tokio::time::Duration;
 
-    use crate::protocol::{Protocol, Response};
+    use crate::domain::entity::{StageState, TimerStatus};
+    use crate::protocol::{Request, Response};
 
     #[tokio::test]
     async fn connection_send() {
@@ -160,6 +239,8 @@ mod tests {
         tokio::spawn(async move {
             let mut raw = BytesMut::new();
             raw.put_u8(b'+');
+            raw.put_u8(Frame::VERSION);
+            raw.put_u8(b'j');
             raw.put_u64(8);
             raw.put_slice(b"whatever");
             sender.write_all(&raw[..]).await.unwrap();
@@ -173,6 +254,29 @@ mod tests {
         ));
     }
 
+    #[tokio::test]
+    async fn connection_receive_error_too_long() {
+        let (mut sender, receiver) = tokio::io::duplex(1024);
+        let mut connection = Connection::from(receiver);
+
+        // The header claims a huge body, which is rejected before any of it
+        // arrives while the peer keeps the connection open.
+        let mut raw = BytesMut::new();
+        raw.put_u8(b'+');
+        raw.put_u8(Frame::VERSION);
+        raw.put_u8(b'j');
+        raw.put_u64(1 << 40);
+        sender.write_all(&raw[..]).await.unwrap();
+
+        assert!(matches!(
+            connection.receive().await,
+            Err(ReceiveFrameError::Parse {
+                source: ParseFrameError::TooLong { .. }
+            })
+        ));
+        drop(sender);
+    }
+
     #[tokio::test]
     async fn connection_receive_error_closed() {
         let (expected, buffer) = new_frame();
@@ -190,13 +294,110 @@ mod tests {
         ));
     }
 
+    #[tokio::test(start_paused = true)]
+    async fn connection_receive_error_timeout() {
+        let (_, buffer) = new_frame();
+        let (mut sender, receiver) = tokio::io::duplex(1024);
+        let timeout = Duration::from_secs(10);
+        let mut connection = Connection::from(receiver).with_read_timeout(timeout);
+
+        // Only half of a frame ever arrives.
+        sender.write_all(&buffer[..buffer.len() / 2]).await.unwrap();
+
+        let start = tokio::time::Instant::now();
+        assert!(matches!(
+            connection.receive().await,
+            Err(ReceiveFrameError::Timeout { timeout: actual }) if actual == timeout
+        ));
+        assert_eq!(start.elapsed(), timeout);
+        drop(sender);
+    }
+
+    #[tokio::test]
+    async fn connection_shutdown_write() {
+        let (expected, _) = new_frame();
+        let (local, peer) = tokio::io::duplex(1024);
+        let mut local = Connection::from(local);
+        let mut peer = Connection::from(peer);
+
+        local.send(expected.clone()).await.unwrap();
+        local.shutdown_write().await.unwrap();
+
+        assert_eq!(peer.receive().await.unwrap(), expected);
+        assert!(matches!(
+            peer.receive().await,
+            Err(ReceiveFrameError::Closed)
+        ));
+        drop(local);
+    }
+
+    #[tokio::test]
+    async fn connection_request() {
+        let (expected, _) = new_frame();
+        let (local, peer) = tokio::io::duplex(1024);
+        let mut local = Connection::from(local);
+        let mut peer = Connection::from(peer);
+
+        let reply = expected.clone();
+        tokio::spawn(async move {
+            let request: Protocol = peer.receive().await.unwrap().into();
+            assert_eq!(request, Protocol::request(Request::Query));
+            peer.send(reply).await.unwrap();
+        });
+
+        let response = local.request(Protocol::request(Request::Query)).await;
+        assert_eq!(response.unwrap(), Protocol::from(expected));
+    }
+
+    #[tokio::test]
+    async fn connection_request_error_receive() {
+        let (local, peer) = tokio::io::duplex(1024);
+        let mut local = Connection::from(local);
+
+        tokio::spawn(async move {
+            let mut peer = Connection::from(peer);
+            let _ = peer.receive().await.unwrap();
+        });
+
+        assert!(matches!(
+            local.request(Protocol::request(Request::Query)).await,
+            Err(RequestFrameError::Receive {
+                source: ReceiveFrameError::Closed
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn connection_reply_in_codec() {
+        let (expected, _) = new_frame();
+        let (local, peer) = tokio::io::duplex(1024);
+        let mut local = Connection::from(local).with_codec(FrameCodec::MessagePack);
+        let mut peer = Connection::from(peer);
+
+        let reply = expected.clone();
+        tokio::spawn(async move {
+            let request = peer.receive().await.unwrap();
+            assert_eq!(request.codec(), FrameCodec::MessagePack);
+            peer.send(reply).await.unwrap();
+        });
+
+        local.send(expected.clone()).await.unwrap();
+        let response = local.receive().await.unwrap();
+        assert_eq!(response.codec(), FrameCodec::MessagePack);
+        assert_eq!(Protocol::from(response), Protocol::from(expected));
+    }
+
     fn new_frame() -> (Frame, BytesMut) {
         let frame: Frame = Protocol::Response(Response::Query {
-            current: "Running".to_owned(),
+            current: TimerStatus::Running,
             stage: "Preparation".to_owned(),
+            kind: StageState::Preparation,
             total: Duration::from_secs(20),
             remaining: Duration::from_secs(15),
             past: Duration::from_secs(5),
+            long_break: false,
+            completed_cycles: 0,
+            muted: false,
         })
         .into();
 
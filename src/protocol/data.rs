@@ -1,13 +1,67 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
 use serde::{Deserialize, Serialize};
 use tokio::time::Duration;
 
+use crate::domain::entity::{AutoPauseTrigger, ScheduledStage, StageState, TimerStats};
+use crate::domain::entity::{TimerEvent, TimerStatus};
+
 /// A [`Protocol`] represents the underlying data type used by
 /// the protocol.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum Protocol {
-    Request(Request),
+    Request {
+        /// The session the request is routed to. The default session is used
+        /// if it is omitted.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        session: Option<String>,
+        #[serde(flatten)]
+        request: Request,
+    },
     Response(Response),
+    /// The first message of a connection from both sides, so that each peer
+    /// can check whether the other one speaks a compatible protocol.
+    Hello {
+        version: ProtocolVersion,
+    },
+    /// Sent by a client to end a connection kept alive for several requests.
+    /// Closing the connection has the same effect.
+    Quit,
+}
+
+impl Protocol {
+    /// Creates a [`Protocol::Request`] routed to the default session.
+    pub fn request(request: Request) -> Self {
+        Self::Request {
+            session: None,
+            request,
+        }
+    }
+}
+
+/// Version of the protocol. Peers are compatible if their major versions are
+/// the same.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProtocolVersion {
+    pub major: u16,
+    pub minor: u16,
+}
+
+impl ProtocolVersion {
+    /// The version spoken by this build.
+    pub const CURRENT: Self = Self { major: 1, minor: 0 };
+
+    /// Check whether a peer speaking `other` could talk with this version.
+    pub fn is_compatible(&self, other: &Self) -> bool {
+        self.major == other.major
+    }
+}
+
+impl Display for ProtocolVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
 }
 
 /// A [`Request`] represents requests from a client.
@@ -17,7 +71,35 @@ pub enum Request {
     Pause,
     Resume,
     Query,
-    Skip,
+    Schedule,
+    Stats,
+    Subscribe,
+    /// A liveness check, which only pings the timer.
+    Health,
+    Skip {
+        #[serde(default)]
+        notify: bool,
+    },
+    Reset,
+    Mute,
+    Unmute,
+    Adjust {
+        delta_secs: i64,
+    },
+    /// Make the current stage last `total` in all.
+    SetDuration {
+        total: Duration,
+    },
+    SkipTo {
+        stage: StageState,
+    },
+    Stop,
+    AutoPause {
+        trigger: AutoPauseTrigger,
+    },
+    AutoResume {
+        trigger: AutoPauseTrigger,
+    },
 }
 
 /// A [`Response`] represents a daemon's reply.
@@ -27,13 +109,53 @@ pub enum Response {
     Pause,
     Resume,
     Query {
-        current: String,
+        current: TimerStatus,
+        /// Name of the current stage.
         stage: String,
+        kind: StageState,
         total: Duration,
         remaining: Duration,
         past: Duration,
+        #[serde(default)]
+        long_break: bool,
+        #[serde(default)]
+        completed_cycles: u32,
+        #[serde(default)]
+        muted: bool,
+    },
+    Schedule {
+        /// The current stage with its remaining time, followed by the stages
+        /// of one round of the sequence.
+        stages: Vec<ScheduledStage>,
+    },
+    Stats {
+        /// Counts of the completed concentration stages.
+        stats: TimerStats,
+    },
+    /// A change of the timer's state, sent after [`Request::Subscribe`]
+    /// until the subscription ends.
+    Event {
+        event: TimerEvent,
+    },
+    Health {
+        /// How long the daemon has been up.
+        uptime: Duration,
+        status: TimerStatus,
     },
     Skip,
+    Reset,
+    Mute,
+    Unmute,
+    Adjust,
+    SetDuration,
+    SkipTo,
+    Stop,
+    AutoPause,
+    AutoResume,
+    /// The request could not be handled, for the reason in `message`.
+    Error {
+        message: String,
+    },
 }
 
 #[cfg(test)]
@@ -47,6 +169,7 @@ mod tests {
             "method": "Query",
             "current": "Running",
             "stage": "Preparation",
+            "kind": "Preparation",
             "total": {
                 "secs": 20,
                 "nanos": 0
@@ -62,13 +185,77 @@ mod tests {
         });
 
         let data = Protocol::Response(Response::Query {
-            current: "Running".to_owned(),
+            current: TimerStatus::Running,
             stage: "Preparation".to_owned(),
+            kind: StageState::Preparation,
             total: Duration::from_secs(20),
             remaining: Duration::from_secs(15),
             past: Duration::from_secs(5),
+            long_break: false,
+            completed_cycles: 0,
+            muted: false,
+        });
+
+        assert_eq!(serde_json::from_value::<Protocol>(text).unwrap(), data);
+    }
+
+    #[test]
+    fn protocol_deserialize_skip_default() {
+        let text = serde_json::json!({
+            "type": "Request",
+            "method": "Skip",
         });
 
+        let data = Protocol::request(Request::Skip { notify: false });
+
+        assert_eq!(serde_json::from_value::<Protocol>(text).unwrap(), data);
+    }
+
+    #[test]
+    fn protocol_request_session() {
+        let text = serde_json::json!({
+            "type": "Request",
+            "method": "Skip",
+            "session": "work",
+            "notify": true,
+        });
+
+        let data = Protocol::Request {
+            session: Some("work".to_owned()),
+            request: Request::Skip { notify: true },
+        };
+
+        assert_eq!(
+            serde_json::from_value::<Protocol>(text.clone()).unwrap(),
+            data
+        );
+        assert_eq!(serde_json::to_value(&data).unwrap(), text);
+
+        let text = serde_json::json!({
+            "type": "Request",
+            "method": "Pause",
+        });
+        assert_eq!(
+            serde_json::to_value(Protocol::request(Request::Pause)).unwrap(),
+            text
+        );
+    }
+
+    #[test]
+    fn protocol_version_compatible() {
+        let version = |major, minor| ProtocolVersion { major, minor };
+
+        assert!(version(1, 0).is_compatible(&version(1, 3)));
+        assert!(!version(1, 0).is_compatible(&version(2, 0)));
+        assert_eq!(version(1, 3).to_string(), "1.3");
+
+        let text = serde_json::json!({
+            "type": "Hello",
+            "version": { "major": 1, "minor": 0 },
+        });
+        let data = Protocol::Hello {
+            version: version(1, 0),
+        };
         assert_eq!(serde_json::from_value::<Protocol>(text).unwrap(), data);
     }
 }
@@ -1,23 +1,136 @@
+use std::error::Error as StdError;
+use std::fmt::{Display, Formatter, Result as FmtResult};
 use std::sync::Arc;
 
 use bytes::{Buf, BufMut};
-use serde_json::Error as SerdeError;
 use snafu::prelude::*;
 
 use crate::protocol::data::Protocol;
 
+/// An error from either of the serde formats behind [`FrameCodec`]s.
+type CodecError = Arc<dyn StdError + Send + Sync>;
+
 /// A wrapper of [`Protocol`] for converting the internal data from and to
 /// bytes and being transmitted through byte stream.
 ///
 /// The layout of a [`Frame`] in bytes is described below:
-/// - starts with a `b'+'` and a `u64` as inner data's length,
+/// - starts with a start symbol selecting the [`FrameFormat`],
+/// - followed by one byte of the protocol version, which must be
+///   [`Frame::VERSION`],
+/// - followed by one byte selecting the [`FrameCodec`] of the data,
+/// - followed by inner data's length encoded in that format,
 /// - followed by data of the length mentioned above.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Frame {
     data: Protocol,
+    format: FrameFormat,
+    codec: FrameCodec,
+}
+
+/// The encoding of a [`Frame`]'s length prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FrameFormat {
+    /// A `b'+'` followed by a big-endian `u64`.
+    #[default]
+    Fixed,
+    /// A `b'~'` followed by an unsigned LEB128 varint, which takes only one
+    /// byte for data shorter than 128 bytes.
+    Varint,
+}
+
+impl FrameFormat {
+    /// The maximum number of bytes of a LEB128-encoded `u64`.
+    const MAX_VARINT_LEN: usize = 10;
+
+    fn start(self) -> u8 {
+        match self {
+            Self::Fixed => b'+',
+            Self::Varint => b'~',
+        }
+    }
+
+    fn from_start(start: u8) -> Option<Self> {
+        match start {
+            b'+' => Some(Self::Fixed),
+            b'~' => Some(Self::Varint),
+            _ => None,
+        }
+    }
+}
+
+/// The encoding of a [`Frame`]'s data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FrameCodec {
+    /// Human-readable JSON, marked by `b'j'`.
+    #[default]
+    Json,
+    /// Compact MessagePack, marked by `b'm'`.
+    MessagePack,
+}
+
+impl FrameCodec {
+    fn marker(self) -> u8 {
+        match self {
+            Self::Json => b'j',
+            Self::MessagePack => b'm',
+        }
+    }
+
+    fn from_marker(marker: u8) -> Option<Self> {
+        match marker {
+            b'j' => Some(Self::Json),
+            b'm' => Some(Self::MessagePack),
+            _ => None,
+        }
+    }
+}
+
+impl Display for FrameCodec {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Json => f.write_str("JSON"),
+            Self::MessagePack => f.write_str("MessagePack"),
+        }
+    }
 }
 
 impl Frame {
+    /// The protocol version written in every [`Frame`]. Bump it whenever
+    /// peers of different versions could no longer understand each other.
+    pub const VERSION: u8 = 2;
+
+    /// The maximum length of a [`Frame`]'s data in bytes, far above any
+    /// message of this protocol. A longer frame is rejected as soon as its
+    /// length is read, so that a peer could never make the receiver wait for
+    /// and buffer a huge payload.
+    pub const MAX_LEN: usize = 4 * 1024 * 1024;
+
+    /// Creates a new [`Frame`] which is written in the given [`FrameFormat`].
+    pub fn with_format(data: Protocol, format: FrameFormat) -> Self {
+        Self {
+            data,
+            format,
+            codec: FrameCodec::default(),
+        }
+    }
+
+    /// Encode the data of this [`Frame`] with the given [`FrameCodec`]
+    /// instead of JSON.
+    pub fn with_codec(mut self, codec: FrameCodec) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    /// Returns the [`FrameFormat`] of this [`Frame`].
+    pub fn format(&self) -> FrameFormat {
+        self.format
+    }
+
+    /// Returns the [`FrameCodec`] of this [`Frame`].
+    pub fn codec(&self) -> FrameCodec {
+        self.codec
+    }
+
     /// Parse a [`Frame`] from one of buf's prefix and advance buf's cursor.
     /// Return a [`Frame`] and the offset from the initial position.
     ///
@@ -30,21 +143,87 @@ impl Frame {
     /// This function will return an error if there is no enough byte or the
     /// data is broken.
     pub fn parse<B: Buf>(mut buf: B) -> Result<(Self, usize), ParseFrameError> {
-        // Try to get `b'+'`.
+        // Try to get the start symbol.
         ensure!(buf.remaining() >= 1, IncompleteSnafu);
-        ensure!(buf.get_u8() == b'+', InvalidStartSnafu);
+        let format = FrameFormat::from_start(buf.get_u8()).context(InvalidStartSnafu)?;
+
+        // Try to get the version.
+        ensure!(buf.remaining() >= 1, IncompleteSnafu);
+        let version = buf.get_u8();
+        ensure!(
+            version == Self::VERSION,
+            UnsupportedVersionSnafu {
+                got: version,
+                expected: Self::VERSION,
+            }
+        );
+
+        // Try to get the codec.
+        ensure!(buf.remaining() >= 1, IncompleteSnafu);
+        let marker = buf.get_u8();
+        let codec = FrameCodec::from_marker(marker).context(UnknownCodecSnafu { marker })?;
 
         // Try to get the length.
-        ensure!(buf.remaining() >= 8, IncompleteSnafu);
-        let len = buf.get_u64() as usize;
+        let (len, header) = match format {
+            FrameFormat::Fixed => {
+                ensure!(buf.remaining() >= 8, IncompleteSnafu);
+                (buf.get_u64(), 8)
+            }
+            FrameFormat::Varint => Self::get_varint(&mut buf)?,
+        };
+        ensure!(
+            len <= Self::MAX_LEN as u64,
+            TooLongSnafu {
+                len,
+                max: Self::MAX_LEN,
+            }
+        );
+        let len = len as usize;
         ensure!(len > 0, InvalidLengthSnafu);
 
         // Try to parse a `Frame` from remaining bytes.
         ensure!(buf.remaining() >= len, IncompleteSnafu);
-        let reader = buf.take(len).reader();
-        let data: Protocol = serde_json::from_reader(reader).context(DeserializationSnafu)?;
+        let data = buf.take(len).reader();
+        let data: Result<Protocol, CodecError> = match codec {
+            FrameCodec::Json => serde_json::from_reader(data).map_err(|err| Arc::new(err) as _),
+            FrameCodec::MessagePack => rmp_serde::from_read(data).map_err(|err| Arc::new(err) as _),
+        };
+        let data = data.context(DeserializationSnafu { codec })?;
+
+        let frame = Self::with_format(data, format).with_codec(codec);
+        Ok((frame, 3 + header + len))
+    }
 
-        Ok((data.into(), 9 + len))
+    /// Read an unsigned LEB128 varint from buf. Return the value and the
+    /// number of bytes it takes.
+    fn get_varint<B: Buf>(mut buf: B) -> Result<(u64, usize), ParseFrameError> {
+        let mut value = 0u64;
+
+        for i in 0..FrameFormat::MAX_VARINT_LEN {
+            ensure!(buf.remaining() >= 1, IncompleteSnafu);
+            let byte = buf.get_u8();
+            let bits = u64::from(byte & 0x7f);
+
+            // The 10th byte could only hold the highest bit of a `u64`.
+            let shift = 7 * i as u32;
+            ensure!(bits << shift >> shift == bits, LengthOverflowSnafu);
+            value |= bits << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok((value, i + 1));
+            }
+        }
+
+        LengthOverflowSnafu.fail()
+    }
+
+    /// Write an unsigned LEB128 varint to buf.
+    fn put_varint<B: BufMut>(mut buf: B, mut value: u64) {
+        while value >= 0x80 {
+            buf.put_u8((value & 0x7f) as u8 | 0x80);
+            value >>= 7;
+        }
+        buf.put_u8(value as u8);
     }
 
     /// Serialize a [`Frame`] and write it to buf.
@@ -53,17 +232,29 @@ impl Frame {
     ///
     /// This function will return an error if the serialization fails.
     pub fn write<B: BufMut>(&self, mut buf: B) -> Result<(), WriteFrameError> {
-        let data = serde_json::to_string(&self.data).context(SerializationSnafu)?;
-        buf.put_u8(b'+');
-        buf.put_u64(data.len() as u64);
-        buf.put_slice(data.as_bytes());
+        let data: Result<Vec<u8>, CodecError> = match self.codec {
+            FrameCodec::Json => serde_json::to_vec(&self.data).map_err(|err| Arc::new(err) as _),
+            FrameCodec::MessagePack => {
+                rmp_serde::to_vec_named(&self.data).map_err(|err| Arc::new(err) as _)
+            }
+        };
+        let data = data.context(SerializationSnafu { codec: self.codec })?;
+
+        buf.put_u8(self.format.start());
+        buf.put_u8(Self::VERSION);
+        buf.put_u8(self.codec.marker());
+        match self.format {
+            FrameFormat::Fixed => buf.put_u64(data.len() as u64),
+            FrameFormat::Varint => Self::put_varint(&mut buf, data.len() as u64),
+        }
+        buf.put_slice(&data);
         Ok(())
     }
 }
 
 impl From<Protocol> for Frame {
     fn from(value: Protocol) -> Self {
-        Self { data: value }
+        Self::with_format(value, FrameFormat::default())
     }
 }
 
@@ -81,12 +272,23 @@ pub enum ParseFrameError {
     Incomplete,
     #[snafu(display("Could not parse the start symbol"))]
     InvalidStart,
+    #[snafu(display(
+        "Unsupported protocol version {got}, expected {expected}, \
+        make sure the client and the daemon are of the same version"
+    ))]
+    UnsupportedVersion { got: u8, expected: u8 },
+    #[snafu(display("Unknown codec marker {marker:#04x}"))]
+    UnknownCodec { marker: u8 },
     #[snafu(display("The content length should be non-zero"))]
     InvalidLength,
-    #[snafu(display("Could not deserialize data"))]
+    #[snafu(display("The content length exceeds the maximum"))]
+    LengthOverflow,
+    #[snafu(display("The content length {len} exceeds the limit of {max} bytes"))]
+    TooLong { len: u64, max: usize },
+    #[snafu(display("Could not deserialize data encoded as {codec}"))]
     Deserialization {
-        #[snafu(source(from(SerdeError, Arc::new)))]
-        source: Arc<SerdeError>,
+        codec: FrameCodec,
+        source: CodecError,
     },
 }
 
@@ -94,10 +296,10 @@ pub enum ParseFrameError {
 #[derive(Debug, Snafu, Clone)]
 #[non_exhaustive]
 pub enum WriteFrameError {
-    #[snafu(display("Could not serialize frame"))]
+    #[snafu(display("Could not serialize frame as {codec}"))]
     Serialization {
-        #[snafu(source(from(SerdeError, Arc::new)))]
-        source: Arc<SerdeError>,
+        codec: FrameCodec,
+        source: CodecError,
     },
 }
 
@@ -108,7 +310,8 @@ mod tests {
     use bytes::BytesMut;
     use tokio::time::Duration;
 
-    use crate::protocol::data::Response;
+    use crate::domain::entity::{StageState, TimerStatus};
+    use crate::protocol::data::{Request, Response};
 
     #[test]
     fn frame_parse() {
@@ -118,6 +321,7 @@ mod tests {
                 "method": "Query",
                 "current": "Running",
                 "stage": "Preparation",
+                "kind": "Preparation",
                 "total": {
                     "secs": 20,
                     "nanos": 0
@@ -134,6 +338,8 @@ mod tests {
         "#;
         let mut raw = BytesMut::new();
         raw.put_u8(b'+');
+        raw.put_u8(Frame::VERSION);
+        raw.put_u8(b'j');
         raw.put_u64(inner.len() as u64);
         raw.put_slice(inner);
         raw.put_slice(b"whatever");
@@ -141,16 +347,20 @@ mod tests {
         let (actual, offset) = Frame::parse(&mut raw).unwrap();
 
         let expected = Protocol::Response(Response::Query {
-            current: "Running".to_owned(),
+            current: TimerStatus::Running,
             stage: "Preparation".to_owned(),
+            kind: StageState::Preparation,
             total: Duration::from_secs(20),
             remaining: Duration::from_secs(15),
             past: Duration::from_secs(5),
+            long_break: false,
+            completed_cycles: 0,
+            muted: false,
         })
         .into();
 
         assert_eq!(actual, expected);
-        assert_eq!(offset, 9 + inner.len());
+        assert_eq!(offset, 11 + inner.len());
 
         assert_eq!(raw.as_ref(), b"whatever");
     }
@@ -165,6 +375,8 @@ mod tests {
 
         let mut raw = BytesMut::new();
         raw.put_u8(b'+');
+        raw.put_u8(Frame::VERSION);
+        raw.put_u8(b'j');
         raw.put_u64(10);
         assert!(matches!(
             Frame::parse(&mut raw),
@@ -173,6 +385,8 @@ mod tests {
 
         let mut raw = BytesMut::new();
         raw.put_u8(b'+');
+        raw.put_u8(Frame::VERSION);
+        raw.put_u8(b'j');
         raw.put_u64(20);
         raw.put_slice(b"not enough");
         assert!(matches!(
@@ -190,10 +404,37 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn frame_parse_error_unsupported_version() {
+        let mut raw = BytesMut::new();
+        raw.put_u8(b'+');
+        raw.put_u8(Frame::VERSION + 1);
+        raw.put_u64(8);
+        raw.put_slice(b"whatever");
+        let err = Frame::parse(&mut raw).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseFrameError::UnsupportedVersion { got, expected }
+                if got == Frame::VERSION + 1 && expected == Frame::VERSION,
+        ));
+
+        // A frame from before versioning has its length in place of the version.
+        let mut raw = BytesMut::new();
+        raw.put_u8(b'+');
+        raw.put_u64(8);
+        raw.put_slice(b"whatever");
+        assert!(matches!(
+            Frame::parse(&mut raw),
+            Err(ParseFrameError::UnsupportedVersion { got: 0, .. }),
+        ));
+    }
+
     #[test]
     fn frame_parse_error_invalid_length() {
         let mut raw = BytesMut::new();
         raw.put_u8(b'+');
+        raw.put_u8(Frame::VERSION);
+        raw.put_u8(b'j');
         raw.put_u64(0);
         assert!(matches!(
             Frame::parse(&mut raw),
@@ -205,6 +446,8 @@ mod tests {
     fn frame_parse_error_deserialization() {
         let mut raw = BytesMut::new();
         raw.put_u8(b'+');
+        raw.put_u8(Frame::VERSION);
+        raw.put_u8(b'j');
         raw.put_u64(8);
         raw.put_slice(b"whatever");
         assert!(matches!(
@@ -212,4 +455,229 @@ mod tests {
             Err(ParseFrameError::Deserialization { .. }),
         ));
     }
+
+    #[test]
+    fn frame_varint_round_trip_small() {
+        let data = Protocol::Response(Response::Skip);
+        let frame = Frame::with_format(data.clone(), FrameFormat::Varint);
+
+        let mut raw = BytesMut::new();
+        frame.write(&mut raw).unwrap();
+
+        let len = serde_json::to_string(&data).unwrap().len();
+        assert!(len < 0x80);
+        assert_eq!(raw[0], b'~');
+        assert_eq!(raw[1], Frame::VERSION);
+        assert_eq!(raw[2], b'j');
+        assert_eq!(raw[3] as usize, len);
+        assert_eq!(raw.len(), 4 + len);
+
+        let (actual, offset) = Frame::parse(&mut raw).unwrap();
+        assert_eq!(actual, frame);
+        assert_eq!(actual.format(), FrameFormat::Varint);
+        assert_eq!(offset, 4 + len);
+    }
+
+    #[test]
+    fn frame_varint_round_trip_large() {
+        let data = Protocol::Response(Response::Query {
+            current: TimerStatus::Running,
+            stage: "Preparation".repeat(64),
+            kind: StageState::Preparation,
+            total: Duration::from_secs(20),
+            remaining: Duration::from_secs(15),
+            past: Duration::from_secs(5),
+            long_break: false,
+            completed_cycles: 0,
+            muted: false,
+        });
+        let frame = Frame::with_format(data.clone(), FrameFormat::Varint);
+
+        let mut raw = BytesMut::new();
+        frame.write(&mut raw).unwrap();
+
+        let len = serde_json::to_string(&data).unwrap().len();
+        assert!((0x80..0x4000).contains(&len));
+        assert_eq!(raw[3] as usize, len & 0x7f | 0x80);
+        assert_eq!(raw[4] as usize, len >> 7);
+
+        let (actual, offset) = Frame::parse(&mut raw).unwrap();
+        assert_eq!(actual, frame);
+        assert_eq!(offset, 5 + len);
+    }
+
+    #[test]
+    fn frame_codec_round_trip() {
+        let data = Protocol::Response(Response::Query {
+            current: TimerStatus::Paused,
+            stage: "Concentration".to_owned(),
+            kind: StageState::Concentration,
+            total: Duration::from_secs(1500),
+            remaining: Duration::from_secs(750),
+            past: Duration::from_secs(750),
+            long_break: true,
+            completed_cycles: 3,
+            muted: false,
+        });
+
+        for format in [FrameFormat::Fixed, FrameFormat::Varint] {
+            for codec in [FrameCodec::Json, FrameCodec::MessagePack] {
+                let frame = Frame::with_format(data.clone(), format).with_codec(codec);
+                let mut raw = BytesMut::new();
+                frame.write(&mut raw).unwrap();
+                assert_eq!(raw[2], codec.marker());
+
+                let len = raw.len();
+                let (actual, offset) = Frame::parse(&mut raw).unwrap();
+                assert_eq!(actual, frame);
+                assert_eq!(actual.codec(), codec);
+                assert_eq!(offset, len);
+            }
+        }
+
+        // A flattened request addressed to a session.
+        let request = Protocol::Request {
+            session: Some("work".to_owned()),
+            request: Request::Adjust { delta_secs: -60 },
+        };
+        let frame = Frame::from(request).with_codec(FrameCodec::MessagePack);
+        let mut raw = BytesMut::new();
+        frame.write(&mut raw).unwrap();
+        assert_eq!(Frame::parse(&mut raw).unwrap().0, frame);
+
+        // MessagePack is the more compact one.
+        let mut json = BytesMut::new();
+        Frame::from(data.clone()).write(&mut json).unwrap();
+        let mut msgpack = BytesMut::new();
+        let frame = Frame::from(data).with_codec(FrameCodec::MessagePack);
+        frame.write(&mut msgpack).unwrap();
+        assert!(msgpack.len() < json.len());
+    }
+
+    #[test]
+    fn frame_parse_error_codec() {
+        let mut raw = BytesMut::new();
+        raw.put_u8(b'+');
+        raw.put_u8(Frame::VERSION);
+        raw.put_u8(b'x');
+        raw.put_u64(8);
+        raw.put_slice(b"whatever");
+        assert!(matches!(
+            Frame::parse(&mut raw),
+            Err(ParseFrameError::UnknownCodec { marker: b'x' }),
+        ));
+
+        // JSON data marked as MessagePack.
+        let data = Protocol::Response(Response::Skip);
+        let mut raw = BytesMut::new();
+        Frame::from(data.clone()).write(&mut raw).unwrap();
+        raw[2] = FrameCodec::MessagePack.marker();
+        let err = Frame::parse(&mut raw).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseFrameError::Deserialization {
+                codec: FrameCodec::MessagePack,
+                ..
+            },
+        ));
+        assert!(err.to_string().contains("MessagePack"));
+
+        // MessagePack data marked as JSON.
+        let mut raw = BytesMut::new();
+        let frame = Frame::from(data).with_codec(FrameCodec::MessagePack);
+        frame.write(&mut raw).unwrap();
+        raw[2] = FrameCodec::Json.marker();
+        let err = Frame::parse(&mut raw).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseFrameError::Deserialization {
+                codec: FrameCodec::Json,
+                ..
+            },
+        ));
+        assert!(err.to_string().contains("JSON"));
+    }
+
+    #[test]
+    fn frame_varint_max_length() {
+        let mut raw = BytesMut::new();
+        Frame::put_varint(&mut raw, u64::MAX);
+        assert_eq!(raw.len(), FrameFormat::MAX_VARINT_LEN);
+        assert_eq!(Frame::get_varint(&mut raw).unwrap(), (u64::MAX, 10));
+
+        // An 11th byte is never allowed.
+        let mut raw = BytesMut::new();
+        raw.put_u8(b'~');
+        raw.put_u8(Frame::VERSION);
+        raw.put_u8(b'j');
+        raw.put_slice(&[0xff; 10]);
+        raw.put_u8(0x01);
+        assert!(matches!(
+            Frame::parse(&mut raw),
+            Err(ParseFrameError::LengthOverflow),
+        ));
+
+        // The 10th byte could not carry more than one bit.
+        let mut raw = BytesMut::new();
+        raw.put_u8(b'~');
+        raw.put_u8(Frame::VERSION);
+        raw.put_u8(b'j');
+        raw.put_slice(&[0xff; 9]);
+        raw.put_u8(0x02);
+        assert!(matches!(
+            Frame::parse(&mut raw),
+            Err(ParseFrameError::LengthOverflow),
+        ));
+    }
+
+    #[test]
+    fn frame_parse_error_too_long() {
+        // Only the header arrives, and the body is never waited for.
+        let mut raw = BytesMut::new();
+        raw.put_u8(b'+');
+        raw.put_u8(Frame::VERSION);
+        raw.put_u8(b'j');
+        raw.put_u64(1 << 40);
+        assert!(matches!(
+            Frame::parse(&mut raw),
+            Err(ParseFrameError::TooLong { len, max: Frame::MAX_LEN }) if len == 1 << 40,
+        ));
+
+        let mut raw = BytesMut::new();
+        raw.put_u8(b'~');
+        raw.put_u8(Frame::VERSION);
+        raw.put_u8(b'j');
+        Frame::put_varint(&mut raw, Frame::MAX_LEN as u64 + 1);
+        assert!(matches!(
+            Frame::parse(&mut raw),
+            Err(ParseFrameError::TooLong {
+                max: Frame::MAX_LEN,
+                ..
+            }),
+        ));
+
+        // The limit itself is still allowed.
+        let mut raw = BytesMut::new();
+        raw.put_u8(b'~');
+        raw.put_u8(Frame::VERSION);
+        raw.put_u8(b'j');
+        Frame::put_varint(&mut raw, Frame::MAX_LEN as u64);
+        assert!(matches!(
+            Frame::parse(&mut raw),
+            Err(ParseFrameError::Incomplete),
+        ));
+    }
+
+    #[test]
+    fn frame_varint_error_incomplete() {
+        let mut raw = BytesMut::new();
+        raw.put_u8(b'~');
+        raw.put_u8(Frame::VERSION);
+        raw.put_u8(b'j');
+        raw.put_u8(0x80);
+        assert!(matches!(
+            Frame::parse(&mut raw),
+            Err(ParseFrameError::Incomplete),
+        ));
+    }
 }
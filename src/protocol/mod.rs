@@ -3,6 +3,6 @@ pub mod frame;
 
 mod data;
 
-pub use connection::Connection;
-pub use data::{Protocol, Request, Response};
-pub use frame::Frame;
+pub use connection::{Connection, RequestFrameError};
+pub use data::{Protocol, ProtocolVersion, Request, Response};
+pub use frame::{Frame, FrameCodec, FrameFormat};
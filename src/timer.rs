@@ -0,0 +1,202 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use snafu::prelude::*;
+
+use crate::daemon::outbound::{NoIdleDetector, NoStageLog, NoStateStore};
+use crate::domain::daemon::inbound::QueryResponse;
+use crate::domain::daemon::outbound::NotifyPort;
+use crate::domain::daemon::{SpawnWorkerError, WorkerHandle, DEFAULT_SESSION};
+use crate::domain::entity::{AutoPauseTrigger, Stage};
+use crate::domain::repository::behavior::{BehaviorRepository, GetBehaviorError};
+use crate::domain::repository::stage::{GetStageError, StageRepository};
+use crate::domain::repository::{DurationRepository, NotificationRepository};
+
+/// A timer driven by a background task in the current process, for embedding
+/// without a daemon or a socket. It runs on the current Tokio runtime, goes
+/// through the default stages, never pauses automatically and keeps no
+/// progress once stopped.
+#[derive(Debug)]
+pub struct Timer {
+    worker: WorkerHandle,
+}
+
+impl Timer {
+    /// Start a [`Timer`] with stages built from the repositories, which emits
+    /// notifications through `notifier`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any repository fails or the
+    /// timer could not start.
+    pub async fn spawn(
+        duration_repository: Arc<dyn DurationRepository>,
+        notification_repository: Arc<dyn NotificationRepository>,
+        notifier: Arc<dyn NotifyPort>,
+    ) -> Result<Self, SpawnTimerError> {
+        let worker = crate::domain::daemon::spawn_worker(
+            DEFAULT_SESSION,
+            Arc::new(NoStateStore::new()),
+            Arc::new(NoStageLog::new()),
+            duration_repository,
+            notification_repository,
+            Arc::new(DefaultStages),
+            Arc::new(NoAutoPause),
+            notifier,
+            Arc::new(NoIdleDetector::new()),
+        )
+        .await
+        .context(SpawnTimerSnafu)?;
+        Ok(Self { worker })
+    }
+
+    /// Pause the timer.
+    pub async fn pause(&self) {
+        self.worker.pause().await;
+    }
+
+    /// Resume the timer.
+    pub async fn resume(&self) {
+        self.worker.resume().await;
+    }
+
+    /// Skip to the next stage. The skipped stage's notification is emitted if
+    /// `notify` is set.
+    pub async fn skip(&self, notify: bool) {
+        self.worker.skip(notify).await;
+    }
+
+    /// Stop showing notifications, while the timer goes on as usual.
+    pub async fn mute(&self) {
+        self.worker.mute().await;
+    }
+
+    /// Show notifications again.
+    pub async fn unmute(&self) {
+        self.worker.unmute().await;
+    }
+
+    /// Get the current state of the timer.
+    pub async fn query(&self) -> QueryResponse {
+        let response = self.worker.query().await;
+        QueryResponse {
+            current: response.current,
+            stage: response.stage,
+            kind: response.kind,
+            total: response.total,
+            remaining: response.remaining,
+            past: response.past,
+            long_break: response.long_break,
+            completed_cycles: response.completed_cycles,
+            muted: response.muted,
+        }
+    }
+
+    /// Stop the timer and wait until its background task exits.
+    pub async fn stop(self) {
+        self.worker.stop(false).await;
+    }
+}
+
+/// A [`StageRepository`] which always asks for the default stages.
+struct DefaultStages;
+
+#[async_trait::async_trait]
+impl StageRepository for DefaultStages {
+    async fn stages(&self) -> Result<Vec<Stage>, GetStageError> {
+        Ok(Vec::new())
+    }
+}
+
+/// A [`BehaviorRepository`] which disables all automatic pauses, since there
+/// is no client to send the triggers.
+struct NoAutoPause;
+
+#[async_trait::async_trait]
+impl BehaviorRepository for NoAutoPause {
+    async fn auto_pause_enabled(
+        &self,
+        _trigger: AutoPauseTrigger,
+    ) -> Result<bool, GetBehaviorError> {
+        Ok(false)
+    }
+
+    async fn idle_threshold(&self) -> Result<Option<Duration>, GetBehaviorError> {
+        Ok(None)
+    }
+
+    async fn start_paused(&self) -> Result<bool, GetBehaviorError> {
+        Ok(false)
+    }
+
+    async fn max_pause(&self) -> Result<Option<Duration>, GetBehaviorError> {
+        Ok(None)
+    }
+}
+
+/// An error type for starting a [`Timer`].
+#[derive(Debug, Snafu)]
+#[snafu(display("Could not start the timer"))]
+pub struct SpawnTimerError {
+    source: SpawnWorkerError,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::daemon::repository::{InMemoryDurationRepository, InMemoryNotificationRepository};
+    use crate::domain::daemon::outbound::{NotifyError, NotifyRequest};
+    use crate::domain::entity::{StageState, TimerStatus};
+
+    #[derive(Default)]
+    struct CountingNotifier {
+        count: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl NotifyPort for CountingNotifier {
+        async fn notify_impl(&self, _request: NotifyRequest) -> Result<(), NotifyError> {
+            self.count.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn timer_full_cycle() {
+        let notifier = Arc::new(CountingNotifier::default());
+        let timer = Timer::spawn(
+            Arc::new(InMemoryDurationRepository::new()),
+            Arc::new(InMemoryNotificationRepository::new()),
+            Arc::clone(&notifier) as Arc<dyn NotifyPort>,
+        )
+        .await
+        .unwrap();
+
+        let response = timer.query().await;
+        assert_eq!(response.kind, StageState::Preparation);
+        assert_eq!(response.remaining, Duration::from_secs(900));
+
+        // Time stands still while paused.
+        timer.pause().await;
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        assert_eq!(timer.query().await.current, TimerStatus::Paused);
+        assert_eq!(timer.query().await.past, Duration::ZERO);
+        timer.resume().await;
+
+        tokio::time::sleep(Duration::from_secs(901)).await;
+        assert_eq!(timer.query().await.kind, StageState::Concentration);
+        timer.skip(true).await;
+        assert_eq!(timer.query().await.kind, StageState::Relaxation);
+        tokio::time::sleep(Duration::from_secs(601)).await;
+
+        let response = timer.query().await;
+        assert_eq!(response.kind, StageState::Concentration);
+        assert_eq!(response.completed_cycles, 1);
+        assert_eq!(notifier.count.load(Ordering::SeqCst), 3);
+
+        timer.stop().await;
+    }
+}
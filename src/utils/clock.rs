@@ -0,0 +1,35 @@
+use std::time::Duration;
+
+/// Format a duration as `MM:SS`, or `H:MM:SS` if it lasts for hours.
+pub fn format_clock(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes:02}:{seconds:02}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_clock_minutes() {
+        assert_eq!(format_clock(Duration::from_secs(0)), "00:00");
+        assert_eq!(format_clock(Duration::from_secs(59)), "00:59");
+        assert_eq!(format_clock(Duration::from_secs(60)), "01:00");
+        assert_eq!(format_clock(Duration::from_secs(885)), "14:45");
+        assert_eq!(format_clock(Duration::from_secs(3599)), "59:59");
+    }
+
+    #[test]
+    fn format_clock_hours() {
+        assert_eq!(format_clock(Duration::from_secs(3600)), "1:00:00");
+        assert_eq!(format_clock(Duration::from_secs(3723)), "1:02:03");
+        assert_eq!(format_clock(Duration::from_secs(5400)), "1:30:00");
+        assert_eq!(format_clock(Duration::from_secs(36000)), "10:00:00");
+    }
+}
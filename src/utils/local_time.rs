@@ -0,0 +1,88 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Number of seconds in a day.
+const DAY_SECS: i64 = 24 * 60 * 60;
+
+/// Get the number of days from the Unix epoch to `time` in the local time
+/// zone, which increases at every local midnight.
+pub fn local_day(time: SystemTime) -> i64 {
+    let secs = unix_secs(time);
+    (secs + utc_offset(secs)).div_euclid(DAY_SECS)
+}
+
+/// Format `time` as an RFC 3339 timestamp in UTC, e.g.
+/// `2024-08-15T09:30:00Z`.
+pub fn format_utc(time: SystemTime) -> String {
+    let secs = unix_secs(time);
+    let (days, rest) = (secs.div_euclid(DAY_SECS), secs.rem_euclid(DAY_SECS));
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        rest / 3600,
+        rest % 3600 / 60,
+        rest % 60,
+    )
+}
+
+fn unix_secs(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => since.as_secs() as i64,
+        Err(err) => -(err.duration().as_secs() as i64),
+    }
+}
+
+/// Get the offset of the local time zone from UTC in seconds at `secs` after
+/// the Unix epoch. UTC is assumed if the time zone is unknown.
+fn utc_offset(secs: i64) -> i64 {
+    let time = secs as libc::time_t;
+    // SAFETY: `localtime_r` only writes the broken-down time to `tm`, which
+    // is valid for writes, and keeps no reference to either argument.
+    unsafe {
+        let mut tm = std::mem::zeroed::<libc::tm>();
+        if libc::localtime_r(&time, &mut tm).is_null() {
+            return 0;
+        }
+        tm.tm_gmtoff as i64
+    }
+}
+
+/// Convert days since the Unix epoch to a date in the proleptic Gregorian
+/// calendar, following Howard Hinnant's `civil_from_days`.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    #[test]
+    fn format_utc_timestamp() {
+        assert_eq!(format_utc(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        let time = UNIX_EPOCH + Duration::from_secs(1_723_714_200);
+        assert_eq!(format_utc(time), "2024-08-15T09:30:00Z");
+        let time = UNIX_EPOCH + Duration::from_secs(951_782_400);
+        assert_eq!(format_utc(time), "2000-02-29T00:00:00Z");
+    }
+
+    #[test]
+    fn local_day_changes_daily() {
+        let now = SystemTime::now();
+        let later = now + Duration::from_secs(10 * DAY_SECS as u64);
+        // A shift of daylight saving time may move the later one across a
+        // midnight.
+        let days = local_day(later) - local_day(now);
+        assert!((9..=11).contains(&days), "{days} days passed");
+    }
+}
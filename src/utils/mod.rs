@@ -1,3 +1,5 @@
+pub mod clock;
+pub mod local_time;
 pub mod stream;
 pub mod tracing;
 pub mod xdg;
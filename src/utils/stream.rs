@@ -1,6 +1,9 @@
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
 use tokio::io::DuplexStream;
 use tokio::io::{AsyncRead, AsyncWrite};
-use tokio::net::UnixStream;
+use tokio::net::{TcpStream, UnixStream};
 
 /// Abstract form of types that are capable of async IO.
 pub trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
@@ -9,4 +12,13 @@ impl Stream for Box<dyn Stream> {}
 
 impl Stream for UnixStream {}
 
+impl Stream for TcpStream {}
+
 impl Stream for DuplexStream {}
+
+/// Return the name of the abstract UNIX socket which `path` refers to, or
+/// `None` for a socket file. A path starting with `@` names an abstract
+/// socket, which is Linux-specific and vanishes with its last user.
+pub fn abstract_name(path: &Path) -> Option<&[u8]> {
+    path.as_os_str().as_bytes().strip_prefix(b"@")
+}